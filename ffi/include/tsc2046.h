@@ -0,0 +1,99 @@
+/*
+ * C interface of the TSC2046 touch screen controller driver.
+ *
+ * Link with libtsc2046_ffi.a, built by the tsc2046-ffi crate. The functions return
+ * TSC2046_OK (or a positive value) on success, and one of the negative TSC2046_ERR_* codes on
+ * failure.
+ */
+#ifndef TSC2046_H
+#define TSC2046_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The operation succeeded. */
+#define TSC2046_OK 0
+/* A null pointer was passed. */
+#define TSC2046_ERR_NULL (-1)
+/* The SPI transfer failed. */
+#define TSC2046_ERR_SPI (-2)
+/* Reading or driving a pin failed. */
+#define TSC2046_ERR_PIN (-3)
+/* Sampling is inhibited. */
+#define TSC2046_ERR_INHIBITED (-5)
+/* No touch was detected in time. */
+#define TSC2046_ERR_TIMEOUT (-6)
+/* A conversion returned an unusable value. */
+#define TSC2046_ERR_INVALID_READING (-7)
+/* A conversion frame does not fit the transfer buffers. */
+#define TSC2046_ERR_FRAME_OVERFLOW (-8)
+
+/* The size of the storage of a driver instance in bytes. */
+#define TSC2046_HANDLE_SIZE 1024
+
+/*
+ * SPI bus of the TSC2046. Every callback receives `context` as its first argument. `select` and
+ * `transfer` are mandatory, a null `delay_us` skips the delays.
+ */
+typedef struct tsc2046_bus {
+    /* Opaque pointer passed to the callbacks. */
+    void *context;
+    /* Asserts (true) or deasserts (false) the chip select line. */
+    void (*select)(void *context, bool selected);
+    /*
+     * Exchanges `len` bytes. `tx` is null when only reading (zeros are sent), `rx` is null when
+     * only writing, and both point to the same buffer for an in-place transfer. Returns 0 on
+     * success.
+     */
+    int32_t (*transfer)(void *context, const uint8_t *tx, uint8_t *rx, size_t len);
+    /* Waits for at least `us` microseconds. */
+    void (*delay_us)(void *context, uint32_t us);
+} tsc2046_bus_t;
+
+/* Touch point. */
+typedef struct tsc2046_touch {
+    /* The raw X position, between 0 and 4095. */
+    uint16_t x;
+    /* The raw Y position, between 0 and 4095. */
+    uint16_t y;
+    /* The pressure, lower values meaning firmer touches. */
+    float z;
+} tsc2046_touch_t;
+
+/* Storage of a driver instance, opaque. The 64 bit words align it to 8 bytes in C and C++. */
+typedef struct tsc2046_handle {
+    uint64_t storage[TSC2046_HANDLE_SIZE / sizeof(uint64_t)];
+} tsc2046_handle_t;
+
+/*
+ * Creates a driver instance in the storage of a handle. The callbacks of `bus` must remain valid
+ * until the instance is released.
+ */
+int32_t tsc2046_init(tsc2046_handle_t *handle, tsc2046_bus_t bus, bool irq_on,
+                     float touch_threshold);
+
+/* Samples the touch screen. Returns 1 if the screen is touched, 0 if not, or an error code. */
+int32_t tsc2046_poll(tsc2046_handle_t *handle);
+
+/*
+ * Reads the touch point of the last poll. Returns 1 if the screen was touched at the last poll,
+ * 0 if not, or an error code.
+ */
+int32_t tsc2046_get_touch(tsc2046_handle_t *handle, tsc2046_touch_t *touch);
+
+/* Sets the touch threshold. */
+int32_t tsc2046_set_threshold(tsc2046_handle_t *handle, float touch_threshold);
+
+/* Destroys the driver instance stored in a handle. */
+void tsc2046_release(tsc2046_handle_t *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TSC2046_H */
@@ -0,0 +1,14 @@
+//! C static library of the TSC2046 driver.
+//!
+//! Links the C interface of [`tsc2046::ffi`] into `libtsc2046_ffi.a`, declared by
+//! `include/tsc2046.h`. On bare-metal targets the library is `no_std`, and a panic halts.
+#![cfg_attr(target_os = "none", no_std)]
+
+pub use tsc2046::ffi::*;
+
+/// Halts on panic. The driver has no panicking code path, so this only guards the invariants.
+#[cfg(target_os = "none")]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
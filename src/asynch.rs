@@ -0,0 +1,748 @@
+//! Async flavor of the driver, for firmware running on an async executor.
+//!
+//! [`AsyncTsc2046`] drives the chip through the `embedded-hal-async` traits. It is a thin shim
+//! over the same protocol core as the blocking [`Tsc2046`]: the scan is sequenced by the shared
+//! state machine, and the frames, the trace, the configuration and the sampling gate are handled
+//! by the same code, so both flavors produce the same samples from the same conversions, in the
+//! same transactions: with [`Config::burst_oversampling`], the oversampled conversions of a
+//! channel share a transaction, otherwise every conversion is its own SPI transaction, awaited on
+//! its own. With [`Config::interleaved`], the driver also yields to the executor between the
+//! conversions of a scan, so other tasks can use the bus.
+//!
+//! Applications mixing blocking initialization code and async runtime code convert the driver
+//! with [`Tsc2046::into_async`] and [`AsyncTsc2046::into_blocking`], which keep the
+//! configuration, the diagnostics and the rest of the driver state. The calibration and the
+//! filters are stages of the [`EventTracker`](crate::event::EventTracker), which is fed by either
+//! flavor, so their state carries over by keeping the same tracker.
+//!
+//! The async flavor covers touch sampling, the interrupt setting, re-initialization and the
+//! inhibition. The configuration is changed as a whole with [`AsyncTsc2046::apply_config`], as
+//! the individual setters of the blocking driver are not duplicated. The auxiliary, battery,
+//! temperature and full reads, the diagnostics routines and the calibration routines are
+//! blocking-only: they run on the blocking driver, converted back and forth without sending any
+//! command.
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use embedded_hal::digital::InputPin;
+use embedded_hal::spi::ErrorType;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::spi::SpiDevice;
+
+use crate::diagnostics::Diagnostics;
+use crate::protocol::{self, ScanStep};
+#[cfg(feature = "stream")]
+use crate::stream::STROKE_SAMPLE_INTERVAL_US;
+use crate::types::ControlBit;
+#[cfg(feature = "stream")]
+use crate::OptionalWait;
+use crate::{
+    AcquisitionMode, Config, Error, NoPin, OptionalInputPin, TouchPoint, TouchSample, Tsc2046,
+    MAX_BURST_LEN,
+};
+
+/// Async driver of the TSC2046 chip.
+pub struct AsyncTsc2046<SPI, DELAY, IRQ = NoPin> {
+    /// The SPI interface used to communicate with the TSC2046 chip.
+    spi: SPI,
+    /// The pin connected to the PENIRQ output of the chip, if owned by the driver.
+    irq_pin: IRQ,
+    /// The delay provider pacing the samples of the event source.
+    delay: DELAY,
+    /// The state shared with the blocking flavor: configuration, diagnostics and trace.
+    core: protocol::Core,
+}
+impl<SPI, DELAY> AsyncTsc2046<SPI, DELAY>
+where
+    SPI: SpiDevice,
+    DELAY: DelayNs,
+{
+    /// Creates a new instance of the async driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - The SPI interface used to communicate with the TSC2046 chip.
+    /// * `delay` - The delay provider pacing the samples of the event source.
+    /// * `irq_on` - Whether to enable the interrupt pin or not.
+    /// * `touch_threshold` - The minimum pressure value required to register a touch event.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `AsyncTsc2046` instance or an error if the register update
+    /// fails.
+    pub async fn new(
+        spi: SPI,
+        delay: DELAY,
+        irq_on: bool,
+        touch_threshold: f32,
+    ) -> Result<Self, Error<<SPI as ErrorType>::Error>> {
+        Self::from_config(
+            spi,
+            delay,
+            Config {
+                irq_on,
+                touch_threshold,
+                acquisition: AcquisitionMode::Averaging,
+                ..Config::new()
+            },
+        )
+        .await
+    }
+
+    /// Creates a new instance of the async driver with the given configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - The SPI interface used to communicate with the TSC2046 chip.
+    /// * `delay` - The delay provider pacing the samples of the event source.
+    /// * `config` - The configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `AsyncTsc2046` instance or an error if the register update
+    /// fails.
+    pub async fn from_config(
+        spi: SPI,
+        delay: DELAY,
+        config: Config,
+    ) -> Result<Self, Error<<SPI as ErrorType>::Error>> {
+        let mut instance = Self {
+            spi,
+            irq_pin: NoPin,
+            delay,
+            core: protocol::Core::new(config),
+        };
+        instance.apply_config(config).await?;
+        Ok(instance)
+    }
+}
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    DELAY: DelayNs,
+{
+    /// Converts the driver into its async flavor.
+    ///
+    /// The SPI interface and the delay provider owned by the driver must implement the
+    /// `embedded-hal-async` traits as well, which HALs commonly provide on the same types. The
+    /// configuration, the diagnostics, the threshold compensation, the inhibition and the trace
+    /// are carried over, and no command is sent to the chip.
+    ///
+    /// # Returns
+    ///
+    /// The async driver, with the pins and the state of this driver.
+    pub fn into_async(self) -> AsyncTsc2046<SPI, DELAY, IRQ> {
+        AsyncTsc2046 {
+            spi: self.spi,
+            irq_pin: self.irq_pin,
+            delay: self.delay,
+            core: self.core,
+        }
+    }
+}
+impl<SPI, DELAY, IRQ> AsyncTsc2046<SPI, DELAY, IRQ> {
+    /// Converts the driver into its blocking flavor.
+    ///
+    /// The state of the driver is carried over as by [`Tsc2046::into_async`], and no command is
+    /// sent to the chip. The blocking methods are available as long as the SPI interface and the
+    /// delay provider implement the `embedded-hal` traits.
+    ///
+    /// # Returns
+    ///
+    /// The blocking driver, with the pins and the state of this driver.
+    pub fn into_blocking(self) -> Tsc2046<SPI, IRQ, DELAY> {
+        Tsc2046 {
+            spi: self.spi,
+            irq_pin: self.irq_pin,
+            delay: self.delay,
+            core: self.core,
+        }
+    }
+}
+impl<SPI, DELAY> AsyncTsc2046<SPI, DELAY, NoPin> {
+    /// Hands the pin connected to the PENIRQ output of the chip over to the driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `irq_pin` - The input pin connected to the PENIRQ output.
+    ///
+    /// # Returns
+    ///
+    /// The driver instance owning the PENIRQ pin.
+    pub fn with_irq_pin<IRQ: InputPin>(self, irq_pin: IRQ) -> AsyncTsc2046<SPI, DELAY, IRQ> {
+        AsyncTsc2046 {
+            spi: self.spi,
+            irq_pin,
+            delay: self.delay,
+            core: self.core,
+        }
+    }
+}
+impl<SPI, DELAY, IRQ> AsyncTsc2046<SPI, DELAY, IRQ>
+where
+    SPI: SpiDevice,
+    DELAY: DelayNs,
+    IRQ: OptionalInputPin,
+{
+    /// Returns a snapshot of the configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// A copy of the current `Config`.
+    pub fn config(&self) -> Config {
+        self.core.config
+    }
+
+    /// Applies a configuration, typically a snapshot taken with [`AsyncTsc2046::config`].
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to apply.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the register update was successful or not.
+    pub async fn apply_config(
+        &mut self,
+        config: Config,
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        self.core.apply_config(config);
+        self.update_register().await
+    }
+
+    /// Enables or disables the interrupt pin.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable_irq` - Whether to enable or disable the interrupt pin.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the interrupt pin configuration was successful or not.
+    pub async fn set_irq(
+        &mut self,
+        enable_irq: bool,
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        self.core.config.irq_on = enable_irq;
+        self.update_register().await
+    }
+
+    /// Re-runs the initialization sequence of the TSC2046 chip with the current configuration.
+    ///
+    /// Same as [`Tsc2046::reinit`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the initialization was successful or not.
+    pub async fn reinit(&mut self) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        self.core.diagnostics.clear_pressure_fallback();
+        self.update_register().await
+    }
+
+    /// Suppresses acquisitions until [`AsyncTsc2046::resume`] is called.
+    ///
+    /// While inhibited, reading a touch returns [`Error::Inhibited`].
+    pub fn inhibit(&mut self) {
+        self.core.inhibited = true;
+    }
+
+    /// Resumes acquisitions suppressed by [`AsyncTsc2046::inhibit`].
+    pub fn resume(&mut self) {
+        self.core.inhibited = false;
+    }
+
+    /// Returns the health information collected while operating.
+    ///
+    /// # Returns
+    ///
+    /// A copy of the `Diagnostics`.
+    pub fn diagnostics(&self) -> Diagnostics {
+        self.core.diagnostics
+    }
+
+    /// Reads the touch point from the TSC2046 chip.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TouchPoint` struct if a touch event is detected, or `None` if no
+    /// touch event is detected.
+    pub async fn get_touch(
+        &mut self,
+    ) -> Result<Option<TouchPoint>, Error<<SPI as ErrorType>::Error>> {
+        Ok(self.get_sample().await?.map(|sample| sample.point))
+    }
+
+    /// Reads a touch sample from the TSC2046 chip.
+    ///
+    /// Same as [`Tsc2046::get_sample`](crate::Tsc2046::get_sample), conversion for conversion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TouchSample` struct if a touch event is detected, or `None` if
+    /// no touch event is detected.
+    pub async fn get_sample(
+        &mut self,
+    ) -> Result<Option<TouchSample>, Error<<SPI as ErrorType>::Error>> {
+        let mut scan = self.core.start_scan()?;
+        loop {
+            match scan.step(&self.core.config, &mut self.core.diagnostics) {
+                ScanStep::Refresh => {
+                    self.update_register().await?;
+                    self.gap().await;
+                }
+                ScanStep::ReadPen => scan.pen(self.read_pen_irq().await?),
+                ScanStep::Convert {
+                    control_word,
+                    count,
+                    burst,
+                } => {
+                    let result = if burst && !self.core.config.interleaved {
+                        self.convert_all(control_word, count, |value| scan.feed(value))
+                            .await
+                    } else {
+                        self.convert_each(control_word, count, |value| scan.feed(value))
+                            .await
+                    };
+                    if let Err(error) = result {
+                        if !scan.recover(&self.core.config) {
+                            return Err(error);
+                        }
+                    }
+                }
+                ScanStep::Done(result) => return result,
+            }
+        }
+    }
+
+    /// Yields to the executor between two transactions if the conversions are interleaved, so
+    /// other tasks can use the bus.
+    async fn gap(&self) {
+        if self.core.config.interleaved {
+            YieldNow(false).await;
+        }
+    }
+
+    /// Reads the PENIRQ pin through the glitch filter, if the driver owns the pin and the
+    /// interrupt is enabled.
+    ///
+    /// Same filter as the blocking driver, with the samples spaced out by awaiting the delay
+    /// provider.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `Some(true)` if the pen is down, `Some(false)` if it is up, or `None`
+    /// if the pen state is not available.
+    async fn read_pen_irq(&mut self) -> Result<Option<bool>, Error<<SPI as ErrorType>::Error>> {
+        if !self.core.config.irq_on {
+            return Ok(None);
+        }
+        for sample in 0..self.core.config.irq_filter_samples {
+            if sample > 0 {
+                self.delay
+                    .delay_us(self.core.config.irq_filter_interval_us)
+                    .await;
+            }
+            match self.irq_pin.read_level().map_err(Error::Pin)? {
+                None => return Ok(None),
+                Some(true) => return Ok(Some(false)),
+                Some(false) => {}
+            }
+        }
+        Ok(Some(true))
+    }
+
+    /// Updates the control register of the TSC2046 chip.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the register update was successful or not.
+    async fn update_register(&mut self) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        self.convert(protocol::register_control_word(&self.core.config))
+            .await?;
+        self.core.registered();
+        Ok(())
+    }
+
+    /// Sends a control word to the TSC2046 chip and reads back the result of the conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word to send.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the 12 bit result of the conversion or an error if the transfer fails.
+    async fn convert(
+        &mut self,
+        control_word: ControlBit,
+    ) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        let result = self.transfer(control_word).await;
+        self.core.diagnostics.record(result)
+    }
+
+    /// Performs the conversions of a reading, each in its own transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word of the conversions.
+    /// * `count` - The number of conversions.
+    /// * `feed` - The function receiving the result of every conversion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether all of the conversions succeeded.
+    async fn convert_each(
+        &mut self,
+        control_word: ControlBit,
+        count: u16,
+        mut feed: impl FnMut(u16),
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        for _ in 0..count {
+            feed(self.convert(control_word).await?);
+            self.gap().await;
+        }
+        Ok(())
+    }
+
+    /// Performs the conversions of a reading in bursts of up to [`MAX_BURST_LEN`], each burst in
+    /// a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word of the conversions.
+    /// * `count` - The number of conversions.
+    /// * `feed` - The function receiving the result of every conversion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether all of the conversions succeeded.
+    async fn convert_all(
+        &mut self,
+        control_word: ControlBit,
+        count: u16,
+        mut feed: impl FnMut(u16),
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        let mut remaining = count;
+        while remaining > 0 {
+            let len = remaining.min(MAX_BURST_LEN);
+            let result = self.transfer_burst(control_word, len, &mut feed).await;
+            self.core
+                .diagnostics
+                .record_conversions(result, u32::from(len))?;
+            remaining = remaining.saturating_sub(len);
+        }
+        Ok(())
+    }
+
+    /// Performs the SPI transfer of a burst of identical conversions, without recording it in the
+    /// diagnostics.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word of the conversions.
+    /// * `count` - The number of conversions, at most [`MAX_BURST_LEN`].
+    /// * `feed` - The function receiving the result of every conversion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the transfer succeeded.
+    async fn transfer_burst(
+        &mut self,
+        control_word: ControlBit,
+        count: u16,
+        feed: &mut impl FnMut(u16),
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        let mut burst = self.core.burst(control_word, count);
+        let channel = burst.channel();
+        let len = burst.operation_count();
+        let mut operations = burst.operations()?;
+        let operations = operations
+            .get_mut(..len)
+            .ok_or(Error::FrameOverflow(channel))?;
+        self.spi
+            .transaction(operations)
+            .await
+            .map_err(|error| Error::Spi { channel, error })?;
+        self.core.complete_burst(&burst, feed);
+        Ok(())
+    }
+
+    /// Performs the SPI transfer of a conversion, without recording it in the diagnostics.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word to send.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the 12 bit result of the conversion or an error if the transfer fails.
+    async fn transfer(
+        &mut self,
+        control_word: ControlBit,
+    ) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        let mut frame = self.core.frame(control_word);
+        let channel = frame.channel();
+        self.spi
+            .transaction(&mut frame.operations()?)
+            .await
+            .map_err(|error| Error::Spi { channel, error })?;
+        Ok(self.core.complete(&frame))
+    }
+}
+/// Future returning `Pending` once, so the executor polls the other tasks before resuming.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<SPI, DELAY, IRQ> AsyncTsc2046<SPI, DELAY, IRQ>
+where
+    SPI: SpiDevice,
+    DELAY: DelayNs,
+    IRQ: OptionalInputPin + OptionalWait,
+{
+    /// Waits until the next sample is due, without sampling the chip.
+    ///
+    /// While a contact is tracked, the samples are [`STROKE_SAMPLE_INTERVAL_US`] apart. Between
+    /// contacts, the PENIRQ pin is awaited if the driver owns it and the interrupt is enabled, so
+    /// the task sleeps until the panel is touched. Otherwise, or while sampling is inhibited, the
+    /// wait lasts one sample interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `touching` - Whether the last sample detected a touch.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether waiting on the PENIRQ pin succeeded or not.
+    pub(crate) async fn wait_for_sample(
+        &mut self,
+        touching: bool,
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        let idle = !touching && !self.core.inhibited && self.core.config.irq_on;
+        if idle && self.irq_pin.wait_low().await.map_err(Error::Pin)? {
+            return Ok(());
+        }
+        self.delay.delay_us(STROKE_SAMPLE_INTERVAL_US).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::{block_on, FakeTsc2046, MockDelay};
+    use crate::{ScanOrder, Tsc2046};
+
+    #[test]
+    fn test_same_scan_as_blocking() {
+        let chip = || {
+            let mut fake_chip = FakeTsc2046::default();
+            fake_chip.queue(FakeTsc2046::X, &[0, 0, 2000, 2010, 2020]);
+            fake_chip.queue(FakeTsc2046::Y, &[1000]);
+            fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+            fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+            fake_chip
+        };
+        let config = Config {
+            touch_threshold: 100.0,
+            acquisition: AcquisitionMode::Median,
+            scan_order: ScanOrder::PressureFirst {
+                min_z1: 10,
+                with_z2: true,
+            },
+            ..Config::new()
+        };
+        let mut blocking = Tsc2046::new(chip(), false, 100.0).expect("Could not create driver");
+        blocking
+            .apply_config(config)
+            .expect("Could not apply config");
+        let expected = blocking.get_sample().expect("Could not read sample");
+        let mut driver = block_on(AsyncTsc2046::new(
+            chip(),
+            MockDelay::default(),
+            false,
+            100.0,
+        ))
+        .expect("Could not create driver");
+        block_on(driver.apply_config(config)).expect("Could not apply config");
+        let sample = block_on(driver.get_sample()).expect("Could not read sample");
+        assert_eq!(sample, expected);
+        assert_eq!(sample.map(|sample| sample.point.x), Some(2010));
+        assert_eq!(driver.spi.commands, blocking.spi.commands);
+        assert_eq!(driver.diagnostics(), blocking.diagnostics());
+    }
+
+    #[test]
+    fn test_same_bursts_as_blocking() {
+        let chip = || {
+            let mut fake_chip = FakeTsc2046::default();
+            fake_chip.queue(FakeTsc2046::X, &[0, 1000, 1010, 1020, 1030]);
+            fake_chip.queue(FakeTsc2046::Y, &[2000]);
+            fake_chip.queue(FakeTsc2046::Z1, &[500]);
+            fake_chip.queue(FakeTsc2046::Z2, &[600]);
+            fake_chip
+        };
+        let config = Config {
+            acquisition: AcquisitionMode::Averaging,
+            oversampling: 4,
+            burst_oversampling: true,
+            ..Config::new()
+        };
+        let mut blocking = Tsc2046::new(chip(), false, 100.0).expect("Could not create driver");
+        blocking
+            .apply_config(config)
+            .expect("Could not apply config");
+        let expected = blocking.get_sample().expect("Could not read sample");
+        let mut driver = block_on(AsyncTsc2046::new(
+            chip(),
+            MockDelay::default(),
+            false,
+            100.0,
+        ))
+        .expect("Could not create driver");
+        block_on(driver.apply_config(config)).expect("Could not apply config");
+        let transactions = driver.spi.transactions;
+        let sample = block_on(driver.get_sample()).expect("Could not read sample");
+        assert_eq!(sample, expected);
+        assert_eq!(sample.map(|sample| sample.point.x), Some(1022));
+        assert_eq!(driver.spi.commands, blocking.spi.commands);
+        assert_eq!(driver.spi.transactions, blocking.spi.transactions);
+        // One transaction per channel, each carrying its four conversions.
+        assert_eq!(driver.spi.transactions - transactions, 4);
+        assert_eq!(driver.diagnostics(), blocking.diagnostics());
+    }
+
+    #[test]
+    fn test_interleaved_yields() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 1000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut driver = block_on(AsyncTsc2046::new(
+            fake_chip,
+            MockDelay::default(),
+            false,
+            100.0,
+        ))
+        .expect("Could not create driver");
+        let config = Config {
+            interleaved: true,
+            ..driver.config()
+        };
+        block_on(driver.apply_config(config)).expect("Could not apply config");
+        let commands_before = driver.spi.commands.len();
+        let mut yields = 0;
+        let sample = {
+            let mut future = core::pin::pin!(driver.get_sample());
+            let mut cx = Context::from_waker(core::task::Waker::noop());
+            loop {
+                match future.as_mut().poll(&mut cx) {
+                    Poll::Ready(sample) => break sample,
+                    Poll::Pending => yields += 1,
+                }
+            }
+        };
+        assert_eq!(
+            sample
+                .expect("Could not read sample")
+                .map(|sample| sample.point.x),
+            Some(1000)
+        );
+        // The executor got the bus back after every conversion.
+        assert_eq!(yields, driver.spi.commands.len() - commands_before);
+    }
+
+    #[test]
+    fn test_shared_operations() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 0, 0, 2048]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut driver = block_on(AsyncTsc2046::new(
+            fake_chip,
+            MockDelay::default(),
+            false,
+            100.0,
+        ))
+        .expect("Could not create driver");
+        block_on(driver.set_irq(true)).expect("Could not enable the interrupt");
+        assert!(driver.config().irq_on);
+        block_on(driver.reinit()).expect("Could not re-initialize");
+        driver.inhibit();
+        assert_eq!(block_on(driver.get_touch()), Err(Error::Inhibited));
+        driver.resume();
+        let touch = block_on(driver.get_touch()).expect("Could not read touch");
+        assert_eq!(touch.map(|point| point.x), Some(2048));
+        assert_eq!(driver.diagnostics().stats.errors, 1);
+    }
+
+    #[cfg(all(feature = "calibration", feature = "events", feature = "filters"))]
+    #[test]
+    fn test_conversions_carry_state() {
+        use crate::calibration::Calibration;
+        use crate::event::{CalibrationStage, EventTracker, FilterStage};
+        use crate::filter::WeightedMovingAverage;
+
+        let mut fake_chip = FakeTsc2046::default();
+        // The first two X conversions are consumed by the register updates.
+        fake_chip.queue(FakeTsc2046::X, &[0, 0, 1000, 3000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut driver = Tsc2046::new(fake_chip, false, 100.0)
+            .expect("Could not create driver")
+            .with_delay(MockDelay::default());
+        let config = Config {
+            touch_threshold: 90.0,
+            oversampling: 1,
+            ..driver.config()
+        };
+        driver.apply_config(config).expect("Could not apply config");
+        let calibration = Calibration::new(0, 4095, 0, 4095, 320, 240);
+        let mut tracker = EventTracker::new()
+            .with_filter(WeightedMovingAverage::<4>::new())
+            .with_calibration(calibration);
+        let first = driver.get_touch().expect("Could not read touch");
+        tracker.update(first, 0);
+        let diagnostics = driver.diagnostics();
+
+        let mut driver = driver.into_async();
+        assert_eq!(driver.config().touch_threshold, 90.0);
+        assert_eq!(driver.diagnostics(), diagnostics);
+        let touch = block_on(driver.get_touch()).expect("Could not read touch");
+        tracker.update(touch, 10);
+        // The filter history of the blocking sample is kept.
+        let mut reference = WeightedMovingAverage::<4>::new();
+        reference.filter(first);
+        let expected = reference
+            .filter(touch)
+            .map(|point| calibration.calibrate(point));
+        assert_ne!(expected, touch.map(|point| calibration.calibrate(point)));
+        let mut last = None;
+        while let Some(event) = tracker.next_event() {
+            last = Some(event.point);
+        }
+        assert_eq!(last, expected);
+
+        let driver = driver.into_blocking();
+        assert_eq!(driver.config().touch_threshold, 90.0);
+        assert_eq!(driver.diagnostics().stats.touches, 2);
+        assert_eq!(driver.spi.commands.len(), 2 + 2 * 4);
+    }
+}
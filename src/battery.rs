@@ -0,0 +1,80 @@
+/// Monitor smoothing battery voltage readings with an exponential moving average.
+///
+/// Load transients briefly pull the battery voltage down, so low-battery warnings should be
+/// based on the averaged voltage rather than on single readings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryMonitor {
+    /// The weight of a new reading in the average, between 0.0 and 1.0.
+    alpha: f32,
+    /// The last reading in millivolts, if any.
+    last_mv: Option<u16>,
+    /// The averaged voltage in millivolts, if any reading was taken.
+    average_mv: Option<f32>,
+}
+
+impl BatteryMonitor {
+    /// Creates a new battery monitor.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha` - The weight of a new reading in the average, clamped between 0.0 and 1.0.
+    ///   Smaller values smooth more.
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            last_mv: None,
+            average_mv: None,
+        }
+    }
+
+    /// Updates the monitor with a new reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `millivolts` - The battery voltage read with [`crate::Tsc2046::read_vbat`].
+    pub fn update(&mut self, millivolts: u16) {
+        let reading = millivolts as f32;
+        self.average_mv = Some(match self.average_mv {
+            Some(average) => average + self.alpha * (reading - average),
+            None => reading,
+        });
+        self.last_mv = Some(millivolts);
+    }
+
+    /// Returns the last reading.
+    ///
+    /// # Returns
+    ///
+    /// The last battery voltage in millivolts, or `None` if no reading was taken yet.
+    pub fn instantaneous_mv(&self) -> Option<u16> {
+        self.last_mv
+    }
+
+    /// Returns the averaged voltage.
+    ///
+    /// # Returns
+    ///
+    /// The averaged battery voltage in millivolts, or `None` if no reading was taken yet.
+    pub fn average_mv(&self) -> Option<u16> {
+        self.average_mv.map(|average| (average + 0.5) as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moving_average() {
+        let mut monitor = BatteryMonitor::new(0.25);
+        assert_eq!(monitor.average_mv(), None);
+        monitor.update(3600);
+        assert_eq!(monitor.average_mv(), Some(3600));
+        // A load transient only moves the average by a fraction of the dip.
+        monitor.update(3200);
+        assert_eq!(monitor.instantaneous_mv(), Some(3200));
+        assert_eq!(monitor.average_mv(), Some(3500));
+        monitor.update(3500);
+        assert_eq!(monitor.average_mv(), Some(3500));
+    }
+}
@@ -0,0 +1,689 @@
+use crate::orientation::Orientation;
+use crate::stage::CalibrationStage;
+use crate::{Rect, TouchPoint};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Struct representing a touch point mapped to screen space.
+pub struct ScreenPoint {
+    /// The x-coordinate of the touch point in pixels.
+    pub x: u16,
+    /// The y-coordinate of the touch point in pixels.
+    pub y: u16,
+    /// The pressure value of the touch point, as read from the chip.
+    pub z: f32,
+    /// The position of the touch point in tenths of a millimeter, if the active area is known.
+    pub physical: Option<PhysicalPoint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Struct representing a position on the panel in physical units.
+pub struct PhysicalPoint {
+    /// The x-coordinate from the origin of the active area, in tenths of a millimeter.
+    pub x: u16,
+    /// The y-coordinate from the origin of the active area, in tenths of a millimeter.
+    pub y: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Rounding strategy applied when quantizing mapped coordinates.
+pub enum Rounding {
+    /// Rounds towards zero.
+    #[default]
+    Truncate,
+    /// Rounds to the nearest value, halves up.
+    HalfUp,
+    /// Rounds with an ordered dither threshold derived from the raw coordinates, so slow drags
+    /// move smoothly between pixels on low-resolution displays.
+    Dithered,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Position of the origin of the coordinates returned by [`Calibration::map`].
+pub enum Origin {
+    /// The top left corner of the screen, the usual convention of framebuffers.
+    #[default]
+    TopLeft,
+    /// The top right corner of the screen, for panels mounted mirrored.
+    TopRight,
+    /// The bottom left corner of the screen, the usual convention of OpenGL-style stacks.
+    BottomLeft,
+    /// The bottom right corner of the screen, for displays rotated by half a turn.
+    BottomRight,
+    /// The center of the screen.
+    Center,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Direction in which the x-coordinates returned by [`Calibration::map`] increase.
+pub enum XDirection {
+    /// The x-coordinate increases towards the right of the screen.
+    #[default]
+    Right,
+    /// The x-coordinate increases towards the left of the screen.
+    Left,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Direction in which the y-coordinates returned by [`Calibration::map`] increase.
+pub enum YDirection {
+    /// The y-coordinate increases towards the bottom of the screen.
+    #[default]
+    Down,
+    /// The y-coordinate increases towards the top of the screen.
+    Up,
+}
+
+/// The version of the calibration records written by [`Calibration::to_bytes`].
+///
+/// Version 1 holds the raw edges and the screen size, version 2 appends the active area and the
+/// rounding mode, and version 3 the swap of the raw axes.
+pub const CALIBRATION_FORMAT_VERSION: u8 = 3;
+/// The length of a calibration record in bytes.
+pub const CALIBRATION_RECORD_LEN: usize = HEADER_LEN + PAYLOAD_LEN;
+/// The marker opening every calibration record.
+const CALIBRATION_MAGIC: [u8; 2] = *b"TC";
+/// The length of the record header: the marker, the version and the payload length.
+const HEADER_LEN: usize = 4;
+/// The length of the payload of the current version.
+const PAYLOAD_LEN: usize = 19;
+/// The length of the payload of version 1: the raw edges and the screen size.
+const V1_PAYLOAD_LEN: usize = 12;
+/// The length of the payload of version 2, adding the active area and the rounding mode.
+const V2_PAYLOAD_LEN: usize = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Error loading a calibration record.
+pub enum CalibrationFormatError {
+    /// The record does not start with the calibration marker.
+    BadMarker,
+    /// The version of the record is not a valid one.
+    UnsupportedVersion(u8),
+    /// The record is shorter than its header claims, or misses mandatory fields.
+    Truncated,
+}
+
+/// 4x4 Bayer matrix of ordered dither thresholds, in sixteenths.
+const DITHER_MATRIX: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Linear mapping from raw touch coordinates to screen coordinates.
+///
+/// The calibration holds the whole [`Orientation`] of the panel: the raw values read at the
+/// left/right and top/bottom edges of the screen can be given in any order, so mirrored axes are
+/// handled by swapping the corresponding minimum and maximum, and rotated panels by swapping the
+/// raw axes. The mapped coordinates then follow the [`Origin`] and directions of the graphics
+/// stack.
+pub struct Calibration {
+    /// Whether the raw X and Y axes are swapped before the mapping, the raw Y axis running along
+    /// the screen width.
+    swap_xy: bool,
+    /// The raw x value at the left edge of the screen.
+    x_min: u16,
+    /// The raw x value at the right edge of the screen.
+    x_max: u16,
+    /// The raw y value at the top edge of the screen.
+    y_min: u16,
+    /// The raw y value at the bottom edge of the screen.
+    y_max: u16,
+    /// The width of the screen in pixels.
+    width: u16,
+    /// The height of the screen in pixels.
+    height: u16,
+    /// The width and height of the panel active area in tenths of a millimeter.
+    active_area: Option<(u16, u16)>,
+    /// The rounding strategy of the mapped coordinates.
+    rounding: Rounding,
+    /// The origin of the mapped coordinates.
+    origin: Origin,
+    /// The direction of the mapped x-coordinates.
+    x_direction: XDirection,
+    /// The direction of the mapped y-coordinates.
+    y_direction: YDirection,
+}
+
+impl Calibration {
+    /// Creates a new calibration from the raw values read at the edges of the screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `x_min` - The raw x value at the left edge of the screen.
+    /// * `x_max` - The raw x value at the right edge of the screen.
+    /// * `y_min` - The raw y value at the top edge of the screen.
+    /// * `y_max` - The raw y value at the bottom edge of the screen.
+    /// * `width` - The width of the screen in pixels.
+    /// * `height` - The height of the screen in pixels.
+    pub fn new(x_min: u16, x_max: u16, y_min: u16, y_max: u16, width: u16, height: u16) -> Self {
+        Self {
+            swap_xy: false,
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+            width,
+            height,
+            active_area: None,
+            rounding: Rounding::Truncate,
+            origin: Origin::TopLeft,
+            x_direction: XDirection::Right,
+            y_direction: YDirection::Down,
+        }
+    }
+
+    /// Sets the dimensions of the panel active area, enabling physical coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the active area in tenths of a millimeter.
+    /// * `height` - The height of the active area in tenths of a millimeter.
+    pub fn with_active_area(mut self, width: u16, height: u16) -> Self {
+        self.active_area = Some((width, height));
+        self
+    }
+
+    /// Sets the rounding strategy of the mapped coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `rounding` - The rounding strategy.
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Sets the orientation of the panel, as detected by [`crate::Tsc2046::detect_orientation`].
+    ///
+    /// The raw edges are reordered to match the inverted axes, so the edges given to
+    /// [`Calibration::new`] then refer to the raw axes after the swap.
+    ///
+    /// # Arguments
+    ///
+    /// * `orientation` - The orientation of the panel.
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.swap_xy = orientation.swap_xy;
+        if orientation.invert_x != (self.x_min > self.x_max) {
+            core::mem::swap(&mut self.x_min, &mut self.x_max);
+        }
+        if orientation.invert_y != (self.y_min > self.y_max) {
+            core::mem::swap(&mut self.y_min, &mut self.y_max);
+        }
+        self
+    }
+
+    /// Returns the orientation of the panel held by the calibration.
+    pub fn orientation(&self) -> Orientation {
+        Orientation {
+            swap_xy: self.swap_xy,
+            invert_x: self.x_min > self.x_max,
+            invert_y: self.y_min > self.y_max,
+        }
+    }
+
+    /// Returns the raw bounds of the screen, after the swap of the raw axes.
+    ///
+    /// # Returns
+    ///
+    /// The `Rect` between the raw edges of the screen, whatever their order.
+    pub fn raw_bounds(&self) -> Rect {
+        Rect {
+            x_min: self.x_min.min(self.x_max),
+            y_min: self.y_min.min(self.y_max),
+            x_max: self.x_min.max(self.x_max),
+            y_max: self.y_min.max(self.y_max),
+        }
+    }
+
+    /// Sets the coordinate convention of the mapped coordinates, to match the graphics stack.
+    /// The convention is not part of the calibration records.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The position of the origin.
+    /// * `x_direction` - The direction in which the x-coordinate increases.
+    /// * `y_direction` - The direction in which the y-coordinate increases.
+    pub fn with_origin(
+        mut self,
+        origin: Origin,
+        x_direction: XDirection,
+        y_direction: YDirection,
+    ) -> Self {
+        self.origin = origin;
+        self.x_direction = x_direction;
+        self.y_direction = y_direction;
+        self
+    }
+
+    /// Serializes the calibration into a versioned record, for persistent storage.
+    ///
+    /// The record starts with a marker, the format version and the payload length. The payload
+    /// holds the fields in little-endian order, and later versions only append fields to it.
+    ///
+    /// # Returns
+    ///
+    /// The record of [`CALIBRATION_FORMAT_VERSION`].
+    pub fn to_bytes(&self) -> [u8; CALIBRATION_RECORD_LEN] {
+        let mut record = [0_u8; CALIBRATION_RECORD_LEN];
+        let (area_width, area_height) = self.active_area.unwrap_or_default();
+        let fields = [
+            self.x_min,
+            self.x_max,
+            self.y_min,
+            self.y_max,
+            self.width,
+            self.height,
+            area_width,
+            area_height,
+        ];
+        let header = [
+            CALIBRATION_MAGIC[0],
+            CALIBRATION_MAGIC[1],
+            CALIBRATION_FORMAT_VERSION,
+            PAYLOAD_LEN as u8,
+        ];
+        let rounding = match self.rounding {
+            Rounding::Truncate => 0,
+            Rounding::HalfUp => 1,
+            Rounding::Dithered => 2,
+        };
+        let tail = [
+            self.active_area.is_some() as u8,
+            rounding,
+            self.swap_xy as u8,
+        ];
+        record
+            .iter_mut()
+            .zip(
+                header
+                    .into_iter()
+                    .chain(fields.into_iter().flat_map(u16::to_le_bytes))
+                    .chain(tail),
+            )
+            .for_each(|(byte, value)| *byte = value);
+        record
+    }
+
+    /// Loads a calibration from a record written by [`Calibration::to_bytes`].
+    ///
+    /// The layout of the payload is chosen from the version byte. Records of older versions are
+    /// migrated: the fields they lack take their default values, as with [`Calibration::new`].
+    /// Records of newer versions are loaded too, ignoring the fields appended since this version,
+    /// so a firmware downgrade keeps the calibration. A payload shorter than its version requires
+    /// is rejected rather than partially loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The record, possibly followed by unrelated bytes.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the calibration, or the reason the record cannot be loaded.
+    pub fn from_bytes(record: &[u8]) -> Result<Self, CalibrationFormatError> {
+        let Some(&[magic0, magic1, version, payload_len]) = record.get(..HEADER_LEN) else {
+            return Err(CalibrationFormatError::Truncated);
+        };
+        if [magic0, magic1] != CALIBRATION_MAGIC {
+            return Err(CalibrationFormatError::BadMarker);
+        }
+        let required_len = match version {
+            0 => return Err(CalibrationFormatError::UnsupportedVersion(version)),
+            1 => V1_PAYLOAD_LEN,
+            2 => V2_PAYLOAD_LEN,
+            _ => PAYLOAD_LEN,
+        };
+        let payload = record
+            .get(HEADER_LEN..HEADER_LEN.saturating_add(payload_len as usize))
+            .filter(|payload| payload.len() >= required_len)
+            .ok_or(CalibrationFormatError::Truncated)?;
+        let field = |index: usize| {
+            payload
+                .get(index.saturating_mul(2)..index.saturating_mul(2).saturating_add(2))
+                .and_then(|bytes| <[u8; 2]>::try_from(bytes).ok())
+                .map(u16::from_le_bytes)
+                .ok_or(CalibrationFormatError::Truncated)
+        };
+        let mut calibration = Self::new(
+            field(0)?,
+            field(1)?,
+            field(2)?,
+            field(3)?,
+            field(4)?,
+            field(5)?,
+        );
+        if version == 1 {
+            return Ok(calibration);
+        }
+        if payload.get(16) == Some(&1) {
+            calibration.active_area = Some((field(6)?, field(7)?));
+        }
+        calibration.rounding = match payload.get(17) {
+            Some(1) => Rounding::HalfUp,
+            Some(2) => Rounding::Dithered,
+            _ => Rounding::Truncate,
+        };
+        if version == 2 {
+            return Ok(calibration);
+        }
+        calibration.swap_xy = payload.get(18) == Some(&1);
+        Ok(calibration)
+    }
+
+    /// Maps a touch point to screen space, in the configured coordinate convention.
+    ///
+    /// The coordinates are unsigned, so the positions on the negative side of the origin, such
+    /// as half of the screen with [`Origin::Center`], are clamped to 0. Use
+    /// [`Calibration::map_to_origin`] for signed coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point read from the chip.
+    ///
+    /// # Returns
+    ///
+    /// The `ScreenPoint` with coordinates clamped to the screen, and physical coordinates if the
+    /// active area is configured. The mapping cannot overflow, whatever the raw values and the
+    /// calibration.
+    pub fn map(&self, point: &TouchPoint) -> ScreenPoint {
+        let (x, y, physical) = self.map_signed(point);
+        let unsigned = |value: i32| value.clamp(0, u16::MAX as i32) as u16;
+        ScreenPoint {
+            x: unsigned(x),
+            y: unsigned(y),
+            z: point.z,
+            physical: physical.map(|(x, y)| PhysicalPoint {
+                x: unsigned(x),
+                y: unsigned(y),
+            }),
+        }
+    }
+
+    /// Maps a touch point to screen space, in the configured coordinate convention.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point read from the chip.
+    ///
+    /// # Returns
+    ///
+    /// The x and y pixel coordinates relative to the configured origin, negative left of or
+    /// against the y direction from the origin.
+    pub fn map_to_origin(&self, point: &TouchPoint) -> (i32, i32) {
+        let (x, y, _) = self.map_signed(point);
+        (x, y)
+    }
+
+    /// Maps a touch point to signed pixel and physical coordinates in the configured convention.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point read from the chip.
+    ///
+    /// # Returns
+    ///
+    /// The x and y pixel coordinates, and the physical coordinates if the active area is known.
+    fn map_signed(&self, point: &TouchPoint) -> (i32, i32, Option<(i32, i32)>) {
+        let point = if self.swap_xy {
+            TouchPoint {
+                x: point.y,
+                y: point.x,
+                z: point.z,
+            }
+        } else {
+            *point
+        };
+        let bias = match self.rounding {
+            Rounding::Truncate => 0,
+            Rounding::HalfUp => 8,
+            Rounding::Dithered => DITHER_MATRIX
+                .get(point.y as usize % 4)
+                .and_then(|row| row.get(point.x as usize % 4))
+                .copied()
+                .unwrap_or(0),
+        };
+        let physical = self.active_area.map(|(width, height)| {
+            self.orient(
+                scale(point.x, self.x_min, self.x_max, width, bias),
+                scale(point.y, self.y_min, self.y_max, height, bias),
+                width,
+                height,
+            )
+        });
+        let width = self.width.saturating_sub(1);
+        let height = self.height.saturating_sub(1);
+        let (x, y) = self.orient(
+            scale(point.x, self.x_min, self.x_max, width, bias),
+            scale(point.y, self.y_min, self.y_max, height, bias),
+            width,
+            height,
+        );
+        (x, y, physical)
+    }
+
+    /// Moves coordinates from the top left corner, increasing right and down, to the configured
+    /// coordinate convention.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate from the left edge.
+    /// * `y` - The y-coordinate from the top edge.
+    /// * `width` - The largest x-coordinate.
+    /// * `height` - The largest y-coordinate.
+    ///
+    /// # Returns
+    ///
+    /// The x and y coordinates relative to the origin, along the configured directions.
+    fn orient(&self, x: u16, y: u16, width: u16, height: u16) -> (i32, i32) {
+        let (origin_x, origin_y) = match self.origin {
+            Origin::TopLeft => (0, 0),
+            Origin::TopRight => (width, 0),
+            Origin::BottomLeft => (0, height),
+            Origin::BottomRight => (width, height),
+            Origin::Center => (width / 2, height / 2),
+        };
+        let x = match self.x_direction {
+            XDirection::Right => (x as i32).saturating_sub(origin_x as i32),
+            XDirection::Left => (origin_x as i32).saturating_sub(x as i32),
+        };
+        let y = match self.y_direction {
+            YDirection::Down => (y as i32).saturating_sub(origin_y as i32),
+            YDirection::Up => (origin_y as i32).saturating_sub(y as i32),
+        };
+        (x, y)
+    }
+}
+
+/// Linearly maps a raw value from the `from..=to` range to the `0..=extent` range.
+///
+/// The `bias` in sixteenths is added before rounding down, so 0 truncates and 8 rounds half up.
+/// The intermediate results are computed on 64 bits, which cannot overflow for any input, so
+/// the saturating operations never saturate.
+fn scale(raw: u16, from: u16, to: u16, extent: u16, bias: u8) -> u16 {
+    let mut span = (to as i64).saturating_sub(from as i64);
+    let mut numerator = (raw as i64)
+        .saturating_sub(from as i64)
+        .saturating_mul(extent as i64);
+    if span < 0 {
+        span = span.saturating_neg();
+        numerator = numerator.saturating_neg();
+    }
+    let scaled = numerator
+        .saturating_mul(16)
+        .saturating_add((bias as i64).saturating_mul(span))
+        .checked_div_euclid(span.saturating_mul(16));
+    // An empty range maps everything to 0.
+    scaled.map_or(0, |scaled| scaled.clamp(0, extent as i64) as u16)
+}
+
+impl CalibrationStage for Calibration {
+    fn calibrate(&self, point: TouchPoint) -> TouchPoint {
+        let screen_point = self.map(&point);
+        TouchPoint {
+            x: screen_point.x,
+            y: screen_point.y,
+            z: screen_point.z,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: u16, y: u16) -> TouchPoint {
+        TouchPoint { x, y, z: 10.0 }
+    }
+
+    #[test]
+    fn test_map_extreme_values() {
+        let calibration = Calibration::new(0, u16::MAX, u16::MAX, 0, u16::MAX, u16::MAX)
+            .with_rounding(Rounding::HalfUp);
+        let screen_point = calibration.map(&point(u16::MAX, u16::MAX));
+        assert_eq!((screen_point.x, screen_point.y), (u16::MAX - 1, 0));
+    }
+
+    #[test]
+    fn test_map_to_pixels() {
+        let calibration = Calibration::new(200, 3900, 300, 3800, 321, 241);
+        let screen_point = calibration.map(&point(2050, 2050));
+        assert_eq!((screen_point.x, screen_point.y), (160, 120));
+        assert_eq!(screen_point.physical, None);
+        let clamped_point = calibration.map(&point(100, 4000));
+        assert_eq!((clamped_point.x, clamped_point.y), (0, 240));
+    }
+
+    #[test]
+    fn test_map_mirrored_with_physical_units() {
+        let calibration =
+            Calibration::new(3900, 200, 300, 3800, 321, 241).with_active_area(700, 520);
+        let screen_point = calibration.map(&point(3900, 3800));
+        assert_eq!((screen_point.x, screen_point.y), (0, 240));
+        assert_eq!(screen_point.physical, Some(PhysicalPoint { x: 0, y: 520 }));
+    }
+
+    #[test]
+    fn test_rounding_strategies() {
+        // 4096 raw counts over 10 pixels, 3072 maps to 7.5 pixels.
+        let calibration = Calibration::new(0, 4096, 0, 4096, 11, 11);
+        let truncated = calibration.map(&point(3072, 3072));
+        assert_eq!(truncated.x, 7);
+        let rounded = calibration
+            .with_rounding(Rounding::HalfUp)
+            .map(&point(3072, 3072));
+        assert_eq!(rounded.x, 8);
+        let dithered = calibration.with_rounding(Rounding::Dithered);
+        let xs: Vec<u16> = (3072..3076)
+            .map(|x| dithered.map(&point(x, 3072)).x)
+            .collect();
+        assert_eq!(xs, [7, 8, 7, 8]);
+    }
+
+    #[test]
+    fn test_map_to_origin() {
+        let calibration = Calibration::new(0, 4095, 0, 4095, 321, 241);
+        let corner = point(0, 4095);
+        assert_eq!(calibration.map_to_origin(&corner), (0, 240));
+        let calibration =
+            calibration.with_origin(Origin::BottomLeft, XDirection::Right, YDirection::Up);
+        assert_eq!(calibration.map_to_origin(&corner), (0, 0));
+        let top_right = calibration.map(&point(4095, 0));
+        assert_eq!((top_right.x, top_right.y), (320, 240));
+        let calibration =
+            calibration.with_origin(Origin::Center, XDirection::Right, YDirection::Up);
+        assert_eq!(calibration.map_to_origin(&corner), (-160, -120));
+        assert_eq!(calibration.map_to_origin(&point(4095, 0)), (160, 120));
+        // The negative side of the origin is clamped by the unsigned mapping.
+        let screen_point = calibration.map(&corner);
+        assert_eq!((screen_point.x, screen_point.y), (0, 0));
+        let calibration = calibration.with_origin(Origin::Center, XDirection::Left, YDirection::Up);
+        assert_eq!(calibration.map_to_origin(&corner), (160, -120));
+        let rotated = Calibration::new(0, 4095, 0, 4095, 321, 241)
+            .with_active_area(1000, 750)
+            .with_origin(Origin::BottomRight, XDirection::Left, YDirection::Up);
+        let screen_point = rotated.map(&point(4095, 4095));
+        assert_eq!((screen_point.x, screen_point.y), (0, 0));
+        assert_eq!(screen_point.physical, Some(PhysicalPoint { x: 0, y: 0 }));
+        let screen_point = rotated.map(&point(0, 0));
+        assert_eq!((screen_point.x, screen_point.y), (320, 240));
+        assert_eq!(
+            screen_point.physical,
+            Some(PhysicalPoint { x: 1000, y: 750 })
+        );
+    }
+
+    #[test]
+    fn test_orientation() {
+        let orientation = Orientation {
+            swap_xy: true,
+            invert_x: false,
+            invert_y: true,
+        };
+        let calibration =
+            Calibration::new(0, 4095, 0, 4095, 321, 241).with_orientation(orientation);
+        assert_eq!(calibration.orientation(), orientation);
+        // The raw Y axis runs along the width, the raw X axis from the bottom to the top.
+        let top_left = calibration.map(&point(4095, 0));
+        assert_eq!((top_left.x, top_left.y), (0, 0));
+        let bottom_right = calibration.map(&point(0, 4095));
+        assert_eq!((bottom_right.x, bottom_right.y), (320, 240));
+        // The edges measured by a calibration routine already encode the inversion.
+        let measured = Calibration::new(200, 3900, 3800, 300, 321, 241);
+        assert_eq!(
+            measured.orientation(),
+            Orientation {
+                swap_xy: false,
+                invert_x: false,
+                invert_y: true,
+            }
+        );
+        assert_eq!(measured.with_orientation(measured.orientation()), measured);
+    }
+
+    #[test]
+    fn test_record_versions() {
+        let calibration = Calibration::new(200, 3900, 3800, 300, 320, 240)
+            .with_active_area(1000, 750)
+            .with_rounding(Rounding::Dithered);
+        let record = calibration.to_bytes();
+        assert_eq!(Calibration::from_bytes(&record), Ok(calibration));
+
+        // A version 1 record with the base fields only.
+        let mut old_record = record;
+        old_record[2] = 1;
+        old_record[3] = V1_PAYLOAD_LEN as u8;
+        let old_record = &old_record[..HEADER_LEN + V1_PAYLOAD_LEN];
+        assert_eq!(
+            Calibration::from_bytes(old_record),
+            Ok(Calibration::new(200, 3900, 3800, 300, 320, 240))
+        );
+        // A version 2 record without the swap of the raw axes.
+        let swapped = calibration.with_orientation(Orientation {
+            swap_xy: true,
+            ..calibration.orientation()
+        });
+        let mut v2_record = swapped.to_bytes();
+        v2_record[2] = 2;
+        v2_record[3] = V2_PAYLOAD_LEN as u8;
+        assert_eq!(Calibration::from_bytes(&v2_record), Ok(calibration));
+        assert_eq!(Calibration::from_bytes(&swapped.to_bytes()), Ok(swapped));
+        // A version 3 record cut down to the base fields is not migrated silently.
+        let mut short_record = record;
+        short_record[3] = V1_PAYLOAD_LEN as u8;
+        assert_eq!(
+            Calibration::from_bytes(&short_record),
+            Err(CalibrationFormatError::Truncated)
+        );
+        // A newer record with an appended field.
+        let mut new_record = [0_u8; CALIBRATION_RECORD_LEN + 2];
+        new_record[..CALIBRATION_RECORD_LEN].copy_from_slice(&record);
+        new_record[2] = CALIBRATION_FORMAT_VERSION + 1;
+        new_record[3] = PAYLOAD_LEN as u8 + 2;
+        assert_eq!(Calibration::from_bytes(&new_record), Ok(calibration));
+
+        assert_eq!(
+            Calibration::from_bytes(&record[..10]),
+            Err(CalibrationFormatError::Truncated)
+        );
+        assert_eq!(
+            Calibration::from_bytes(&[0xFF; CALIBRATION_RECORD_LEN]),
+            Err(CalibrationFormatError::BadMarker)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+#[cfg(feature = "trace")]
+use crate::trace::{MAX_TRACE_CAPACITY, TRACE_CAPACITY};
+#[cfg(feature = "aux")]
+use crate::AuxScaling;
+use crate::{
+    AcquisitionMode, ArithmeticMode, AxisRange, ConfigError, MeasurementMode, OutOfRangePolicy,
+    PartialSamplePolicy, Preset, PressureSemantics, Rect, ScanOrder, ThresholdCompensation,
+    ZeroPressurePolicy, DEFAULT_REFERENCE_WARMUP_US, DEFAULT_TOUCH_THRESHOLD, MAX_IGNORE_REGIONS,
+    MAX_OVERSAMPLING, MAX_PADDING_BYTES,
+};
+
+/// The largest Z1 conversion of an untouched panel in the low-latency preset.
+const LOW_LATENCY_MIN_Z1: u16 = 16;
+
+#[derive(Debug, Clone, Copy)]
+/// Configuration of the `Tsc2046` driver.
+///
+/// Retrieved with [`crate::Tsc2046::config`] and restored with [`crate::Tsc2046::apply_config`],
+/// for example to snapshot the driver before a calibration or factory test and restore it
+/// exactly afterwards.
+pub struct Config {
+    /// Whether the interrupt pin is enabled or not.
+    pub irq_on: bool,
+    /// The minimum pressure value required to register a touch event.
+    pub touch_threshold: f32,
+    /// The number of consecutive low samples required on the PENIRQ pin to register a pen-down
+    /// (a value of 0 is treated as 1).
+    pub irq_filter_samples: u8,
+    /// The delay between two consecutive samples of the PENIRQ pin in microseconds.
+    pub irq_filter_interval_us: u32,
+    /// The number of conversions averaged for every axis, between 1 and
+    /// [`crate::MAX_OVERSAMPLING`].
+    pub oversampling: u16,
+    /// Optional gate deciding whether an acquisition may take place.
+    pub sampling_gate: Option<fn() -> bool>,
+    /// The settling time of the internal reference after power-up, in microseconds.
+    pub reference_warmup_us: u32,
+    /// The conversion of AUX readings into engineering units.
+    #[cfg(feature = "aux")]
+    pub aux_scaling: AuxScaling,
+    /// The number of conversions averaged for every AUX reading, between 1 and
+    /// [`crate::MAX_OVERSAMPLING`].
+    #[cfg(feature = "aux")]
+    pub aux_oversampling: u16,
+    /// The acquisition mode of the AUX readings.
+    #[cfg(feature = "aux")]
+    pub aux_acquisition: AcquisitionMode,
+    /// The optional temperature compensation of the touch threshold.
+    pub threshold_compensation: Option<ThresholdCompensation>,
+    /// Optional observer called with every control byte sent and the decoded result.
+    pub observer: Option<fn(u8, u16)>,
+    /// The mode of the X, Y and pressure measurements.
+    pub measurement_mode: MeasurementMode,
+    /// Whether to refresh the control register and retry when a sample looks anomalous.
+    pub auto_refresh: bool,
+    /// The number of dummy bytes clocked at the end of every frame.
+    pub padding_bytes: u8,
+    /// What to do with the position when a pressure conversion fails.
+    pub partial_samples: PartialSamplePolicy,
+    /// What to do when the Z1 pressure channel reads zero.
+    pub zero_pressure: ZeroPressurePolicy,
+    /// The behavior of the pressure calculation on out-of-range results.
+    pub arithmetic: ArithmeticMode,
+    /// The acquisition mode of the X and Y positions.
+    pub acquisition: AcquisitionMode,
+    /// Whether the oversampled conversions of an axis are issued back-to-back in a single
+    /// transaction.
+    pub burst_oversampling: bool,
+    /// The domain of the reported pressure and of the touch threshold.
+    pub pressure_semantics: PressureSemantics,
+    /// The plausible range of the raw X conversions, if restricted.
+    pub x_range: Option<AxisRange>,
+    /// The plausible range of the raw Y conversions, if restricted.
+    pub y_range: Option<AxisRange>,
+    /// What to do with positions outside of the axis ranges.
+    pub out_of_range: OutOfRangePolicy,
+    /// The raw areas in which touches are ignored.
+    pub ignore_regions: [Option<Rect>; MAX_IGNORE_REGIONS],
+    /// The raw area of the panel over the display, if restricted.
+    pub active_area: Option<Rect>,
+    /// The order of the conversions of a scan.
+    pub scan_order: ScanOrder,
+    /// Whether every conversion is its own SPI transaction, so other devices on the bus can be
+    /// served between the conversions of a scan or of a full read.
+    pub interleaved: bool,
+    /// The number of scans after which the control register is sent again, 0 to disable.
+    pub refresh_interval: u16,
+    /// The number of SPI exchanges kept in the trace buffer, between 1 and
+    /// [`crate::trace::MAX_TRACE_CAPACITY`].
+    #[cfg(feature = "trace")]
+    pub trace_capacity: usize,
+}
+
+impl From<Preset> for Config {
+    /// Returns the configuration of a preset, the other settings keeping their default.
+    fn from(preset: Preset) -> Self {
+        match preset {
+            Preset::LowLatency => Self {
+                oversampling: 1,
+                acquisition: AcquisitionMode::Averaging,
+                scan_order: ScanOrder::PressureFirst {
+                    min_z1: LOW_LATENCY_MIN_Z1,
+                    with_z2: false,
+                },
+                ..Self::default()
+            },
+            Preset::Balanced => Self::default(),
+            Preset::LowNoise => Self {
+                irq_filter_samples: 3,
+                irq_filter_interval_us: 100,
+                oversampling: 16,
+                acquisition: AcquisitionMode::Averaging,
+                burst_oversampling: true,
+                auto_refresh: true,
+                ..Self::default()
+            },
+        }
+    }
+}
+
+impl Config {
+    /// Creates a configuration giving a working touch input on most panels, usable in constants.
+    ///
+    /// The interrupt pin is enabled, the conversions have 12 bits, and the X and Y positions are
+    /// the median of three conversions, which rejects single glitches. The touch threshold is a
+    /// touch resistance of twice the X-plate resistance.
+    pub const fn new() -> Self {
+        Self {
+            irq_on: true,
+            touch_threshold: DEFAULT_TOUCH_THRESHOLD,
+            irq_filter_samples: 1,
+            irq_filter_interval_us: 0,
+            oversampling: 1,
+            sampling_gate: None,
+            reference_warmup_us: DEFAULT_REFERENCE_WARMUP_US,
+            #[cfg(feature = "aux")]
+            aux_scaling: AuxScaling::Raw,
+            #[cfg(feature = "aux")]
+            aux_oversampling: 1,
+            #[cfg(feature = "aux")]
+            aux_acquisition: AcquisitionMode::Averaging,
+            threshold_compensation: None,
+            observer: None,
+            measurement_mode: MeasurementMode::Differential,
+            auto_refresh: false,
+            padding_bytes: 0,
+            partial_samples: PartialSamplePolicy::Discard,
+            zero_pressure: ZeroPressurePolicy::NoTouch,
+            arithmetic: ArithmeticMode::Saturating,
+            acquisition: AcquisitionMode::Median,
+            burst_oversampling: false,
+            pressure_semantics: PressureSemantics::RawRatio,
+            x_range: None,
+            y_range: None,
+            out_of_range: OutOfRangePolicy::Reject,
+            ignore_regions: [None; MAX_IGNORE_REGIONS],
+            active_area: None,
+            scan_order: ScanOrder::PositionFirst,
+            interleaved: false,
+            refresh_interval: 0,
+            #[cfg(feature = "trace")]
+            trace_capacity: TRACE_CAPACITY,
+        }
+    }
+
+    /// Checks the configuration for inconsistencies the driver would otherwise silently correct
+    /// or misbehave on.
+    ///
+    /// Being a `const fn`, it fails the build when asserted on a configuration constant, as in
+    /// `const _: () = assert!(CONFIG.validate().is_ok());`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the configuration is valid, or the first [`ConfigError`]
+    /// found.
+    pub const fn validate(&self) -> Result<(), ConfigError> {
+        if !self.touch_threshold.is_finite() || self.touch_threshold <= 0.0 {
+            return Err(ConfigError::InvalidThreshold);
+        }
+        if !is_sample_count(self.oversampling) || self.irq_filter_samples == 0 {
+            return Err(ConfigError::InvalidSampleCount);
+        }
+        #[cfg(feature = "aux")]
+        if !is_sample_count(self.aux_oversampling) {
+            return Err(ConfigError::InvalidSampleCount);
+        }
+        #[cfg(feature = "trace")]
+        if self.trace_capacity == 0 || self.trace_capacity > MAX_TRACE_CAPACITY {
+            return Err(ConfigError::InvalidTraceCapacity);
+        }
+        if self.padding_bytes as usize > MAX_PADDING_BYTES {
+            return Err(ConfigError::TooManyPaddingBytes);
+        }
+        if !is_range_ordered(&self.x_range) || !is_range_ordered(&self.y_range) {
+            return Err(ConfigError::InvertedRange);
+        }
+        if let Some(area) = &self.active_area {
+            if !area.is_ordered() {
+                return Err(ConfigError::InvertedRange);
+            }
+        }
+        let mut regions = self.ignore_regions.as_slice();
+        while let [region, rest @ ..] = regions {
+            if let Some(region) = region {
+                if !region.is_ordered() {
+                    return Err(ConfigError::InvertedRange);
+                }
+            }
+            regions = rest;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Checks whether a sample count is between 1 and [`MAX_OVERSAMPLING`].
+const fn is_sample_count(samples: u16) -> bool {
+    samples >= 1 && samples <= MAX_OVERSAMPLING
+}
+
+/// Checks whether the minimum of an optional range is not above its maximum.
+const fn is_range_ordered(range: &Option<AxisRange>) -> bool {
+    match range {
+        Some(range) => range.min <= range.max,
+        None => true,
+    }
+}
@@ -0,0 +1,105 @@
+use core::fmt::{Result, Write};
+
+use crate::event::TouchEvent;
+use crate::TouchPoint;
+
+/// The header line of the sample log.
+pub const SAMPLE_HEADER: &str = "timestamp_ms,x,y,z";
+/// The header line of the event log.
+pub const EVENT_HEADER: &str = "timestamp_ms,kind,x,y,z,band";
+
+/// Writes a sample as a CSV line, in the columns of [`SAMPLE_HEADER`].
+///
+/// A released panel is written with empty position and pressure fields, so the gaps show up in
+/// the plot.
+///
+/// # Arguments
+///
+/// * `out` - The sink of the line, like a UART writer.
+/// * `timestamp_ms` - The timestamp of the sample, in milliseconds.
+/// * `sample` - The touch point read, or `None` if the panel was not touched.
+///
+/// # Returns
+///
+/// The result of the writes to the sink.
+pub fn write_sample<W: Write>(
+    out: &mut W,
+    timestamp_ms: u32,
+    sample: Option<&TouchPoint>,
+) -> Result {
+    match sample {
+        Some(point) => writeln!(out, "{},{},{},{}", timestamp_ms, point.x, point.y, point.z),
+        None => writeln!(out, "{},,,", timestamp_ms),
+    }
+}
+
+/// Writes an event as a CSV line, in the columns of [`EVENT_HEADER`].
+///
+/// The band field is empty if pressure bands are not configured.
+///
+/// # Arguments
+///
+/// * `out` - The sink of the line, like a UART writer.
+/// * `event` - The event to write.
+///
+/// # Returns
+///
+/// The result of the writes to the sink.
+pub fn write_event<W: Write>(out: &mut W, event: &TouchEvent) -> Result {
+    write!(
+        out,
+        "{},{:?},{},{},{},",
+        event.timestamp_ms, event.kind, event.point.x, event.point.y, event.point.z
+    )?;
+    match event.band {
+        Some(band) => writeln!(out, "{:?}", band),
+        None => writeln!(out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventKind;
+    use crate::pressure::PressureBand;
+
+    #[test]
+    fn test_csv_lines() {
+        let point = TouchPoint {
+            x: 1200,
+            y: 3400,
+            z: 12.5,
+        };
+        let mut log = String::new();
+        writeln!(log, "{}", SAMPLE_HEADER).expect("Could not write");
+        write_sample(&mut log, 10, Some(&point)).expect("Could not write");
+        write_sample(&mut log, 20, None).expect("Could not write");
+        assert_eq!(log, "timestamp_ms,x,y,z\n10,1200,3400,12.5\n20,,,\n");
+
+        let mut log = String::new();
+        write_event(
+            &mut log,
+            &TouchEvent {
+                kind: EventKind::LevelPressed(1),
+                point,
+                band: Some(PressureBand::Firm),
+                timestamp_ms: 30,
+            },
+        )
+        .expect("Could not write");
+        write_event(
+            &mut log,
+            &TouchEvent {
+                kind: EventKind::Up,
+                point,
+                band: None,
+                timestamp_ms: 40,
+            },
+        )
+        .expect("Could not write");
+        assert_eq!(
+            log,
+            "30,LevelPressed(1),1200,3400,12.5,Firm\n40,Up,1200,3400,12.5,\n"
+        );
+    }
+}
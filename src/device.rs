@@ -0,0 +1,447 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, InputPin, OutputPin};
+use embedded_hal::spi::{self, ErrorKind, ErrorType, Operation, SpiBus, SpiDevice};
+
+use crate::{
+    Config, Error, NoPin, OptionalInputPin, Tsc2046, BUSY_POLL_INTERVAL_US, BUSY_TIMEOUT_US,
+};
+
+/// Error of the wait for the BUSY output of the chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyError {
+    /// Reading the BUSY pin failed.
+    Pin(digital::ErrorKind),
+    /// The BUSY output did not go low within the timeout.
+    Timeout,
+}
+
+/// Error of a [`CsDevice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsDeviceError<BUS, CS> {
+    /// The SPI bus reported an error.
+    Bus(BUS),
+    /// Driving the chip select pin failed.
+    ChipSelect(CS),
+    /// Waiting for the end of a conversion on the BUSY pin failed.
+    Busy(BusyError),
+}
+
+impl<BUS, CS> spi::Error for CsDeviceError<BUS, CS>
+where
+    BUS: spi::Error,
+    CS: core::fmt::Debug,
+{
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Bus(error) => error.kind(),
+            Self::ChipSelect(_) => ErrorKind::ChipSelectFault,
+            Self::Busy(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Error of a driver on a [`CsDevice`].
+type DriverError<BUS, CS> = Error<
+    CsDeviceError<<BUS as ErrorType>::Error, <CS as embedded_hal::digital::ErrorType>::Error>,
+>;
+
+/// Frames the operations of a transaction with the chip select pin (active low).
+///
+/// The chip select is deasserted even if the operations fail, and the error of the operations is
+/// reported first.
+///
+/// # Arguments
+///
+/// * `cs` - The chip select pin.
+/// * `select` - The function driving the chip select pin, low to assert it.
+/// * `execute` - The function executing the operations of the transaction.
+///
+/// # Returns
+///
+/// A `Result` indicating whether the transaction succeeded.
+pub(crate) fn framed<CS, E>(
+    cs: &mut CS,
+    select: impl Fn(&mut CS, bool) -> Result<(), E>,
+    execute: impl FnOnce() -> Result<(), E>,
+) -> Result<(), E> {
+    select(cs, false)?;
+    let result = execute();
+    let deasserted = select(cs, true);
+    result?;
+    deasserted
+}
+
+/// Waits for the BUSY output of the chip to go low, between the command and the result of a
+/// conversion.
+///
+/// The pin is polled every [`BUSY_POLL_INTERVAL_US`], for up to [`BUSY_TIMEOUT_US`].
+///
+/// # Arguments
+///
+/// * `level` - The function reading the BUSY pin, `None` if no pin is connected.
+/// * `pause_us` - The function pausing between two polls, in microseconds.
+///
+/// # Returns
+///
+/// A `Result` indicating whether the conversion ended in time.
+pub(crate) fn wait_while_busy(
+    mut level: impl FnMut() -> Result<Option<bool>, digital::ErrorKind>,
+    mut pause_us: impl FnMut(u32),
+) -> Result<(), BusyError> {
+    let mut waited_us = 0_u32;
+    while level().map_err(BusyError::Pin)? == Some(true) {
+        if waited_us >= BUSY_TIMEOUT_US {
+            return Err(BusyError::Timeout);
+        }
+        pause_us(BUSY_POLL_INTERVAL_US);
+        waited_us = waited_us.saturating_add(BUSY_POLL_INTERVAL_US);
+    }
+    Ok(())
+}
+
+/// An [`SpiDevice`] built from an [`SpiBus`], a chip select pin and a delay provider.
+///
+/// Owns the SPI bus exclusively, and asserts the chip select pin (active low) around each
+/// transaction, so every conversion is framed like the chip expects. The delay provider serves
+/// the delays requested within the transactions, such as the warm-up of the internal reference,
+/// and paces the polls of the BUSY pin, if the device owns one.
+pub struct CsDevice<BUS, CS, D, BUSY = NoPin> {
+    /// The SPI bus, used only while the chip select is asserted.
+    bus: BUS,
+    /// The chip select pin of the TSC2046, active low.
+    cs: CS,
+    /// The delay performing the `DelayNs` operations of the transactions.
+    delay: D,
+    /// The pin connected to the BUSY output of the chip, if owned by the device.
+    busy: BUSY,
+}
+
+impl<BUS, CS, D> CsDevice<BUS, CS, D> {
+    /// Creates a new SPI device.
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - The SPI bus.
+    /// * `cs` - The chip select pin of the TSC2046.
+    /// * `delay` - The delay provider serving the delays requested within the transactions.
+    ///
+    /// # Returns
+    ///
+    /// The SPI device.
+    pub fn new(bus: BUS, cs: CS, delay: D) -> Self {
+        Self {
+            bus,
+            cs,
+            delay,
+            busy: NoPin,
+        }
+    }
+
+    /// Hands the pin connected to the BUSY output of the chip over to the device.
+    ///
+    /// The device then waits for BUSY to go low after every command byte, before clocking out
+    /// the result of the conversion. The chip select is asserted all along, so BUSY is driven by
+    /// the chip. A BUSY output stuck high fails the transaction with [`BusyError::Timeout`].
+    /// Build the driver with [`Tsc2046::from_config`] to use the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `busy` - The input pin connected to the BUSY output.
+    ///
+    /// # Returns
+    ///
+    /// The device owning the BUSY pin.
+    pub fn with_busy_pin<BUSY: InputPin>(self, busy: BUSY) -> CsDevice<BUS, CS, D, BUSY> {
+        CsDevice {
+            bus: self.bus,
+            cs: self.cs,
+            delay: self.delay,
+            busy,
+        }
+    }
+}
+
+impl<BUS, CS, D, BUSY> CsDevice<BUS, CS, D, BUSY> {
+    /// Releases the bus, the chip select pin and the delay provider.
+    ///
+    /// # Returns
+    ///
+    /// The parts the device was built from, without the BUSY pin.
+    pub fn release(self) -> (BUS, CS, D) {
+        (self.bus, self.cs, self.delay)
+    }
+}
+
+/// Executes the operations of a transaction on the bus.
+///
+/// Every command byte written is flushed and followed by the wait for the BUSY output, if a BUSY
+/// pin is connected.
+///
+/// # Arguments
+///
+/// * `bus` - The SPI bus.
+/// * `busy` - The pin connected to the BUSY output, or [`NoPin`].
+/// * `delay` - The delay provider.
+/// * `operations` - The operations to execute.
+///
+/// # Returns
+///
+/// A `Result` indicating whether every operation succeeded.
+fn execute<BUS: SpiBus, BUSY: OptionalInputPin, D: DelayNs, CS>(
+    bus: &mut BUS,
+    busy: &mut BUSY,
+    delay: &mut D,
+    operations: &mut [Operation<'_, u8>],
+) -> Result<(), CsDeviceError<BUS::Error, CS>> {
+    for operation in operations {
+        match operation {
+            Operation::Read(buf) => bus.read(buf),
+            Operation::Write(buf) => bus.write(buf),
+            Operation::Transfer(read, write) => bus.transfer(read, write),
+            Operation::TransferInPlace(buf) => bus.transfer_in_place(buf),
+            Operation::DelayNs(ns) => bus.flush().map(|()| delay.delay_ns(*ns)),
+        }
+        .map_err(CsDeviceError::Bus)?;
+        if BUSY::CONNECTED && matches!(operation, Operation::Write(_)) {
+            bus.flush().map_err(CsDeviceError::Bus)?;
+            wait_while_busy(|| busy.read_level(), |us| delay.delay_us(us))
+                .map_err(CsDeviceError::Busy)?;
+        }
+    }
+    bus.flush().map_err(CsDeviceError::Bus)
+}
+
+impl<BUS, CS, D, BUSY> ErrorType for CsDevice<BUS, CS, D, BUSY>
+where
+    BUS: SpiBus,
+    CS: OutputPin,
+{
+    type Error = CsDeviceError<BUS::Error, CS::Error>;
+}
+
+impl<BUS, CS, D, BUSY> SpiDevice for CsDevice<BUS, CS, D, BUSY>
+where
+    BUS: SpiBus,
+    CS: OutputPin,
+    D: DelayNs,
+    BUSY: OptionalInputPin,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        let Self {
+            bus,
+            cs,
+            delay,
+            busy,
+        } = self;
+        framed(
+            cs,
+            |cs, high| {
+                if high { cs.set_high() } else { cs.set_low() }.map_err(CsDeviceError::ChipSelect)
+            },
+            || execute(bus, busy, delay, operations),
+        )
+    }
+}
+
+/// Delay provider of a [`CsDevice`] built without one, which does not pause.
+///
+/// The delays requested within the transactions, such as the warm-up of the internal reference,
+/// are skipped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoOpDelay;
+
+impl DelayNs for NoOpDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+impl<BUS, CS> Tsc2046<CsDevice<BUS, CS, NoOpDelay>>
+where
+    BUS: SpiBus,
+    CS: OutputPin,
+{
+    /// Creates a new instance of the `Tsc2046` driver on a raw SPI bus and chip select pin.
+    ///
+    /// The device does not pause within the transactions, so the warm-up of the internal
+    /// reference is skipped. Use [`Tsc2046::new_with_cs_and_delay`] to serve it.
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - The SPI bus, owned exclusively by the driver.
+    /// * `cs_pin` - The chip select pin of the TSC2046.
+    /// * `config` - The configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Tsc2046` instance or an error if the register update fails.
+    pub fn new_with_cs(bus: BUS, cs_pin: CS, config: Config) -> Result<Self, DriverError<BUS, CS>> {
+        Self::new_with_cs_and_delay(bus, cs_pin, NoOpDelay, config)
+    }
+}
+
+impl<BUS, CS, D> Tsc2046<CsDevice<BUS, CS, D>>
+where
+    BUS: SpiBus,
+    CS: OutputPin,
+    D: DelayNs,
+{
+    /// Creates a new instance of the `Tsc2046` driver on a raw SPI bus, chip select pin and delay
+    /// provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - The SPI bus, owned exclusively by the driver.
+    /// * `cs_pin` - The chip select pin of the TSC2046.
+    /// * `delay` - The delay provider serving the warm-up of the internal reference.
+    /// * `config` - The configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Tsc2046` instance or an error if the register update fails.
+    pub fn new_with_cs_and_delay(
+        bus: BUS,
+        cs_pin: CS,
+        delay: D,
+        config: Config,
+    ) -> Result<Self, DriverError<BUS, CS>> {
+        Self::from_config(CsDevice::new(bus, cs_pin, delay), config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::{FakeCs, MockDelay, MockInputPin};
+    use crate::Channel;
+    use std::vec::Vec;
+
+    #[derive(Default)]
+    struct FakeBus {
+        written: Vec<u8>,
+        flushes: usize,
+    }
+
+    impl ErrorType for FakeBus {
+        type Error = ErrorKind;
+    }
+
+    impl SpiBus for FakeBus {
+        fn read(&mut self, words: &mut [u8]) -> Result<(), ErrorKind> {
+            words.fill(0x5A);
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), ErrorKind> {
+            self.written.extend_from_slice(words);
+            Ok(())
+        }
+
+        fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), ErrorKind> {
+            self.written.extend_from_slice(write);
+            read.fill(0x5A);
+            Ok(())
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), ErrorKind> {
+            self.written.extend_from_slice(words);
+            words.fill(0x5A);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), ErrorKind> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_new_with_cs() {
+        let mut driver = Tsc2046::new_with_cs(
+            FakeBus::default(),
+            FakeCs::default(),
+            Config {
+                irq_on: false,
+                ..Config::default()
+            },
+        )
+        .expect("Could not create driver");
+        assert_eq!(driver.raw_command(0xD3), Ok(0x5A5A >> 3 & 0xFFF));
+        let (bus, cs, _) = driver.spi.release();
+        // The register update, then the raw command, each framed by the chip select.
+        assert_eq!(bus.written, [0xD3, 0xD3]);
+        assert_eq!(bus.flushes, 2);
+        assert_eq!(cs.toggles, [false, true, false, true]);
+    }
+
+    #[test]
+    fn test_new_with_cs_and_delay() {
+        let mut driver = Tsc2046::new_with_cs_and_delay(
+            FakeBus::default(),
+            FakeCs::default(),
+            MockDelay::default(),
+            Config {
+                irq_on: true,
+                ..Config::default()
+            },
+        )
+        .expect("Could not create driver");
+        driver
+            .read_temperature()
+            .expect("Could not read temperature");
+        let (_, _, delay) = driver.spi.release();
+        // The warm-up of the internal reference is served by the delay provider.
+        assert!(delay.elapsed_ns > 0);
+    }
+
+    #[test]
+    fn test_busy_pin_wait() {
+        // BUSY stays high for two polls after the command, then goes low.
+        let mut busy = MockInputPin::new();
+        let mut levels = [true, true, false].into_iter();
+        busy.expect_is_high()
+            .times(3)
+            .returning(move || Ok(levels.next().unwrap()));
+        let mut device = CsDevice::new(FakeBus::default(), FakeCs::default(), MockDelay::default())
+            .with_busy_pin(busy);
+        let mut read = [0u8; 2];
+        device
+            .transaction(&mut [Operation::Write(&[0xD3]), Operation::Read(&mut read)])
+            .expect("Transaction failed");
+        assert_eq!(read, [0x5A; 2]);
+        assert_eq!(device.delay.elapsed_ns, 2000);
+        let (bus, cs, _) = device.release();
+        // The command is flushed before BUSY is polled, with the chip still selected.
+        assert_eq!(bus.flushes, 2);
+        assert_eq!(cs.toggles, [false, true]);
+    }
+
+    #[test]
+    fn test_busy_pin_timeout() {
+        let mut busy = MockInputPin::new();
+        busy.expect_is_high().returning(|| Ok(true));
+        let device = CsDevice::new(FakeBus::default(), FakeCs::default(), MockDelay::default())
+            .with_busy_pin(busy);
+        let result = Tsc2046::from_config(device, Config::default());
+        assert!(matches!(
+            result,
+            Err(Error::Spi {
+                channel: Channel::X,
+                error: CsDeviceError::Busy(BusyError::Timeout),
+            })
+        ));
+    }
+
+    #[test]
+    fn test_wait_while_busy() {
+        let mut paused_us = 0;
+        assert_eq!(wait_while_busy(|| Ok(None), |us| paused_us += us), Ok(()));
+        assert_eq!(
+            wait_while_busy(|| Ok(Some(true)), |us| paused_us += us),
+            Err(BusyError::Timeout)
+        );
+        assert_eq!(paused_us, crate::BUSY_TIMEOUT_US);
+        assert_eq!(
+            wait_while_busy(|| Err(digital::ErrorKind::Other), |_| {}),
+            Err(BusyError::Pin(digital::ErrorKind::Other))
+        );
+    }
+}
@@ -0,0 +1,797 @@
+use embedded_hal::spi::{ErrorKind, ErrorType, SpiDevice};
+
+use crate::stage::FilterStage;
+use crate::types::{AuxChannel, Axes};
+use crate::{Channel, Error, OptionalDelay, OptionalInputPin, RoutineDelay, TouchPoint, Tsc2046};
+
+/// The number of identical consecutive touch positions after which the readings are considered
+/// stuck.
+const STUCK_SAMPLE_LIMIT: u16 = 50;
+
+/// The number of consecutive touches, signalled by PENIRQ, with a pressure channel at one of the
+/// rails after which the pressure channels are considered unusable.
+const PRESSURE_RAIL_LIMIT: u16 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Counters of the operations of the driver.
+pub struct Stats {
+    /// The number of successful conversions, counting every conversion of the transactions that
+    /// carry several.
+    pub conversions: u32,
+    /// The number of samples in which a touch was detected.
+    pub touches: u32,
+    /// The number of samples in which no touch was detected.
+    pub no_touches: u32,
+    /// The number of failed operations.
+    pub errors: u32,
+    /// The number of readings rejected by the majority vote, also counted as failed operations.
+    pub rejected_votes: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Health information of the driver and the panel, collected while operating.
+pub struct Diagnostics {
+    /// The last error which occurred, with the SPI error reduced to its kind.
+    pub last_error: Option<Error<ErrorKind>>,
+    /// The operation counters.
+    pub stats: Stats,
+    /// The result of the last [`Tsc2046::estimate_noise_floor`] call.
+    pub noise_floor: Option<NoiseFloor>,
+    /// Whether the touch position has been identical for a suspicious number of samples.
+    pub stuck: bool,
+    /// Whether a panel was found by the last [`Tsc2046::check_channels`] call.
+    pub panel_present: Option<bool>,
+    /// Whether touch detection fell back to the PENIRQ pin, read through the glitch filter,
+    /// because the pressure channels kept reading rail values while the pen was down.
+    pub pressure_fallback: bool,
+    /// The last touch position, used to detect stuck readings.
+    last_position: Option<(u16, u16)>,
+    /// The number of consecutive repetitions of the last touch position.
+    repeats: u16,
+    /// The number of consecutive touches with a pressure channel at one of the rails.
+    railed_pressure: u16,
+}
+
+impl Diagnostics {
+    /// Records the outcome of an operation performing a single conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The result of the operation.
+    ///
+    /// # Returns
+    ///
+    /// The result, unchanged.
+    pub(crate) fn record<T, E: embedded_hal::spi::Error>(
+        &mut self,
+        result: Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        self.record_conversions(result, 1)
+    }
+
+    /// Records the outcome of an operation performing several conversions.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The result of the operation.
+    /// * `count` - The number of conversions performed by the operation, counted if it succeeded.
+    ///
+    /// # Returns
+    ///
+    /// The result, unchanged.
+    pub(crate) fn record_conversions<T, E: embedded_hal::spi::Error>(
+        &mut self,
+        result: Result<T, Error<E>>,
+        count: u32,
+    ) -> Result<T, Error<E>> {
+        match &result {
+            Ok(_) => self.stats.conversions = self.stats.conversions.wrapping_add(count),
+            Err(error) => {
+                self.stats.errors = self.stats.errors.wrapping_add(1);
+                self.last_error = Some(match error {
+                    Error::Spi { channel, error } => Error::Spi {
+                        channel: *channel,
+                        error: error.kind(),
+                    },
+                    Error::SpiBatch(error) => Error::SpiBatch(error.kind()),
+                    Error::Pin(kind) => Error::Pin(*kind),
+                    Error::Inhibited => Error::Inhibited,
+                    Error::Timeout => Error::Timeout,
+                    Error::InvalidReading(channel) => Error::InvalidReading(*channel),
+                    Error::FrameOverflow(channel) => Error::FrameOverflow(*channel),
+                });
+            }
+        }
+        result
+    }
+
+    /// Records a reading rejected by the majority vote.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel of the reading.
+    ///
+    /// # Returns
+    ///
+    /// The [`Error::InvalidReading`] error of the channel.
+    pub(crate) fn record_rejected_vote<T, E: embedded_hal::spi::Error>(
+        &mut self,
+        channel: Channel,
+    ) -> Result<T, Error<E>> {
+        self.stats.rejected_votes = self.stats.rejected_votes.wrapping_add(1);
+        self.record(Err(Error::InvalidReading(channel)))
+    }
+
+    /// Records a sample in which a touch was detected.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The raw x-coordinate of the touch.
+    /// * `y` - The raw y-coordinate of the touch.
+    pub(crate) fn record_touch(&mut self, x: u16, y: u16) {
+        self.stats.touches = self.stats.touches.wrapping_add(1);
+        if self.last_position == Some((x, y)) {
+            self.repeats = self.repeats.saturating_add(1);
+        } else {
+            self.repeats = 0;
+        }
+        self.last_position = Some((x, y));
+        self.stuck = self.repeats >= STUCK_SAMPLE_LIMIT;
+    }
+
+    /// Checks whether recording a touch would make the readings stuck.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The raw x-coordinate of the touch.
+    /// * `y` - The raw y-coordinate of the touch.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the readings are not stuck yet, and the touch repeats the last position for the
+    /// last time before they are.
+    pub(crate) fn becomes_stuck(&self, x: u16, y: u16) -> bool {
+        !self.stuck
+            && self.last_position == Some((x, y))
+            && self.repeats.saturating_add(1) >= STUCK_SAMPLE_LIMIT
+    }
+
+    /// Records the pressure readings of a touch signalled by PENIRQ.
+    ///
+    /// # Arguments
+    ///
+    /// * `railed` - Whether Z1 or Z2 sits at one of the rails.
+    pub(crate) fn record_pressure(&mut self, railed: bool) {
+        if railed {
+            self.railed_pressure = self.railed_pressure.saturating_add(1);
+        } else {
+            self.railed_pressure = 0;
+        }
+        self.pressure_fallback = self.railed_pressure >= PRESSURE_RAIL_LIMIT;
+    }
+
+    /// Clears the fallback to PENIRQ-only touch detection.
+    pub(crate) fn clear_pressure_fallback(&mut self) {
+        self.railed_pressure = 0;
+        self.pressure_fallback = false;
+    }
+
+    /// Records a sample in which no touch was detected.
+    pub(crate) fn record_no_touch(&mut self) {
+        self.stats.no_touches = self.stats.no_touches.wrapping_add(1);
+        self.last_position = None;
+        self.repeats = 0;
+        self.stuck = false;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Statistics of a series of conversions of a single channel.
+///
+/// Recorded on the Z1 pressure channel while the panel is not touched, Z1 should read close to
+/// zero, so its spread is the noise picked up by the panel. The statistics can be used to choose
+/// the touch threshold and the filter parameters.
+pub struct NoiseFloor {
+    /// The number of conversions the statistics are computed from.
+    pub samples: u16,
+    /// The smallest raw value read.
+    pub min: u16,
+    /// The largest raw value read.
+    pub max: u16,
+    /// The mean of the raw values.
+    pub mean: f32,
+    /// The variance of the raw values.
+    pub variance: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Effective resolution of the touch coordinates, in bits.
+pub struct Resolution {
+    /// The effective resolution of the x-coordinate.
+    pub x_bits: f32,
+    /// The effective resolution of the y-coordinate.
+    pub y_bits: f32,
+}
+
+impl Resolution {
+    /// Computes the effective resolution of an axis from the noise of single conversions.
+    ///
+    /// Averaging `oversampling` conversions divides the noise variance accordingly, and the
+    /// quantization noise of the 12 bit result is added, so noiseless conversions yield 12 bits.
+    ///
+    /// # Arguments
+    ///
+    /// * `variance` - The variance of single conversions in ADC counts squared.
+    /// * `oversampling` - The number of conversions averaged.
+    ///
+    /// # Returns
+    ///
+    /// The effective number of bits, between 0 and 12.
+    pub fn effective_bits(variance: f32, oversampling: u16) -> f32 {
+        let variance = variance.max(0.0) / oversampling.max(1) as f32 + 1.0 / 12.0;
+        (12.0 - 0.5 * log2(12.0 * variance)).clamp(0.0, 12.0)
+    }
+}
+
+impl NoiseFloor {
+    /// Returns the peak-to-peak noise.
+    ///
+    /// # Returns
+    ///
+    /// The difference between the largest and the smallest raw Z1 value.
+    pub fn peak_to_peak(&self) -> u16 {
+        self.max.saturating_sub(self.min)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Positional jitter of a series of touch points.
+pub struct Jitter {
+    /// The difference between the largest and the smallest x-coordinate.
+    pub x_peak_to_peak: u16,
+    /// The difference between the largest and the smallest y-coordinate.
+    pub y_peak_to_peak: u16,
+    /// The root mean square deviation of the x-coordinate from its mean.
+    pub x_rms: f32,
+    /// The root mean square deviation of the y-coordinate from its mean.
+    pub y_rms: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Positional jitter of a still contact, before and after a filter chain.
+pub struct JitterReport {
+    /// The number of touch points the jitter is computed from.
+    pub samples: u16,
+    /// The jitter of the touch points read from the chip.
+    pub raw: Jitter,
+    /// The jitter of the touch points output by the filter chain.
+    pub filtered: Jitter,
+}
+
+/// Running statistics of a series of raw values, like a coordinate or the conversions of a
+/// channel.
+#[derive(Debug, Clone, Copy, Default)]
+struct DeviationStats {
+    /// The first value, the deviations are summed from, to keep the precision of large values.
+    first: Option<u16>,
+    /// The smallest value.
+    min: u16,
+    /// The largest value.
+    max: u16,
+    /// The number of values.
+    count: u16,
+    /// The sum of the deviations from the first value.
+    sum: f32,
+    /// The sum of the squared deviations from the first value.
+    sum_squares: f32,
+}
+
+impl DeviationStats {
+    /// Adds a value to the statistics.
+    fn update(&mut self, value: u16) {
+        let first = *self.first.get_or_insert(value);
+        if self.count == 0 {
+            (self.min, self.max) = (value, value);
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.count = self.count.saturating_add(1);
+        let deviation = value as f32 - first as f32;
+        self.sum += deviation;
+        self.sum_squares += deviation * deviation;
+    }
+
+    /// Returns the mean deviation of the values from the first one, 0 without values.
+    fn mean_deviation(&self) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum / self.count as f32
+    }
+
+    /// Returns the mean of the values, 0 without values.
+    fn mean(&self) -> f32 {
+        self.first.unwrap_or(0) as f32 + self.mean_deviation()
+    }
+
+    /// Returns the variance of the values, 0 without values.
+    fn variance(&self) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let mean_deviation = self.mean_deviation();
+        (self.sum_squares / self.count as f32 - mean_deviation * mean_deviation).max(0.0)
+    }
+
+    /// Returns the peak-to-peak and the root mean square deviation of the values.
+    fn finish(&self) -> (u16, f32) {
+        (self.max.saturating_sub(self.min), sqrt(self.variance()))
+    }
+}
+
+/// Running statistics of both coordinates of a series of touch points.
+#[derive(Debug, Clone, Copy, Default)]
+struct JitterStats {
+    /// The statistics of the x-coordinate.
+    x: DeviationStats,
+    /// The statistics of the y-coordinate.
+    y: DeviationStats,
+}
+
+impl JitterStats {
+    /// Adds a touch point to the statistics.
+    fn update(&mut self, point: &TouchPoint) {
+        self.x.update(point.x);
+        self.y.update(point.y);
+    }
+
+    /// Returns the jitter of the touch points.
+    fn finish(&self) -> Jitter {
+        let (x_peak_to_peak, x_rms) = self.x.finish();
+        let (y_peak_to_peak, y_rms) = self.y.finish();
+        Jitter {
+            x_peak_to_peak,
+            y_peak_to_peak,
+            x_rms,
+            y_rms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Plausible ranges of every input of the chip, with the panel not touched.
+///
+/// All ranges are inclusive. The raw ranges are in ADC counts.
+pub struct ChannelLimits {
+    /// The plausible raw X values.
+    pub x: (u16, u16),
+    /// The plausible raw Y values.
+    pub y: (u16, u16),
+    /// The plausible raw Z1 values. Without a touch, Z1 reads close to zero.
+    pub z1: (u16, u16),
+    /// The plausible raw Z2 values. Without a touch, Z2 reads close to full scale.
+    pub z2: (u16, u16),
+    /// The plausible battery voltages in millivolts.
+    pub vbat_mv: (u16, u16),
+    /// The plausible chip temperatures in degrees Celsius.
+    pub temperature_celsius: (f32, f32),
+    /// The plausible raw AUX values.
+    pub aux: (u16, u16),
+}
+
+impl Default for ChannelLimits {
+    fn default() -> Self {
+        Self {
+            x: (0, 4095),
+            y: (0, 4095),
+            z1: (0, 200),
+            z2: (3500, 4095),
+            vbat_mv: (2000, 6000),
+            temperature_celsius: (-20.0, 70.0),
+            aux: (0, 4095),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Reading of a single input, with the result of its plausibility check.
+pub struct ChannelResult<T> {
+    /// The value read.
+    pub value: T,
+    /// Whether the value is within the plausible range.
+    pub plausible: bool,
+}
+
+impl<T: PartialOrd> ChannelResult<T> {
+    /// Checks a value against an inclusive range.
+    fn check(value: T, (min, max): (T, T)) -> Self {
+        let plausible = value >= min && value <= max;
+        Self { value, plausible }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Result of the plausibility check of every input of the chip.
+pub struct ChannelReport {
+    /// The raw X reading.
+    pub x: ChannelResult<u16>,
+    /// The raw Y reading.
+    pub y: ChannelResult<u16>,
+    /// The raw Z1 reading.
+    pub z1: ChannelResult<u16>,
+    /// The raw Z2 reading.
+    pub z2: ChannelResult<u16>,
+    /// The battery voltage in millivolts.
+    pub vbat_mv: ChannelResult<u16>,
+    /// The chip temperature in degrees Celsius.
+    pub temperature_celsius: ChannelResult<f32>,
+    /// The raw AUX reading.
+    pub aux: ChannelResult<u16>,
+}
+
+impl ChannelReport {
+    /// Checks whether every input is plausible.
+    ///
+    /// # Returns
+    ///
+    /// `true` if no assembly fault was detected.
+    pub fn plausible(&self) -> bool {
+        self.x.plausible
+            && self.y.plausible
+            && self.z1.plausible
+            && self.z2.plausible
+            && self.vbat_mv.plausible
+            && self.temperature_celsius.plausible
+            && self.aux.plausible
+    }
+}
+
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
+    /// Samples the Z1 pressure channel while no touch is expected, to estimate the noise floor.
+    ///
+    /// Meant to be called at factory test or on every boot, while nobody touches the panel. The
+    /// configured oversampling is not applied, as the noise of single conversions is measured.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The number of conversions (a value of 0 is treated as 1).
+    /// * `interval_us` - The delay between two consecutive conversions in microseconds.
+    /// * `delay` - The delay provider used to wait between conversions, or [`crate::OwnedDelay`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `NoiseFloor` statistics, or an error if any of the reads fails.
+    pub fn estimate_noise_floor<D: RoutineDelay<DELAY>>(
+        &mut self,
+        samples: u16,
+        interval_us: u32,
+        delay: &mut D,
+    ) -> Result<NoiseFloor, Error<<SPI as ErrorType>::Error>> {
+        let noise_floor = self.measure_noise(Axes::Z1, samples, interval_us, delay)?;
+        self.core.diagnostics.noise_floor = Some(noise_floor);
+        Ok(noise_floor)
+    }
+
+    /// Estimates the effective resolution of the touch coordinates with the configured
+    /// oversampling.
+    ///
+    /// The panel has to be pressed at a fixed position during the measurement, for example with
+    /// a weighted stylus, so the spread of the conversions is noise only.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The number of conversions per axis (a value of 0 is treated as 1).
+    /// * `interval_us` - The delay between two consecutive conversions in microseconds.
+    /// * `delay` - The delay provider used to wait between conversions, or [`crate::OwnedDelay`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Resolution` of both axes, or an error if any of the reads fails.
+    pub fn estimate_resolution<D: RoutineDelay<DELAY>>(
+        &mut self,
+        samples: u16,
+        interval_us: u32,
+        delay: &mut D,
+    ) -> Result<Resolution, Error<<SPI as ErrorType>::Error>> {
+        let x_noise = self.measure_noise(Axes::X, samples, interval_us, delay)?;
+        let y_noise = self.measure_noise(Axes::Y, samples, interval_us, delay)?;
+        let oversampling = self.core.config.oversampling;
+        Ok(Resolution {
+            x_bits: Resolution::effective_bits(x_noise.variance, oversampling),
+            y_bits: Resolution::effective_bits(y_noise.variance, oversampling),
+        })
+    }
+
+    /// Measures the positional jitter of a still contact, before and after a filter chain.
+    ///
+    /// The panel has to be pressed at a fixed position during the measurement, for example with
+    /// a stylus held still. Readings without a touch are passed to the filter, but left out of the
+    /// statistics.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The filter chain, typically the filter stage of the application event tracker.
+    /// * `samples` - The number of touch readings.
+    /// * `interval_us` - The delay between two consecutive readings in microseconds.
+    /// * `delay` - The delay provider used to wait between readings, or [`crate::OwnedDelay`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `JitterReport`, or an error if any of the reads fails.
+    pub fn measure_jitter<F: FilterStage, D: RoutineDelay<DELAY>>(
+        &mut self,
+        filter: &mut F,
+        samples: u16,
+        interval_us: u32,
+        delay: &mut D,
+    ) -> Result<JitterReport, Error<<SPI as ErrorType>::Error>> {
+        let mut raw = JitterStats::default();
+        let mut filtered = JitterStats::default();
+        for sample in 0..samples {
+            if sample > 0 {
+                delay.pause_us(&mut self.delay, interval_us);
+            }
+            let touch = self.get_touch()?;
+            let output = filter.filter(touch);
+            if let (Some(point), Some(output)) = (touch, output) {
+                raw.update(&point);
+                filtered.update(&output);
+            }
+        }
+        Ok(JitterReport {
+            samples: raw.x.count,
+            raw: raw.finish(),
+            filtered: filtered.finish(),
+        })
+    }
+
+    /// Computes the statistics of a series of single conversions of an axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - The axis to read.
+    /// * `samples` - The number of conversions (a value of 0 is treated as 1).
+    /// * `interval_us` - The delay between two consecutive conversions in microseconds.
+    /// * `delay` - The delay provider used to wait between conversions, or [`crate::OwnedDelay`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `NoiseFloor` statistics, or an error if any of the reads fails.
+    fn measure_noise<D: RoutineDelay<DELAY>>(
+        &mut self,
+        axis: Axes,
+        samples: u16,
+        interval_us: u32,
+        delay: &mut D,
+    ) -> Result<NoiseFloor, Error<<SPI as ErrorType>::Error>> {
+        let samples = samples.max(1);
+        let mut stats = DeviationStats::default();
+        for sample in 0..samples {
+            if sample > 0 {
+                delay.pause_us(&mut self.delay, interval_us);
+            }
+            stats.update(self.read_axis(axis)?);
+        }
+        Ok(NoiseFloor {
+            samples,
+            min: stats.min,
+            max: stats.max,
+            mean: stats.mean(),
+            variance: stats.variance(),
+        })
+    }
+
+    /// Reads every input of the chip and checks it against a plausible range.
+    ///
+    /// Meant for end-of-line testing with the panel not touched, to flag assembly faults like
+    /// swapped or open panel wires. The panel is considered present if the pressure channels are
+    /// plausible.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - The plausible ranges of the inputs.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ChannelReport`, or an error if any of the reads fails.
+    pub fn check_channels(
+        &mut self,
+        limits: &ChannelLimits,
+    ) -> Result<ChannelReport, Error<<SPI as ErrorType>::Error>> {
+        let report = ChannelReport {
+            x: ChannelResult::check(self.read_axis(Axes::X)?, limits.x),
+            y: ChannelResult::check(self.read_axis(Axes::Y)?, limits.y),
+            z1: ChannelResult::check(self.read_axis(Axes::Z1)?, limits.z1),
+            z2: ChannelResult::check(self.read_axis(Axes::Z2)?, limits.z2),
+            vbat_mv: ChannelResult::check(self.read_vbat()?, limits.vbat_mv),
+            temperature_celsius: ChannelResult::check(
+                self.read_temperature()?,
+                limits.temperature_celsius,
+            ),
+            aux: ChannelResult::check(self.read_single_ended(AuxChannel::Aux)?, limits.aux),
+        };
+        self.core.diagnostics.panel_present = Some(report.z1.plausible && report.z2.plausible);
+        Ok(report)
+    }
+}
+
+/// Approximates the square root of a non-negative number.
+///
+/// The exponent of the float representation is halved for the first guess, which three Newton
+/// iterations refine to about 1e-6 relative error.
+///
+/// # Arguments
+///
+/// * `value` - The number.
+///
+/// # Returns
+///
+/// The square root, or 0 for non-positive numbers.
+fn sqrt(value: f32) -> f32 {
+    if value <= 0.0 {
+        return 0.0;
+    }
+    let mut root = f32::from_bits((value.to_bits() >> 1).saturating_add(0x1FC0_0000));
+    for _ in 0..3 {
+        root = 0.5 * (root + value / root);
+    }
+    root
+}
+
+/// Approximates the base 2 logarithm of a positive number.
+///
+/// The exponent is taken from the float representation, and the logarithm of the mantissa is
+/// approximated by the series `ln(m) = 2 * atanh((m - 1) / (m + 1))`, accurate to about 1e-5.
+pub(crate) fn log2(value: f32) -> f32 {
+    if value <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    let bits = value.to_bits();
+    let exponent = (((bits >> 23) & 0xFF) as i32).saturating_sub(127);
+    let mantissa = f32::from_bits((bits & 0x007F_FFFF) | 0x3F80_0000);
+    let s = (mantissa - 1.0) / (mantissa + 1.0);
+    let s2 = s * s;
+    let ln_mantissa = 2.0 * s * (1.0 + s2 * (1.0 / 3.0 + s2 * (1.0 / 5.0 + s2 / 7.0)));
+    exponent as f32 + ln_mantissa * core::f32::consts::LOG2_E
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::{FakeTsc2046, MockDelay};
+
+    #[test]
+    fn test_estimate_noise_floor() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::Z1, &[2, 6, 2, 6]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let mut delay = MockDelay::default();
+        let noise = test_driver
+            .estimate_noise_floor(4, 1000, &mut delay)
+            .expect("Could not estimate noise floor");
+        assert_eq!((noise.samples, noise.min, noise.max), (4, 2, 6));
+        assert_eq!(noise.peak_to_peak(), 4);
+        assert_eq!((noise.mean, noise.variance), (4.0, 4.0));
+        assert_eq!(delay.elapsed_ns, 3_000_000);
+    }
+
+    #[test]
+    fn test_check_channels() {
+        let mut fake_chip = FakeTsc2046::default();
+        // Swapped wires make Z1 read high on the second check.
+        fake_chip.queue(FakeTsc2046::Z1, &[10, 3000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[4000]);
+        fake_chip.queue(FakeTsc2046::VBAT, &[1500]);
+        fake_chip.queue(FakeTsc2046::TEMP0, &[600]);
+        fake_chip.queue(FakeTsc2046::TEMP1, &[790]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let report = test_driver
+            .check_channels(&ChannelLimits::default())
+            .expect("Could not check channels");
+        assert!(report.plausible());
+
+        let report = test_driver
+            .check_channels(&ChannelLimits::default())
+            .expect("Could not check channels");
+        assert!(!report.plausible());
+        assert!(!report.z1.plausible);
+        assert!(report.z2.plausible);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        for _ in 0..=STUCK_SAMPLE_LIMIT {
+            assert!(test_driver.get_touch().unwrap().is_some());
+        }
+        test_driver.inhibit();
+        assert_eq!(test_driver.get_touch(), Err(Error::Inhibited));
+        let diagnostics = test_driver.diagnostics();
+        assert!(diagnostics.stuck);
+        assert_eq!(diagnostics.last_error, Some(Error::Inhibited));
+        assert_eq!(
+            diagnostics.stats,
+            Stats {
+                conversions: 1 + 4 * (STUCK_SAMPLE_LIMIT as u32 + 1),
+                touches: STUCK_SAMPLE_LIMIT as u32 + 1,
+                no_touches: 0,
+                errors: 1,
+                rejected_votes: 0,
+            }
+        );
+        assert_eq!(diagnostics.noise_floor, None);
+        assert_eq!(diagnostics.panel_present, None);
+    }
+
+    #[test]
+    fn test_effective_resolution() {
+        assert!(Resolution::effective_bits(0.0, 1) > 11.999);
+        // Noise of 16 counts RMS costs about 6 bits, averaging 16 conversions recovers 2.
+        let bits = Resolution::effective_bits(256.0, 1);
+        assert!((bits - 6.21).abs() < 0.01);
+        let bits = Resolution::effective_bits(256.0, 16);
+        assert!((bits - 8.20).abs() < 0.01);
+
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2000, 2002, 2000, 2002]);
+        fake_chip.queue(FakeTsc2046::Y, &[3000]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let resolution = test_driver
+            .estimate_resolution(4, 0, &mut MockDelay::default())
+            .expect("Could not estimate resolution");
+        assert!((resolution.x_bits - 10.15).abs() < 0.01);
+        assert!(resolution.y_bits > 11.999);
+    }
+
+    #[test]
+    fn test_measure_jitter() {
+        /// Averages each point with the previous one.
+        struct PairAverage(Option<TouchPoint>);
+        impl FilterStage for PairAverage {
+            fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+                let previous = core::mem::replace(&mut self.0, touch);
+                let (touch, previous) = (touch?, previous.or(touch)?);
+                Some(TouchPoint {
+                    x: (touch.x + previous.x) / 2,
+                    y: (touch.y + previous.y) / 2,
+                    z: touch.z,
+                })
+            }
+        }
+
+        let mut fake_chip = FakeTsc2046::default();
+        // The first X conversion is consumed by the initial register update.
+        fake_chip.queue(FakeTsc2046::X, &[0, 100, 110, 100, 110, 100]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 1000.0).expect("Could not create driver");
+        let mut delay = MockDelay::default();
+        let report = test_driver
+            .measure_jitter(&mut PairAverage(None), 4, 1000, &mut delay)
+            .expect("Could not measure jitter");
+        assert_eq!(report.samples, 4);
+        assert_eq!(report.raw.x_peak_to_peak, 10);
+        assert!((report.raw.x_rms - 5.0).abs() < 1e-3);
+        assert_eq!(report.raw.y_peak_to_peak, 0);
+        assert_eq!(report.filtered.x_peak_to_peak, 5);
+        assert!(report.filtered.x_rms < report.raw.x_rms);
+        assert_eq!(delay.elapsed_ns, 3_000_000);
+        assert!((sqrt(2.0) - core::f32::consts::SQRT_2).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,352 @@
+use embedded_hal::digital;
+use embedded_hal::spi::{self, ErrorKind, ErrorType, Operation, SpiDevice};
+use embedded_hal_02::blocking::delay::DelayUs;
+use embedded_hal_02::blocking::spi::{Transfer, Write};
+use embedded_hal_02::digital::v2::{InputPin, OutputPin};
+
+use crate::device::{framed, wait_while_busy, BusyError};
+use crate::{Config, Error, NoPin, Tsc2046};
+
+/// Error of an [`Eh0Device`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eh0Error<SPI, CS> {
+    /// The SPI bus reported an error.
+    Spi(SPI),
+    /// Driving the chip select pin failed.
+    ChipSelect(CS),
+    /// Waiting for the end of a conversion on the BUSY pin failed.
+    Busy(BusyError),
+}
+
+impl<SPI, CS> spi::Error for Eh0Error<SPI, CS>
+where
+    SPI: core::fmt::Debug,
+    CS: core::fmt::Debug,
+{
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Spi(_) => ErrorKind::Other,
+            Self::ChipSelect(_) => ErrorKind::ChipSelectFault,
+            Self::Busy(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// A BUSY pin implementing the embedded-hal 0.2 traits, which may or may not be connected.
+pub trait OptionalEh0Pin {
+    /// Reads the level of the pin.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `Some(true)` if the pin is high, `Some(false)` if it is low, `None` if
+    /// no pin is connected, or the kind of the error if reading the pin fails.
+    fn read_level(&mut self) -> Result<Option<bool>, digital::ErrorKind>;
+}
+
+impl OptionalEh0Pin for NoPin {
+    fn read_level(&mut self) -> Result<Option<bool>, digital::ErrorKind> {
+        Ok(None)
+    }
+}
+
+impl<P: InputPin> OptionalEh0Pin for P {
+    fn read_level(&mut self) -> Result<Option<bool>, digital::ErrorKind> {
+        self.is_high()
+            .map(Some)
+            .map_err(|_| digital::ErrorKind::Other)
+    }
+}
+
+/// An embedded-hal 1.0 [`SpiDevice`] built from the embedded-hal 0.2 traits.
+///
+/// Owns the SPI bus exclusively, and asserts the chip select pin (active low) around each
+/// transaction. The delay provider serves the delays requested within the transactions, and
+/// paces the polls of the BUSY pin, if the device owns one.
+pub struct Eh0Device<SPI, CS, D, BUSY = NoPin> {
+    /// The SPI bus, used only while the chip select is asserted.
+    spi: SPI,
+    /// The chip select pin of the TSC2046, active low.
+    cs: CS,
+    /// The delay performing the `DelayNs` operations of the transactions.
+    delay: D,
+    /// The pin connected to the BUSY output of the chip, if owned by the device.
+    busy: BUSY,
+}
+
+impl<SPI, CS, D> Eh0Device<SPI, CS, D> {
+    /// Creates a new SPI device.
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - The SPI bus implementing the embedded-hal 0.2 `Transfer` and `Write` traits.
+    /// * `cs` - The chip select pin of the TSC2046.
+    /// * `delay` - The delay provider implementing the embedded-hal 0.2 `DelayUs` trait.
+    ///
+    /// # Returns
+    ///
+    /// The SPI device.
+    pub fn new(spi: SPI, cs: CS, delay: D) -> Self {
+        Self {
+            spi,
+            cs,
+            delay,
+            busy: NoPin,
+        }
+    }
+
+    /// Hands the pin connected to the BUSY output of the chip over to the device.
+    ///
+    /// Same as [`CsDevice::with_busy_pin`](crate::device::CsDevice::with_busy_pin), for a pin
+    /// implementing the embedded-hal 0.2 `InputPin` trait.
+    ///
+    /// # Arguments
+    ///
+    /// * `busy` - The input pin connected to the BUSY output.
+    ///
+    /// # Returns
+    ///
+    /// The device owning the BUSY pin.
+    pub fn with_busy_pin<BUSY: InputPin>(self, busy: BUSY) -> Eh0Device<SPI, CS, D, BUSY> {
+        Eh0Device {
+            spi: self.spi,
+            cs: self.cs,
+            delay: self.delay,
+            busy,
+        }
+    }
+}
+
+impl<SPI, CS, D, BUSY> Eh0Device<SPI, CS, D, BUSY> {
+    /// Releases the bus, the chip select pin and the delay provider.
+    ///
+    /// # Returns
+    ///
+    /// The parts the device was built from, without the BUSY pin.
+    pub fn release(self) -> (SPI, CS, D) {
+        (self.spi, self.cs, self.delay)
+    }
+}
+
+/// Executes the operations of a transaction on the bus.
+///
+/// Every command byte written is followed by the wait for the BUSY output.
+///
+/// # Arguments
+///
+/// * `spi` - The SPI bus.
+/// * `busy` - The pin connected to the BUSY output, or [`NoPin`].
+/// * `delay` - The delay provider.
+/// * `operations` - The operations to execute.
+///
+/// # Returns
+///
+/// A `Result` indicating whether every operation succeeded.
+fn execute<SPI, BUSY, D, E, CS>(
+    spi: &mut SPI,
+    busy: &mut BUSY,
+    delay: &mut D,
+    operations: &mut [Operation<'_, u8>],
+) -> Result<(), Eh0Error<E, CS>>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    BUSY: OptionalEh0Pin,
+    D: DelayUs<u32>,
+{
+    for operation in operations {
+        match operation {
+            Operation::Read(buf) => {
+                buf.fill(0);
+                spi.transfer(buf).map_err(Eh0Error::Spi)?;
+            }
+            Operation::Write(buf) => {
+                spi.write(buf).map_err(Eh0Error::Spi)?;
+                wait_while_busy(|| busy.read_level(), |us| delay.delay_us(us))
+                    .map_err(Eh0Error::Busy)?;
+            }
+            Operation::Transfer(read, write) => {
+                for index in 0..read.len().max(write.len()) {
+                    let mut word = [write.get(index).copied().unwrap_or_default()];
+                    spi.transfer(&mut word).map_err(Eh0Error::Spi)?;
+                    if let Some(slot) = read.get_mut(index) {
+                        *slot = word[0];
+                    }
+                }
+            }
+            Operation::TransferInPlace(buf) => {
+                spi.transfer(buf).map_err(Eh0Error::Spi)?;
+            }
+            Operation::DelayNs(ns) => delay.delay_us(ns.div_ceil(1000)),
+        }
+    }
+    Ok(())
+}
+
+impl<SPI, CS, D, BUSY, E> ErrorType for Eh0Device<SPI, CS, D, BUSY>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+    E: core::fmt::Debug,
+    CS::Error: core::fmt::Debug,
+{
+    type Error = Eh0Error<E, CS::Error>;
+}
+
+impl<SPI, CS, D, BUSY, E> SpiDevice for Eh0Device<SPI, CS, D, BUSY>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+    D: DelayUs<u32>,
+    BUSY: OptionalEh0Pin,
+    E: core::fmt::Debug,
+    CS::Error: core::fmt::Debug,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        let Self {
+            spi,
+            cs,
+            delay,
+            busy,
+        } = self;
+        framed(
+            cs,
+            |cs, high| {
+                if high { cs.set_high() } else { cs.set_low() }.map_err(Eh0Error::ChipSelect)
+            },
+            || execute(spi, busy, delay, operations),
+        )
+    }
+}
+
+impl<SPI, CS, D, E> Tsc2046<Eh0Device<SPI, CS, D>>
+where
+    SPI: Transfer<u8, Error = E> + Write<u8, Error = E>,
+    CS: OutputPin,
+    D: DelayUs<u32>,
+    E: core::fmt::Debug,
+    CS::Error: core::fmt::Debug,
+{
+    /// Creates a new instance of the `Tsc2046` driver on an embedded-hal 0.2 SPI bus.
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - The SPI bus implementing the embedded-hal 0.2 `Transfer` and `Write` traits.
+    /// * `cs` - The chip select pin of the TSC2046.
+    /// * `delay` - The delay provider implementing the embedded-hal 0.2 `DelayUs` trait.
+    /// * `config` - The configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Tsc2046` instance or an error if the register update fails.
+    pub fn new_eh0(
+        spi: SPI,
+        cs: CS,
+        delay: D,
+        config: Config,
+    ) -> Result<Self, Error<Eh0Error<E, CS::Error>>> {
+        Self::from_config(Eh0Device::new(spi, cs, delay), config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::FakeCs;
+    use core::convert::Infallible;
+    use std::vec::Vec;
+
+    #[derive(Default)]
+    struct FakeBus {
+        written: Vec<u8>,
+        response: u8,
+    }
+
+    impl Transfer<u8> for FakeBus {
+        type Error = Infallible;
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Infallible> {
+            self.written.extend_from_slice(words);
+            words.fill(self.response);
+            Ok(words)
+        }
+    }
+
+    impl Write<u8> for FakeBus {
+        type Error = Infallible;
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+            self.written.extend_from_slice(words);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeDelay {
+        elapsed_us: u32,
+    }
+
+    impl DelayUs<u32> for FakeDelay {
+        fn delay_us(&mut self, us: u32) {
+            self.elapsed_us += us;
+        }
+    }
+
+    #[test]
+    fn test_eh0_device() {
+        let bus = FakeBus {
+            response: 0x5A,
+            ..FakeBus::default()
+        };
+        let mut device = Eh0Device::new(bus, FakeCs::default(), FakeDelay::default());
+        let mut read = [0u8; 3];
+        device
+            .transaction(&mut [
+                Operation::Write(&[0x93]),
+                Operation::Transfer(&mut read, &[1, 2]),
+                Operation::DelayNs(1500),
+            ])
+            .expect("Transaction failed");
+        assert_eq!(read, [0x5A; 3]);
+        let (bus, cs, delay) = device.release();
+        assert_eq!(bus.written, [0x93, 1, 2, 0]);
+        assert_eq!(cs.toggles, [false, true]);
+        assert_eq!(delay.elapsed_us, 2);
+
+        let driver = Tsc2046::new_eh0(bus, cs, delay, Config::new());
+        assert!(driver.is_ok());
+    }
+
+    struct FakeBusy {
+        /// The number of polls left before BUSY goes low.
+        busy_polls: core::cell::Cell<u32>,
+    }
+
+    impl InputPin for FakeBusy {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            let polls = self.busy_polls.get();
+            self.busy_polls.set(polls.saturating_sub(1));
+            Ok(polls > 0)
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    #[test]
+    fn test_eh0_busy_pin() {
+        let mut device =
+            Eh0Device::new(FakeBus::default(), FakeCs::default(), FakeDelay::default())
+                .with_busy_pin(FakeBusy {
+                    busy_polls: core::cell::Cell::new(3),
+                });
+        let mut read = [0u8; 2];
+        device
+            .transaction(&mut [Operation::Write(&[0x93]), Operation::Read(&mut read)])
+            .expect("Transaction failed");
+        let (_, _, delay) = device.release();
+        // BUSY is polled between the command and the result.
+        assert_eq!(delay.elapsed_us, 3);
+    }
+}
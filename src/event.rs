@@ -0,0 +1,1420 @@
+#[cfg(feature = "gestures")]
+use crate::gesture::GestureConfig;
+use crate::pressure::{PressureBand, PressureClassifier};
+pub use crate::stage::{CalibrationStage, Disabled, FilterStage};
+use crate::{ConfigError, PressureSemantics, Rect, TouchPoint};
+
+/// The maximum number of events waiting to be read from the tracker.
+pub const EVENT_QUEUE_CAPACITY: usize = 8;
+/// The maximum number of press levels the tracker can be configured with.
+pub const MAX_PRESS_LEVELS: usize = 4;
+/// The maximum number of listeners the tracker can call.
+pub const MAX_EVENT_LISTENERS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Kind of a touch event.
+pub enum EventKind {
+    /// The touch screen has been touched.
+    Down,
+    /// The touch point moved while touching the screen.
+    Move,
+    /// The touch has been released.
+    Up,
+    /// The pressure crossed the configured press level with the given index.
+    LevelPressed(u8),
+    /// The pressure dropped back below the configured press level with the given index.
+    LevelReleased(u8),
+    /// The contact has been held in place long enough to be a long press.
+    LongPress,
+    /// The long press is still held, emitted periodically if auto-repeat is enabled.
+    Repeat,
+    /// Vertical drag within the scroll region, by the given number of scroll steps.
+    Scroll(i16),
+    /// A contact has been released in click mode, the point is the centroid of the contact.
+    Click,
+    /// The touch point moved while drag mode is latched by a long press.
+    Drag,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Struct representing a touch event.
+pub struct TouchEvent {
+    /// The kind of the event.
+    pub kind: EventKind,
+    /// The touch point of the event. For `Up` events, this is the last touch point of the contact.
+    pub point: TouchPoint,
+    /// The pressure band of the touch point, if pressure bands are configured.
+    pub band: Option<PressureBand>,
+    /// The timestamp of the reading which produced the event, in milliseconds.
+    pub timestamp_ms: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A press level with hysteresis.
+///
+/// The level is pressed when the pressure value becomes firmer than `press`, and released when
+/// it becomes softer than `release` again. Firmer means lower in the raw ratio, and higher in the
+/// other [`PressureSemantics`], which must match the semantics configured in the driver.
+pub struct PressLevel {
+    /// The pressure value firmer than which the level is pressed.
+    pub press: f32,
+    /// The pressure value softer than which the level is released, not firmer than `press`.
+    pub release: f32,
+    /// The domain of the pressure values.
+    pub semantics: PressureSemantics,
+}
+
+impl PressLevel {
+    /// Checks that the level is pressed and released at finite values, in the right order.
+    ///
+    /// Being a `const fn`, it fails the build when asserted on a press level constant.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the level is valid, or the [`ConfigError`] found.
+    pub const fn validate(&self) -> Result<(), ConfigError> {
+        if !self.press.is_finite() || !self.release.is_finite() {
+            return Err(ConfigError::InvalidThreshold);
+        }
+        if self.semantics.is_firmer(self.release, self.press) {
+            return Err(ConfigError::InvertedHysteresis);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Parameters of the long press detection of an [`EventTracker`].
+pub struct LongPressConfig {
+    /// The time a contact has to be held for a long press, in milliseconds.
+    pub duration_ms: u32,
+    /// The maximum movement of a contact to be a long press.
+    pub max_movement: u16,
+}
+
+impl Default for LongPressConfig {
+    fn default() -> Self {
+        Self {
+            duration_ms: 800,
+            max_movement: 40,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// State of the long press detection of a contact.
+enum LongPressState {
+    /// The contact may still become a long press.
+    #[default]
+    Pending,
+    /// The contact moved too much to be a long press.
+    Cancelled,
+    /// The long press has been detected, the last event was emitted at the given timestamp.
+    Detected(u32),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// Running sum of the touch points of a contact, to compute its centroid.
+///
+/// The coordinates are summed on 64 bits, which cannot overflow before the count does. Once the
+/// count is exhausted, further points are ignored.
+struct Centroid {
+    /// The sum of the x-coordinates.
+    x: u64,
+    /// The sum of the y-coordinates.
+    y: u64,
+    /// The sum of the pressure values.
+    z: f32,
+    /// The number of touch points summed.
+    count: u32,
+}
+
+impl Centroid {
+    /// Adds a touch point to the sum.
+    fn add(&mut self, point: TouchPoint) {
+        let Some(count) = self.count.checked_add(1) else {
+            return;
+        };
+        self.x = self.x.saturating_add(point.x as u64);
+        self.y = self.y.saturating_add(point.y as u64);
+        self.z += point.z;
+        self.count = count;
+    }
+
+    /// Computes the centroid of the touch points summed, if any.
+    fn point(&self) -> Option<TouchPoint> {
+        let count = self.count as u64;
+        Some(TouchPoint {
+            x: self.x.checked_div(count)?.min(u16::MAX as u64) as u16,
+            y: self.y.checked_div(count)?.min(u16::MAX as u64) as u16,
+            z: self.z / self.count as f32,
+        })
+    }
+}
+
+/// Stage of an [`EventTracker`] recognizing gestures from the touch events.
+pub trait GestureStage {
+    /// The gestures recognized by the stage.
+    type Gesture;
+
+    /// Updates the recognizer with a touch event.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The touch event.
+    ///
+    /// # Returns
+    ///
+    /// The gesture completed by the event, if any.
+    fn recognize(&mut self, event: &TouchEvent) -> Option<Self::Gesture>;
+}
+
+impl GestureStage for Disabled {
+    /// A disabled stage recognizes no gesture.
+    type Gesture = Disabled;
+
+    #[inline(always)]
+    fn recognize(&mut self, _event: &TouchEvent) -> Option<Self::Gesture> {
+        None
+    }
+}
+
+/// Tracker turning successive touch readings into touch events.
+///
+/// The readings pass through the optional filter and calibration stages first, and the events
+/// through the optional gesture stage, such as a [`crate::gesture::GestureRecognizer`]. The stages
+/// are selected by the type parameters, and a stage set to [`Disabled`] is compiled out entirely,
+/// so unused features cost no flash.
+#[derive(Debug, Default)]
+pub struct EventTracker<F = Disabled, C = Disabled, G: GestureStage = Disabled> {
+    /// The filter stage.
+    filter: F,
+    /// The calibration stage.
+    calibration: C,
+    /// The gesture stage.
+    gestures: G,
+    /// The last gesture recognized and not yet read.
+    gesture: Option<G::Gesture>,
+    /// The last touch point of the current contact, if any.
+    last: Option<TouchPoint>,
+    /// The classifier used to assign pressure bands to events.
+    classifier: Option<PressureClassifier>,
+    /// The parameters of the long press detection.
+    long_press_config: LongPressConfig,
+    /// The configured press levels.
+    levels: [Option<PressLevel>; MAX_PRESS_LEVELS],
+    /// Whether each of the press levels is currently pressed or not.
+    levels_pressed: [bool; MAX_PRESS_LEVELS],
+    /// The interval of the repeat events following a long press in milliseconds, if enabled.
+    repeat_interval_ms: Option<u32>,
+    /// The first touch point of the current contact.
+    contact_origin: Option<TouchPoint>,
+    /// The timestamp of the start of the current contact in milliseconds.
+    contact_start_ms: u32,
+    /// The state of the long press detection of the current contact.
+    long_press: LongPressState,
+    /// The area of the scroll region and the raw distance of one scroll step, if enabled.
+    scroll_region: Option<(Rect, u16)>,
+    /// Whether the current contact started in the scroll region or not.
+    scrolling: bool,
+    /// The raw vertical distance not yet reported as scroll steps.
+    scroll_remainder: i32,
+    /// The minimum duration of a contact before any event is reported, in milliseconds.
+    min_press_ms: u32,
+    /// The timestamp of the first reading of the current contact, while it is too short.
+    press_start_ms: Option<u32>,
+    /// The tolerated duration of pressure dropouts while dragging, if the drag latch is enabled.
+    drag_latch_ms: Option<u32>,
+    /// Whether drag mode is latched for the current contact.
+    dragging: bool,
+    /// The timestamp of the start of the current pressure dropout while dragging.
+    dropout_start_ms: Option<u32>,
+    /// The largest displacement of a coalesced `Move` event, 0 if coalescing is disabled.
+    move_epsilon: u16,
+    /// The touch point of the last `Down` or `Move` event of the current contact.
+    reported: Option<TouchPoint>,
+    /// Whether single failed readings within a contact are interpolated.
+    interpolate_dropouts: bool,
+    /// The timestamp of the failed reading held back until the next reading.
+    pending_dropout_ms: Option<u32>,
+    /// The last reading passed to the filter stage, before filtering.
+    last_raw: Option<TouchPoint>,
+    /// The touch points of the current contact summed in click mode, if enabled.
+    click: Option<Centroid>,
+    /// The listeners called with every event.
+    listeners: [Option<fn(&TouchEvent)>; MAX_EVENT_LISTENERS],
+    /// Whether the events are only passed to the listeners, and not queued.
+    queue_disabled: bool,
+    /// Ring buffer of the events waiting to be read.
+    queue: [Option<TouchEvent>; EVENT_QUEUE_CAPACITY],
+    /// The index of the oldest event in the queue.
+    queue_head: usize,
+    /// The number of events in the queue.
+    queue_len: usize,
+}
+
+impl EventTracker {
+    /// Creates a new event tracker, with the filter, calibration and gesture stages disabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C, G: GestureStage> EventTracker<Disabled, C, G> {
+    /// Enables the filter stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The filter applied to the readings.
+    pub fn with_filter<F: FilterStage>(self, filter: F) -> EventTracker<F, C, G> {
+        self.with_stages(|_, calibration, gestures| (filter, calibration, gestures))
+    }
+}
+
+impl<F, G: GestureStage> EventTracker<F, Disabled, G> {
+    /// Enables the calibration stage.
+    ///
+    /// The coordinates of the events, the scroll region and the movement tolerances are then in
+    /// screen space.
+    ///
+    /// # Arguments
+    ///
+    /// * `calibration` - The mapping applied to the filtered readings.
+    pub fn with_calibration<C: CalibrationStage>(self, calibration: C) -> EventTracker<F, C, G> {
+        self.with_stages(|filter, _, gestures| (filter, calibration, gestures))
+    }
+}
+
+impl<F, C> EventTracker<F, C, Disabled> {
+    /// Enables the gesture stage.
+    ///
+    /// Every event is passed to the stage, and the gestures it recognizes are read with
+    /// [`EventTracker::next_gesture`].
+    ///
+    /// # Arguments
+    ///
+    /// * `gestures` - The recognizer of the gestures.
+    pub fn with_gestures<G: GestureStage>(self, gestures: G) -> EventTracker<F, C, G> {
+        self.with_stages(|filter, calibration, _| (filter, calibration, gestures))
+    }
+}
+
+impl<F, C, G: GestureStage> EventTracker<F, C, G> {
+    /// Returns the filter stage, to retune it without dropping the current contact.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the filter stage.
+    pub fn filter_mut(&mut self) -> &mut F {
+        &mut self.filter
+    }
+
+    /// Reads the last gesture recognized by the gesture stage.
+    ///
+    /// Only the last gesture is kept, so a gesture not read before the next one is recognized is
+    /// lost.
+    ///
+    /// # Returns
+    ///
+    /// The gesture, or `None` if no gesture was recognized since the last call.
+    pub fn next_gesture(&mut self) -> Option<G::Gesture> {
+        self.gesture.take()
+    }
+
+    /// Replaces the stages of the tracker, keeping its configuration and state. A gesture not
+    /// read yet is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `stages` - The function turning the filter, calibration and gesture stages into the new
+    ///   ones.
+    fn with_stages<F2, C2, G2: GestureStage>(
+        self,
+        stages: impl FnOnce(F, C, G) -> (F2, C2, G2),
+    ) -> EventTracker<F2, C2, G2> {
+        let Self {
+            filter,
+            calibration,
+            gestures,
+            gesture: _,
+            last,
+            classifier,
+            long_press_config,
+            levels,
+            levels_pressed,
+            repeat_interval_ms,
+            contact_origin,
+            contact_start_ms,
+            long_press,
+            scroll_region,
+            scrolling,
+            scroll_remainder,
+            min_press_ms,
+            press_start_ms,
+            drag_latch_ms,
+            dragging,
+            dropout_start_ms,
+            move_epsilon,
+            reported,
+            interpolate_dropouts,
+            pending_dropout_ms,
+            last_raw,
+            click,
+            listeners,
+            queue_disabled,
+            queue,
+            queue_head,
+            queue_len,
+        } = self;
+        let (filter, calibration, gestures) = stages(filter, calibration, gestures);
+        EventTracker {
+            filter,
+            calibration,
+            gestures,
+            gesture: None,
+            last,
+            classifier,
+            long_press_config,
+            levels,
+            levels_pressed,
+            repeat_interval_ms,
+            contact_origin,
+            contact_start_ms,
+            long_press,
+            scroll_region,
+            scrolling,
+            scroll_remainder,
+            min_press_ms,
+            press_start_ms,
+            drag_latch_ms,
+            dragging,
+            dropout_start_ms,
+            move_epsilon,
+            reported,
+            interpolate_dropouts,
+            pending_dropout_ms,
+            last_raw,
+            click,
+            listeners,
+            queue_disabled,
+            queue,
+            queue_head,
+            queue_len,
+        }
+    }
+
+    /// Enables the classification of the event pressure into bands.
+    ///
+    /// # Arguments
+    ///
+    /// * `classifier` - The classifier used to assign pressure bands to events.
+    pub fn with_pressure_bands(mut self, classifier: PressureClassifier) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    /// Sets the parameters of the long press detection.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The long press parameters.
+    pub fn with_long_press_config(mut self, config: LongPressConfig) -> Self {
+        self.long_press_config = config;
+        self
+    }
+
+    /// Sets the parameters of the long press detection from the gesture parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The gesture parameters, of which the long press duration and the tap movement
+    ///   tolerance are used.
+    #[cfg(feature = "gestures")]
+    pub fn with_gesture_config(self, config: GestureConfig) -> Self {
+        self.with_long_press_config(LongPressConfig {
+            duration_ms: config.long_press_ms,
+            max_movement: config.tap_max_movement,
+        })
+    }
+
+    /// Sets a scroll region, in which vertical drags produce `Scroll` events instead of position
+    /// events.
+    ///
+    /// Contacts starting inside the region only produce `Scroll` events until they are released,
+    /// even if they leave the region.
+    ///
+    /// # Arguments
+    ///
+    /// * `area` - The area of the scroll region, e.g. a strip along the right edge of the screen.
+    /// * `step` - The raw vertical distance of one scroll step (a value of 0 is treated as 1).
+    pub fn with_scroll_region(mut self, area: Rect, step: u16) -> Self {
+        self.scroll_region = Some((area, step.max(1)));
+        self
+    }
+
+    /// Adds a press level, for which `LevelPressed` and `LevelReleased` events are emitted.
+    ///
+    /// Levels are indexed in the order they are added. Levels beyond [`MAX_PRESS_LEVELS`] are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The press level to add.
+    pub fn with_press_level(mut self, level: PressLevel) -> Self {
+        if let Some(slot) = self.levels.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(level);
+        }
+        self
+    }
+
+    /// Requires contacts to last a minimum duration before any event is reported.
+    ///
+    /// Shorter contacts, like brushes and bounces, are ignored entirely. Longer contacts are
+    /// reported from the reading at which they reach the duration.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration_ms` - The minimum duration of a contact in milliseconds.
+    pub fn with_min_press_duration(mut self, duration_ms: u32) -> Self {
+        self.min_press_ms = duration_ms;
+        self
+    }
+
+    /// Enables the drag latch: a long press latches drag mode for the rest of the contact.
+    ///
+    /// While latched, motion is reported as `Drag` events, and releases shorter than the dropout
+    /// tolerance are bridged, as resistive panels lose pressure briefly during fast drags. The
+    /// `Up` event is emitted once the release lasts longer than the tolerance.
+    ///
+    /// # Arguments
+    ///
+    /// * `dropout_ms` - The tolerated duration of pressure dropouts in milliseconds.
+    pub fn with_drag_latch(mut self, dropout_ms: u32) -> Self {
+        self.drag_latch_ms = Some(dropout_ms);
+        self
+    }
+
+    /// Enables click mode, for button-style UIs.
+    ///
+    /// In click mode, the touch points of a contact are accumulated, and a single `Click` event
+    /// is emitted at release, located at the centroid of the contact. No other events are
+    /// emitted, which avoids jitter-induced misclicks at the border of buttons.
+    pub fn with_click_mode(mut self) -> Self {
+        self.click = Some(Centroid::default());
+        self
+    }
+
+    /// Enables the coalescing of small movements.
+    ///
+    /// `Move` events are only emitted once the contact moved by more than the epsilon along
+    /// either axis from the last reported position, so a UI redrawing on every event is not woken
+    /// up by jitter. The press level and long press detection still see every reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `epsilon` - The largest raw displacement merged into the next `Move` event.
+    pub fn with_move_coalescing(mut self, epsilon: u16) -> Self {
+        self.move_epsilon = epsilon;
+        self
+    }
+
+    /// Enables the interpolation of dropouts.
+    ///
+    /// A reading without touch within a contact is held back until the next reading. If the
+    /// contact continues, the missing point is interpolated between its neighbors instead of
+    /// emitting an `Up` and `Down` pair. Releases are therefore reported one reading late.
+    pub fn with_dropout_interpolation(mut self) -> Self {
+        self.interpolate_dropouts = true;
+        self
+    }
+
+    /// Adds a listener, called synchronously with every event as it is produced.
+    ///
+    /// Listeners are a simpler alternative to reading the queue in small superloop applications.
+    /// The events are queued all the same, the oldest being dropped if the queue is not read,
+    /// unless queueing is disabled with [`EventTracker::without_queue`]. Listeners needing
+    /// context, like a UI state, are passed to [`EventTracker::update_with`] instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The function called with every event.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the listener was added, `false` if [`MAX_EVENT_LISTENERS`] are already set.
+    pub fn add_listener(&mut self, listener: fn(&TouchEvent)) -> bool {
+        let Some(slot) = self.listeners.iter_mut().find(|slot| slot.is_none()) else {
+            return false;
+        };
+        *slot = Some(listener);
+        true
+    }
+
+    /// Disables the event queue, for applications handling every event in listeners.
+    ///
+    /// The events are only passed to the listeners, and [`EventTracker::next_event`] always
+    /// returns `None`.
+    pub fn without_queue(mut self) -> Self {
+        self.queue_disabled = true;
+        self
+    }
+
+    /// Enables periodic `Repeat` events while a long press is held, like keyboard auto-repeat.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval_ms` - The interval of the repeat events in milliseconds.
+    pub fn with_auto_repeat(mut self, interval_ms: u32) -> Self {
+        self.repeat_interval_ms = Some(interval_ms.max(1));
+        self
+    }
+}
+
+impl<F: FilterStage, C: CalibrationStage, G: GestureStage> EventTracker<F, C, G> {
+    /// Updates the tracker with the latest touch reading, queueing the resulting events.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    /// * `now_ms` - The timestamp of the reading in milliseconds, from a monotonic clock.
+    pub fn update(&mut self, touch: Option<TouchPoint>, now_ms: u32) {
+        self.update_with(touch, now_ms, |_| {});
+    }
+
+    /// Updates the tracker with the latest touch reading, passing the resulting events to a
+    /// listener.
+    ///
+    /// Dropouts are bridged on the raw reading, which then passes through the filter and
+    /// calibration stages, so the history of the filter survives a bridged dropout. The listener
+    /// is called after the registered listeners, and may borrow the state of the application. The
+    /// events are queued all the same, unless queueing is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    /// * `now_ms` - The timestamp of the reading in milliseconds, from a monotonic clock.
+    /// * `listener` - The function called with every event produced by the reading.
+    pub fn update_with(
+        &mut self,
+        touch: Option<TouchPoint>,
+        now_ms: u32,
+        mut listener: impl FnMut(&TouchEvent),
+    ) {
+        let listener: &mut dyn FnMut(&TouchEvent) = &mut listener;
+        if self.bridge_drag_dropout(touch.is_some(), now_ms) {
+            return;
+        }
+        if self.interpolate_dropouts {
+            match (self.pending_dropout_ms.take(), self.last_raw, touch) {
+                (Some(dropout_ms), Some(last), Some(point)) => {
+                    let interpolated = TouchPoint {
+                        x: last.x.midpoint(point.x),
+                        y: last.y.midpoint(point.y),
+                        z: (last.z + point.z) / 2.0,
+                    };
+                    self.stage(Some(interpolated), dropout_ms, listener);
+                }
+                (Some(dropout_ms), _, _) => self.stage(None, dropout_ms, listener),
+                (None, Some(_), None) if self.last.is_some() => {
+                    self.pending_dropout_ms = Some(now_ms);
+                    return;
+                }
+                (None, _, _) => {}
+            }
+        }
+        self.stage(touch, now_ms, listener);
+    }
+
+    /// Passes a raw reading through the filter and calibration stages, then processes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The raw touch reading, `None` if the screen is not touched.
+    /// * `now_ms` - The timestamp of the reading in milliseconds.
+    /// * `listener` - The function called with every event.
+    fn stage(
+        &mut self,
+        touch: Option<TouchPoint>,
+        now_ms: u32,
+        listener: &mut dyn FnMut(&TouchEvent),
+    ) {
+        self.last_raw = touch;
+        let touch = self
+            .filter
+            .filter(touch)
+            .map(|point| self.calibration.calibrate(point));
+        self.process(touch, now_ms, listener);
+    }
+}
+
+impl<F, C, G: GestureStage> EventTracker<F, C, G> {
+    /// Processes a touch reading, queueing the resulting events.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the touch reading, `None` if the screen is not touched.
+    /// * `now_ms` - The timestamp of the reading in milliseconds.
+    /// * `listener` - The function called with every event.
+    fn process(
+        &mut self,
+        touch: Option<TouchPoint>,
+        now_ms: u32,
+        listener: &mut dyn FnMut(&TouchEvent),
+    ) {
+        if touch.is_none() {
+            self.press_start_ms = None;
+        } else if self.last.is_none() && self.min_press_ms > 0 {
+            let start_ms = *self.press_start_ms.get_or_insert(now_ms);
+            if now_ms.wrapping_sub(start_ms) < self.min_press_ms {
+                return;
+            }
+        }
+        if let Some(centroid) = &mut self.click {
+            match touch {
+                Some(point) => centroid.add(point),
+                None => {
+                    let click = centroid.point();
+                    *centroid = Centroid::default();
+                    if let Some(point) = click {
+                        self.push(EventKind::Click, point, now_ms, listener);
+                    }
+                }
+            }
+            self.last = touch;
+            return;
+        }
+        if self.update_drag(touch, now_ms, listener) {
+            return;
+        }
+        if self.update_scroll(touch, now_ms, listener) {
+            self.last = touch;
+            return;
+        }
+        match (self.last, touch) {
+            (None, Some(point)) => {
+                self.contact_origin = Some(point);
+                self.contact_start_ms = now_ms;
+                self.long_press = LongPressState::Pending;
+                self.reported = Some(point);
+                self.push(EventKind::Down, point, now_ms, listener);
+                self.update_levels(point, now_ms, listener);
+                self.update_long_press(point, now_ms, listener);
+            }
+            (Some(_), Some(point)) => {
+                let coalesced = self.reported.is_some_and(|reported| {
+                    reported.x.abs_diff(point.x) <= self.move_epsilon
+                        && reported.y.abs_diff(point.y) <= self.move_epsilon
+                });
+                if self.move_epsilon == 0 || !coalesced {
+                    self.reported = Some(point);
+                    self.push(EventKind::Move, point, now_ms, listener);
+                }
+                self.update_levels(point, now_ms, listener);
+                self.update_long_press(point, now_ms, listener);
+            }
+            (Some(last), None) => {
+                let levels_pressed = core::mem::take(&mut self.levels_pressed);
+                for (index, pressed) in levels_pressed.into_iter().enumerate() {
+                    if pressed {
+                        self.push(
+                            EventKind::LevelReleased(index as u8),
+                            last,
+                            now_ms,
+                            listener,
+                        );
+                    }
+                }
+                self.push(EventKind::Up, last, now_ms, listener);
+            }
+            (None, None) => {}
+        }
+        self.last = touch;
+    }
+
+    /// Reads the oldest event waiting in the queue.
+    ///
+    /// # Returns
+    ///
+    /// The oldest `TouchEvent`, or `None` if there are no events waiting.
+    pub fn next_event(&mut self) -> Option<TouchEvent> {
+        if self.queue_len == 0 {
+            return None;
+        }
+        let event = self.queue.get_mut(self.queue_head).and_then(Option::take);
+        self.queue_head = self.queue_head.wrapping_add(1) % EVENT_QUEUE_CAPACITY;
+        self.queue_len = self.queue_len.saturating_sub(1);
+        event
+    }
+
+    /// Bridges the pressure dropouts of contacts with a latched drag mode, before the reading
+    /// reaches the filter stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `touched` - Whether the latest reading detected a touch.
+    /// * `now_ms` - The timestamp of the reading in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the reading is a dropout shorter than the tolerance, and must be skipped.
+    fn bridge_drag_dropout(&mut self, touched: bool, now_ms: u32) -> bool {
+        let (true, Some(dropout_ms)) = (self.dragging, self.drag_latch_ms) else {
+            return false;
+        };
+        if touched {
+            self.dropout_start_ms = None;
+            return false;
+        }
+        let start_ms = *self.dropout_start_ms.get_or_insert(now_ms);
+        if now_ms.wrapping_sub(start_ms) < dropout_ms {
+            return true;
+        }
+        // The release is final, let it be handled as usual.
+        self.dragging = false;
+        self.dropout_start_ms = None;
+        false
+    }
+
+    /// Emits the drag events of contacts with a latched drag mode.
+    ///
+    /// The dropouts have been bridged by [`EventTracker::bridge_drag_dropout`] already.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading.
+    /// * `now_ms` - The timestamp of the reading in milliseconds.
+    /// * `listener` - The function called with every event.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the reading has been handled as part of the drag.
+    fn update_drag(
+        &mut self,
+        touch: Option<TouchPoint>,
+        now_ms: u32,
+        listener: &mut dyn FnMut(&TouchEvent),
+    ) -> bool {
+        let (true, Some(_)) = (self.dragging, self.drag_latch_ms) else {
+            return false;
+        };
+        let Some(point) = touch else {
+            self.dragging = false;
+            return false;
+        };
+        self.push(EventKind::Drag, point, now_ms, listener);
+        self.last = Some(point);
+        true
+    }
+
+    /// Emits the scroll events of contacts in the scroll region.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading.
+    /// * `now_ms` - The timestamp of the reading in milliseconds.
+    /// * `listener` - The function called with every event.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the reading belongs to a contact in the scroll region, and must not produce
+    /// position events.
+    fn update_scroll(
+        &mut self,
+        touch: Option<TouchPoint>,
+        now_ms: u32,
+        listener: &mut dyn FnMut(&TouchEvent),
+    ) -> bool {
+        let Some((area, step)) = self.scroll_region else {
+            return false;
+        };
+        match (self.last, touch) {
+            (None, Some(point)) if area.contains(point.x, point.y) => {
+                self.scrolling = true;
+                self.scroll_remainder = 0;
+            }
+            (Some(last), Some(point)) if self.scrolling => {
+                // The remainder is the travel not reported yet, bounded by the coordinate range,
+                // but it may exceed the range of a single event.
+                self.scroll_remainder = self
+                    .scroll_remainder
+                    .saturating_add((point.y as i32).saturating_sub(last.y as i32));
+                let steps = self
+                    .scroll_remainder
+                    .checked_div(step as i32)
+                    .unwrap_or_default()
+                    .clamp(i16::MIN as i32, i16::MAX as i32);
+                if steps != 0 {
+                    self.scroll_remainder = self
+                        .scroll_remainder
+                        .saturating_sub(steps.saturating_mul(step as i32));
+                    self.push(EventKind::Scroll(steps as i16), point, now_ms, listener);
+                }
+            }
+            (_, None) if self.scrolling => self.scrolling = false,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Emits the press level events for a touch point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point of the current contact.
+    /// * `now_ms` - The timestamp of the touch point in milliseconds.
+    /// * `listener` - The function called with every event.
+    fn update_levels(
+        &mut self,
+        point: TouchPoint,
+        now_ms: u32,
+        listener: &mut dyn FnMut(&TouchEvent),
+    ) {
+        let levels = self.levels;
+        for (index, level) in levels.into_iter().enumerate() {
+            let (Some(level), Some(pressed)) = (level, self.levels_pressed.get_mut(index)) else {
+                continue;
+            };
+            let kind = if !*pressed && level.semantics.is_firmer(point.z, level.press) {
+                EventKind::LevelPressed(index as u8)
+            } else if *pressed && level.semantics.is_firmer(level.release, point.z) {
+                EventKind::LevelReleased(index as u8)
+            } else {
+                continue;
+            };
+            *pressed = !*pressed;
+            self.push(kind, point, now_ms, listener);
+        }
+    }
+
+    /// Emits the long press and repeat events for a touch point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point of the current contact.
+    /// * `now_ms` - The timestamp of the touch point in milliseconds.
+    /// * `listener` - The function called with every event.
+    fn update_long_press(
+        &mut self,
+        point: TouchPoint,
+        now_ms: u32,
+        listener: &mut dyn FnMut(&TouchEvent),
+    ) {
+        match self.long_press {
+            LongPressState::Pending => {
+                let origin = self.contact_origin.unwrap_or(point);
+                let max_movement = self.long_press_config.max_movement;
+                if origin.x.abs_diff(point.x) > max_movement
+                    || origin.y.abs_diff(point.y) > max_movement
+                {
+                    self.long_press = LongPressState::Cancelled;
+                } else if now_ms.wrapping_sub(self.contact_start_ms)
+                    >= self.long_press_config.duration_ms
+                {
+                    self.long_press = LongPressState::Detected(now_ms);
+                    self.dragging = self.drag_latch_ms.is_some();
+                    self.push(EventKind::LongPress, point, now_ms, listener);
+                }
+            }
+            LongPressState::Detected(last_ms) => {
+                if let Some(interval_ms) = self.repeat_interval_ms {
+                    if now_ms.wrapping_sub(last_ms) >= interval_ms {
+                        self.long_press =
+                            LongPressState::Detected(last_ms.wrapping_add(interval_ms));
+                        self.push(EventKind::Repeat, point, now_ms, listener);
+                    }
+                }
+            }
+            LongPressState::Cancelled => {}
+        }
+    }
+
+    /// Queues an event, dropping the oldest one if the queue is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of the event.
+    /// * `point` - The touch point of the event.
+    /// * `timestamp_ms` - The timestamp of the event in milliseconds.
+    /// * `listener` - The function called with the event after the registered listeners.
+    fn push(
+        &mut self,
+        kind: EventKind,
+        point: TouchPoint,
+        timestamp_ms: u32,
+        listener: &mut dyn FnMut(&TouchEvent),
+    ) {
+        let event = TouchEvent {
+            kind,
+            point,
+            band: self
+                .classifier
+                .map(|classifier| classifier.classify(point.z)),
+            timestamp_ms,
+        };
+        if let Some(gesture) = self.gestures.recognize(&event) {
+            self.gesture = Some(gesture);
+        }
+        for registered in self.listeners.iter().flatten() {
+            registered(&event);
+        }
+        listener(&event);
+        if self.queue_disabled {
+            return;
+        }
+        if self.queue_len == EVENT_QUEUE_CAPACITY {
+            self.next_event();
+        }
+        let index = self.queue_head.wrapping_add(self.queue_len) % EVENT_QUEUE_CAPACITY;
+        if let Some(slot) = self.queue.get_mut(index) {
+            *slot = Some(event);
+            self.queue_len = self.queue_len.saturating_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: u16, y: u16, z: f32) -> TouchPoint {
+        TouchPoint { x, y, z }
+    }
+
+    fn kinds<F, C, G: GestureStage>(tracker: &mut EventTracker<F, C, G>) -> Vec<EventKind> {
+        core::iter::from_fn(|| tracker.next_event())
+            .map(|event| event.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_event_sequence() {
+        let mut tracker = EventTracker::new();
+        tracker.update(None, 0);
+        assert_eq!(tracker.next_event(), None);
+        tracker.update(Some(point(10, 10, 20.0)), 20);
+        let down = tracker.next_event().unwrap();
+        assert_eq!((down.kind, down.band), (EventKind::Down, None));
+        tracker.update(Some(point(12, 10, 20.0)), 40);
+        assert_eq!(tracker.next_event().unwrap().kind, EventKind::Move);
+        tracker.update(None, 60);
+        let up = tracker.next_event().unwrap();
+        assert_eq!((up.kind, up.point), (EventKind::Up, point(12, 10, 20.0)));
+        tracker.update(None, 80);
+        assert_eq!(tracker.next_event(), None);
+    }
+
+    #[test]
+    fn test_press_level_validation() {
+        const LEVEL: PressLevel = PressLevel {
+            press: 10.0,
+            release: 12.0,
+            semantics: PressureSemantics::RawRatio,
+        };
+        const _: () = assert!(LEVEL.validate().is_ok());
+
+        let level = PressLevel {
+            release: 8.0,
+            ..LEVEL
+        };
+        assert_eq!(level.validate(), Err(ConfigError::InvertedHysteresis));
+        let level = PressLevel {
+            semantics: PressureSemantics::Normalized,
+            ..LEVEL
+        };
+        assert_eq!(level.validate(), Err(ConfigError::InvertedHysteresis));
+    }
+
+    #[test]
+    fn test_disabled_gesture_stage() {
+        let mut tracker = EventTracker::new();
+        tracker.update(Some(point(10, 10, 20.0)), 0);
+        tracker.update(None, 50);
+        assert_eq!(kinds(&mut tracker), [EventKind::Down, EventKind::Up]);
+        assert_eq!(tracker.next_gesture(), None);
+    }
+
+    #[cfg(feature = "gestures")]
+    #[test]
+    fn test_gesture_stage() {
+        use crate::gesture::{Gesture, GestureRecognizer};
+
+        let mut tracker =
+            EventTracker::new().with_gestures(GestureRecognizer::new(GestureConfig::default()));
+        tracker.update(Some(point(10, 10, 20.0)), 0);
+        assert!(tracker.next_gesture().is_none());
+        tracker.update(None, 50);
+        assert!(matches!(tracker.next_gesture(), Some(Gesture::Tap(_))));
+        assert!(tracker.next_gesture().is_none());
+        // The events are still queued for the application.
+        assert_eq!(tracker.next_event().unwrap().kind, EventKind::Down);
+        assert_eq!(tracker.next_event().unwrap().kind, EventKind::Up);
+    }
+
+    #[test]
+    fn test_event_pressure_bands() {
+        let mut tracker =
+            EventTracker::new().with_pressure_bands(PressureClassifier::new(40.0, 10.0));
+        tracker.update(Some(point(10, 10, 60.0)), 0);
+        assert_eq!(
+            tracker.next_event().unwrap().band,
+            Some(PressureBand::Light)
+        );
+        tracker.update(Some(point(10, 10, 5.0)), 20);
+        assert_eq!(tracker.next_event().unwrap().band, Some(PressureBand::Firm));
+    }
+
+    #[test]
+    fn test_press_levels_with_hysteresis() {
+        let mut tracker = EventTracker::new().with_press_level(PressLevel {
+            press: 10.0,
+            release: 15.0,
+            semantics: PressureSemantics::RawRatio,
+        });
+        tracker.update(Some(point(10, 10, 20.0)), 0);
+        assert_eq!(kinds(&mut tracker), [EventKind::Down]);
+        tracker.update(Some(point(10, 10, 8.0)), 20);
+        assert_eq!(
+            kinds(&mut tracker),
+            [EventKind::Move, EventKind::LevelPressed(0)]
+        );
+        tracker.update(Some(point(10, 10, 12.0)), 40);
+        assert_eq!(kinds(&mut tracker), [EventKind::Move]);
+        tracker.update(Some(point(10, 10, 16.0)), 60);
+        assert_eq!(
+            kinds(&mut tracker),
+            [EventKind::Move, EventKind::LevelReleased(0)]
+        );
+        tracker.update(Some(point(10, 10, 8.0)), 80);
+        tracker.update(None, 100);
+        assert_eq!(
+            kinds(&mut tracker),
+            [
+                EventKind::Move,
+                EventKind::LevelPressed(0),
+                EventKind::LevelReleased(0),
+                EventKind::Up
+            ]
+        );
+    }
+
+    #[test]
+    fn test_press_levels_normalized() {
+        let mut tracker = EventTracker::new()
+            .with_press_level(PressLevel {
+                press: 0.8,
+                release: 0.6,
+                semantics: PressureSemantics::Normalized,
+            })
+            .with_pressure_bands(PressureClassifier::with_semantics(
+                0.5,
+                0.8,
+                PressureSemantics::Normalized,
+            ));
+        tracker.update(Some(point(10, 10, 0.4)), 0);
+        assert_eq!(
+            tracker.next_event().unwrap().band,
+            Some(PressureBand::Light)
+        );
+        tracker.update(Some(point(10, 10, 0.9)), 20);
+        assert_eq!(
+            kinds(&mut tracker),
+            [EventKind::Move, EventKind::LevelPressed(0)]
+        );
+        tracker.update(Some(point(10, 10, 0.7)), 40);
+        assert_eq!(
+            tracker.next_event().unwrap().band,
+            Some(PressureBand::Normal)
+        );
+        tracker.update(Some(point(10, 10, 0.5)), 60);
+        assert_eq!(
+            kinds(&mut tracker),
+            [EventKind::Move, EventKind::LevelReleased(0)]
+        );
+    }
+
+    #[test]
+    fn test_long_press_with_auto_repeat() {
+        let mut tracker = EventTracker::new().with_auto_repeat(100);
+        tracker.update(Some(point(100, 100, 20.0)), 1000);
+        tracker.update(Some(point(110, 95, 20.0)), 1500);
+        assert_eq!(kinds(&mut tracker), [EventKind::Down, EventKind::Move]);
+        tracker.update(Some(point(110, 95, 20.0)), 1800);
+        assert_eq!(kinds(&mut tracker), [EventKind::Move, EventKind::LongPress]);
+        tracker.update(Some(point(110, 95, 20.0)), 1850);
+        assert_eq!(kinds(&mut tracker), [EventKind::Move]);
+        tracker.update(Some(point(110, 95, 20.0)), 1905);
+        assert_eq!(kinds(&mut tracker), [EventKind::Move, EventKind::Repeat]);
+        tracker.update(Some(point(110, 95, 20.0)), 2000);
+        assert_eq!(kinds(&mut tracker), [EventKind::Move, EventKind::Repeat]);
+    }
+
+    #[test]
+    fn test_long_press_cancelled_by_movement() {
+        let mut tracker = EventTracker::new();
+        tracker.update(Some(point(100, 100, 20.0)), 0);
+        tracker.update(Some(point(200, 100, 20.0)), 100);
+        tracker.update(Some(point(100, 100, 20.0)), 1000);
+        assert_eq!(
+            kinds(&mut tracker),
+            [EventKind::Down, EventKind::Move, EventKind::Move]
+        );
+    }
+
+    #[test]
+    fn test_scroll_region() {
+        let strip = Rect {
+            x_min: 3600,
+            y_min: 0,
+            x_max: 4095,
+            y_max: 4095,
+        };
+        let mut tracker = EventTracker::new().with_scroll_region(strip, 100);
+        tracker.update(Some(point(3800, 1000, 20.0)), 0);
+        tracker.update(Some(point(3800, 1150, 20.0)), 20);
+        tracker.update(Some(point(3500, 1240, 20.0)), 40);
+        tracker.update(Some(point(3500, 900, 20.0)), 60);
+        tracker.update(None, 80);
+        assert_eq!(
+            kinds(&mut tracker),
+            [
+                EventKind::Scroll(1),
+                EventKind::Scroll(1),
+                EventKind::Scroll(-3)
+            ]
+        );
+        // Contacts outside of the region produce position events.
+        tracker.update(Some(point(1000, 1000, 20.0)), 100);
+        tracker.update(None, 120);
+        assert_eq!(kinds(&mut tracker), [EventKind::Down, EventKind::Up]);
+    }
+
+    #[test]
+    fn test_scroll_beyond_event_range() {
+        let strip = Rect {
+            x_min: 0,
+            y_min: 0,
+            x_max: 4095,
+            y_max: 4095,
+        };
+        let mut tracker = EventTracker::new().with_scroll_region(strip, 1);
+        tracker.update(Some(point(100, 0, 20.0)), 0);
+        tracker.update(Some(point(100, 60000, 20.0)), 20);
+        tracker.update(Some(point(100, 60000, 20.0)), 40);
+        assert_eq!(
+            kinds(&mut tracker),
+            [EventKind::Scroll(i16::MAX), EventKind::Scroll(27233)]
+        );
+    }
+
+    #[test]
+    fn test_centroid_limits() {
+        let mut centroid = Centroid::default();
+        centroid.add(point(u16::MAX, u16::MAX, 1.0));
+        centroid.count = u32::MAX;
+        centroid.x = u16::MAX as u64 * u32::MAX as u64;
+        centroid.y = centroid.x;
+        centroid.add(point(0, 0, 1.0));
+        assert_eq!(centroid.count, u32::MAX);
+        let centroid = centroid.point().expect("No centroid");
+        assert_eq!((centroid.x, centroid.y), (u16::MAX, u16::MAX));
+    }
+
+    #[test]
+    fn test_click_mode() {
+        let mut tracker = EventTracker::new().with_click_mode();
+        tracker.update(Some(point(1000, 2000, 20.0)), 0);
+        tracker.update(Some(point(1010, 1990, 10.0)), 10);
+        tracker.update(Some(point(1020, 2000, 30.0)), 20);
+        assert_eq!(tracker.next_event(), None);
+        tracker.update(None, 30);
+        let click = tracker.next_event().expect("No click event");
+        assert_eq!(click.kind, EventKind::Click);
+        assert_eq!((click.point.x, click.point.y), (1010, 1996));
+        assert_eq!(click.point.z, 20.0);
+        tracker.update(None, 40);
+        assert_eq!(tracker.next_event(), None);
+    }
+
+    #[test]
+    fn test_move_coalescing() {
+        let mut tracker = EventTracker::new().with_move_coalescing(8);
+        tracker.update(Some(point(1000, 1000, 20.0)), 0);
+        tracker.update(Some(point(1004, 998, 20.0)), 10);
+        tracker.update(Some(point(1008, 1003, 20.0)), 20);
+        assert_eq!(kinds(&mut tracker), [EventKind::Down]);
+        tracker.update(Some(point(1009, 1003, 20.0)), 30);
+        let moved = tracker.next_event().unwrap();
+        assert_eq!(
+            (moved.kind, moved.point),
+            (EventKind::Move, point(1009, 1003, 20.0))
+        );
+        // The displacement is measured from the last reported position.
+        tracker.update(Some(point(1015, 1003, 20.0)), 40);
+        tracker.update(None, 50);
+        assert_eq!(kinds(&mut tracker), [EventKind::Up]);
+    }
+
+    #[test]
+    fn test_dropout_interpolation() {
+        let mut tracker = EventTracker::new().with_dropout_interpolation();
+        tracker.update(Some(point(1000, 1000, 20.0)), 0);
+        tracker.update(None, 10);
+        assert_eq!(kinds(&mut tracker), [EventKind::Down]);
+        tracker.update(Some(point(1020, 1000, 20.0)), 20);
+        let interpolated = tracker.next_event().unwrap();
+        assert_eq!(
+            (interpolated.point, interpolated.timestamp_ms),
+            (point(1010, 1000, 20.0), 10)
+        );
+        assert_eq!(kinds(&mut tracker), [EventKind::Move]);
+        tracker.update(None, 30);
+        tracker.update(None, 40);
+        let up = tracker.next_event().unwrap();
+        assert_eq!((up.kind, up.timestamp_ms), (EventKind::Up, 30));
+    }
+
+    /// Filter stage counting the readings since its history was last cleared.
+    #[derive(Debug, Default)]
+    struct HistoryFilter {
+        history: u32,
+    }
+
+    impl FilterStage for HistoryFilter {
+        fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+            self.history = if touch.is_some() { self.history + 1 } else { 0 };
+            touch
+        }
+    }
+
+    #[test]
+    fn test_dropout_keeps_filter_history() {
+        let mut tracker = EventTracker::new()
+            .with_dropout_interpolation()
+            .with_filter(HistoryFilter::default());
+        tracker.update(Some(point(1000, 1000, 20.0)), 0);
+        tracker.update(None, 10);
+        tracker.update(Some(point(1020, 1000, 20.0)), 20);
+        // The interpolated point and the next reading extend the history of the filter.
+        assert_eq!(tracker.filter.history, 3);
+        tracker.update(None, 30);
+        assert_eq!(tracker.filter.history, 3);
+        // The dropout turns into a release, which clears the history.
+        tracker.update(None, 40);
+        assert_eq!(tracker.filter.history, 0);
+    }
+
+    #[test]
+    fn test_drag_dropout_keeps_filter_history() {
+        let mut tracker = EventTracker::new()
+            .with_drag_latch(30)
+            .with_filter(HistoryFilter::default());
+        tracker.update(Some(point(1000, 1000, 20.0)), 0);
+        tracker.update(Some(point(1000, 1000, 20.0)), 800);
+        tracker.update(None, 810);
+        tracker.update(Some(point(1200, 1000, 20.0)), 820);
+        // The bridged dropout never reaches the filter.
+        assert_eq!(tracker.filter.history, 3);
+        tracker.update(None, 830);
+        tracker.update(None, 860);
+        assert_eq!(tracker.filter.history, 0);
+        assert_eq!(kinds(&mut tracker).last(), Some(&EventKind::Up));
+    }
+
+    #[test]
+    fn test_event_listeners() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+        static DOWNS: AtomicU32 = AtomicU32::new(0);
+        let mut tracker = EventTracker::new();
+        assert!(tracker.add_listener(|event| {
+            if event.kind == EventKind::Down {
+                DOWNS.fetch_add(1, Ordering::Relaxed);
+            }
+        }));
+        for _ in 1..MAX_EVENT_LISTENERS {
+            assert!(tracker.add_listener(|_| {}));
+        }
+        assert!(!tracker.add_listener(|_| {}));
+        let mut events = 0;
+        tracker.update_with(Some(point(1000, 1000, 20.0)), 0, |_| events += 1);
+        tracker.update_with(Some(point(1010, 1000, 20.0)), 10, |_| events += 1);
+        tracker.update(None, 20);
+        assert_eq!(DOWNS.load(Ordering::Relaxed), 1);
+        assert_eq!(events, 2);
+        assert_eq!(
+            kinds(&mut tracker),
+            [EventKind::Down, EventKind::Move, EventKind::Up]
+        );
+    }
+
+    #[test]
+    fn test_without_queue() {
+        let mut tracker = EventTracker::new().without_queue();
+        let mut kinds = Vec::new();
+        tracker.update_with(Some(point(1000, 1000, 20.0)), 0, |event| {
+            kinds.push(event.kind)
+        });
+        tracker.update_with(None, 10, |event| kinds.push(event.kind));
+        assert_eq!(kinds, [EventKind::Down, EventKind::Up]);
+        assert_eq!(tracker.next_event(), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "calibration", feature = "filters"))]
+    fn test_tracker_stages() {
+        use crate::calibration::Calibration;
+        use crate::filter::WeightedMovingAverage;
+
+        let mut tracker = EventTracker::new()
+            .with_filter(WeightedMovingAverage::<2>::new())
+            .with_calibration(Calibration::new(0, 4095, 0, 4095, 320, 240));
+        tracker.update(Some(point(2048, 2048, 10.0)), 0);
+        tracker.update(Some(point(4095, 2048, 10.0)), 10);
+        let down = tracker.next_event().expect("No down event");
+        assert_eq!((down.point.x, down.point.y), (159, 119));
+        // The filter weights the readings 1:2 before they are mapped to the screen.
+        let moved = tracker.next_event().expect("No move event");
+        assert_eq!(moved.point.x, 265);
+    }
+
+    #[test]
+    fn test_disabled_stages_are_zero_sized() {
+        assert_eq!(core::mem::size_of::<Disabled>(), 0);
+    }
+
+    #[test]
+    fn test_min_press_duration() {
+        let mut tracker = EventTracker::new().with_min_press_duration(50);
+        tracker.update(Some(point(1000, 1000, 20.0)), 0);
+        tracker.update(Some(point(1000, 1000, 20.0)), 30);
+        tracker.update(None, 40);
+        assert_eq!(kinds(&mut tracker), []);
+        tracker.update(Some(point(1000, 1000, 20.0)), 100);
+        tracker.update(Some(point(1000, 1000, 20.0)), 140);
+        assert_eq!(kinds(&mut tracker), []);
+        tracker.update(Some(point(1000, 1000, 20.0)), 150);
+        tracker.update(None, 160);
+        assert_eq!(kinds(&mut tracker), [EventKind::Down, EventKind::Up]);
+    }
+
+    #[test]
+    fn test_drag_latch() {
+        let mut tracker = EventTracker::new().with_drag_latch(30);
+        tracker.update(Some(point(1000, 1000, 20.0)), 0);
+        tracker.update(Some(point(1000, 1000, 20.0)), 800);
+        assert_eq!(
+            kinds(&mut tracker),
+            [EventKind::Down, EventKind::Move, EventKind::LongPress]
+        );
+        tracker.update(Some(point(1200, 1000, 20.0)), 810);
+        // A brief dropout is bridged.
+        tracker.update(None, 820);
+        tracker.update(Some(point(1400, 1000, 20.0)), 840);
+        assert_eq!(kinds(&mut tracker), [EventKind::Drag, EventKind::Drag]);
+        tracker.update(None, 850);
+        tracker.update(None, 870);
+        assert_eq!(kinds(&mut tracker), []);
+        tracker.update(None, 880);
+        let up = tracker.next_event().expect("No up event");
+        assert_eq!((up.kind, up.point.x), (EventKind::Up, 1400));
+    }
+}
@@ -0,0 +1,335 @@
+use core::fmt;
+
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::diagnostics::NoiseFloor;
+use crate::{Error, OptionalDelay, OptionalInputPin, RoutineDelay, Tsc2046};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Limits a panel has to meet to pass the production test.
+pub struct FactoryLimits {
+    /// The smallest chip temperature accepted by the self-test, in degrees Celsius.
+    pub min_temperature_celsius: f32,
+    /// The largest chip temperature accepted by the self-test, in degrees Celsius.
+    pub max_temperature_celsius: f32,
+    /// The largest peak-to-peak noise of the Z1 channel accepted without a touch, in ADC counts.
+    pub max_noise: u16,
+    /// The largest deviation of a corner touch from its expected position, in ADC counts.
+    pub corner_tolerance: u16,
+}
+
+impl Default for FactoryLimits {
+    fn default() -> Self {
+        Self {
+            min_temperature_celsius: -10.0,
+            max_temperature_celsius: 60.0,
+            max_noise: 32,
+            corner_tolerance: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Corner of the touch screen.
+pub enum Corner {
+    /// The corner with the smallest x and y values.
+    TopLeft,
+    /// The corner with the largest x and the smallest y values.
+    TopRight,
+    /// The corner with the smallest x and the largest y values.
+    BottomLeft,
+    /// The corner with the largest x and y values.
+    BottomRight,
+}
+
+impl Corner {
+    /// All corners, in the order of the report.
+    pub const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+
+    /// Returns the position of the corner in the report.
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Returns the name of the corner used in the report.
+    fn name(&self) -> &'static str {
+        match self {
+            Corner::TopLeft => "top_left",
+            Corner::TopRight => "top_right",
+            Corner::BottomLeft => "bottom_left",
+            Corner::BottomRight => "bottom_right",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Result of the verification of a corner touch.
+pub struct CornerResult {
+    /// The expected raw position of the touch.
+    pub expected: (u16, u16),
+    /// The raw position read, or `None` if no touch was detected.
+    pub measured: Option<(u16, u16)>,
+    /// Whether the touch was detected within tolerance.
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Machine-readable result of the production test.
+///
+/// The `Display` implementation prints one `key=value` pair per line, for easy parsing by the
+/// test station. Steps which were not run are omitted.
+pub struct FactoryReport {
+    /// The temperature measured by the self-test, if it completed.
+    pub temperature_celsius: Option<f32>,
+    /// Whether the self-test passed, if it was run.
+    pub self_test_passed: Option<bool>,
+    /// The noise floor measured without a touch, if measured.
+    pub noise: Option<NoiseFloor>,
+    /// Whether the noise is within limits, if measured.
+    pub noise_passed: Option<bool>,
+    /// The result of every corner verification, in the order of [`Corner::ALL`].
+    pub corners: [Option<CornerResult>; 4],
+}
+
+impl FactoryReport {
+    /// Checks whether the panel passed the production test.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every step was run and passed.
+    pub fn passed(&self) -> bool {
+        self.self_test_passed == Some(true)
+            && self.noise_passed == Some(true)
+            && self
+                .corners
+                .iter()
+                .all(|corner| corner.is_some_and(|corner| corner.passed))
+    }
+}
+
+impl fmt::Display for FactoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(temperature) = self.temperature_celsius {
+            writeln!(f, "temperature_celsius={temperature:.1}")?;
+        }
+        if let Some(passed) = self.self_test_passed {
+            writeln!(f, "self_test_passed={passed}")?;
+        }
+        if let Some(noise) = self.noise {
+            writeln!(f, "noise_peak_to_peak={}", noise.peak_to_peak())?;
+            writeln!(f, "noise_mean={:.2}", noise.mean)?;
+        }
+        if let Some(passed) = self.noise_passed {
+            writeln!(f, "noise_passed={passed}")?;
+        }
+        for corner in Corner::ALL {
+            if let Some(result) = self.corners.get(corner.index()).copied().flatten() {
+                let name = corner.name();
+                if let Some((x, y)) = result.measured {
+                    writeln!(f, "{name}_x={x}")?;
+                    writeln!(f, "{name}_y={y}")?;
+                }
+                writeln!(f, "{name}_passed={}", result.passed)?;
+            }
+        }
+        writeln!(f, "passed={}", self.passed())
+    }
+}
+
+/// Production test qualifying a panel, collecting the results into a `FactoryReport`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FactoryTest {
+    /// The limits the panel has to meet.
+    limits: FactoryLimits,
+    /// The results collected so far.
+    report: FactoryReport,
+}
+
+impl FactoryTest {
+    /// Creates a new production test.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - The limits the panel has to meet.
+    pub fn new(limits: FactoryLimits) -> Self {
+        Self {
+            limits,
+            report: FactoryReport::default(),
+        }
+    }
+
+    /// Checks that the chip responds and its ADC and internal reference work, by measuring the
+    /// chip temperature.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - The driver of the chip under test.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the self-test passed, or an error if the chip could not be
+    /// read.
+    pub fn run_self_test<SPI, IRQ, DELAY>(
+        &mut self,
+        driver: &mut Tsc2046<SPI, IRQ, DELAY>,
+    ) -> Result<bool, Error<<SPI as ErrorType>::Error>>
+    where
+        SPI: SpiDevice,
+        IRQ: OptionalInputPin,
+        DELAY: OptionalDelay,
+    {
+        let result = driver.read_temperature();
+        let passed = result.as_ref().is_ok_and(|temperature| {
+            (self.limits.min_temperature_celsius..=self.limits.max_temperature_celsius)
+                .contains(temperature)
+        });
+        self.report.temperature_celsius = result.as_ref().ok().copied();
+        self.report.self_test_passed = Some(passed);
+        result.map(|_| passed)
+    }
+
+    /// Measures the noise of the panel while nobody touches it.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - The driver of the chip under test.
+    /// * `samples` - The number of conversions to take.
+    /// * `interval_us` - The delay between two consecutive conversions in microseconds.
+    /// * `delay` - The delay provider used to wait between conversions, or [`crate::OwnedDelay`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the noise is within limits, or an error if the chip could
+    /// not be read.
+    pub fn measure_noise<SPI, IRQ, DELAY, D>(
+        &mut self,
+        driver: &mut Tsc2046<SPI, IRQ, DELAY>,
+        samples: u16,
+        interval_us: u32,
+        delay: &mut D,
+    ) -> Result<bool, Error<<SPI as ErrorType>::Error>>
+    where
+        SPI: SpiDevice,
+        IRQ: OptionalInputPin,
+        DELAY: OptionalDelay,
+        D: RoutineDelay<DELAY>,
+    {
+        let noise = driver.estimate_noise_floor(samples, interval_us, delay)?;
+        let passed = noise.peak_to_peak() <= self.limits.max_noise;
+        self.report.noise = Some(noise);
+        self.report.noise_passed = Some(passed);
+        Ok(passed)
+    }
+
+    /// Verifies a touch at a corner of the screen, which has to be pressed while calling this
+    /// method.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - The driver of the chip under test.
+    /// * `corner` - The corner being pressed.
+    /// * `expected` - The expected raw position of the touch.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the touch was detected within tolerance, or an error if the
+    /// chip could not be read.
+    pub fn verify_corner<SPI, IRQ, DELAY>(
+        &mut self,
+        driver: &mut Tsc2046<SPI, IRQ, DELAY>,
+        corner: Corner,
+        expected: (u16, u16),
+    ) -> Result<bool, Error<<SPI as ErrorType>::Error>>
+    where
+        SPI: SpiDevice,
+        IRQ: OptionalInputPin,
+        DELAY: OptionalDelay,
+    {
+        // The corner is compared in the raw range, before the mapping to the active area.
+        let active_area = driver.core.config.active_area.take();
+        let touch = driver.get_touch();
+        driver.core.config.active_area = active_area;
+        let measured = touch?.map(|point| (point.x, point.y));
+        let tolerance = self.limits.corner_tolerance;
+        let passed = measured.is_some_and(|(x, y)| {
+            x.abs_diff(expected.0) <= tolerance && y.abs_diff(expected.1) <= tolerance
+        });
+        let result = CornerResult {
+            expected,
+            measured,
+            passed,
+        };
+        if let Some(slot) = self.report.corners.get_mut(corner.index()) {
+            *slot = Some(result);
+        }
+        Ok(passed)
+    }
+
+    /// Returns the results collected so far.
+    pub fn report(&self) -> FactoryReport {
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::{FakeTsc2046, MockDelay};
+    use crate::Rect;
+
+    #[test]
+    fn test_factory_report() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::TEMP0, &[600]);
+        fake_chip.queue(FakeTsc2046::TEMP1, &[790]);
+        fake_chip.queue(FakeTsc2046::Z1, &[3, 5, 4, 4, 1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        fake_chip.queue(FakeTsc2046::X, &[0, 300, 3800, 300, 2000]);
+        fake_chip.queue(FakeTsc2046::Y, &[300, 300, 3800, 2000]);
+        let mut driver = Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let mut test = FactoryTest::new(FactoryLimits::default());
+        assert_eq!(test.run_self_test(&mut driver), Ok(true));
+        assert_eq!(
+            test.measure_noise(&mut driver, 4, 0, &mut MockDelay::default()),
+            Ok(true)
+        );
+        let corners = [(300, 300), (3800, 300), (300, 3800), (3800, 3800)];
+        for (corner, expected) in Corner::ALL.into_iter().zip(corners) {
+            let passed = test.verify_corner(&mut driver, corner, expected);
+            assert_eq!(passed, Ok(corner != Corner::BottomRight));
+        }
+        let report = test.report();
+        assert!(!report.passed());
+        let text = format!("{report}");
+        assert!(text.contains("self_test_passed=true\n"));
+        assert!(text.contains("noise_peak_to_peak=2\n"));
+        assert!(text.contains("bottom_right_x=2000\n"));
+        assert!(text.ends_with("bottom_right_passed=false\npassed=false\n"));
+    }
+
+    #[test]
+    fn test_corner_is_raw() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 300]);
+        fake_chip.queue(FakeTsc2046::Y, &[3800]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut driver = Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let area = Rect {
+            x_min: 200,
+            y_min: 200,
+            x_max: 3900,
+            y_max: 3900,
+        };
+        driver.set_active_area(Some(area));
+        let mut test = FactoryTest::new(FactoryLimits::default());
+        let passed = test.verify_corner(&mut driver, Corner::BottomLeft, (300, 3800));
+        assert_eq!(passed, Ok(true));
+        assert_eq!(driver.config().active_area, Some(area));
+    }
+}
@@ -0,0 +1,484 @@
+//! C interface of the driver.
+//!
+//! The driver lives in storage provided by the caller, a [`Tsc2046Handle`], so that no allocator
+//! is needed. The SPI bus is provided as a set of callbacks, see [`Tsc2046Bus`]. A typical polling
+//! loop reads:
+//!
+//! ```c
+//! static tsc2046_handle_t touch;
+//! tsc2046_init(&touch, bus, true, 2.0f);
+//! for (;;) {
+//!     tsc2046_touch_t point;
+//!     if (tsc2046_poll(&touch) > 0 && tsc2046_get_touch(&touch, &point) > 0) {
+//!         handle_touch(point.x, point.y);
+//!     }
+//! }
+//! ```
+//!
+//! The C declarations of the items below are in `ffi/include/tsc2046.h`, and the `tsc2046-ffi`
+//! crate of the workspace builds them into a static library.
+//!
+//! The functions return [`TSC2046_OK`] (or a positive value) on success, and one of the negative
+//! `TSC2046_ERR_*` codes on failure.
+
+use core::ffi::c_void;
+use core::mem::{align_of, size_of, MaybeUninit};
+
+use embedded_hal::spi::{self, ErrorKind, ErrorType, Operation, SpiDevice};
+
+use crate::{Error, TouchPoint, Tsc2046};
+
+/// The operation succeeded.
+pub const TSC2046_OK: i32 = 0;
+/// A null pointer was passed.
+pub const TSC2046_ERR_NULL: i32 = -1;
+/// The SPI transfer failed.
+pub const TSC2046_ERR_SPI: i32 = -2;
+/// Reading or driving a pin failed.
+pub const TSC2046_ERR_PIN: i32 = -3;
+/// Sampling is inhibited.
+pub const TSC2046_ERR_INHIBITED: i32 = -5;
+/// No touch was detected in time.
+pub const TSC2046_ERR_TIMEOUT: i32 = -6;
+/// A conversion returned an unusable value.
+pub const TSC2046_ERR_INVALID_READING: i32 = -7;
+/// A conversion frame does not fit the transfer buffers.
+pub const TSC2046_ERR_FRAME_OVERFLOW: i32 = -8;
+
+/// The size of the storage of a driver instance in bytes.
+pub const TSC2046_HANDLE_SIZE: usize = 1024;
+
+/// SPI bus of the TSC2046, implemented by the C side.
+///
+/// Every callback receives `context` as its first argument. The callbacks are nullable function
+/// pointers on the C side: `select` and `transfer` are checked by [`tsc2046_init`], and a null
+/// `delay_us` skips the delays, such as the warm-up of the internal reference.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Tsc2046Bus {
+    /// Opaque pointer passed to the callbacks.
+    pub context: *mut c_void,
+    /// Asserts (`true`) or deasserts (`false`) the chip select line.
+    pub select: Option<unsafe extern "C" fn(context: *mut c_void, selected: bool)>,
+    /// Exchanges `len` bytes. `tx` is null when only reading (zeros are sent), `rx` is null when
+    /// only writing, and both point to the same buffer for an in-place transfer. Returns 0 on
+    /// success.
+    pub transfer: Option<
+        unsafe extern "C" fn(context: *mut c_void, tx: *const u8, rx: *mut u8, len: usize) -> i32,
+    >,
+    /// Waits for at least `us` microseconds, or null to skip the delays.
+    pub delay_us: Option<unsafe extern "C" fn(context: *mut c_void, us: u32)>,
+}
+
+/// Error reported by the C side of the SPI bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusError(pub i32);
+
+impl spi::Error for BusError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl ErrorType for Tsc2046Bus {
+    type Error = BusError;
+}
+
+impl Tsc2046Bus {
+    /// Calls the select callback, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `selected` - Whether to assert the chip select line.
+    fn select(&mut self, selected: bool) {
+        if let Some(select) = self.select {
+            // SAFETY: the callbacks are valid for the lifetime of the bus, as required by
+            // `tsc2046_init`.
+            unsafe { select(self.context, selected) };
+        }
+    }
+
+    /// Calls the transfer callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The bytes to send, or null to send zeros.
+    /// * `rx` - The buffer of the received bytes, or null to discard them.
+    /// * `len` - The number of bytes to exchange.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the callback succeeded, [`TSC2046_ERR_NULL`] if there is
+    /// no transfer callback.
+    fn exchange(&mut self, tx: *const u8, rx: *mut u8, len: usize) -> Result<(), BusError> {
+        if len == 0 {
+            return Ok(());
+        }
+        let transfer = self.transfer.ok_or(BusError(TSC2046_ERR_NULL))?;
+        // SAFETY: the pointers are null or valid for `len` bytes, as required by the callback,
+        // which is valid for the lifetime of the bus.
+        match unsafe { transfer(self.context, tx, rx, len) } {
+            0 => Ok(()),
+            code => Err(BusError(code)),
+        }
+    }
+}
+
+impl SpiDevice for Tsc2046Bus {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), BusError> {
+        self.select(true);
+        let result = operations
+            .iter_mut()
+            .try_for_each(|operation| match operation {
+                Operation::Read(buf) => {
+                    self.exchange(core::ptr::null(), buf.as_mut_ptr(), buf.len())
+                }
+                Operation::Write(buf) => {
+                    self.exchange(buf.as_ptr(), core::ptr::null_mut(), buf.len())
+                }
+                Operation::Transfer(read, write) => {
+                    let common = read.len().min(write.len());
+                    self.exchange(write.as_ptr(), read.as_mut_ptr(), common)?;
+                    match (read.get_mut(common..), write.get(common..)) {
+                        (Some(read), _) if !read.is_empty() => {
+                            self.exchange(core::ptr::null(), read.as_mut_ptr(), read.len())
+                        }
+                        (_, Some(write)) => {
+                            self.exchange(write.as_ptr(), core::ptr::null_mut(), write.len())
+                        }
+                        _ => Ok(()),
+                    }
+                }
+                Operation::TransferInPlace(buf) => {
+                    self.exchange(buf.as_ptr(), buf.as_mut_ptr(), buf.len())
+                }
+                Operation::DelayNs(ns) => {
+                    if let Some(delay_us) = self.delay_us {
+                        // SAFETY: the callbacks are valid for the lifetime of the bus.
+                        unsafe { delay_us(self.context, ns.div_ceil(1000)) };
+                    }
+                    Ok(())
+                }
+            });
+        self.select(false);
+        result
+    }
+}
+
+/// Touch point reported to the C side.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tsc2046Touch {
+    /// The raw X position, between 0 and 4095.
+    pub x: u16,
+    /// The raw Y position, between 0 and 4095.
+    pub y: u16,
+    /// The pressure, lower values meaning firmer touches.
+    pub z: f32,
+}
+
+impl From<TouchPoint> for Tsc2046Touch {
+    fn from(point: TouchPoint) -> Self {
+        Self {
+            x: point.x,
+            y: point.y,
+            z: point.z,
+        }
+    }
+}
+
+/// Storage of a driver instance, opaque to the C side.
+#[repr(C, align(8))]
+pub struct Tsc2046Handle {
+    storage: MaybeUninit<[u8; TSC2046_HANDLE_SIZE]>,
+}
+
+/// The driver instance stored in a handle.
+struct State {
+    driver: Tsc2046<Tsc2046Bus>,
+    last_touch: Option<TouchPoint>,
+}
+
+const _: () = assert!(size_of::<State>() <= TSC2046_HANDLE_SIZE);
+const _: () = assert!(align_of::<State>() <= align_of::<Tsc2046Handle>());
+
+/// Converts a driver error into an error code.
+///
+/// # Arguments
+///
+/// * `error` - The driver error.
+///
+/// # Returns
+///
+/// The negative error code.
+fn error_code(error: &Error<BusError>) -> i32 {
+    match error {
+        Error::Spi { .. } | Error::SpiBatch(_) => TSC2046_ERR_SPI,
+        Error::Pin(_) => TSC2046_ERR_PIN,
+        Error::Inhibited => TSC2046_ERR_INHIBITED,
+        Error::Timeout => TSC2046_ERR_TIMEOUT,
+        Error::InvalidReading(_) => TSC2046_ERR_INVALID_READING,
+        Error::FrameOverflow(_) => TSC2046_ERR_FRAME_OVERFLOW,
+    }
+}
+
+/// Borrows the driver instance stored in a handle.
+///
+/// # Safety
+///
+/// `handle` must be null or point to a handle initialized by [`tsc2046_init`].
+///
+/// # Returns
+///
+/// The driver instance, or `None` if the handle is null.
+unsafe fn state<'a>(handle: *mut Tsc2046Handle) -> Option<&'a mut State> {
+    // SAFETY: an initialized handle holds a `State`, suitably aligned (see the assertions above).
+    unsafe { handle.cast::<State>().as_mut() }
+}
+
+/// Creates a driver instance in the storage of a handle.
+///
+/// # Safety
+///
+/// `handle` must be null or valid for writes, and must not hold an instance already (see
+/// [`tsc2046_release`]). The callbacks of `bus` must remain valid until the instance is released.
+///
+/// # Arguments
+///
+/// * `handle` - The storage of the instance.
+/// * `bus` - The SPI bus of the TSC2046.
+/// * `irq_on` - Whether to enable the interrupt pin or not.
+/// * `touch_threshold` - The minimum pressure value required to register a touch event.
+///
+/// # Returns
+///
+/// [`TSC2046_OK`], [`TSC2046_ERR_NULL`] if the handle, the `select` or the `transfer` callback
+/// is null, or an error code if the register update fails. The handle is left uninitialized on
+/// error.
+#[no_mangle]
+pub unsafe extern "C" fn tsc2046_init(
+    handle: *mut Tsc2046Handle,
+    bus: Tsc2046Bus,
+    irq_on: bool,
+    touch_threshold: f32,
+) -> i32 {
+    if handle.is_null() || bus.select.is_none() || bus.transfer.is_none() {
+        return TSC2046_ERR_NULL;
+    }
+    let driver = match Tsc2046::new(bus, irq_on, touch_threshold) {
+        Ok(driver) => driver,
+        Err(error) => return error_code(&error),
+    };
+    // SAFETY: the handle is valid for writes, and large and aligned enough for a `State`.
+    unsafe {
+        handle.cast::<State>().write(State {
+            driver,
+            last_touch: None,
+        })
+    };
+    TSC2046_OK
+}
+
+/// Samples the touch screen, keeping the touch point for [`tsc2046_get_touch`].
+///
+/// # Safety
+///
+/// `handle` must be null or point to a handle initialized by [`tsc2046_init`].
+///
+/// # Arguments
+///
+/// * `handle` - The driver instance.
+///
+/// # Returns
+///
+/// 1 if the screen is touched, 0 if not, or an error code.
+#[no_mangle]
+pub unsafe extern "C" fn tsc2046_poll(handle: *mut Tsc2046Handle) -> i32 {
+    // SAFETY: guaranteed by the caller.
+    let Some(state) = (unsafe { state(handle) }) else {
+        return TSC2046_ERR_NULL;
+    };
+    match state.driver.get_touch() {
+        Ok(touch) => {
+            state.last_touch = touch;
+            touch.is_some() as i32
+        }
+        Err(error) => {
+            state.last_touch = None;
+            error_code(&error)
+        }
+    }
+}
+
+/// Reads the touch point of the last poll.
+///
+/// # Safety
+///
+/// `handle` must be null or point to a handle initialized by [`tsc2046_init`], and `touch` must be
+/// null or valid for writes.
+///
+/// # Arguments
+///
+/// * `handle` - The driver instance.
+/// * `touch` - The touch point written, if the screen was touched.
+///
+/// # Returns
+///
+/// 1 if the screen was touched at the last poll, 0 if not, or an error code.
+#[no_mangle]
+pub unsafe extern "C" fn tsc2046_get_touch(
+    handle: *mut Tsc2046Handle,
+    touch: *mut Tsc2046Touch,
+) -> i32 {
+    // SAFETY: guaranteed by the caller.
+    let (Some(state), Some(touch)) = (unsafe { state(handle) }, unsafe { touch.as_mut() }) else {
+        return TSC2046_ERR_NULL;
+    };
+    match state.last_touch {
+        Some(point) => {
+            *touch = point.into();
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Sets the touch threshold.
+///
+/// # Safety
+///
+/// `handle` must be null or point to a handle initialized by [`tsc2046_init`].
+///
+/// # Arguments
+///
+/// * `handle` - The driver instance.
+/// * `touch_threshold` - The minimum pressure value required to register a touch event.
+///
+/// # Returns
+///
+/// [`TSC2046_OK`], or an error code.
+#[no_mangle]
+pub unsafe extern "C" fn tsc2046_set_threshold(
+    handle: *mut Tsc2046Handle,
+    touch_threshold: f32,
+) -> i32 {
+    // SAFETY: guaranteed by the caller.
+    let Some(state) = (unsafe { state(handle) }) else {
+        return TSC2046_ERR_NULL;
+    };
+    state.driver.set_touch_threshold(touch_threshold);
+    TSC2046_OK
+}
+
+/// Destroys the driver instance stored in a handle.
+///
+/// # Safety
+///
+/// `handle` must be null or point to a handle initialized by [`tsc2046_init`]. The handle must
+/// not be used afterwards, until initialized again.
+///
+/// # Arguments
+///
+/// * `handle` - The driver instance.
+#[no_mangle]
+pub unsafe extern "C" fn tsc2046_release(handle: *mut Tsc2046Handle) {
+    if !handle.is_null() {
+        // SAFETY: guaranteed by the caller.
+        unsafe { handle.cast::<State>().drop_in_place() };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chip answering every conversion with a fixed value per channel, at the byte level.
+    #[derive(Default)]
+    struct FakeChip {
+        values: [u16; 8],
+        result: Option<u16>,
+        selects: u32,
+    }
+
+    unsafe extern "C" fn select(context: *mut c_void, selected: bool) {
+        let chip = unsafe { &mut *context.cast::<FakeChip>() };
+        chip.selects += selected as u32;
+    }
+
+    unsafe extern "C" fn transfer(
+        context: *mut c_void,
+        tx: *const u8,
+        rx: *mut u8,
+        len: usize,
+    ) -> i32 {
+        let chip = unsafe { &mut *context.cast::<FakeChip>() };
+        if !tx.is_null() {
+            let command = unsafe { *tx };
+            chip.result = Some(chip.values[((command >> 4) & 0b111) as usize]);
+        }
+        if !rx.is_null() {
+            let rx = unsafe { core::slice::from_raw_parts_mut(rx, len) };
+            rx.fill(0);
+            if let Some(result) = chip.result.take() {
+                rx[0] = (result >> 5) as u8;
+                rx[1] = (result << 3) as u8;
+            }
+        }
+        0
+    }
+
+    unsafe extern "C" fn delay_us(_context: *mut c_void, _us: u32) {}
+
+    #[test]
+    fn test_ffi_touch() {
+        let mut chip = FakeChip::default();
+        chip.values[0b101] = 100;
+        chip.values[0b001] = 200;
+        chip.values[0b011] = 500;
+        chip.values[0b100] = 600;
+        let bus = Tsc2046Bus {
+            context: (&mut chip as *mut FakeChip).cast(),
+            select: Some(select),
+            transfer: Some(transfer),
+            delay_us: Some(delay_us),
+        };
+        let mut handle = MaybeUninit::<Tsc2046Handle>::uninit();
+        let handle = handle.as_mut_ptr();
+        let mut touch = Tsc2046Touch { x: 0, y: 0, z: 0.0 };
+        unsafe {
+            assert_eq!(tsc2046_init(handle, bus, false, 1000.0), TSC2046_OK);
+            assert_eq!(tsc2046_get_touch(handle, &mut touch), 0);
+            assert_eq!(tsc2046_poll(handle), 1);
+            assert_eq!(tsc2046_get_touch(handle, &mut touch), 1);
+            assert_eq!(tsc2046_set_threshold(handle, 0.0), TSC2046_OK);
+            assert_eq!(tsc2046_poll(handle), 0);
+            assert_eq!(tsc2046_poll(core::ptr::null_mut()), TSC2046_ERR_NULL);
+            tsc2046_release(handle);
+        }
+        assert_eq!((touch.x, touch.y), (100, 200));
+        assert_eq!(chip.selects, 9);
+    }
+
+    #[test]
+    fn test_ffi_null_callbacks() {
+        let mut chip = FakeChip::default();
+        let bus = Tsc2046Bus {
+            context: (&mut chip as *mut FakeChip).cast(),
+            select: Some(select),
+            transfer: None,
+            delay_us: None,
+        };
+        let mut handle = MaybeUninit::<Tsc2046Handle>::uninit();
+        let handle = handle.as_mut_ptr();
+        unsafe {
+            assert_eq!(tsc2046_init(handle, bus, false, 1.0), TSC2046_ERR_NULL);
+            let bus = Tsc2046Bus {
+                transfer: Some(transfer),
+                ..bus
+            };
+            assert_eq!(tsc2046_init(handle, bus, false, 1.0), TSC2046_OK);
+            tsc2046_release(handle);
+        }
+        assert_eq!(chip.selects, 1);
+    }
+}
@@ -0,0 +1,1000 @@
+use core::num::NonZeroU64;
+
+use crate::stage::FilterStage;
+use crate::{Preset, TouchPoint};
+
+/// Weighted moving average of the touch readings.
+///
+/// The last points of the current contact are averaged, up to a window of at most `N` points, the
+/// most recent point with the weight of the window and the oldest with weight 1. Compared to a
+/// plain average of the same length, the output lags less behind a drag, at the price of slightly
+/// less smoothing. The history is cleared when the screen is released, so consecutive contacts are
+/// never blended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedMovingAverage<const N: usize> {
+    /// The last points of the contact, oldest first.
+    history: [TouchPoint; N],
+    /// The number of valid points in the history.
+    len: usize,
+    /// The number of points averaged, at most `N`.
+    window: usize,
+}
+
+impl<const N: usize> WeightedMovingAverage<N> {
+    /// Creates a new weighted moving average over `N` points.
+    pub fn new() -> Self {
+        Self {
+            history: [TouchPoint { x: 0, y: 0, z: 0.0 }; N],
+            len: 0,
+            window: N,
+        }
+    }
+
+    /// Changes the number of points averaged, keeping the history of the current contact.
+    ///
+    /// The last `N` points are always kept, so a longer window takes effect right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The number of points averaged, clamped between 1 and `N`.
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.clamp(1, N.max(1));
+    }
+
+    /// Filters a touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The weighted average of the recent points, or `None` if the screen is not touched.
+    pub fn update(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        let Some(point) = touch else {
+            self.len = 0;
+            return None;
+        };
+        if N == 0 {
+            return Some(point);
+        }
+        if self.len == N {
+            self.history.copy_within(1.., 0);
+        } else {
+            self.len = self.len.saturating_add(1);
+        }
+        if let Some(slot) = self.history.get_mut(self.len.saturating_sub(1)) {
+            *slot = point;
+        }
+        let skipped = self.len.saturating_sub(self.window);
+        // Summed on 64 bits, the weighted coordinates cannot overflow below 2^24 points.
+        let (mut x, mut y, mut z, mut total) = (0_u64, 0_u64, 0_f32, 0_u64);
+        let recent = self.history.iter().take(self.len).skip(skipped);
+        for (index, point) in recent.enumerate() {
+            let weight = (index as u64).saturating_add(1);
+            x = x.saturating_add((point.x as u64).saturating_mul(weight));
+            y = y.saturating_add((point.y as u64).saturating_mul(weight));
+            z += point.z * weight as f32;
+            total = total.saturating_add(weight);
+        }
+        let total = NonZeroU64::new(total).unwrap_or(NonZeroU64::MIN);
+        let average = |sum: u64| (sum.saturating_add(total.get() / 2) / total).min(u16::MAX as u64);
+        Some(TouchPoint {
+            x: average(x) as u16,
+            y: average(y) as u16,
+            z: z / total.get() as f32,
+        })
+    }
+}
+
+impl<const N: usize> Default for WeightedMovingAverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Median filter of the touch coordinates.
+///
+/// The coordinates are the medians of the last points of the current contact, up to a window of
+/// at most `N` points, which rejects isolated outliers without smoothing the edges of a stroke.
+/// The pressure of the latest point is passed through. The history is cleared when the screen is
+/// released.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MedianFilter<const N: usize> {
+    /// The last points of the contact, oldest first.
+    history: [TouchPoint; N],
+    /// The number of valid points in the history.
+    len: usize,
+    /// The number of points the median is taken over, at most `N`.
+    window: usize,
+}
+
+impl<const N: usize> MedianFilter<N> {
+    /// Creates a new median filter over `N` points.
+    pub fn new() -> Self {
+        Self {
+            history: [TouchPoint { x: 0, y: 0, z: 0.0 }; N],
+            len: 0,
+            window: N,
+        }
+    }
+
+    /// Changes the number of points the median is taken over, keeping the history of the current
+    /// contact.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The number of points, clamped between 1 and `N`.
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.clamp(1, N.max(1));
+    }
+
+    /// Filters a touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The median of the recent points, the upper one for an even number of points, or `None`
+    /// if the screen is not touched.
+    pub fn update(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        let Some(point) = touch else {
+            self.len = 0;
+            return None;
+        };
+        if N == 0 {
+            return Some(point);
+        }
+        if self.len == N {
+            self.history.copy_within(1.., 0);
+        } else {
+            self.len = self.len.saturating_add(1);
+        }
+        if let Some(slot) = self.history.get_mut(self.len.saturating_sub(1)) {
+            *slot = point;
+        }
+        let skipped = self.len.saturating_sub(self.window);
+        let count = self.len.saturating_sub(skipped);
+        let (mut xs, mut ys) = ([0_u16; N], [0_u16; N]);
+        let recent = self.history.iter().take(self.len).skip(skipped);
+        for ((x, y), point) in xs.iter_mut().zip(ys.iter_mut()).zip(recent) {
+            (*x, *y) = (point.x, point.y);
+        }
+        let median = |values: &mut [u16; N]| {
+            let values = values.get_mut(..count).unwrap_or_default();
+            values.sort_unstable();
+            values.get(count / 2).copied()
+        };
+        Some(TouchPoint {
+            x: median(&mut xs).unwrap_or(point.x),
+            y: median(&mut ys).unwrap_or(point.y),
+            z: point.z,
+        })
+    }
+}
+
+impl<const N: usize> Default for MedianFilter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Exponential moving average of the touch readings.
+///
+/// Every new point moves the output by a fraction `alpha` of its distance to the output. The
+/// average restarts from the first point of every contact, so consecutive contacts are never
+/// blended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialMovingAverage {
+    /// The weight of a new point, between 0.0 (exclusive) and 1.0.
+    alpha: f32,
+    /// The averaged x-coordinate, y-coordinate and pressure of the current contact, if any.
+    average: Option<(f32, f32, f32)>,
+}
+
+impl ExponentialMovingAverage {
+    /// Creates a new exponential moving average.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha` - The weight of a new point, clamped between 0.001 and 1.0. Lower values smooth
+    ///   more and lag more.
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha: clamp_alpha(alpha),
+            average: None,
+        }
+    }
+
+    /// Changes the weight of a new point, keeping the average of the current contact.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha` - The weight of a new point, clamped between 0.001 and 1.0.
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = clamp_alpha(alpha);
+    }
+
+    /// Filters a touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The averaged point, with the coordinates rounded, or `None` if the screen is not touched.
+    pub fn update(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        let Some(point) = touch else {
+            self.average = None;
+            return None;
+        };
+        let sample = (point.x as f32, point.y as f32, point.z);
+        let (x, y, z) = match self.average {
+            Some((x, y, z)) => (
+                x + self.alpha * (sample.0 - x),
+                y + self.alpha * (sample.1 - y),
+                z + self.alpha * (sample.2 - z),
+            ),
+            None => sample,
+        };
+        self.average = Some((x, y, z));
+        Some(TouchPoint {
+            x: (x + 0.5).clamp(0.0, u16::MAX as f32) as u16,
+            y: (y + 0.5).clamp(0.0, u16::MAX as f32) as u16,
+            z,
+        })
+    }
+}
+
+/// Clamps the weight of an exponential moving average, so a misconfigured weight cannot freeze
+/// the output or overshoot.
+///
+/// # Arguments
+///
+/// * `alpha` - The weight of a new point.
+///
+/// # Returns
+///
+/// The weight, between 0.001 and 1.0, or 1.0 if it is not a number.
+fn clamp_alpha(alpha: f32) -> f32 {
+    if alpha.is_nan() {
+        return 1.0;
+    }
+    alpha.clamp(0.001, 1.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// State of a One Euro filter for a single coordinate.
+struct OneEuroState {
+    /// The filtered value.
+    value: f32,
+    /// The filtered rate of change, in units per second.
+    derivative: f32,
+}
+
+/// One Euro filter of the touch coordinates.
+///
+/// An exponential moving average whose cutoff frequency rises with the speed of the contact: slow
+/// movements are smoothed strongly, removing the jitter of a resting finger, while fast strokes
+/// follow with little lag. The cutoff is `min_cutoff_hz + beta * speed`, with the speed in raw
+/// units per second. The touch screen is assumed to be sampled at a fixed rate. The pressure is
+/// passed through unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OneEuroFilter {
+    /// The rate at which the touch screen is sampled, in Hz.
+    sample_rate_hz: f32,
+    /// The cutoff frequency of a resting contact, in Hz.
+    min_cutoff_hz: f32,
+    /// The increase of the cutoff frequency per raw unit per second of speed.
+    beta: f32,
+    /// The cutoff frequency of the speed estimate, in Hz.
+    derivative_cutoff_hz: f32,
+    /// The state of the x-coordinate.
+    x: OneEuroState,
+    /// The state of the y-coordinate.
+    y: OneEuroState,
+    /// Whether the state holds points of the current contact.
+    primed: bool,
+}
+
+impl OneEuroFilter {
+    /// Creates a new One Euro filter, with a cutoff frequency of 1 Hz for the speed estimate.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate_hz` - The rate at which the touch screen is sampled, in Hz.
+    /// * `min_cutoff_hz` - The cutoff frequency of a resting contact, in Hz. Lower values remove
+    ///   more jitter.
+    /// * `beta` - The increase of the cutoff frequency per raw unit per second of speed. Higher
+    ///   values reduce the lag of fast strokes.
+    pub fn new(sample_rate_hz: f32, min_cutoff_hz: f32, beta: f32) -> Self {
+        Self {
+            sample_rate_hz,
+            min_cutoff_hz,
+            beta,
+            derivative_cutoff_hz: 1.0,
+            x: OneEuroState::default(),
+            y: OneEuroState::default(),
+            primed: false,
+        }
+    }
+
+    /// Sets the cutoff frequency of the speed estimate.
+    ///
+    /// # Arguments
+    ///
+    /// * `derivative_cutoff_hz` - The cutoff frequency in Hz.
+    pub fn with_derivative_cutoff(mut self, derivative_cutoff_hz: f32) -> Self {
+        self.derivative_cutoff_hz = derivative_cutoff_hz;
+        self
+    }
+
+    /// Replaces the cutoff parameters, keeping the state of the current contact.
+    ///
+    /// Meant to retune the filter at runtime, for example from a sensitivity setting. The output
+    /// continues from the filtered position without a jump.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_cutoff_hz` - The cutoff frequency of a resting contact, in Hz.
+    /// * `beta` - The increase of the cutoff frequency per raw unit per second of speed.
+    pub fn set_cutoff(&mut self, min_cutoff_hz: f32, beta: f32) {
+        self.min_cutoff_hz = min_cutoff_hz;
+        self.beta = beta;
+    }
+
+    /// Computes the weight of a new value for a cutoff frequency.
+    ///
+    /// # Arguments
+    ///
+    /// * `cutoff_hz` - The cutoff frequency in Hz.
+    ///
+    /// # Returns
+    ///
+    /// The weight, between 0.0 and 1.0.
+    fn alpha(&self, cutoff_hz: f32) -> f32 {
+        let cutoff = 2.0 * core::f32::consts::PI * cutoff_hz.max(0.0);
+        let alpha = cutoff / (cutoff + self.sample_rate_hz);
+        if alpha.is_nan() {
+            return 1.0;
+        }
+        alpha.clamp(0.0, 1.0)
+    }
+
+    /// Filters a single coordinate.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state of the coordinate.
+    /// * `value` - The new value of the coordinate.
+    ///
+    /// # Returns
+    ///
+    /// The filtered value.
+    fn step(&self, state: &mut OneEuroState, value: f32) -> f32 {
+        let derivative = (value - state.value) * self.sample_rate_hz;
+        let alpha = self.alpha(self.derivative_cutoff_hz);
+        state.derivative += alpha * (derivative - state.derivative);
+        let cutoff = self.min_cutoff_hz + self.beta * state.derivative.abs();
+        state.value += self.alpha(cutoff) * (value - state.value);
+        state.value
+    }
+
+    /// Filters a touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The filtered point, with the coordinates rounded and clamped to the raw range, or `None`
+    /// if the screen is not touched.
+    pub fn update(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        let Some(point) = touch else {
+            self.primed = false;
+            return None;
+        };
+        if !self.primed {
+            self.x = OneEuroState {
+                value: point.x as f32,
+                derivative: 0.0,
+            };
+            self.y = OneEuroState {
+                value: point.y as f32,
+                derivative: 0.0,
+            };
+            self.primed = true;
+            return Some(point);
+        }
+        let (mut x_state, mut y_state) = (self.x, self.y);
+        let x = self.step(&mut x_state, point.x as f32);
+        let y = self.step(&mut y_state, point.y as f32);
+        (self.x, self.y) = (x_state, y_state);
+        Some(TouchPoint {
+            x: (x + 0.5).clamp(0.0, u16::MAX as f32) as u16,
+            y: (y + 0.5).clamp(0.0, u16::MAX as f32) as u16,
+            z: point.z,
+        })
+    }
+}
+
+/// Finite impulse response low-pass filter of the touch readings, with user-supplied taps.
+///
+/// Meant for panels whose noise spectrum has been characterized, so the taps can be designed for
+/// a specific response. The taps should sum to 1.0 to preserve the position. When a contact
+/// starts, the history is filled with its first point, so the output does not ramp up from zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FirFilter<const N: usize> {
+    /// The coefficients, the first one applied to the most recent point.
+    taps: [f32; N],
+    /// The last points of the contact, most recent first.
+    history: [TouchPoint; N],
+    /// Whether the history holds points of the current contact.
+    primed: bool,
+}
+
+impl<const N: usize> FirFilter<N> {
+    /// Creates a new FIR filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `taps` - The coefficients, the first one applied to the most recent point.
+    pub fn new(taps: [f32; N]) -> Self {
+        Self {
+            taps,
+            history: [TouchPoint { x: 0, y: 0, z: 0.0 }; N],
+            primed: false,
+        }
+    }
+
+    /// Replaces the coefficients, keeping the history of the current contact.
+    ///
+    /// # Arguments
+    ///
+    /// * `taps` - The coefficients, the first one applied to the most recent point.
+    pub fn set_taps(&mut self, taps: [f32; N]) {
+        self.taps = taps;
+    }
+
+    /// Filters a touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The filtered point, with the coordinates rounded and clamped to the raw range, or `None`
+    /// if the screen is not touched.
+    pub fn update(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        let Some(point) = touch else {
+            self.primed = false;
+            return None;
+        };
+        if N == 0 {
+            return Some(point);
+        }
+        if self.primed {
+            self.history.copy_within(..N.saturating_sub(1), 1);
+            if let Some(slot) = self.history.first_mut() {
+                *slot = point;
+            }
+        } else {
+            self.history = [point; N];
+            self.primed = true;
+        }
+        let (mut x, mut y, mut z) = (0_f32, 0_f32, 0_f32);
+        for (tap, point) in self.taps.iter().zip(&self.history) {
+            x += tap * point.x as f32;
+            y += tap * point.y as f32;
+            z += tap * point.z;
+        }
+        Some(TouchPoint {
+            x: (x + 0.5).clamp(0.0, u16::MAX as f32) as u16,
+            y: (y + 0.5).clamp(0.0, u16::MAX as f32) as u16,
+            z,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Coefficients of a biquad filter, normalized so the leading feedback coefficient is 1.0.
+pub struct BiquadCoefficients {
+    /// The feedforward coefficient of the current input.
+    pub b0: f32,
+    /// The feedforward coefficient of the previous input.
+    pub b1: f32,
+    /// The feedforward coefficient of the input before the previous one.
+    pub b2: f32,
+    /// The feedback coefficient of the previous output.
+    pub a1: f32,
+    /// The feedback coefficient of the output before the previous one.
+    pub a2: f32,
+}
+
+impl BiquadCoefficients {
+    /// Computes the coefficients of a second-order low-pass filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `cutoff_hz` - The cutoff frequency in Hz, below half the sample rate.
+    /// * `sample_rate_hz` - The rate at which the touch screen is sampled, in Hz.
+    /// * `q` - The quality factor, 0.707 for a maximally flat response.
+    pub fn low_pass(cutoff_hz: f32, sample_rate_hz: f32, q: f32) -> Self {
+        let (sin, cos) = sin_cos(2.0 * core::f32::consts::PI * cutoff_hz / sample_rate_hz);
+        let alpha = sin / (2.0 * q);
+        Self::normalized(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Computes the coefficients of a notch filter, for example to remove the coupling of the
+    /// backlight PWM.
+    ///
+    /// # Arguments
+    ///
+    /// * `center_hz` - The rejected frequency in Hz, below half the sample rate. Frequencies
+    ///   above are aliased, so the frequency seen by the filter has to be given.
+    /// * `sample_rate_hz` - The rate at which the touch screen is sampled, in Hz.
+    /// * `q` - The quality factor, higher values give a narrower notch.
+    pub fn notch(center_hz: f32, sample_rate_hz: f32, q: f32) -> Self {
+        let (sin, cos) = sin_cos(2.0 * core::f32::consts::PI * center_hz / sample_rate_hz);
+        let alpha = sin / (2.0 * q);
+        Self::normalized(
+            [1.0, -2.0 * cos, 1.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Normalizes the coefficients by the leading feedback coefficient.
+    ///
+    /// # Arguments
+    ///
+    /// * `b` - The feedforward coefficients.
+    /// * `a` - The feedback coefficients.
+    fn normalized(b: [f32; 3], a: [f32; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// State of a biquad filter for a single coordinate.
+struct BiquadState {
+    /// The previous two inputs, most recent first.
+    inputs: [f32; 2],
+    /// The previous two outputs, most recent first.
+    outputs: [f32; 2],
+}
+
+impl BiquadState {
+    /// Filters a single value.
+    ///
+    /// # Arguments
+    ///
+    /// * `coefficients` - The filter coefficients.
+    /// * `input` - The new input value.
+    ///
+    /// # Returns
+    ///
+    /// The filtered value.
+    fn step(&mut self, coefficients: &BiquadCoefficients, input: f32) -> f32 {
+        let output = coefficients.b0 * input
+            + coefficients.b1 * self.inputs[0]
+            + coefficients.b2 * self.inputs[1]
+            - coefficients.a1 * self.outputs[0]
+            - coefficients.a2 * self.outputs[1];
+        self.inputs = [input, self.inputs[0]];
+        self.outputs = [output, self.outputs[0]];
+        output
+    }
+}
+
+/// Biquad IIR filter of the touch coordinates.
+///
+/// The pressure is passed through unchanged. When a contact starts, the state is set to the
+/// steady state of its first point, so filters with unity gain at DC, like the low-pass and notch
+/// filters, start without a transient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Biquad {
+    /// The filter coefficients.
+    coefficients: BiquadCoefficients,
+    /// The state of the x-coordinate.
+    x: BiquadState,
+    /// The state of the y-coordinate.
+    y: BiquadState,
+    /// Whether the state holds points of the current contact.
+    primed: bool,
+}
+
+impl Biquad {
+    /// Creates a new biquad filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `coefficients` - The filter coefficients, see [`BiquadCoefficients::low_pass`] and
+    ///   [`BiquadCoefficients::notch`].
+    pub fn new(coefficients: BiquadCoefficients) -> Self {
+        Self {
+            coefficients,
+            x: BiquadState::default(),
+            y: BiquadState::default(),
+            primed: false,
+        }
+    }
+
+    /// Replaces the coefficients, keeping the state of the current contact.
+    ///
+    /// Meant to retune the filter at runtime, for example from a sensitivity setting. Coefficients
+    /// with unity gain at DC continue from the filtered position without a jump.
+    ///
+    /// # Arguments
+    ///
+    /// * `coefficients` - The filter coefficients.
+    pub fn set_coefficients(&mut self, coefficients: BiquadCoefficients) {
+        self.coefficients = coefficients;
+    }
+
+    /// Filters a touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The filtered point, with the coordinates rounded and clamped to the raw range, or `None`
+    /// if the screen is not touched.
+    pub fn update(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        let Some(point) = touch else {
+            self.primed = false;
+            return None;
+        };
+        if !self.primed {
+            let (x, y) = (point.x as f32, point.y as f32);
+            self.x = BiquadState {
+                inputs: [x; 2],
+                outputs: [x; 2],
+            };
+            self.y = BiquadState {
+                inputs: [y; 2],
+                outputs: [y; 2],
+            };
+            self.primed = true;
+            return Some(point);
+        }
+        let x = self.x.step(&self.coefficients, point.x as f32);
+        let y = self.y.step(&self.coefficients, point.y as f32);
+        Some(TouchPoint {
+            x: (x + 0.5).clamp(0.0, u16::MAX as f32) as u16,
+            y: (y + 0.5).clamp(0.0, u16::MAX as f32) as u16,
+            z: point.z,
+        })
+    }
+}
+
+/// Approximates the sine and cosine of an angle.
+///
+/// The angle is reduced to the range -pi/2..=pi/2, where a Taylor series is accurate to better
+/// than 1e-5.
+///
+/// # Arguments
+///
+/// * `angle` - The angle in radians.
+///
+/// # Returns
+///
+/// The sine and the cosine of the angle.
+fn sin_cos(angle: f32) -> (f32, f32) {
+    use core::f32::consts::{FRAC_PI_2, PI, TAU};
+    let turns = angle / TAU + 0.5;
+    let mut whole_turns = turns as i32;
+    if (whole_turns as f32) > turns {
+        whole_turns = whole_turns.saturating_sub(1);
+    }
+    let mut angle = angle - TAU * whole_turns as f32;
+    let mut sign = 1.0;
+    if angle > FRAC_PI_2 {
+        angle = PI - angle;
+        sign = -1.0;
+    } else if angle < -FRAC_PI_2 {
+        angle = -PI - angle;
+        sign = -1.0;
+    }
+    let square = angle * angle;
+    let sin = angle
+        * (1.0
+            - square / 6.0 * (1.0 - square / 20.0 * (1.0 - square / 42.0 * (1.0 - square / 72.0))));
+    let cos = 1.0
+        - square / 2.0
+            * (1.0
+                - square / 12.0
+                    * (1.0 - square / 30.0 * (1.0 - square / 56.0 * (1.0 - square / 90.0))));
+    (sin, sign * cos)
+}
+
+impl Preset {
+    /// Returns the filter stage of the preset, for [`crate::event::EventTracker::with_filter`].
+    ///
+    /// The filters are second-order low-pass filters, with a cutoff at a quarter of the sample
+    /// rate for [`Preset::LowLatency`], an eighth for [`Preset::Balanced`] and a twentieth for
+    /// [`Preset::LowNoise`]. The cutoff follows the sample rate, so it does not have to be known.
+    ///
+    /// # Returns
+    ///
+    /// The `Biquad` filter of the preset.
+    pub fn filter(&self) -> Biquad {
+        let cutoff = match self {
+            Preset::LowLatency => 0.25,
+            Preset::Balanced => 0.125,
+            Preset::LowNoise => 0.05,
+        };
+        Biquad::new(BiquadCoefficients::low_pass(cutoff, 1.0, 0.707))
+    }
+}
+
+impl<const N: usize> FilterStage for WeightedMovingAverage<N> {
+    fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        self.update(touch)
+    }
+}
+
+impl<const N: usize> FilterStage for MedianFilter<N> {
+    fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        self.update(touch)
+    }
+}
+
+impl FilterStage for ExponentialMovingAverage {
+    fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        self.update(touch)
+    }
+}
+
+impl FilterStage for OneEuroFilter {
+    fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        self.update(touch)
+    }
+}
+
+impl<const N: usize> FilterStage for FirFilter<N> {
+    fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        self.update(touch)
+    }
+}
+
+impl FilterStage for Biquad {
+    fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        self.update(touch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: u16, y: u16) -> TouchPoint {
+        TouchPoint { x, y, z: 10.0 }
+    }
+
+    #[test]
+    fn test_weighted_moving_average_limits() {
+        let mut filter = WeightedMovingAverage::<1024>::new();
+        let corner = TouchPoint {
+            x: u16::MAX,
+            y: u16::MAX,
+            z: 1.0,
+        };
+        let mut output = None;
+        for _ in 0..1024 {
+            output = filter.update(Some(corner));
+        }
+        assert_eq!(
+            output.map(|point| (point.x, point.y)),
+            Some((u16::MAX, u16::MAX))
+        );
+    }
+
+    #[test]
+    fn test_weighted_moving_average() {
+        let mut filter = WeightedMovingAverage::<3>::new();
+        assert_eq!(filter.update(Some(point(100, 100))), Some(point(100, 100)));
+        filter.update(Some(point(100, 100)));
+        // Weights 1, 2 and 3: (100 + 200 + 3 * 160) / 6 = 130.
+        let filtered = filter.update(Some(point(160, 100))).expect("No point");
+        assert_eq!((filtered.x, filtered.y), (130, 100));
+        // The oldest point is dropped: (100 + 2 * 160 + 3 * 160) / 6 = 150.
+        let filtered = filter.update(Some(point(160, 100))).expect("No point");
+        assert_eq!(filtered.x, 150);
+        assert_eq!(filter.update(None), None);
+        assert_eq!(filter.update(Some(point(0, 0))), Some(point(0, 0)));
+    }
+
+    #[test]
+    fn test_median_filter() {
+        let mut filter = MedianFilter::<3>::new();
+        assert_eq!(filter.update(Some(point(100, 100))), Some(point(100, 100)));
+        filter.update(Some(point(102, 100)));
+        // A single outlier is rejected.
+        let filtered = filter.update(Some(point(900, 100))).expect("No point");
+        assert_eq!((filtered.x, filtered.y), (102, 100));
+        assert_eq!(filter.update(None), None);
+        assert_eq!(filter.update(Some(point(0, 0))), Some(point(0, 0)));
+    }
+
+    #[test]
+    fn test_exponential_moving_average() {
+        let mut filter = ExponentialMovingAverage::new(0.25);
+        assert_eq!(filter.update(Some(point(100, 100))), Some(point(100, 100)));
+        // 100 + 0.25 * (180 - 100) = 120.
+        let filtered = filter.update(Some(point(180, 100))).expect("No point");
+        assert_eq!((filtered.x, filtered.y), (120, 100));
+        assert_eq!(filter.update(None), None);
+        assert_eq!(filter.update(Some(point(10, 20))), Some(point(10, 20)));
+        assert_eq!(ExponentialMovingAverage::new(f32::NAN).alpha, 1.0);
+    }
+
+    #[test]
+    fn test_one_euro_filter() {
+        let mut filter = OneEuroFilter::new(100.0, 1.0, 0.0);
+        assert_eq!(
+            filter.update(Some(point(1000, 1000))),
+            Some(point(1000, 1000))
+        );
+        // Without the speed term, a jitter of 10 units is mostly removed.
+        let filtered = filter.update(Some(point(1010, 1000))).expect("No point");
+        assert!(filtered.x > 1000 && filtered.x < 1002);
+        // With it, a fast stroke is followed closely.
+        let mut filter = OneEuroFilter::new(100.0, 1.0, 1.0);
+        filter.update(Some(point(1000, 1000)));
+        let filtered = filter.update(Some(point(2000, 1000))).expect("No point");
+        assert!(filtered.x > 1500);
+        assert_eq!(filter.update(None), None);
+        assert_eq!(filter.update(Some(point(10, 20))), Some(point(10, 20)));
+    }
+
+    #[test]
+    fn test_fir_filter() {
+        let mut filter = FirFilter::new([0.5, 0.25, 0.25]);
+        assert_eq!(filter.update(Some(point(100, 200))), Some(point(100, 200)));
+        // 0.5 * 180 + 0.25 * 100 + 0.25 * 100 = 140.
+        let filtered = filter.update(Some(point(180, 200))).expect("No point");
+        assert_eq!((filtered.x, filtered.y), (140, 200));
+        // 0.5 * 180 + 0.25 * 180 + 0.25 * 100 = 160.
+        let filtered = filter.update(Some(point(180, 200))).expect("No point");
+        assert_eq!(filtered.x, 160);
+        assert_eq!(filter.update(None), None);
+        assert_eq!(filter.update(Some(point(10, 20))), Some(point(10, 20)));
+    }
+
+    #[test]
+    fn test_sin_cos() {
+        for step in -40..=40 {
+            let angle = step as f32 * 0.2;
+            let (sin, cos) = sin_cos(angle);
+            assert!((sin - angle.sin()).abs() < 1e-5);
+            assert!((cos - angle.cos()).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_biquad_notch() {
+        // Wobble at a quarter of the sample rate, as aliased backlight PWM coupling would cause.
+        let mut filter = Biquad::new(BiquadCoefficients::notch(25.0, 100.0, 2.0));
+        let wobble = [0, 20, 0, -20];
+        let mut filtered = None;
+        for step in 0..200 {
+            let x = (1000 + wobble[step % 4]) as u16;
+            filtered = filter.update(Some(point(x, 500)));
+        }
+        let filtered = filtered.expect("No point");
+        assert!(filtered.x.abs_diff(1000) <= 1);
+        assert_eq!(filtered.y, 500);
+    }
+
+    #[test]
+    fn test_biquad_low_pass() {
+        let coefficients = BiquadCoefficients::low_pass(10.0, 100.0, 0.707);
+        let dc_gain = (coefficients.b0 + coefficients.b1 + coefficients.b2)
+            / (1.0 + coefficients.a1 + coefficients.a2);
+        assert!((dc_gain - 1.0).abs() < 1e-4);
+        let mut filter = Biquad::new(coefficients);
+        assert_eq!(filter.update(Some(point(100, 100))), Some(point(100, 100)));
+        let filtered = filter.update(Some(point(200, 100))).expect("No point");
+        assert!(filtered.x > 100 && filtered.x < 150);
+        assert_eq!(filter.update(None), None);
+    }
+
+    #[test]
+    fn test_retune_during_contact() {
+        let mut fir = FirFilter::new([0.5, 0.5]);
+        assert_eq!(fir.update(Some(point(100, 100))).map(|p| p.x), Some(100));
+        assert_eq!(fir.update(Some(point(200, 100))).map(|p| p.x), Some(150));
+        fir.set_taps([0.0, 1.0]);
+        // The history of the contact is kept, so the previous point comes out.
+        assert_eq!(fir.update(Some(point(300, 100))).map(|p| p.x), Some(200));
+
+        let mut biquad = Biquad::new(BiquadCoefficients::low_pass(5.0, 100.0, 0.707));
+        biquad.update(Some(point(1000, 1000)));
+        biquad.update(Some(point(2000, 1000)));
+        biquad.set_coefficients(BiquadCoefficients {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        });
+        assert_eq!(
+            biquad.update(Some(point(3000, 1000))).map(|p| p.x),
+            Some(3000)
+        );
+
+        let mut average = WeightedMovingAverage::<3>::new();
+        average.update(Some(point(100, 100)));
+        average.update(Some(point(100, 100)));
+        average.set_window(1);
+        assert_eq!(
+            average.update(Some(point(160, 100))).map(|p| p.x),
+            Some(160)
+        );
+        // The points outside the short window were kept: (100 + 2 * 160 + 3 * 160) / 6 = 150.
+        average.set_window(3);
+        assert_eq!(
+            average.update(Some(point(160, 100))).map(|p| p.x),
+            Some(150)
+        );
+
+        let mut median = MedianFilter::<5>::new();
+        median.set_window(1);
+        median.update(Some(point(100, 100)));
+        median.update(Some(point(100, 100)));
+        assert_eq!(median.update(Some(point(900, 100))).map(|p| p.x), Some(900));
+        median.set_window(5);
+        assert_eq!(median.update(Some(point(900, 100))).map(|p| p.x), Some(900));
+        assert_eq!(median.update(Some(point(100, 100))).map(|p| p.x), Some(100));
+
+        let mut ema = ExponentialMovingAverage::new(0.5);
+        ema.update(Some(point(100, 100)));
+        assert_eq!(ema.update(Some(point(200, 100))).map(|p| p.x), Some(150));
+        ema.set_alpha(0.1);
+        // The average continues from 150: 150 + 0.1 * (250 - 150) = 160.
+        assert_eq!(ema.update(Some(point(250, 100))).map(|p| p.x), Some(160));
+
+        let mut one_euro = OneEuroFilter::new(100.0, 1.0, 0.0);
+        one_euro.update(Some(point(1000, 1000)));
+        one_euro.update(Some(point(1000, 1000)));
+        one_euro.set_cutoff(1000.0, 0.0);
+        // The state is kept, and the high cutoff now follows the new point almost at once.
+        let filtered = one_euro.update(Some(point(1100, 1000))).expect("No point");
+        assert!(filtered.x > 1090 && filtered.x <= 1100);
+    }
+
+    #[test]
+    fn test_preset_filters() {
+        let step = |preset: Preset| {
+            let mut filter = preset.filter();
+            filter.update(Some(point(1000, 1000)));
+            filter.update(Some(point(2000, 1000))).map(|p| p.x)
+        };
+        let low_latency = step(Preset::LowLatency).expect("No point");
+        let balanced = step(Preset::Balanced).expect("No point");
+        let low_noise = step(Preset::LowNoise).expect("No point");
+        assert!(low_latency > balanced && balanced > low_noise && low_noise > 1000);
+        let mut filter = Preset::LowNoise.filter();
+        let mut settled = None;
+        for _ in 0..200 {
+            settled = filter.update(Some(point(1500, 700)));
+        }
+        assert_eq!(settled, Some(point(1500, 700)));
+    }
+}
@@ -0,0 +1,698 @@
+use core::num::NonZeroU32;
+
+#[cfg(feature = "calibration")]
+use crate::calibration::Calibration;
+use crate::event::{EventKind, GestureStage, TouchEvent};
+use crate::orientation::Orientation;
+use crate::{Rect, TouchPoint};
+
+/// The number of touch events used to estimate the exit velocity of a stroke.
+const EXIT_VELOCITY_SAMPLES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Timing and distance parameters shared by all gesture detectors.
+///
+/// Distances are in the raw units of the touch points, velocities in raw units per second.
+pub struct GestureConfig {
+    /// The orientation of the panel, applied to the touch points before the detection, so the
+    /// gestures are reported in display coordinates. The inverted axes are flipped within the
+    /// `screen` bounds.
+    pub orientation: Orientation,
+    /// The maximum duration of a contact to be a tap, in milliseconds.
+    pub tap_max_duration_ms: u32,
+    /// The maximum movement of a contact to be a tap or a long press.
+    pub tap_max_movement: u16,
+    /// The maximum time between two taps to be a double tap, in milliseconds.
+    pub double_tap_window_ms: u32,
+    /// The time a contact has to be held for a long press, in milliseconds.
+    pub long_press_ms: u32,
+    /// The minimum distance travelled along the main axis of a swipe.
+    pub swipe_min_distance: u16,
+    /// The minimum average velocity of a swipe.
+    pub swipe_min_velocity: u32,
+    /// The maximum duration of a stroke to be a flick, in milliseconds.
+    pub flick_max_duration_ms: u32,
+    /// The minimum velocity at the end of a stroke to be a flick.
+    pub flick_min_velocity: u32,
+    /// The distance from the screen edges within which a swipe is an edge swipe, 0 to disable.
+    pub edge_margin: u16,
+    /// The bounds of the screen in display coordinates, used for the detection of edge swipes and
+    /// the flip of the inverted axes.
+    pub screen: Rect,
+}
+
+impl GestureConfig {
+    /// Creates the default gesture configuration for a calibrated panel.
+    ///
+    /// The orientation of the panel and the raw bounds of the screen are read from the
+    /// calibration, so the gestures of raw touch events are reported in display coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `calibration` - The calibration of the panel.
+    #[cfg(feature = "calibration")]
+    pub fn from_calibration(calibration: &Calibration) -> Self {
+        Self {
+            orientation: calibration.orientation(),
+            screen: calibration.raw_bounds(),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            orientation: Orientation::default(),
+            tap_max_duration_ms: 250,
+            tap_max_movement: 40,
+            double_tap_window_ms: 300,
+            long_press_ms: 800,
+            swipe_min_distance: 400,
+            swipe_min_velocity: 1000,
+            flick_max_duration_ms: 300,
+            flick_min_velocity: 3000,
+            edge_margin: 0,
+            screen: Rect {
+                x_min: 0,
+                y_min: 0,
+                x_max: 4095,
+                y_max: 4095,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Direction of a gesture, in display coordinates after the configured [`Orientation`].
+pub enum Direction {
+    /// Towards decreasing y values.
+    Up,
+    /// Towards increasing y values.
+    Down,
+    /// Towards decreasing x values.
+    Left,
+    /// Towards increasing x values.
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Edge of the screen, in display coordinates after the configured [`Orientation`].
+pub enum Edge {
+    /// The edge with the smallest y values.
+    Top,
+    /// The edge with the largest y values.
+    Bottom,
+    /// The edge with the smallest x values.
+    Left,
+    /// The edge with the largest x values.
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Gesture recognized from a sequence of touch events.
+pub enum Gesture {
+    /// A short contact without movement.
+    Tap(TouchPoint),
+    /// A second tap shortly after a first one.
+    DoubleTap(TouchPoint),
+    /// A stroke along one of the axes.
+    Swipe {
+        /// The direction of the stroke.
+        direction: Direction,
+        /// The distance travelled along the main axis of the stroke.
+        distance: u16,
+    },
+    /// A swipe starting at an edge of the screen, towards the center of the screen.
+    EdgeSwipe {
+        /// The edge the swipe started at.
+        edge: Edge,
+        /// The distance travelled along the main axis of the stroke.
+        distance: u16,
+    },
+    /// A short stroke released at high velocity.
+    Flick {
+        /// The direction of the stroke.
+        direction: Direction,
+        /// The velocity along the main axis at the end of the stroke.
+        velocity: u32,
+    },
+}
+
+/// Recognizer detecting gestures from the events of an [`crate::event::EventTracker`].
+#[derive(Debug, Default)]
+pub struct GestureRecognizer {
+    /// The parameters of the gesture detection.
+    config: GestureConfig,
+    /// The `Down` event of the current contact.
+    start: Option<TouchEvent>,
+    /// The most recent events of the current contact, used to estimate the exit velocity.
+    recent: [Option<TouchEvent>; EXIT_VELOCITY_SAMPLES],
+    /// The index of the slot of `recent` receiving the next event.
+    recent_index: usize,
+    /// The release timestamp and position of the last tap, if it may still become a double tap.
+    last_tap: Option<(u32, TouchPoint)>,
+}
+
+impl GestureRecognizer {
+    /// Creates a new gesture recognizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The parameters of the gesture detection.
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            start: None,
+            recent: [None; EXIT_VELOCITY_SAMPLES],
+            recent_index: 0,
+            last_tap: None,
+        }
+    }
+
+    /// Updates the recognizer with the next touch event.
+    ///
+    /// The touch point of the event is transformed by the configured orientation first, within
+    /// the configured screen bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The next event read from the event tracker, in raw coordinates.
+    ///
+    /// # Returns
+    ///
+    /// The `Gesture` completed by the event, if any, in display coordinates.
+    pub fn update(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        let event = &TouchEvent {
+            point: self
+                .config
+                .orientation
+                .apply_within(&event.point, &self.config.screen),
+            ..*event
+        };
+        match event.kind {
+            EventKind::Down => {
+                self.start = Some(*event);
+                self.recent = [None; EXIT_VELOCITY_SAMPLES];
+                self.record(event);
+                None
+            }
+            EventKind::Move => {
+                self.record(event);
+                None
+            }
+            EventKind::Up => {
+                let start = self.start.take()?;
+                self.recognize(&start, event)
+            }
+            _ => None,
+        }
+    }
+
+    /// Recognizes the gesture of a completed contact.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The `Down` event of the contact.
+    /// * `end` - The `Up` event of the contact.
+    ///
+    /// # Returns
+    ///
+    /// The `Gesture` performed by the contact, if any.
+    fn recognize(&mut self, start: &TouchEvent, end: &TouchEvent) -> Option<Gesture> {
+        let duration_ms = end.timestamp_ms.wrapping_sub(start.timestamp_ms);
+        let dx = (end.point.x as i32).saturating_sub(start.point.x as i32);
+        let dy = (end.point.y as i32).saturating_sub(start.point.y as i32);
+        let distance = dx.unsigned_abs().max(dy.unsigned_abs());
+
+        if duration_ms <= self.config.tap_max_duration_ms
+            && distance <= self.config.tap_max_movement as u32
+        {
+            return Some(self.tap(end));
+        }
+        let direction = direction(dx, dy);
+        if duration_ms <= self.config.flick_max_duration_ms {
+            let velocity = self.exit_velocity(end, direction);
+            if velocity >= self.config.flick_min_velocity {
+                return Some(Gesture::Flick {
+                    direction,
+                    velocity,
+                });
+            }
+        }
+        let velocity =
+            distance.saturating_mul(1000) / NonZeroU32::new(duration_ms).unwrap_or(NonZeroU32::MIN);
+        if distance >= self.config.swipe_min_distance as u32
+            && velocity >= self.config.swipe_min_velocity
+        {
+            if let Some(edge) = self.starting_edge(&start.point, direction) {
+                return Some(Gesture::EdgeSwipe {
+                    edge,
+                    distance: distance as u16,
+                });
+            }
+            return Some(Gesture::Swipe {
+                direction,
+                distance: distance as u16,
+            });
+        }
+        None
+    }
+
+    /// Determines the edge a swipe started at.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The first touch point of the swipe.
+    /// * `direction` - The direction of the swipe.
+    ///
+    /// # Returns
+    ///
+    /// The `Edge` opposite to the direction of the swipe, if the swipe started within the edge
+    /// margin of it.
+    fn starting_edge(&self, start: &TouchPoint, direction: Direction) -> Option<Edge> {
+        let margin = self.config.edge_margin;
+        let screen = &self.config.screen;
+        if margin == 0 {
+            return None;
+        }
+        let (edge, distance_from_edge) = match direction {
+            Direction::Right => (Edge::Left, start.x.saturating_sub(screen.x_min)),
+            Direction::Left => (Edge::Right, screen.x_max.saturating_sub(start.x)),
+            Direction::Down => (Edge::Top, start.y.saturating_sub(screen.y_min)),
+            Direction::Up => (Edge::Bottom, screen.y_max.saturating_sub(start.y)),
+        };
+        (distance_from_edge <= margin).then_some(edge)
+    }
+
+    /// Records an event of the current contact for the exit velocity estimation.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The `Down` or `Move` event to record.
+    fn record(&mut self, event: &TouchEvent) {
+        if let Some(slot) = self.recent.get_mut(self.recent_index) {
+            *slot = Some(*event);
+        }
+        self.recent_index = self.recent_index.wrapping_add(1) % EXIT_VELOCITY_SAMPLES;
+    }
+
+    /// Estimates the velocity at the end of a stroke, from the oldest of the recent events.
+    ///
+    /// # Arguments
+    ///
+    /// * `end` - The `Up` event of the stroke.
+    /// * `direction` - The direction of the stroke.
+    ///
+    /// # Returns
+    ///
+    /// The velocity along the main axis of the stroke, in raw units per second.
+    fn exit_velocity(&self, end: &TouchEvent, direction: Direction) -> u32 {
+        let Some(oldest) = self
+            .recent
+            .iter()
+            .flatten()
+            .max_by_key(|event| end.timestamp_ms.wrapping_sub(event.timestamp_ms))
+        else {
+            return 0;
+        };
+        let displacement = match direction {
+            Direction::Left | Direction::Right => oldest.point.x.abs_diff(end.point.x),
+            Direction::Up | Direction::Down => oldest.point.y.abs_diff(end.point.y),
+        };
+        let elapsed_ms = end.timestamp_ms.wrapping_sub(oldest.timestamp_ms);
+        (displacement as u32).saturating_mul(1000)
+            / NonZeroU32::new(elapsed_ms).unwrap_or(NonZeroU32::MIN)
+    }
+
+    /// Turns a tap into a double tap, if it closely follows a previous tap.
+    ///
+    /// # Arguments
+    ///
+    /// * `end` - The `Up` event of the tap.
+    ///
+    /// # Returns
+    ///
+    /// The `Tap` or `DoubleTap` gesture.
+    fn tap(&mut self, end: &TouchEvent) -> Gesture {
+        if let Some((last_ms, last_point)) = self.last_tap.take() {
+            if end.timestamp_ms.wrapping_sub(last_ms) <= self.config.double_tap_window_ms
+                && last_point.x.abs_diff(end.point.x) <= self.config.tap_max_movement
+                && last_point.y.abs_diff(end.point.y) <= self.config.tap_max_movement
+            {
+                return Gesture::DoubleTap(end.point);
+            }
+        }
+        self.last_tap = Some((end.timestamp_ms, end.point));
+        Gesture::Tap(end.point)
+    }
+}
+
+impl GestureStage for GestureRecognizer {
+    type Gesture = Gesture;
+
+    fn recognize(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        self.update(event)
+    }
+}
+
+/// Detector of circular scrolling motion within a region, like a click wheel.
+#[derive(Debug)]
+pub struct CircularScroll {
+    /// The region of the wheel.
+    region: Rect,
+    /// The minimum distance from the center of the region for the motion to be tracked.
+    min_radius: u16,
+    /// The minimum angle reported at once, in degrees.
+    min_step_deg: f32,
+    /// The angle of the last tracked touch point, if a contact is being tracked.
+    last_angle_deg: Option<f32>,
+    /// The angle accumulated since the last report, in degrees.
+    pending_deg: f32,
+}
+
+impl CircularScroll {
+    /// Creates a new circular scroll detector.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - The region of the wheel. Contacts starting outside of it are ignored.
+    /// * `min_radius` - The minimum distance from the center of the region, below which the angle
+    ///   is too unstable to be tracked.
+    /// * `min_step_deg` - The minimum angle reported at once, smaller movements are accumulated.
+    pub fn new(region: Rect, min_radius: u16, min_step_deg: f32) -> Self {
+        Self {
+            region,
+            min_radius,
+            min_step_deg,
+            last_angle_deg: None,
+            pending_deg: 0.0,
+        }
+    }
+
+    /// Updates the detector with the next touch event.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The next event read from the event tracker.
+    ///
+    /// # Returns
+    ///
+    /// The angle travelled around the center of the region since the last report in degrees,
+    /// positive in the direction from the x axis towards the y axis.
+    pub fn update(&mut self, event: &TouchEvent) -> Option<f32> {
+        let point = &event.point;
+        match event.kind {
+            EventKind::Down if self.region.contains(point.x, point.y) => {
+                self.last_angle_deg = self.angle_deg(point);
+                self.pending_deg = 0.0;
+                None
+            }
+            EventKind::Down | EventKind::Up => {
+                self.last_angle_deg = None;
+                None
+            }
+            _ => {
+                let last_angle_deg = self.last_angle_deg?;
+                let angle_deg = self.angle_deg(point)?;
+                let mut delta_deg = angle_deg - last_angle_deg;
+                if delta_deg > 180.0 {
+                    delta_deg -= 360.0;
+                } else if delta_deg < -180.0 {
+                    delta_deg += 360.0;
+                }
+                self.last_angle_deg = Some(angle_deg);
+                self.pending_deg += delta_deg;
+                if self.pending_deg.abs() < self.min_step_deg {
+                    return None;
+                }
+                let reported_deg = self.pending_deg;
+                self.pending_deg = 0.0;
+                Some(reported_deg)
+            }
+        }
+    }
+
+    /// Computes the angle of a touch point around the center of the region.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point.
+    ///
+    /// # Returns
+    ///
+    /// The angle in degrees, or `None` if the point is closer to the center than the minimum
+    /// radius.
+    fn angle_deg(&self, point: &TouchPoint) -> Option<f32> {
+        let center_x = (self.region.x_min as f32 + self.region.x_max as f32) / 2.0;
+        let center_y = (self.region.y_min as f32 + self.region.y_max as f32) / 2.0;
+        let dx = point.x as f32 - center_x;
+        let dy = point.y as f32 - center_y;
+        let min_radius = self.min_radius as f32;
+        if dx * dx + dy * dy < min_radius * min_radius {
+            return None;
+        }
+        Some(atan2(dy, dx).to_degrees())
+    }
+}
+
+/// Approximates the four-quadrant arctangent of `y / x`, with an error below 0.005 radians.
+///
+/// # Arguments
+///
+/// * `y` - The y-coordinate.
+/// * `x` - The x-coordinate.
+///
+/// # Returns
+///
+/// The angle in radians, in the range of -π to π.
+fn atan2(y: f32, x: f32) -> f32 {
+    use core::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+    let atan = |z: f32| FRAC_PI_4 * z + 0.273 * z * (1.0 - z.abs());
+    if x == 0.0 && y == 0.0 {
+        0.0
+    } else if x.abs() >= y.abs() {
+        let angle = atan(y / x);
+        match (x < 0.0, y < 0.0) {
+            (false, _) => angle,
+            (true, false) => angle + PI,
+            (true, true) => angle - PI,
+        }
+    } else {
+        let angle = -atan(x / y);
+        if y > 0.0 {
+            angle + FRAC_PI_2
+        } else {
+            angle - FRAC_PI_2
+        }
+    }
+}
+
+/// Determines the direction of a stroke from its displacement.
+///
+/// # Arguments
+///
+/// * `dx` - The displacement along the x axis.
+/// * `dy` - The displacement along the y axis.
+///
+/// # Returns
+///
+/// The `Direction` of the main axis of the displacement.
+fn direction(dx: i32, dy: i32) -> Direction {
+    match (dx.abs() >= dy.abs(), dx > 0, dy > 0) {
+        (true, true, _) => Direction::Right,
+        (true, false, _) => Direction::Left,
+        (false, _, true) => Direction::Down,
+        (false, _, false) => Direction::Up,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: EventKind, x: u16, y: u16, timestamp_ms: u32) -> TouchEvent {
+        TouchEvent {
+            kind,
+            point: TouchPoint { x, y, z: 20.0 },
+            band: None,
+            timestamp_ms,
+        }
+    }
+
+    #[test]
+    fn test_tap_and_double_tap() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        assert_eq!(
+            recognizer.update(&event(EventKind::Down, 100, 100, 0)),
+            None
+        );
+        let tap = recognizer.update(&event(EventKind::Up, 105, 100, 100));
+        assert!(matches!(tap, Some(Gesture::Tap(_))));
+        recognizer.update(&event(EventKind::Down, 110, 100, 250));
+        let double_tap = recognizer.update(&event(EventKind::Up, 110, 100, 350));
+        assert!(matches!(double_tap, Some(Gesture::DoubleTap(_))));
+        recognizer.update(&event(EventKind::Down, 110, 100, 500));
+        let tap = recognizer.update(&event(EventKind::Up, 110, 100, 600));
+        assert!(matches!(tap, Some(Gesture::Tap(_))));
+    }
+
+    #[test]
+    fn test_swipe() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        recognizer.update(&event(EventKind::Down, 2000, 2000, 0));
+        recognizer.update(&event(EventKind::Move, 1800, 2100, 100));
+        let swipe = recognizer.update(&event(EventKind::Up, 1500, 2100, 200));
+        assert_eq!(
+            swipe,
+            Some(Gesture::Swipe {
+                direction: Direction::Left,
+                distance: 500
+            })
+        );
+        // Too slow to be a swipe.
+        recognizer.update(&event(EventKind::Down, 2000, 2000, 1000));
+        assert_eq!(
+            recognizer.update(&event(EventKind::Up, 2000, 2500, 2000)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_flick() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+        recognizer.update(&event(EventKind::Down, 2000, 2000, 0));
+        for (index, y) in [1990, 1970, 1900, 1750, 1550].into_iter().enumerate() {
+            recognizer.update(&event(EventKind::Move, 2000, y, 20 * (index as u32 + 1)));
+        }
+        let flick = recognizer.update(&event(EventKind::Up, 2000, 1350, 120));
+        // The exit velocity is measured from the oldest of the last four events, at 40 ms.
+        assert_eq!(
+            flick,
+            Some(Gesture::Flick {
+                direction: Direction::Up,
+                velocity: 7750
+            })
+        );
+    }
+
+    #[test]
+    fn test_edge_swipe() {
+        let mut recognizer = GestureRecognizer::new(GestureConfig {
+            edge_margin: 150,
+            screen: Rect {
+                x_min: 200,
+                y_min: 300,
+                x_max: 3900,
+                y_max: 3800,
+            },
+            ..GestureConfig::default()
+        });
+        recognizer.update(&event(EventKind::Down, 2000, 3700, 0));
+        let edge_swipe = recognizer.update(&event(EventKind::Up, 2000, 3000, 400));
+        assert_eq!(
+            edge_swipe,
+            Some(Gesture::EdgeSwipe {
+                edge: Edge::Bottom,
+                distance: 700
+            })
+        );
+        // Swiping towards the edge is a plain swipe.
+        recognizer.update(&event(EventKind::Down, 2000, 3000, 1000));
+        let swipe = recognizer.update(&event(EventKind::Up, 2000, 3700, 1400));
+        assert!(matches!(swipe, Some(Gesture::Swipe { .. })));
+    }
+
+    #[test]
+    fn test_gestures_follow_orientation() {
+        // The raw Y axis runs along the screen width, and the raw X axis is inverted.
+        let mut recognizer = GestureRecognizer::new(GestureConfig {
+            orientation: Orientation {
+                swap_xy: true,
+                invert_x: false,
+                invert_y: true,
+            },
+            edge_margin: 150,
+            ..GestureConfig::default()
+        });
+        recognizer.update(&event(EventKind::Down, 2000, 1000, 0));
+        let swipe = recognizer.update(&event(EventKind::Up, 2000, 1600, 400));
+        assert_eq!(
+            swipe,
+            Some(Gesture::Swipe {
+                direction: Direction::Right,
+                distance: 600
+            })
+        );
+        // A stroke away from the raw X maximum starts at the top edge of the display.
+        recognizer.update(&event(EventKind::Down, 4000, 2000, 1000));
+        let edge_swipe = recognizer.update(&event(EventKind::Up, 3300, 2000, 1400));
+        assert_eq!(
+            edge_swipe,
+            Some(Gesture::EdgeSwipe {
+                edge: Edge::Top,
+                distance: 700
+            })
+        );
+    }
+
+    #[cfg(feature = "calibration")]
+    #[test]
+    fn test_gestures_follow_calibration() {
+        // The raw Y axis runs along the screen width, and the raw X axis from 3900 at the top to
+        // 200 at the bottom.
+        let calibration =
+            Calibration::new(300, 3800, 3900, 200, 320, 240).with_orientation(Orientation {
+                swap_xy: true,
+                invert_x: false,
+                invert_y: true,
+            });
+        let mut recognizer = GestureRecognizer::new(GestureConfig {
+            edge_margin: 150,
+            ..GestureConfig::from_calibration(&calibration)
+        });
+        // A stroke away from the raw X edge of 3900 starts at the top edge of the display.
+        recognizer.update(&event(EventKind::Down, 3800, 2000, 0));
+        let edge_swipe = recognizer.update(&event(EventKind::Up, 3100, 2000, 400));
+        assert_eq!(
+            edge_swipe,
+            Some(Gesture::EdgeSwipe {
+                edge: Edge::Top,
+                distance: 700
+            })
+        );
+    }
+
+    #[test]
+    fn test_atan2_approximation() {
+        for step in -179..=180 {
+            let angle = (step as f32).to_radians();
+            let (y, x) = (angle.sin(), angle.cos());
+            assert!((atan2(y, x) - y.atan2(x)).abs() < 0.005);
+        }
+    }
+
+    #[test]
+    fn test_circular_scroll() {
+        let region = Rect {
+            x_min: 1000,
+            y_min: 1000,
+            x_max: 3000,
+            y_max: 3000,
+        };
+        let mut wheel = CircularScroll::new(region, 200, 10.0);
+        assert_eq!(wheel.update(&event(EventKind::Down, 2800, 2000, 0)), None);
+        // Less than the minimum step is accumulated.
+        assert_eq!(wheel.update(&event(EventKind::Move, 2800, 2050, 20)), None);
+        let delta = wheel
+            .update(&event(EventKind::Move, 2000, 2800, 40))
+            .expect("No angle reported");
+        assert!((delta - 90.0).abs() < 0.5);
+        let delta = wheel
+            .update(&event(EventKind::Move, 2800, 2000, 60))
+            .expect("No angle reported");
+        assert!((delta + 90.0).abs() < 0.5);
+        wheel.update(&event(EventKind::Up, 2800, 2000, 80));
+        assert_eq!(wheel.update(&event(EventKind::Move, 2000, 2800, 100)), None);
+    }
+}
@@ -0,0 +1,79 @@
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::{Error, OptionalDelay, OptionalInputPin, RoutineDelay, TouchSample, Tsc2046};
+
+/// Routine measuring the touches of the user over a sequence of prompted steps.
+pub(crate) trait GuidedRoutine {
+    /// The step of the routine, announced to the user.
+    type Step: Copy + PartialEq;
+
+    /// Returns the current step of the routine, or `None` once the measurements are complete.
+    fn pending_step(&self) -> Option<Self::Step>;
+
+    /// Records a touch sample for the current step.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - The touch sample, read once the panel has been released since the prompt.
+    fn record_sample(&mut self, sample: &TouchSample);
+}
+
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
+    /// Runs a guided routine until its measurements are complete.
+    ///
+    /// The `prompt` is called whenever the routine moves to a new step. Every step only starts
+    /// measuring once the panel has been released, so a touch is never counted for the wrong
+    /// step. The panel reads as released when PENIRQ is high, if available, or when no sample is
+    /// detected.
+    ///
+    /// # Arguments
+    ///
+    /// * `routine` - The routine collecting the measurements.
+    /// * `delay` - The delay provider used to wait between samples.
+    /// * `interval_ms` - The interval between two samples in milliseconds.
+    /// * `timeout_ms` - The maximum duration of the routine in milliseconds.
+    /// * `prompt` - The function asking the user to perform a step.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is [`Error::Timeout`] if the routine did not complete in time.
+    pub(crate) fn run_guided<R: GuidedRoutine, D: RoutineDelay<DELAY>>(
+        &mut self,
+        routine: &mut R,
+        delay: &mut D,
+        interval_ms: u32,
+        timeout_ms: u32,
+        mut prompt: impl FnMut(R::Step),
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        let mut prompted = None;
+        let mut released = false;
+        let mut elapsed_ms = 0_u32;
+        while let Some(step) = routine.pending_step() {
+            if prompted != Some(step) {
+                prompt(step);
+                prompted = Some(step);
+                released = false;
+            }
+            let sample = match self.read_pen_irq(|owned, us| delay.pause_us(owned, us))? {
+                Some(false) => None,
+                _ => self.get_sample()?,
+            };
+            match sample {
+                Some(sample) if released => routine.record_sample(&sample),
+                Some(_) => {}
+                None => released = true,
+            }
+            if elapsed_ms >= timeout_ms {
+                return Err(Error::Timeout);
+            }
+            delay.pause_us(&mut self.delay, interval_ms.saturating_mul(1000));
+            elapsed_ms = elapsed_ms.saturating_add(interval_ms);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use crate::event::{EventKind, TouchEvent};
+use crate::TouchPoint;
+
+/// The number of distinct raw coordinate values along each axis.
+const RAW_RANGE: u32 = 4096;
+
+/// Coarse grid counting touches per cell, for wear analysis and usage analytics.
+///
+/// The grid has `COLS` columns and `ROWS` rows spread evenly over the raw coordinate range. The
+/// counts saturate instead of wrapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap<const COLS: usize, const ROWS: usize> {
+    /// The number of touches counted in every cell, indexed by row and then by column.
+    cells: [[u16; COLS]; ROWS],
+}
+
+impl<const COLS: usize, const ROWS: usize> Default for Heatmap<COLS, ROWS> {
+    fn default() -> Self {
+        Self {
+            cells: [[0; COLS]; ROWS],
+        }
+    }
+}
+
+impl<const COLS: usize, const ROWS: usize> Heatmap<COLS, ROWS> {
+    /// Creates a new heatmap with all counts at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a touch at a touch point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The raw touch point.
+    pub fn record(&mut self, point: &TouchPoint) {
+        if COLS == 0 || ROWS == 0 {
+            return;
+        }
+        let col = ((point.x as u32).saturating_mul(COLS as u32) / RAW_RANGE) as usize;
+        let row = ((point.y as u32).saturating_mul(ROWS as u32) / RAW_RANGE) as usize;
+        if let Some(cell) = self
+            .cells
+            .get_mut(row.min(ROWS.saturating_sub(1)))
+            .and_then(|cells| cells.get_mut(col.min(COLS.saturating_sub(1))))
+        {
+            *cell = cell.saturating_add(1);
+        }
+    }
+
+    /// Counts a touch if the event starts a contact, so every contact is counted once.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The touch event.
+    pub fn record_event(&mut self, event: &TouchEvent) {
+        if event.kind == EventKind::Down {
+            self.record(&event.point);
+        }
+    }
+
+    /// Returns the number of touches counted in a cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - The column of the cell, from the left.
+    /// * `row` - The row of the cell, from the top.
+    ///
+    /// # Returns
+    ///
+    /// The count, or `None` if the cell is outside of the grid.
+    pub fn count(&self, col: usize, row: usize) -> Option<u16> {
+        self.cells.get(row)?.get(col).copied()
+    }
+
+    /// Returns the counts of all cells, indexed by row and then by column.
+    pub fn cells(&self) -> &[[u16; COLS]; ROWS] {
+        &self.cells
+    }
+
+    /// Resets all counts to zero.
+    pub fn clear(&mut self) {
+        self.cells = [[0; COLS]; ROWS];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heatmap() {
+        let mut heatmap = Heatmap::<4, 2>::new();
+        let point = |x, y| TouchPoint { x, y, z: 10.0 };
+        heatmap.record(&point(0, 0));
+        heatmap.record(&point(1023, 2047));
+        heatmap.record(&point(4095, 4095));
+        heatmap.record_event(&TouchEvent {
+            kind: EventKind::Move,
+            point: point(4095, 4095),
+            band: None,
+            timestamp_ms: 0,
+        });
+        heatmap.record_event(&TouchEvent {
+            kind: EventKind::Down,
+            point: point(4095, 4095),
+            band: None,
+            timestamp_ms: 0,
+        });
+        assert_eq!(heatmap.count(0, 0), Some(2));
+        assert_eq!(heatmap.count(3, 1), Some(2));
+        assert_eq!(heatmap.count(4, 0), None);
+        heatmap.clear();
+        assert_eq!(heatmap.cells(), &[[0; 4]; 2]);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::relative::RelativeReport;
+
+/// The length of a boot protocol HID mouse report in bytes.
+pub const MOUSE_REPORT_LEN: usize = 3;
+/// The maximum number of button transitions waiting to be sent.
+pub const BUTTON_QUEUE_CAPACITY: usize = 8;
+/// The bit of the left button in the button byte of a HID mouse report.
+const LEFT_BUTTON: u8 = 0b0000_0001;
+
+/// Packer turning trackpad mode output into boot protocol HID mouse reports.
+///
+/// Each report consists of the button byte followed by the x and y motion as signed bytes. Motion
+/// exceeding the range of a single report is spread over multiple reports. Every button transition
+/// gets a report of its own, in order, so a tap pressed and released between two reports is still
+/// sent as a click.
+#[derive(Debug, Default)]
+pub struct MouseReportPacker {
+    /// The motion along the x axis not yet sent.
+    pending_dx: i32,
+    /// The motion along the y axis not yet sent.
+    pending_dy: i32,
+    /// The state of the buttons after the last transition pushed.
+    buttons: u8,
+    /// The state of the buttons in the last report sent.
+    sent_buttons: u8,
+    /// The button states of the transitions not yet sent, oldest first from `queue_head`.
+    queue: [u8; BUTTON_QUEUE_CAPACITY],
+    /// The index of the oldest transition in the queue.
+    queue_head: usize,
+    /// The number of transitions in the queue.
+    queue_len: usize,
+}
+
+impl MouseReportPacker {
+    /// Creates a new HID mouse report packer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the output of the trackpad mode to the reports to be sent.
+    ///
+    /// A change of the buttons is queued as a transition. If the queue is full, the new transition
+    /// cancels the last one queued, dropping the shortest tap rather than desynchronizing the
+    /// button state.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - The relative motion and button state reported by the trackpad.
+    pub fn push(&mut self, report: &RelativeReport) {
+        self.pending_dx = self.pending_dx.saturating_add(report.dx as i32);
+        self.pending_dy = self.pending_dy.saturating_add(report.dy as i32);
+        let buttons = if report.button { LEFT_BUTTON } else { 0 };
+        if buttons == self.buttons {
+            return;
+        }
+        self.buttons = buttons;
+        if self.queue_len == BUTTON_QUEUE_CAPACITY {
+            self.queue_len = self.queue_len.saturating_sub(1);
+            return;
+        }
+        let index = self.queue_head.wrapping_add(self.queue_len) % BUTTON_QUEUE_CAPACITY;
+        if let Some(slot) = self.queue.get_mut(index) {
+            *slot = buttons;
+            self.queue_len = self.queue_len.saturating_add(1);
+        }
+    }
+
+    /// Packs the next HID mouse report to be sent.
+    ///
+    /// The oldest queued button transition is sent first, with the motion pending.
+    ///
+    /// # Returns
+    ///
+    /// The next report, or `None` if there is no motion pending and no button transition queued.
+    pub fn next_report(&mut self) -> Option<[u8; MOUSE_REPORT_LEN]> {
+        if let Some(&buttons) = self
+            .queue
+            .get(self.queue_head)
+            .filter(|_| self.queue_len > 0)
+        {
+            self.queue_head = self.queue_head.wrapping_add(1) % BUTTON_QUEUE_CAPACITY;
+            self.queue_len = self.queue_len.saturating_sub(1);
+            self.sent_buttons = buttons;
+        } else if self.pending_dx == 0 && self.pending_dy == 0 {
+            return None;
+        }
+        let dx = self.pending_dx.clamp(-127, 127);
+        let dy = self.pending_dy.clamp(-127, 127);
+        self.pending_dx = self.pending_dx.saturating_sub(dx);
+        self.pending_dy = self.pending_dy.saturating_sub(dy);
+        Some([self.sent_buttons, dx as i8 as u8, dy as i8 as u8])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_pending_motion_saturates() {
+        // 65539 reports of the largest motion exceed the range of the pending motion.
+        let mut packer = MouseReportPacker::new();
+        let report = RelativeReport {
+            dx: i16::MAX,
+            dy: i16::MIN,
+            button: false,
+        };
+        for _ in 0..u16::MAX as u32 + 4 {
+            packer.push(&report);
+        }
+        assert_eq!(packer.next_report(), Some([0, 127, (-127_i8) as u8]));
+    }
+
+    #[test]
+    fn test_mouse_reports() {
+        let mut packer = MouseReportPacker::new();
+        assert_eq!(packer.next_report(), None);
+        packer.push(&RelativeReport {
+            dx: 200,
+            dy: -3,
+            button: false,
+        });
+        assert_eq!(packer.next_report(), Some([0, 127, (-3_i8) as u8]));
+        assert_eq!(packer.next_report(), Some([0, 73, 0]));
+        assert_eq!(packer.next_report(), None);
+        packer.push(&RelativeReport {
+            dx: 0,
+            dy: 0,
+            button: true,
+        });
+        assert_eq!(packer.next_report(), Some([1, 0, 0]));
+        assert_eq!(packer.next_report(), None);
+    }
+
+    #[test]
+    fn test_quick_tap() {
+        let mut packer = MouseReportPacker::new();
+        let report = |dx, button| RelativeReport { dx, dy: 0, button };
+        // Pressed and released before the next report.
+        packer.push(&report(5, true));
+        packer.push(&report(0, false));
+        assert_eq!(packer.next_report(), Some([1, 5, 0]));
+        assert_eq!(packer.next_report(), Some([0, 0, 0]));
+        assert_eq!(packer.next_report(), None);
+        // A double tap within a single report interval.
+        for button in [true, false, true, false] {
+            packer.push(&report(0, button));
+        }
+        let reports: Vec<_> = core::iter::from_fn(|| packer.next_report()).collect();
+        assert_eq!(reports, [[1, 0, 0], [0, 0, 0], [1, 0, 0], [0, 0, 0]]);
+    }
+
+    #[test]
+    fn test_button_queue_overflow() {
+        let mut packer = MouseReportPacker::new();
+        for index in 0..BUTTON_QUEUE_CAPACITY + 2 {
+            packer.push(&RelativeReport {
+                dx: 0,
+                dy: 0,
+                button: index % 2 == 0,
+            });
+        }
+        // The last tap is dropped, the transitions still alternate and end released.
+        let reports: Vec<_> = core::iter::from_fn(|| packer.next_report()).collect();
+        assert_eq!(reports.len(), BUTTON_QUEUE_CAPACITY);
+        assert!(reports
+            .iter()
+            .zip([1, 0].iter().cycle())
+            .all(|(report, &buttons)| report[0] == buttons));
+        assert_eq!(reports.last(), Some(&[0, 0, 0]));
+    }
+}
@@ -0,0 +1,166 @@
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::calibration::Calibration;
+use crate::screen::{PanelGeometry, ScreenTsc2046};
+use crate::{Error, OptionalDelay, OptionalInputPin, Tsc2046};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Contact reported by a [`PointerInput`], independent of the touch controller.
+pub struct PointerContact {
+    /// The x-coordinate of the contact in screen pixels.
+    pub x: u16,
+    /// The y-coordinate of the contact in screen pixels.
+    pub y: u16,
+    /// The pressure of the contact between 0.0 and 1.0 (firmest), if the controller measures it.
+    pub pressure: Option<f32>,
+}
+
+/// Source of pointer input, such as a resistive or capacitive touch controller.
+///
+/// GUI integrations and application code can depend on this trait instead of a concrete driver,
+/// so the touch controller can be swapped without touching the consumers. The contacts are in
+/// screen coordinates, so the raw coordinates of the TSC2046 are mapped by a
+/// [`ScreenTsc2046`] or a [`CalibratedPointer`] first.
+pub trait PointerInput {
+    /// The error reported when the input cannot be read.
+    type Error;
+
+    /// Reads the current state of the pointer.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the contact, or `None` if the pointer is up.
+    fn read_pointer(&mut self) -> Result<Option<PointerContact>, Self::Error>;
+}
+
+impl<T: PointerInput + ?Sized> PointerInput for &mut T {
+    type Error = T::Error;
+
+    fn read_pointer(&mut self) -> Result<Option<PointerContact>, Self::Error> {
+        (**self).read_pointer()
+    }
+}
+
+impl<SPI, const W: u16, const H: u16, IRQ, DELAY, P: PanelGeometry> PointerInput
+    for ScreenTsc2046<SPI, W, H, IRQ, DELAY, P>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
+    type Error = Error<<SPI as ErrorType>::Error>;
+
+    fn read_pointer(&mut self) -> Result<Option<PointerContact>, Self::Error> {
+        let semantics = self.driver().config().pressure_semantics;
+        Ok(self.get_touch()?.map(|point| PointerContact {
+            x: point.x,
+            y: point.y,
+            pressure: Some(semantics.to_normalized(point.z)),
+        }))
+    }
+}
+
+/// Pointer input mapping the touch points of a driver onto the screen with a runtime
+/// calibration.
+pub struct CalibratedPointer<SPI, IRQ, DELAY> {
+    /// The driver sampled for touches.
+    driver: Tsc2046<SPI, IRQ, DELAY>,
+    /// The mapping of the raw coordinates onto the screen.
+    calibration: Calibration,
+}
+
+impl<SPI, IRQ, DELAY> CalibratedPointer<SPI, IRQ, DELAY> {
+    /// Wraps a driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - The driver sampled for touches.
+    /// * `calibration` - The mapping of the raw coordinates onto the screen.
+    pub fn new(driver: Tsc2046<SPI, IRQ, DELAY>, calibration: Calibration) -> Self {
+        Self {
+            driver,
+            calibration,
+        }
+    }
+
+    /// Returns the wrapped driver.
+    pub fn driver(&mut self) -> &mut Tsc2046<SPI, IRQ, DELAY> {
+        &mut self.driver
+    }
+
+    /// Replaces the calibration, for example after the user recalibrated the screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `calibration` - The new mapping of the raw coordinates onto the screen.
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    /// Releases the wrapped driver.
+    pub fn release(self) -> Tsc2046<SPI, IRQ, DELAY> {
+        self.driver
+    }
+}
+
+impl<SPI, IRQ, DELAY> PointerInput for CalibratedPointer<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
+    type Error = Error<<SPI as ErrorType>::Error>;
+
+    fn read_pointer(&mut self) -> Result<Option<PointerContact>, Self::Error> {
+        let semantics = self.driver.config().pressure_semantics;
+        Ok(self.driver.get_touch()?.map(|point| {
+            let screen = self.calibration.map(&point);
+            PointerContact {
+                x: screen.x,
+                y: screen.y,
+                pressure: Some(semantics.to_normalized(point.z)),
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::FakeTsc2046;
+
+    /// Collects the contacts of a few samples, through the abstraction only.
+    fn contacts<P: PointerInput>(mut input: P, samples: usize) -> Vec<Option<PointerContact>> {
+        (0..samples)
+            .filter_map(|_| input.read_pointer().ok())
+            .collect()
+    }
+
+    fn fake_chip() -> FakeTsc2046 {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 4095]);
+        fake_chip.queue(FakeTsc2046::Y, &[0]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500, 0]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1000]);
+        fake_chip
+    }
+
+    #[test]
+    fn test_pointer_input() {
+        let driver = Tsc2046::new(fake_chip(), false, 100.0).expect("Could not create driver");
+        let mut screen = driver.into_screen::<320, 240>();
+        let read = contacts(&mut screen, 2);
+        // The raw ratio of 4095 / 4096 * (1000 / 500 - 1) is normalized to about 1/2.
+        let contact = read[0].expect("No contact");
+        assert_eq!((contact.x, contact.y), (319, 0));
+        assert!((contact.pressure.expect("No pressure") - 0.5).abs() < 1e-3);
+        assert_eq!(read[1], None);
+
+        let driver = Tsc2046::new(fake_chip(), false, 100.0).expect("Could not create driver");
+        let calibration = Calibration::new(4095, 0, 0, 4095, 320, 240);
+        let read = contacts(CalibratedPointer::new(driver, calibration), 2);
+        let contact = read[0].expect("No contact");
+        assert_eq!((contact.x, contact.y), (0, 0));
+        assert_eq!(read[1], None);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{Rect, TouchPoint};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Event of a grid keypad, carrying the index of the key.
+///
+/// The keys are indexed row by row from the top left corner, `row * cols + col`.
+pub enum KeyEvent {
+    /// The key has been pressed.
+    Pressed(u32),
+    /// The key has been released.
+    Released(u32),
+}
+
+/// Keypad dividing an area of the screen into a grid of keys, like a membrane keypad.
+///
+/// A key is only pressed or released once the touch has been stable for the debounce count of
+/// readings, so a contact wandering across the border of two keys does not chatter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridKeypad {
+    /// The area covered by the keys, in the coordinates of the touch points.
+    area: Rect,
+    /// The number of columns of keys.
+    cols: u16,
+    /// The number of rows of keys.
+    rows: u16,
+    /// The number of consecutive readings required to change the pressed key.
+    debounce: u8,
+    /// The key currently pressed.
+    pressed: Option<u32>,
+    /// The key the readings point to, different from the pressed one.
+    candidate: Option<u32>,
+    /// The number of consecutive readings pointing to the candidate.
+    stable: u8,
+}
+
+impl GridKeypad {
+    /// Creates a new grid keypad, without debouncing.
+    ///
+    /// # Arguments
+    ///
+    /// * `area` - The area covered by the keys, in the coordinates of the touch points, usually
+    ///   calibrated screen coordinates.
+    /// * `cols` - The number of columns of keys, at least 1.
+    /// * `rows` - The number of rows of keys, at least 1.
+    pub fn new(area: Rect, cols: u16, rows: u16) -> Self {
+        Self {
+            area,
+            cols: cols.max(1),
+            rows: rows.max(1),
+            debounce: 1,
+            pressed: None,
+            candidate: None,
+            stable: 0,
+        }
+    }
+
+    /// Sets the number of consecutive readings required to press or release a key.
+    ///
+    /// # Arguments
+    ///
+    /// * `readings` - The number of readings, at least 1.
+    pub fn with_debounce(mut self, readings: u8) -> Self {
+        self.debounce = readings.max(1);
+        self
+    }
+
+    /// Returns the key at a touch point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point.
+    ///
+    /// # Returns
+    ///
+    /// The index of the key, or `None` if the point is outside of the keypad. The index is
+    /// computed in 32 bits, so it never overflows, even for a grid of `u16::MAX` rows and columns.
+    pub fn key_at(&self, point: &TouchPoint) -> Option<u32> {
+        if !self.area.contains(point.x, point.y) {
+            return None;
+        }
+        let cell = |value: u16, min: u16, max: u16, count: u16| {
+            let extent = (max.saturating_sub(min) as u32).saturating_add(1);
+            (value.saturating_sub(min) as u32)
+                .saturating_mul(count as u32)
+                .checked_div(extent)
+                .unwrap_or_default()
+        };
+        let col = cell(point.x, self.area.x_min, self.area.x_max, self.cols);
+        let row = cell(point.y, self.area.y_min, self.area.y_max, self.rows);
+        row.checked_mul(self.cols as u32)?.checked_add(col)
+    }
+
+    /// Returns the key currently pressed.
+    pub fn pressed(&self) -> Option<u32> {
+        self.pressed
+    }
+
+    /// Updates the keypad with the latest touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The key event, if any. When the touch slides from a key to another, the first key is
+    /// released, and the other one pressed at the next reading.
+    pub fn update(&mut self, touch: Option<TouchPoint>) -> Option<KeyEvent> {
+        let key = touch.and_then(|point| self.key_at(&point));
+        if key == self.pressed {
+            self.candidate = key;
+            self.stable = 0;
+            return None;
+        }
+        if key == self.candidate {
+            self.stable = self.stable.saturating_add(1);
+        } else {
+            self.candidate = key;
+            self.stable = 1;
+        }
+        if self.stable < self.debounce {
+            return None;
+        }
+        match self.pressed.take() {
+            Some(released) => Some(KeyEvent::Released(released)),
+            None => {
+                self.pressed = key;
+                self.stable = 0;
+                key.map(KeyEvent::Pressed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_keypad() {
+        let area = Rect {
+            x_min: 0,
+            y_min: 0,
+            x_max: 299,
+            y_max: 399,
+        };
+        let mut keypad = GridKeypad::new(area, 3, 4).with_debounce(2);
+        let touch = |x, y| Some(TouchPoint { x, y, z: 10.0 });
+        assert_eq!(
+            keypad.key_at(&TouchPoint {
+                x: 299,
+                y: 399,
+                z: 10.0
+            }),
+            Some(11)
+        );
+        assert_eq!(keypad.update(touch(150, 150)), None);
+        assert_eq!(keypad.update(touch(150, 150)), Some(KeyEvent::Pressed(4)));
+        // A single reading on the neighboring key is ignored.
+        assert_eq!(keypad.update(touch(250, 150)), None);
+        assert_eq!(keypad.update(touch(150, 150)), None);
+        assert_eq!(keypad.update(touch(250, 150)), None);
+        assert_eq!(keypad.update(touch(250, 150)), Some(KeyEvent::Released(4)));
+        assert_eq!(keypad.update(touch(250, 150)), Some(KeyEvent::Pressed(5)));
+        assert_eq!(keypad.pressed(), Some(5));
+        assert_eq!(keypad.update(None), None);
+        assert_eq!(keypad.update(None), Some(KeyEvent::Released(5)));
+        assert_eq!(keypad.update(None), None);
+    }
+
+    #[test]
+    fn test_large_grid_keypad() {
+        let area = Rect {
+            x_min: 0,
+            y_min: 0,
+            x_max: u16::MAX,
+            y_max: u16::MAX,
+        };
+        let keypad = GridKeypad::new(area, u16::MAX, u16::MAX);
+        let corner = TouchPoint {
+            x: u16::MAX,
+            y: u16::MAX,
+            z: 10.0,
+        };
+        assert_eq!(
+            keypad.key_at(&corner),
+            Some(u16::MAX as u32 * u16::MAX as u32 - 1)
+        );
+    }
+}
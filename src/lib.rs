@@ -1,30 +1,674 @@
 #![cfg_attr(not(test), no_std)]
+// No code path of the driver may panic, as it often ends up in safety-related HMI firmware.
+// The lints below reject the constructs which may panic, outside of the tests.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::arithmetic_side_effects,
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::todo,
+        clippy::unimplemented,
+        clippy::unreachable,
+        clippy::unwrap_used
+    )
+)]
 
+#[cfg(feature = "async")]
+pub mod asynch;
+#[cfg(feature = "battery")]
+pub mod battery;
+#[cfg(feature = "calibration")]
+pub mod calibration;
+mod config;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod device;
+pub mod diagnostics;
+#[cfg(feature = "eh0")]
+pub mod eh0;
+#[cfg(feature = "events")]
+pub mod event;
+#[cfg(feature = "factory")]
+pub mod factory;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "filters")]
+pub mod filter;
+#[cfg(feature = "gestures")]
+pub mod gesture;
+#[cfg(any(feature = "orientation", feature = "tuning"))]
+mod guided;
+#[cfg(feature = "heatmap")]
+pub mod heatmap;
+#[cfg(feature = "hid")]
+pub mod hid;
+#[cfg(feature = "input")]
+pub mod input;
+#[cfg(feature = "keypad")]
+pub mod keypad;
 #[cfg(test)]
 mod mock_peripherals;
+#[cfg(feature = "orientation")]
+pub mod orientation;
+#[cfg(feature = "debug-overlay")]
+pub mod overlay;
+#[cfg(any(feature = "storage", feature = "storage-async"))]
+pub mod persist;
+mod pins;
+#[cfg(feature = "pressure")]
+pub mod pressure;
+mod protocol;
+#[cfg(feature = "relative")]
+pub mod relative;
+#[cfg(feature = "replay")]
+pub mod replay;
+#[cfg(feature = "calibration")]
+pub mod screen;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod stage;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "tuning")]
+pub mod tuning;
 mod types;
+#[cfg(feature = "zones")]
+pub mod zones;
 
+pub use config::Config;
+use diagnostics::Diagnostics;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorKind, InputPin};
 use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
-use types::{Axes, ControlBit};
+#[cfg(feature = "async")]
+pub use pins::OptionalWait;
+pub use pins::{NoDelay, NoPin, OptionalDelay, OptionalInputPin, OwnedDelay, RoutineDelay};
+#[cfg(feature = "pressure")]
+use pressure::{ForceCalibration, PressureCurve};
+use protocol::ScanStep;
+use types::{AuxChannel, Axes, ControlBit};
 
-#[derive(Debug, PartialEq)]
+/// The maximum time the BUSY pin may stay high before giving up on a conversion, in microseconds.
+const BUSY_TIMEOUT_US: u32 = 1000;
+/// The interval between two polls of the BUSY pin, in microseconds.
+const BUSY_POLL_INTERVAL_US: u32 = 1;
+/// The default settling time of the internal reference after power-up, in microseconds.
+const DEFAULT_REFERENCE_WARMUP_US: u32 = 500;
+/// The default touch threshold, a touch resistance of twice the X-plate resistance.
+const DEFAULT_TOUCH_THRESHOLD: f32 = 2.0;
+/// The maximum number of dummy bytes clocked at the end of every frame.
+pub const MAX_PADDING_BYTES: usize = 4;
+/// The maximum number of conversions averaged for a single reading. The conversions are summed
+/// on 32 bits, so the limit only bounds the duration of a reading.
+pub const MAX_OVERSAMPLING: u16 = 256;
+/// The maximum number of conversions issued in a single transaction by the burst oversampling.
+/// Larger oversampling counts are split into several bursts.
+pub const MAX_BURST_LEN: u16 = 16;
+/// The maximum number of regions in which touches are ignored.
+pub const MAX_IGNORE_REGIONS: usize = 4;
+/// The voltage of the internal reference in millivolts.
+const INTERNAL_REFERENCE_MV: u32 = 2500;
+/// The interval between two polls while waiting for a touch, in microseconds.
+const TOUCH_POLL_INTERVAL_US: u32 = 1000;
+/// The number of conversions of a single-ended read with warm-up: the warm-up, the read and the
+/// restore of the power-down mode.
+const WARMUP_CONVERSIONS: u32 = 3;
+/// The number of conversions of a snapshot of every input: the eight inputs, the warm-up of the
+/// reference and the restore of the power-down mode.
+const SNAPSHOT_CONVERSIONS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Input channel of the TSC2046 chip.
+pub enum Channel {
+    /// The X position.
+    X,
+    /// The Y position.
+    Y,
+    /// The Z1 pressure channel.
+    Z1,
+    /// The Z2 pressure channel.
+    Z2,
+    /// The first temperature channel.
+    Temp0,
+    /// The second temperature channel.
+    Temp1,
+    /// The battery voltage.
+    Vbat,
+    /// The auxiliary input.
+    Aux,
+}
+impl Channel {
+    /// Decodes the channel addressed by a control word.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word.
+    ///
+    /// # Returns
+    ///
+    /// The channel selected by the address bits.
+    fn from_control_word(control_word: &ControlBit) -> Self {
+        let address = control_word.bits() & ControlBit::ADDRESS.bits();
+        let axis = Axes::ALL
+            .into_iter()
+            .find(|axis| axis.ctrl_bits().bits() == address)
+            .map(Channel::from);
+        let aux = || {
+            AuxChannel::ALL
+                .into_iter()
+                .find(|channel| channel.ctrl_bits().bits() == address)
+                .map(Channel::from)
+        };
+        // The eight addresses are split between the axes and the single-ended channels.
+        axis.or_else(aux).unwrap_or(Channel::Temp0)
+    }
+}
+impl From<Axes> for Channel {
+    fn from(axis: Axes) -> Self {
+        match axis {
+            Axes::X => Channel::X,
+            Axes::Y => Channel::Y,
+            Axes::Z1 => Channel::Z1,
+            Axes::Z2 => Channel::Z2,
+        }
+    }
+}
+impl From<AuxChannel> for Channel {
+    fn from(channel: AuxChannel) -> Self {
+        match channel {
+            AuxChannel::Temp0 => Channel::Temp0,
+            AuxChannel::Temp1 => Channel::Temp1,
+            AuxChannel::Vbat => Channel::Vbat,
+            AuxChannel::Aux => Channel::Aux,
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Errors that can occur while communicating with the TSC2046 chip.
+pub enum Error<E> {
+    /// An error occurred on the SPI bus while converting a channel.
+    Spi {
+        /// The channel being converted.
+        channel: Channel,
+        /// The error of the SPI bus.
+        error: E,
+    },
+    /// An error occurred on the SPI bus during a transaction converting several channels, such as
+    /// the one of [`Tsc2046::read_all`]. The bus does not tell which of the conversions failed.
+    SpiBatch(E),
+    /// An error occurred while reading one of the pins owned by the driver.
+    Pin(ErrorKind),
+    /// Sampling is currently suppressed by [`Tsc2046::inhibit`] or the sampling gate.
+    Inhibited,
+    /// No touch was detected before the deadline.
+    Timeout,
+    /// The given channel returned a value which cannot be processed.
+    InvalidReading(Channel),
+    /// The frame of a conversion of the given channel does not fit the transfer buffers, which
+    /// the configuration limits rule out.
+    FrameOverflow(Channel),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Inconsistency found by the validation of a configuration.
+pub enum ConfigError {
+    /// The touch threshold is not a finite positive number.
+    InvalidThreshold,
+    /// A sample count is zero, or above [`MAX_OVERSAMPLING`] for the oversampling.
+    InvalidSampleCount,
+    /// The number of padding bytes is above [`MAX_PADDING_BYTES`].
+    TooManyPaddingBytes,
+    /// The minimum of a range or an area is above its maximum.
+    InvertedRange,
+    /// The release value of a press level is below its press value.
+    InvertedHysteresis,
+    /// The trace capacity is zero or above [`trace::MAX_TRACE_CAPACITY`].
+    #[cfg(feature = "trace")]
+    InvalidTraceCapacity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 /// Struct representing a touch point on the touch screen.
 pub struct TouchPoint {
     /// The x-coordinate of the touch point, ranging from 0 to 4096.
     pub x: u16,
     /// The y-coordinate of the touch point, ranging from 0 to 4096.
     pub y: u16,
-    /// The pressure value of the touch point, ranging from 0.0 (max pressure) to the set touch threshold.
+    /// The pressure value of the touch point, in the configured [`PressureSemantics`]. By default
+    /// the raw ratio, ranging from 0.0 (max pressure) to the set touch threshold.
     pub z: f32,
 }
+#[cfg(feature = "pressure")]
+impl TouchPoint {
+    /// Estimates the force applied at the touch point.
+    ///
+    /// # Arguments
+    ///
+    /// * `calibration` - The force calibration of the touch screen.
+    ///
+    /// # Returns
+    ///
+    /// The estimated force, in the unit used when recording the calibration.
+    pub fn force(&self, calibration: &ForceCalibration) -> f32 {
+        calibration.estimate(self.z)
+    }
+
+    /// Maps the pressure of the touch point through a response curve.
+    ///
+    /// # Arguments
+    ///
+    /// * `curve` - The pressure response curve.
+    ///
+    /// # Returns
+    ///
+    /// The normalized pressure, between 0.0 (light) and 1.0 (firm).
+    pub fn pressure(&self, curve: &PressureCurve) -> f32 {
+        curve.apply(self.z)
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Struct representing a rectangular area of the touch screen, with inclusive bounds.
+pub struct Rect {
+    /// The smallest x-coordinate inside the area.
+    pub x_min: u16,
+    /// The smallest y-coordinate inside the area.
+    pub y_min: u16,
+    /// The largest x-coordinate inside the area.
+    pub x_max: u16,
+    /// The largest y-coordinate inside the area.
+    pub y_max: u16,
+}
+impl Rect {
+    /// Checks whether a position is inside the area.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the position.
+    /// * `y` - The y-coordinate of the position.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the position is inside the area or on its border.
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        (self.x_min..=self.x_max).contains(&x) && (self.y_min..=self.y_max).contains(&y)
+    }
+
+    /// Checks whether the minimum of both coordinates is not above their maximum.
+    pub(crate) const fn is_ordered(&self) -> bool {
+        self.x_min <= self.x_max && self.y_min <= self.y_max
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Signal quality of a touch sample, computed from the raw conversions.
+pub enum SampleQuality {
+    /// No problem detected.
+    Good,
+    /// The pressure is close to the touch threshold, the contact may be about to break.
+    MarginalPressure,
+    /// The oversampled conversions of a coordinate spread widely.
+    HighVariance,
+    /// A conversion sits at one of the rails, the position is likely wrong.
+    Railed,
+    /// The pressure is not available, because its conversions failed or touch detection fell
+    /// back to the PENIRQ pin. The position is reported without pressure.
+    Degraded,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Struct representing a touch sample, with additional information on the quality of the reading.
+pub struct TouchSample {
+    /// The touch point read from the touch screen.
+    pub point: TouchPoint,
+    /// The confidence of the position, ranging from 0.0 (unreliable) to 1.0 (reliable).
+    ///
+    /// Computed from the spread of the individual conversions when oversampling is enabled,
+    /// always 1.0 otherwise.
+    pub confidence: f32,
+    /// The signal quality of the sample. If several problems are detected, the most severe one
+    /// is reported.
+    pub quality: SampleQuality,
+}
+#[derive(Debug, Clone, Copy)]
+/// Conversion of raw AUX readings into engineering units.
+#[cfg(feature = "aux")]
+pub enum AuxScaling {
+    /// No conversion, the value equals the raw reading.
+    Raw,
+    /// Linear conversion computing `raw * scale + offset`.
+    Linear {
+        /// The factor applied to the raw reading.
+        scale: f32,
+        /// The offset added after scaling.
+        offset: f32,
+    },
+    /// Custom conversion, for example for non-linear sensors.
+    Custom(fn(u16) -> f32),
+}
+#[cfg(feature = "aux")]
+impl AuxScaling {
+    /// Converts a raw AUX reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - The raw reading of the AUX input.
+    ///
+    /// # Returns
+    ///
+    /// The reading in engineering units.
+    pub fn apply(&self, raw: u16) -> f32 {
+        match *self {
+            AuxScaling::Raw => raw as f32,
+            AuxScaling::Linear { scale, offset } => raw as f32 * scale + offset,
+            AuxScaling::Custom(convert) => convert(raw),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Struct representing a reading of the AUX input.
+#[cfg(feature = "aux")]
+pub struct AuxReading {
+    /// The raw reading, relative to the 2.5 V internal reference.
+    pub raw: u16,
+    /// The reading converted with the configured `AuxScaling`.
+    pub value: f32,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Raw conversions of every input of the chip, taken in a single transaction.
+pub struct ChannelSnapshot {
+    /// The X position.
+    pub x: u16,
+    /// The Y position.
+    pub y: u16,
+    /// The Z1 pressure channel.
+    pub z1: u16,
+    /// The Z2 pressure channel.
+    pub z2: u16,
+    /// The first temperature channel.
+    pub temp0: u16,
+    /// The second temperature channel.
+    pub temp1: u16,
+    /// The battery voltage channel.
+    pub vbat: u16,
+    /// The auxiliary input.
+    pub aux: u16,
+}
+impl ChannelSnapshot {
+    /// Returns the battery voltage.
+    ///
+    /// # Returns
+    ///
+    /// The battery voltage in millivolts.
+    pub fn vbat_mv(&self) -> u16 {
+        vbat_mv(self.vbat)
+    }
+
+    /// Returns the temperature of the chip.
+    ///
+    /// # Returns
+    ///
+    /// The temperature in degrees Celsius, from the difference of the two temperature inputs.
+    pub fn temperature(&self) -> f32 {
+        temperature(self.temp0, self.temp1)
+    }
+}
+/// Converts a VBAT conversion into a battery voltage.
+///
+/// # Arguments
+///
+/// * `raw` - The raw VBAT conversion.
+///
+/// # Returns
+///
+/// The battery voltage in millivolts.
+fn vbat_mv(raw: u16) -> u16 {
+    // The VBAT input is divided by 4 internally.
+    ((raw as u32).saturating_mul(INTERNAL_REFERENCE_MV * 4) / 4096).min(u16::MAX as u32) as u16
+}
+/// Takes the part of a transfer buffer holding a frame.
+///
+/// # Arguments
+///
+/// * `buf` - The transfer buffer.
+/// * `len` - The length of the frame.
+/// * `channel` - The channel converted in the frame.
+///
+/// # Returns
+///
+/// A `Result` containing the first `len` bytes of the buffer, or [`Error::FrameOverflow`] if the
+/// buffer is shorter.
+fn frame<E>(buf: &mut [u8], len: usize, channel: Channel) -> Result<&mut [u8], Error<E>> {
+    buf.get_mut(..len).ok_or(Error::FrameOverflow(channel))
+}
+/// Converts the two temperature conversions into a temperature.
+///
+/// # Arguments
+///
+/// * `temp0` - The raw TEMP0 conversion.
+/// * `temp1` - The raw TEMP1 conversion.
+///
+/// # Returns
+///
+/// The temperature in degrees Celsius.
+fn temperature(temp0: u16, temp1: u16) -> f32 {
+    let delta_mv = (temp1 as f32 - temp0 as f32) * INTERNAL_REFERENCE_MV as f32 / 4096.0;
+    2.573 * delta_mv - 273.15
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Mode of the X, Y and pressure measurements.
+pub enum MeasurementMode {
+    /// Ratiometric measurement, with the panel drivers as the reference. Cancels out the
+    /// variations of the panel excitation.
+    #[default]
+    Differential,
+    /// Absolute measurement against the reference voltage.
+    SingleEnded {
+        /// Whether to use the internal 2.5 V reference, which is then kept on between
+        /// conversions, instead of the VREF input.
+        internal_reference: bool,
+    },
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Policy applied when the position was read, but a pressure conversion failed.
+pub enum PartialSamplePolicy {
+    /// The position is discarded and the error is returned.
+    #[default]
+    Discard,
+    /// The position is returned as a sample of [`SampleQuality::Degraded`] quality, with a NaN
+    /// pressure. The error is still recorded in the diagnostics.
+    ///
+    /// Without the pressure, a touch cannot be told apart from a released panel, so the sample
+    /// should be validated by other means, for example with the PENIRQ pin.
+    Degraded,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Policy applied when the Z1 pressure channel reads zero, which would divide by zero in the
+/// pressure calculation.
+pub enum ZeroPressurePolicy {
+    /// The sample is reported as no touch.
+    #[default]
+    NoTouch,
+    /// Z1 is clamped to 1, which reports the firmest pressure measurable.
+    Clamp,
+    /// [`Error::InvalidReading`] is returned.
+    InvalidReading,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Domain of the reported pressure and of the touch threshold.
+///
+/// The raw ratio is the touch resistance relative to the X-plate resistance, which decreases as
+/// the panel is pressed harder. The other domains increase with the force, which is what most
+/// applications expect. The touch threshold is given in the selected domain. The pressure curves,
+/// force calibrations and press levels expect the raw ratio.
+pub enum PressureSemantics {
+    /// The raw ratio, from 0.0 (firmest) upwards. A touch is registered below the threshold.
+    #[default]
+    RawRatio,
+    /// The inverse of the raw ratio, up to 4096.0 (firmest). A touch is registered above the
+    /// threshold.
+    Inverted,
+    /// The raw ratio mapped to the range 0.0 to 1.0 (firmest), as `1 / (1 + ratio)`. A touch is
+    /// registered above the threshold.
+    Normalized,
+}
+impl PressureSemantics {
+    /// Converts a raw ratio into the domain.
+    ///
+    /// # Arguments
+    ///
+    /// * `ratio` - The raw ratio.
+    ///
+    /// # Returns
+    ///
+    /// The pressure in the domain.
+    pub fn from_raw(&self, ratio: f32) -> f32 {
+        match self {
+            PressureSemantics::RawRatio => ratio,
+            PressureSemantics::Inverted => 1.0 / ratio.max(1.0 / 4096.0),
+            PressureSemantics::Normalized => 1.0 / (1.0 + ratio.max(0.0)),
+        }
+    }
+
+    /// Converts a pressure of the domain into the normalized domain.
+    ///
+    /// # Arguments
+    ///
+    /// * `pressure` - The pressure in the domain.
+    ///
+    /// # Returns
+    ///
+    /// The pressure between 0.0 and 1.0 (firmest).
+    pub fn to_normalized(&self, pressure: f32) -> f32 {
+        let normalized = match self {
+            PressureSemantics::RawRatio => 1.0 / (1.0 + pressure.max(0.0)),
+            PressureSemantics::Inverted => pressure.max(0.0) / (1.0 + pressure.max(0.0)),
+            PressureSemantics::Normalized => pressure,
+        };
+        normalized.clamp(0.0, 1.0)
+    }
+
+    /// Checks whether a pressure is firmer than a threshold.
+    ///
+    /// # Arguments
+    ///
+    /// * `pressure` - The pressure in the domain.
+    /// * `threshold` - The threshold in the domain.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the pressure is strictly firmer than the threshold.
+    pub const fn is_firmer(&self, pressure: f32, threshold: f32) -> bool {
+        match self {
+            PressureSemantics::RawRatio => pressure < threshold,
+            PressureSemantics::Inverted | PressureSemantics::Normalized => pressure > threshold,
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Plausible range of the raw conversions of an axis.
+pub struct AxisRange {
+    /// The smallest plausible conversion.
+    pub min: u16,
+    /// The largest plausible conversion.
+    pub max: u16,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Policy applied to positions outside of the configured axis ranges.
+pub enum OutOfRangePolicy {
+    /// The scan is rejected and reported as no touch.
+    #[default]
+    Reject,
+    /// The position is clamped to the range.
+    Clamp,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Order of the conversions of a scan.
+pub enum ScanOrder {
+    /// The position is read first, then the pressure.
+    #[default]
+    PositionFirst,
+    /// The pressure is read first, and the scan is aborted without reading the position when the
+    /// panel is clearly not touched. Idle polls, the most frequent ones, are cheaper, but a
+    /// failed pressure conversion cannot produce a degraded sample.
+    PressureFirst {
+        /// The largest Z1 conversion of an untouched panel.
+        min_z1: u16,
+        /// Whether Z2 is read first as well, aborting the scan if it does not exceed Z1, which a
+        /// touch cannot produce.
+        with_z2: bool,
+    },
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Named starting point for the acquisition settings, turned into a [`Config`] with `into`.
+///
+/// Every preset also comes with a filter stage for the `EventTracker`, returned by
+/// `Preset::filter` with the `filters` feature.
+pub enum Preset {
+    /// A single conversion per axis, the pressure read first so idle polls are short, and a light
+    /// low-pass filter. For pointing and scrolling.
+    LowLatency,
+    /// The default configuration, the median of three conversions per axis, and a moderate
+    /// low-pass filter.
+    #[default]
+    Balanced,
+    /// Sixteen conversions averaged per axis in bursts, a filtered PENIRQ, a retry of anomalous
+    /// samples, and a strong low-pass filter. For handwriting and precise targets, at the cost of
+    /// the sample rate and of some lag.
+    LowNoise,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Acquisition mode of the X and Y positions.
+pub enum AcquisitionMode {
+    /// The configured number of conversions is averaged.
+    #[default]
+    Averaging,
+    /// The median of three conversions is taken, which rejects a single glitch without any
+    /// tolerance to tune.
+    Median,
+    /// Three conversions are taken, and two of them have to agree within the tolerance, else the
+    /// scan fails with [`Error::InvalidReading`]. The agreeing conversions are averaged, so a
+    /// glitch is never blended into the result.
+    MajorityVote {
+        /// The largest difference between two agreeing conversions, in raw units.
+        tolerance: u16,
+    },
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Behavior of the pressure calculation when its result is out of range.
+///
+/// The pressure is negative when Z2 reads below Z1, which the panel cannot produce. The integer
+/// coordinate math is widened so it cannot overflow for any input, see
+/// [`calibration::Calibration::map`].
+pub enum ArithmeticMode {
+    /// Out-of-range results saturate to the nearest valid value.
+    #[default]
+    Saturating,
+    /// Out-of-range results are reported as [`Error::InvalidReading`].
+    Checked,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Linear temperature compensation of the touch threshold.
+///
+/// The contact resistance of resistive panels drifts with temperature, so the threshold is
+/// adjusted by `per_degree * (temperature - reference_celsius)`.
+pub struct ThresholdCompensation {
+    /// The temperature at which the configured touch threshold applies, in degrees Celsius.
+    pub reference_celsius: f32,
+    /// The change of the touch threshold per degree Celsius.
+    pub per_degree: f32,
+}
 /// Driver for the TSC2046 4-wire touch screen controller.
-pub struct Tsc2046<SPI> {
+pub struct Tsc2046<SPI, IRQ = NoPin, DELAY = NoDelay> {
     /// The SPI interface used to communicate with the TSC2046 chip.
     spi: SPI,
-    /// Whether the interrupt pin is enabled or not.
-    irq_on: bool,
-    /// The minimum pressure value required to register a touch event.
-    touch_threshold: f32,
+    /// The pin connected to the PENIRQ output of the chip, if owned by the driver.
+    irq_pin: IRQ,
+    /// The delay provider, if owned by the driver.
+    delay: DELAY,
+    /// The state shared with the async flavor: configuration, diagnostics and trace.
+    core: protocol::Core,
 }
 impl<SPI> Tsc2046<SPI>
 where
@@ -45,38 +689,153 @@ where
         spi: SPI,
         irq_on: bool,
         touch_threshold: f32,
-    ) -> Result<Self, <SPI as ErrorType>::Error> {
+    ) -> Result<Self, Error<<SPI as ErrorType>::Error>> {
+        Self::from_config(
+            spi,
+            Config {
+                irq_on,
+                touch_threshold,
+                acquisition: AcquisitionMode::Averaging,
+                ..Config::new()
+            },
+        )
+    }
+
+    /// Creates a new instance of the `Tsc2046` driver with the default configuration.
+    ///
+    /// The interrupt pin is enabled and the positions are the median of three conversions, see
+    /// [`Config::default`]. Meant to get a working touch input in one line, before tuning.
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - The SPI interface used to communicate with the TSC2046 chip.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Tsc2046` instance or an error if the register update fails.
+    pub fn with_defaults(spi: SPI) -> Result<Self, Error<<SPI as ErrorType>::Error>> {
+        Self::from_config(spi, Config::default())
+    }
+
+    /// Creates a new instance of the `Tsc2046` driver with the configuration of a preset.
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - The SPI interface used to communicate with the TSC2046 chip.
+    /// * `preset` - The preset of the acquisition settings.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Tsc2046` instance or an error if the register update fails.
+    pub fn with_preset(spi: SPI, preset: Preset) -> Result<Self, Error<<SPI as ErrorType>::Error>> {
+        Self::from_config(spi, preset.into())
+    }
+
+    /// Creates a new instance of the `Tsc2046` driver with the given configuration.
+    ///
+    /// Takes any SPI device, such as a [`device::CsDevice`] owning the BUSY pin of the chip.
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - The SPI interface used to communicate with the TSC2046 chip.
+    /// * `config` - The configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Tsc2046` instance or an error if the register update fails.
+    pub fn from_config(spi: SPI, config: Config) -> Result<Self, Error<<SPI as ErrorType>::Error>> {
         let mut instance = Self {
             spi,
-            irq_on,
-            touch_threshold,
+            irq_pin: NoPin,
+            delay: NoDelay,
+            core: protocol::Core::new(config),
         };
-        instance.update_register()?;
+        instance.apply_config(config)?;
         Ok(instance)
     }
+}
+impl<SPI, DELAY> Tsc2046<SPI, NoPin, DELAY>
+where
+    SPI: SpiDevice,
+{
+    /// Hands the pin connected to the PENIRQ output of the chip over to the driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `irq_pin` - The input pin connected to the PENIRQ output.
+    ///
+    /// # Returns
+    ///
+    /// The driver instance owning the PENIRQ pin.
+    pub fn with_irq_pin<IRQ: InputPin>(self, irq_pin: IRQ) -> Tsc2046<SPI, IRQ, DELAY> {
+        Tsc2046 {
+            spi: self.spi,
+            irq_pin,
+            delay: self.delay,
+            core: self.core,
+        }
+    }
+}
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
     /// Updates the control register of the TSC2046 chip.
     ///
     /// # Returns
     ///
     /// A `Result` indicating whether the register update was successful or not.
-    fn update_register(&mut self) -> Result<(), <SPI as ErrorType>::Error> {
-        let mut control_word = ControlBit::S; //start bit always on
-        control_word &= !ControlBit::MODE; // 12 bit mode
-        control_word &= !ControlBit::SER; // enable differential mode
-        control_word |= Axes::X.ctrl_bits();
-        if self.irq_on {
-            control_word &= !ControlBit::PD0;
-            control_word &= !ControlBit::PD1;
-        } else {
-            control_word |= ControlBit::PD0;
-            control_word |= ControlBit::PD1;
-        }
-
-        let mut buf = [0_u8; 2];
-        self.spi.transaction(&mut [
-            Operation::Write(&[control_word.bits()]),
-            Operation::Read(&mut buf),
-        ])
+    fn update_register(&mut self) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        let control_word = self.register_control_word();
+        self.convert(control_word)?;
+        self.core.registered();
+        Ok(())
+    }
+    /// Builds the control word programming the configured power-down mode.
+    ///
+    /// # Returns
+    ///
+    /// The control word of an X conversion with the configured power-down bits.
+    fn register_control_word(&self) -> ControlBit {
+        protocol::register_control_word(&self.core.config)
+    }
+    /// Sends a control word to the TSC2046 chip and reads back the result of the conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word to send.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the 12 bit result of the conversion or an error if the transfer fails.
+    fn convert(
+        &mut self,
+        control_word: ControlBit,
+    ) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        let result = self.transfer(control_word);
+        self.core.diagnostics.record(result)
+    }
+    /// Performs the SPI transfer of a conversion, without recording it in the diagnostics.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word to send.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the 12 bit result of the conversion or an error if the transfer fails.
+    fn transfer(
+        &mut self,
+        control_word: ControlBit,
+    ) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        let mut frame = self.core.frame(control_word);
+        let channel = frame.channel();
+        self.spi
+            .transaction(&mut frame.operations()?)
+            .map_err(|error| Error::Spi { channel, error })?;
+        Ok(self.core.complete(&frame))
     }
     /// Reads the value of the specified axis from the TSC2046 chip.
     ///
@@ -87,26 +846,148 @@ where
     /// # Returns
     ///
     /// A `Result` containing the raw value of the specified axis or an error if the read fails.
-    fn read_axis(&mut self, axis: Axes) -> Result<u16, <SPI as ErrorType>::Error> {
-        let mut control_word = ControlBit::S; //start bit always on
-        control_word &= !ControlBit::MODE; // 12 bit mode
-        control_word &= !ControlBit::SER; // enable differential mode
-        control_word |= axis.ctrl_bits();
+    fn read_axis(&mut self, axis: Axes) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        self.convert(protocol::axis_control_word(axis, &self.core.config))
+    }
+
+    /// Reads a single-ended channel against the internal reference.
+    ///
+    /// If the internal reference is powered down between conversions, it is switched on by a first
+    /// conversion, given the configured settling time, and switched off again after the
+    /// conversion. All of this happens within a single SPI transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel to read.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the raw value of the channel or an error if the read fails.
+    fn read_single_ended(
+        &mut self,
+        channel: AuxChannel,
+    ) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        let control_word = protocol::single_ended_control_word(channel);
+        if !self.core.config.irq_on {
+            // The internal reference is always on, no warm-up needed.
+            return self.convert(control_word);
+        }
+        let result = self.transfer_with_warmup(control_word);
+        self.core
+            .diagnostics
+            .record_conversions(result, WARMUP_CONVERSIONS)
+    }
+    /// Performs the SPI transfer of a single-ended conversion preceded by the warm-up of the
+    /// internal reference, without recording it in the diagnostics.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word of the single-ended conversion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the 12 bit result of the conversion or an error if the transfer fails.
+    fn transfer_with_warmup(
+        &mut self,
+        control_word: ControlBit,
+    ) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        let channel = Channel::from_control_word(&control_word);
+        let restore_word = self.register_control_word();
+        let len = self.core.frame_len();
+        // The result is followed by the padding in every frame.
+        let mut warmup_buf = [0_u8; 2 + MAX_PADDING_BYTES];
+        let mut buf = [0_u8; 2 + MAX_PADDING_BYTES];
+        let mut restore_buf = [0_u8; 2 + MAX_PADDING_BYTES];
+        self.spi
+            .transaction(&mut [
+                Operation::Write(&[control_word.bits()]),
+                Operation::Read(frame(&mut warmup_buf, len, channel)?),
+                Operation::DelayNs(self.core.config.reference_warmup_us.saturating_mul(1000)),
+                Operation::Write(&[control_word.bits()]),
+                Operation::Read(frame(&mut buf, len, channel)?),
+                Operation::Write(&[restore_word.bits()]),
+                Operation::Read(frame(&mut restore_buf, len, channel)?),
+            ])
+            .map_err(|error| Error::Spi { channel, error })?;
+        let [warmup_buf, buf, restore_buf] =
+            [warmup_buf, buf, restore_buf].map(|frame| [frame[0], frame[1]]);
+        let result = protocol::decode(&buf);
+        self.core
+            .observe(control_word.bits(), protocol::decode(&warmup_buf));
+        self.core.observe(control_word.bits(), result);
+        self.core
+            .observe(restore_word.bits(), protocol::decode(&restore_buf));
+        Ok(result)
+    }
 
-        if self.irq_on {
-            control_word &= !ControlBit::PD0;
-            control_word &= !ControlBit::PD1;
-        } else {
-            control_word |= ControlBit::PD0;
-            control_word |= ControlBit::PD1;
+    /// Performs the conversions of a reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word of the conversions.
+    /// * `count` - The number of conversions.
+    /// * `burst` - Whether the conversions are issued in bursts of up to [`MAX_BURST_LEN`], or
+    ///   each in its own transaction.
+    /// * `feed` - The function receiving the result of every conversion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether all of the conversions succeeded.
+    fn convert_all(
+        &mut self,
+        control_word: ControlBit,
+        count: u16,
+        burst: bool,
+        mut feed: impl FnMut(u16),
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        if !burst {
+            for _ in 0..count {
+                feed(self.convert(control_word)?);
+            }
+            return Ok(());
+        }
+        let mut remaining = count;
+        while remaining > 0 {
+            let len = remaining.min(MAX_BURST_LEN);
+            let result = self.transfer_burst(control_word, len, &mut feed);
+            self.core
+                .diagnostics
+                .record_conversions(result, u32::from(len))?;
+            remaining = remaining.saturating_sub(len);
         }
+        Ok(())
+    }
 
-        let mut buf = [0_u8; 2];
-        self.spi.transaction(&mut [
-            Operation::Write(&[control_word.bits()]),
-            Operation::Read(&mut buf),
-        ])?;
-        Ok((((buf[0] as u16) << 8 | buf[1] as u16) >> 3) & 0xFFF)
+    /// Performs the SPI transfer of a burst of identical conversions, without recording it in the
+    /// diagnostics.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word of the conversions.
+    /// * `count` - The number of conversions, at most [`MAX_BURST_LEN`].
+    /// * `feed` - The function receiving the result of every conversion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the transfer succeeded.
+    fn transfer_burst(
+        &mut self,
+        control_word: ControlBit,
+        count: u16,
+        feed: &mut impl FnMut(u16),
+    ) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        let mut burst = self.core.burst(control_word, count);
+        let channel = burst.channel();
+        let len = burst.operation_count();
+        let mut operations = burst.operations()?;
+        let operations = operations
+            .get_mut(..len)
+            .ok_or(Error::FrameOverflow(channel))?;
+        self.spi
+            .transaction(operations)
+            .map_err(|error| Error::Spi { channel, error })?;
+        self.core.complete_burst(&burst, feed);
+        Ok(())
     }
 
     /// Enables or disables the interrupt pin.
@@ -118,8 +999,64 @@ where
     /// # Returns
     ///
     /// A `Result` indicating whether the interrupt pin configuration was successful or not.
-    pub fn set_irq(&mut self, enable_irq: bool) -> Result<(), <SPI as ErrorType>::Error> {
-        self.irq_on = enable_irq;
+    pub fn set_irq(&mut self, enable_irq: bool) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        self.core.config.irq_on = enable_irq;
+        self.update_register()
+    }
+
+    /// Returns a snapshot of the configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// A copy of the current `Config`.
+    pub fn config(&self) -> Config {
+        self.core.config
+    }
+
+    /// Applies a configuration, typically a snapshot taken with [`Tsc2046::config`].
+    ///
+    /// The control register of the chip is updated, as the configuration may change the
+    /// power-down mode. The temperature compensation offset is kept, unless the compensation is
+    /// disabled by the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to apply.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the register update was successful or not.
+    pub fn apply_config(&mut self, config: Config) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        self.core.apply_config(config);
+        self.update_register()
+    }
+
+    /// Re-runs the initialization sequence of the TSC2046 chip with the current configuration.
+    ///
+    /// Meant for products where the power rail of the panel or the chip can drop independently
+    /// of the MCU, after a brownout or hot-plug. The configuration is held by the driver and sent
+    /// with every conversion, so re-initializing restores the power-down mode of the chip. A
+    /// fallback to PENIRQ-only touch detection is cleared, as the panel may have been replaced.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the initialization was successful or not.
+    pub fn reinit(&mut self) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        self.core.diagnostics.clear_pressure_fallback();
+        self.update_register()
+    }
+
+    /// Prepares the TSC2046 chip for the MCU entering deep sleep, with PENIRQ as a wake source.
+    ///
+    /// Enables the interrupt pin and programs both power-down bits to zero, so the chip powers
+    /// down between conversions while keeping the PENIRQ output active. The control byte is sent
+    /// in a single SPI transaction, which is flushed before this method returns.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the control byte was committed successfully or not.
+    pub fn prepare_for_sleep(&mut self) -> Result<(), Error<<SPI as ErrorType>::Error>> {
+        self.core.config.irq_on = true;
         self.update_register()
     }
 
@@ -129,37 +1066,968 @@ where
     ///
     /// * `touch_threshold` - The minimum pressure value (between 0.0 and inf).   
     pub fn set_touch_threshold(&mut self, touch_threshold: f32) {
-        self.touch_threshold = touch_threshold;
+        self.core.config.touch_threshold = touch_threshold;
     }
 
-    /// Reads the touch point from the TSC2046 chip.
+    /// Sets the number of conversions averaged for every axis.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// A `Result` containing the `TouchPoint` struct if a touch event is detected, or `None` if no touch event is detected or an error occurs during the read operation.
-    /// The `x` and `y` coordinates of the `TouchPoint` are in the range of 0 to 4096.
-    pub fn get_touch(&mut self) -> Result<Option<TouchPoint>, <SPI as ErrorType>::Error> {
-        let x_raw = self.read_axis(Axes::X)?;
-        let y_raw = self.read_axis(Axes::Y)?;
-        let z1_raw = self.read_axis(Axes::Z1)?;
-        let z2_raw = self.read_axis(Axes::Z2)?;
-        let z_value = x_raw as f32 / 4096_f32 * (z2_raw as f32 / z1_raw as f32 - 1.0f32);
-        if z_value < self.touch_threshold {
-            Ok(Some(TouchPoint {
-                x: x_raw,
-                y: y_raw,
-                z: z_value,
-            }))
-        } else {
-            Ok(None)
+    /// * `samples` - The number of conversions per axis, clamped between 1 and
+    ///   [`MAX_OVERSAMPLING`].
+    pub fn set_oversampling(&mut self, samples: u16) {
+        self.core.config.oversampling = samples.clamp(1, MAX_OVERSAMPLING);
+    }
+
+    /// Enables or disables the burst oversampling.
+    ///
+    /// When enabled, the conversions averaged for an axis are issued back-to-back within a single
+    /// transaction of up to [`MAX_BURST_LEN`] conversions, so the chip select toggles once per
+    /// burst instead of once per conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable` - Whether to issue the conversions in bursts.
+    pub fn set_burst_oversampling(&mut self, enable: bool) {
+        self.core.config.burst_oversampling = enable;
+    }
+
+    /// Enables or disables the automatic refresh of the control register on anomalies.
+    ///
+    /// When enabled, a touch sample with a coordinate at one of the rails, or stuck readings,
+    /// cause the control byte to be sent again before the sample is retried once. This recovers
+    /// from the chip losing its state due to ESD or supply glitches.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable` - Whether to enable the automatic refresh.
+    pub fn set_auto_refresh(&mut self, enable: bool) {
+        self.core.config.auto_refresh = enable;
+    }
+
+    /// Sets the number of scans after which the control register is sent again.
+    ///
+    /// The refresh restores the power-down and reference configuration if ESD or a supply
+    /// glitch reset the chip without the samples looking anomalous. For a refresh on a timer
+    /// instead, call [`Tsc2046::reinit`] from the timer.
+    ///
+    /// # Arguments
+    ///
+    /// * `scans` - The number of scans between two refreshes, 0 to disable the refresh.
+    pub fn set_refresh_interval(&mut self, scans: u16) {
+        self.core.config.refresh_interval = scans;
+    }
+
+    /// Sets the policy applied when a pressure conversion fails after the position was read.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The partial sample policy.
+    pub fn set_partial_sample_policy(&mut self, policy: PartialSamplePolicy) {
+        self.core.config.partial_samples = policy;
+    }
+
+    /// Sets the policy applied when the Z1 pressure channel reads zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The zero pressure policy.
+    pub fn set_zero_pressure_policy(&mut self, policy: ZeroPressurePolicy) {
+        self.core.config.zero_pressure = policy;
+    }
+
+    /// Restricts the raw X and Y conversions to plausible ranges.
+    ///
+    /// Panels rarely reach the full range of the ADC, and conversions beyond the edges of the
+    /// active area are glitches. They are rejected or clamped, but the pressure and the quality
+    /// of the sample are always computed from the raw conversions: only the reported position is
+    /// clamped.
+    ///
+    /// # Arguments
+    ///
+    /// * `x_range` - The plausible range of the X conversions, or `None` for the full range.
+    /// * `y_range` - The plausible range of the Y conversions, or `None` for the full range.
+    /// * `policy` - What to do with positions outside of the ranges.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the ranges were applied, or [`ConfigError::InvertedRange`]
+    /// if the minimum of a range is above its maximum.
+    pub fn set_axis_ranges(
+        &mut self,
+        x_range: Option<AxisRange>,
+        y_range: Option<AxisRange>,
+        policy: OutOfRangePolicy,
+    ) -> Result<(), ConfigError> {
+        if [x_range, y_range]
+            .into_iter()
+            .flatten()
+            .any(|range| range.min > range.max)
+        {
+            return Err(ConfigError::InvertedRange);
+        }
+        self.core.config.x_range = x_range;
+        self.core.config.y_range = y_range;
+        self.core.config.out_of_range = policy;
+        Ok(())
+    }
+
+    /// Adds a region in which touches are ignored entirely, for example over a bezel cutout or a
+    /// damaged area of the panel.
+    ///
+    /// Touches in the region are reported as no touch, before any event or gesture processing.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - The area to ignore, in raw coordinates.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the region was added, `false` if [`MAX_IGNORE_REGIONS`] regions are already
+    /// registered.
+    pub fn add_ignore_region(&mut self, region: Rect) -> bool {
+        let Some(slot) = self
+            .core
+            .config
+            .ignore_regions
+            .iter_mut()
+            .find(|slot| slot.is_none())
+        else {
+            return false;
+        };
+        *slot = Some(region);
+        true
+    }
+
+    /// Removes all the regions in which touches are ignored.
+    pub fn clear_ignore_regions(&mut self) {
+        self.core.config.ignore_regions = [None; MAX_IGNORE_REGIONS];
+    }
+
+    /// Restricts the touches to the part of the panel over the display.
+    ///
+    /// Touches outside of the active area are reported as no touch, and the coordinates inside it
+    /// are stretched to the full raw range, so the rest of the processing sees the active area as
+    /// the whole panel. The ignore regions are still given in panel coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `area` - The active area in raw coordinates, or `None` to use the whole panel.
+    pub fn set_active_area(&mut self, area: Option<Rect>) {
+        self.core.config.active_area = area;
+    }
+
+    /// Sets the order of the conversions of a scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The order of the conversions.
+    pub fn set_scan_order(&mut self, order: ScanOrder) {
+        self.core.config.scan_order = order;
+    }
+
+    /// Sets the domain of the reported pressure and of the touch threshold.
+    ///
+    /// The touch threshold is not converted, it has to be set in the new domain.
+    ///
+    /// # Arguments
+    ///
+    /// * `semantics` - The domain of the pressure.
+    pub fn set_pressure_semantics(&mut self, semantics: PressureSemantics) {
+        self.core.config.pressure_semantics = semantics;
+    }
+
+    /// Sets the behavior of the pressure calculation on out-of-range results.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The arithmetic mode.
+    pub fn set_arithmetic_mode(&mut self, mode: ArithmeticMode) {
+        self.core.config.arithmetic = mode;
+    }
+
+    /// Sets the acquisition mode of the X and Y positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The acquisition mode.
+    pub fn set_acquisition_mode(&mut self, mode: AcquisitionMode) {
+        self.core.config.acquisition = mode;
+    }
+
+    /// Sets the number of dummy bytes clocked at the end of every frame.
+    ///
+    /// For SPI controllers which can only transfer multiples of a certain number of words. The
+    /// padding is ignored when decoding the results.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The number of dummy bytes, limited to [`MAX_PADDING_BYTES`].
+    pub fn set_padding_bytes(&mut self, bytes: u8) {
+        self.core.config.padding_bytes = bytes.min(MAX_PADDING_BYTES as u8);
+    }
+
+    /// Sets the mode of the X, Y and pressure measurements.
+    ///
+    /// The differential mode is ratiometric and recommended for most setups. The single-ended
+    /// mode measures against the reference voltage, for setups where the panel excitation is
+    /// monitored externally.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The measurement mode.
+    pub fn set_measurement_mode(&mut self, mode: MeasurementMode) {
+        self.core.config.measurement_mode = mode;
+    }
+
+    /// Sets the settling time given to the internal reference after it is switched on.
+    ///
+    /// The internal reference is only switched on for battery, temperature and auxiliary reads
+    /// while the interrupt pin is enabled, as it is powered down between conversions in that mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `warmup_us` - The settling time in microseconds.
+    pub fn set_reference_warmup(&mut self, warmup_us: u32) {
+        self.core.config.reference_warmup_us = warmup_us;
+    }
+
+    /// Reads the battery voltage from the VBAT input.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the battery voltage in millivolts or an error if the read fails.
+    pub fn read_vbat(&mut self) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        let raw = self.read_single_ended(AuxChannel::Vbat)?;
+        Ok(vbat_mv(raw))
+    }
+
+    /// Reads the temperature of the chip, using the difference of the two temperature inputs.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the temperature in degrees Celsius or an error if the read fails.
+    pub fn read_temperature(&mut self) -> Result<f32, Error<<SPI as ErrorType>::Error>> {
+        let temp0 = self.read_single_ended(AuxChannel::Temp0)?;
+        let temp1 = self.read_single_ended(AuxChannel::Temp1)?;
+        Ok(temperature(temp0, temp1))
+    }
+
+    /// Reads the battery voltage from the VBAT input, as a dimensional quantity.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the battery voltage or an error if the read fails.
+    #[cfg(feature = "uom")]
+    pub fn read_vbat_quantity(
+        &mut self,
+    ) -> Result<uom::si::f32::ElectricPotential, Error<<SPI as ErrorType>::Error>> {
+        let millivolts = self.read_vbat()? as f32;
+        Ok(uom::si::f32::ElectricPotential::new::<
+            uom::si::electric_potential::millivolt,
+        >(millivolts))
+    }
+
+    /// Reads the temperature of the chip, as a dimensional quantity.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the temperature or an error if the read fails.
+    #[cfg(feature = "uom")]
+    pub fn read_temperature_quantity(
+        &mut self,
+    ) -> Result<uom::si::f32::ThermodynamicTemperature, Error<<SPI as ErrorType>::Error>> {
+        let celsius = self.read_temperature()?;
+        Ok(uom::si::f32::ThermodynamicTemperature::new::<
+            uom::si::thermodynamic_temperature::degree_celsius,
+        >(celsius))
+    }
+
+    /// Enables or disables the temperature compensation of the touch threshold.
+    ///
+    /// The compensation takes effect on the next call of
+    /// [`Tsc2046::update_threshold_compensation`]. Disabling it restores the configured threshold.
+    ///
+    /// # Arguments
+    ///
+    /// * `compensation` - The compensation to apply, or `None` to disable it.
+    pub fn set_threshold_compensation(&mut self, compensation: Option<ThresholdCompensation>) {
+        self.core.config.threshold_compensation = compensation;
+        self.core.threshold_offset = 0.0;
+    }
+
+    /// Measures the temperature of the chip and adjusts the touch threshold accordingly.
+    ///
+    /// Temperature changes slowly, so calling this method every few seconds is sufficient.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the measured temperature in degrees Celsius, or an error if the read
+    /// fails. The threshold is left unchanged if the compensation is disabled.
+    pub fn update_threshold_compensation(
+        &mut self,
+    ) -> Result<f32, Error<<SPI as ErrorType>::Error>> {
+        let temperature = self.read_temperature()?;
+        if let Some(compensation) = self.core.config.threshold_compensation {
+            self.core.threshold_offset =
+                compensation.per_degree * (temperature - compensation.reference_celsius);
+        }
+        Ok(temperature)
+    }
+
+    /// Sets the number of conversions averaged for every AUX reading.
+    ///
+    /// External sensors usually benefit from heavier filtering than the touch coordinates. Note
+    /// that while the interrupt pin is enabled, every conversion includes the warm-up of the
+    /// internal reference.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The number of conversions to average, clamped between 1 and
+    ///   [`MAX_OVERSAMPLING`].
+    #[cfg(feature = "aux")]
+    pub fn set_aux_oversampling(&mut self, samples: u16) {
+        self.core.config.aux_oversampling = samples.clamp(1, MAX_OVERSAMPLING);
+    }
+
+    /// Sets the acquisition mode of the AUX readings.
+    ///
+    /// The averaging takes the number of conversions set with
+    /// [`Tsc2046::set_aux_oversampling`], the median and the majority vote always three. A
+    /// reading rejected by the majority vote returns [`Error::InvalidReading`].
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The acquisition mode.
+    #[cfg(feature = "aux")]
+    pub fn set_aux_acquisition_mode(&mut self, mode: AcquisitionMode) {
+        self.core.config.aux_acquisition = mode;
+    }
+
+    /// Sets the conversion of AUX readings into engineering units.
+    ///
+    /// # Arguments
+    ///
+    /// * `scaling` - The conversion applied by [`Tsc2046::read_aux`].
+    #[cfg(feature = "aux")]
+    pub fn set_aux_scaling(&mut self, scaling: AuxScaling) {
+        self.core.config.aux_scaling = scaling;
+    }
+
+    /// Reads the AUX input.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the raw and the converted value of the AUX input, or an error if the
+    /// read fails.
+    #[cfg(feature = "aux")]
+    pub fn read_aux(&mut self) -> Result<AuxReading, Error<<SPI as ErrorType>::Error>> {
+        let reading = protocol::acquire(
+            self.core.config.aux_acquisition,
+            self.core.config.aux_oversampling,
+            || self.read_single_ended(AuxChannel::Aux),
+        )?;
+        let Some((raw, _)) = reading else {
+            return self.core.diagnostics.record_rejected_vote(Channel::Aux);
+        };
+        Ok(AuxReading {
+            raw,
+            value: self.core.config.aux_scaling.apply(raw),
+        })
+    }
+
+    /// Reads every input of the chip in a single transaction.
+    ///
+    /// The touch screen inputs are converted in the configured measurement mode, and the
+    /// temperature, battery and auxiliary inputs single-ended against the internal reference. The
+    /// reference is switched on by a first, discarded conversion and given the configured settling
+    /// time, then the configured power-down mode is restored by a final conversion. The
+    /// transaction holds the bus for all ten conversions, unless the conversions are interleaved,
+    /// in which case every input is read in its own transaction.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the raw conversions of all inputs, or an error if the transfer fails:
+    /// [`Error::SpiBatch`] for the single transaction, or [`Error::Spi`] with the failing channel
+    /// when every input is read in its own transaction.
+    pub fn read_all(&mut self) -> Result<ChannelSnapshot, Error<<SPI as ErrorType>::Error>> {
+        if self.core.config.interleaved {
+            return self.read_each();
+        }
+        let result = self.transfer_all();
+        self.core
+            .diagnostics
+            .record_conversions(result, SNAPSHOT_CONVERSIONS as u32)
+    }
+    /// Reads every input of the chip, each in its own transaction.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the raw conversions of all inputs or an error if a read fails.
+    fn read_each(&mut self) -> Result<ChannelSnapshot, Error<<SPI as ErrorType>::Error>> {
+        Ok(ChannelSnapshot {
+            x: self.read_axis(Axes::X)?,
+            y: self.read_axis(Axes::Y)?,
+            z1: self.read_axis(Axes::Z1)?,
+            z2: self.read_axis(Axes::Z2)?,
+            temp0: self.read_single_ended(AuxChannel::Temp0)?,
+            temp1: self.read_single_ended(AuxChannel::Temp1)?,
+            vbat: self.read_single_ended(AuxChannel::Vbat)?,
+            aux: self.read_single_ended(AuxChannel::Aux)?,
+        })
+    }
+    /// Performs the SPI transfer of [`Tsc2046::read_all`], without recording it in the
+    /// diagnostics.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the raw conversions of all inputs or an error if the transfer fails.
+    fn transfer_all(&mut self) -> Result<ChannelSnapshot, Error<<SPI as ErrorType>::Error>> {
+        let axis = |axis| protocol::axis_control_word(axis, &self.core.config).bits();
+        let single_ended = |channel| protocol::single_ended_control_word(channel).bits();
+        let commands = [
+            axis(Axes::X),
+            axis(Axes::Y),
+            axis(Axes::Z1),
+            axis(Axes::Z2),
+            single_ended(AuxChannel::Temp0),
+            single_ended(AuxChannel::Temp0),
+            single_ended(AuxChannel::Temp1),
+            single_ended(AuxChannel::Vbat),
+            single_ended(AuxChannel::Aux),
+            self.register_control_word().bits(),
+        ];
+        let len = self.core.frame_len();
+        // The result is followed by the padding in every frame.
+        let mut frames = [[0_u8; 2 + MAX_PADDING_BYTES]; SNAPSHOT_CONVERSIONS];
+        let [x, y, z1, z2, warmup, temp0, temp1, vbat, aux, restore] = &mut frames;
+        let [x_cmd, y_cmd, z1_cmd, z2_cmd, warmup_cmd, temp0_cmd, temp1_cmd, vbat_cmd, aux_cmd, restore_cmd] =
+            &commands;
+        self.spi
+            .transaction(&mut [
+                Operation::Write(core::slice::from_ref(x_cmd)),
+                Operation::Read(frame(x, len, Channel::X)?),
+                Operation::Write(core::slice::from_ref(y_cmd)),
+                Operation::Read(frame(y, len, Channel::Y)?),
+                Operation::Write(core::slice::from_ref(z1_cmd)),
+                Operation::Read(frame(z1, len, Channel::Z1)?),
+                Operation::Write(core::slice::from_ref(z2_cmd)),
+                Operation::Read(frame(z2, len, Channel::Z2)?),
+                Operation::Write(core::slice::from_ref(warmup_cmd)),
+                Operation::Read(frame(warmup, len, Channel::Temp0)?),
+                Operation::DelayNs(self.core.config.reference_warmup_us.saturating_mul(1000)),
+                Operation::Write(core::slice::from_ref(temp0_cmd)),
+                Operation::Read(frame(temp0, len, Channel::Temp0)?),
+                Operation::Write(core::slice::from_ref(temp1_cmd)),
+                Operation::Read(frame(temp1, len, Channel::Temp1)?),
+                Operation::Write(core::slice::from_ref(vbat_cmd)),
+                Operation::Read(frame(vbat, len, Channel::Vbat)?),
+                Operation::Write(core::slice::from_ref(aux_cmd)),
+                Operation::Read(frame(aux, len, Channel::Aux)?),
+                Operation::Write(core::slice::from_ref(restore_cmd)),
+                Operation::Read(frame(restore, len, Channel::X)?),
+            ])
+            .map_err(Error::SpiBatch)?;
+        let [x, y, z1, z2, warmup, temp0, temp1, vbat, aux, restore] =
+            frames.map(|frame| protocol::decode(&[frame[0], frame[1]]));
+        for (command, result) in commands
+            .into_iter()
+            .zip([x, y, z1, z2, warmup, temp0, temp1, vbat, aux, restore])
+        {
+            self.core.observe(command, result);
+        }
+        Ok(ChannelSnapshot {
+            x,
+            y,
+            z1,
+            z2,
+            temp0,
+            temp1,
+            vbat,
+            aux,
+        })
+    }
+
+    /// Sends an arbitrary control byte and reads back the result of the conversion.
+    ///
+    /// Expert API for cases the high-level API does not cover. The byte is sent as is, so a
+    /// power-down mode different from the configured one stays in effect until the next
+    /// conversion issued by the driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The control byte to send, including the start bit.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the 12 bit result of the conversion or an error if the transfer fails.
+    pub fn raw_command(&mut self, command: u8) -> Result<u16, Error<<SPI as ErrorType>::Error>> {
+        self.convert(ControlBit::from_bits_retain(command))
+    }
+
+    /// Sets an observer which is called with every control byte sent and the decoded result.
+    ///
+    /// Useful to mirror the traffic to a debug console, or to check protocol expectations in
+    /// integration tests. The observer is only called for transfers that succeeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - The observer function, or `None` to remove the observer.
+    pub fn set_observer(&mut self, observer: Option<fn(u8, u16)>) {
+        self.core.config.observer = observer;
+    }
+
+    /// Returns the most recent SPI exchanges with the chip.
+    ///
+    /// # Returns
+    ///
+    /// The trace buffer holding the last [`Config::trace_capacity`] exchanges.
+    #[cfg(feature = "trace")]
+    pub fn trace(&self) -> &trace::TraceBuffer {
+        &self.core.trace
+    }
+
+    /// Suppresses acquisitions until [`Tsc2046::resume`] is called.
+    ///
+    /// Useful to avoid sampling during display refresh or backlight PWM edges, which couple badly
+    /// into resistive panels. While inhibited, reading a touch returns [`Error::Inhibited`].
+    pub fn inhibit(&mut self) {
+        self.core.inhibited = true;
+    }
+
+    /// Resumes acquisitions suppressed by [`Tsc2046::inhibit`].
+    pub fn resume(&mut self) {
+        self.core.inhibited = false;
+    }
+
+    /// Sets a gate which is called before every acquisition, and suppresses it by returning
+    /// `false`.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The gate function, or `None` to remove the gate.
+    pub fn set_sampling_gate(&mut self, gate: Option<fn() -> bool>) {
+        self.core.config.sampling_gate = gate;
+    }
+
+    /// Sets whether every conversion is its own SPI transaction.
+    ///
+    /// While enabled, a display sharing the bus can interleave its transfers with a scan or with
+    /// [`Tsc2046::read_all`]. Burst oversampling is bypassed, as a burst holds the bus for all of
+    /// its conversions, and the async flavor yields to the executor between the conversions. Use
+    /// [`Tsc2046::get_sample_interleaved`] to run code between the transactions.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable` - Whether to interleave the conversions.
+    pub fn set_interleaved(&mut self, enable: bool) {
+        self.core.config.interleaved = enable;
+    }
+
+    /// Reads the touch point from the TSC2046 chip.
+    ///
+    /// If the driver owns the PENIRQ pin and the interrupt is enabled, the pin is read through the
+    /// glitch filter first, and a pen-up ends the scan without any conversion.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TouchPoint` struct if a touch event is detected, or `None` if no touch event is detected or an error occurs during the read operation.
+    /// The `x` and `y` coordinates of the `TouchPoint` are in the range of 0 to 4096.
+    pub fn get_touch(&mut self) -> Result<Option<TouchPoint>, Error<<SPI as ErrorType>::Error>> {
+        Ok(self.get_sample()?.map(|sample| sample.point))
+    }
+
+    /// Reads a touch sample from the TSC2046 chip.
+    ///
+    /// Same as [`Tsc2046::get_touch`], but the touch point is accompanied by a confidence score,
+    /// derived from the worst spread of the oversampled X and Y conversions.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TouchSample` struct if a touch event is detected, or `None` if
+    /// no touch event is detected.
+    pub fn get_sample(&mut self) -> Result<Option<TouchSample>, Error<<SPI as ErrorType>::Error>> {
+        let mut scan = self.core.start_scan()?;
+        self.scan(&mut scan, false, || {})
+    }
+
+    /// Reads a touch sample from the TSC2046 chip, calling `gap` after every transaction.
+    ///
+    /// Same as [`Tsc2046::get_sample`], but every conversion of the scan is its own short SPI
+    /// transaction, whatever the configuration, and `gap` is called between them. The gap can
+    /// serve another device sharing the bus, such as a display, with whatever state it captures.
+    ///
+    /// # Arguments
+    ///
+    /// * `gap` - The function called after every transaction of the scan.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TouchSample` struct if a touch event is detected, or `None` if
+    /// no touch event is detected.
+    pub fn get_sample_interleaved(
+        &mut self,
+        gap: impl FnMut(),
+    ) -> Result<Option<TouchSample>, Error<<SPI as ErrorType>::Error>> {
+        let mut scan = self.core.start_scan()?;
+        self.scan(&mut scan, true, gap)
+    }
+
+    /// Runs a scan of the touch screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `scan` - The scan to run, holding the raw readings once done.
+    /// * `interleaved` - Whether every conversion is forced into its own transaction.
+    /// * `gap` - The function called after every transaction which is not a burst.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TouchSample` struct if a touch event is detected, or `None` if
+    /// no touch event is detected.
+    pub(crate) fn scan(
+        &mut self,
+        scan: &mut protocol::Scan,
+        interleaved: bool,
+        mut gap: impl FnMut(),
+    ) -> Result<Option<TouchSample>, Error<<SPI as ErrorType>::Error>> {
+        loop {
+            match scan.step(&self.core.config, &mut self.core.diagnostics) {
+                ScanStep::Refresh => {
+                    self.update_register()?;
+                    gap();
+                }
+                ScanStep::ReadPen => {
+                    let pause_us = |delay: &mut DELAY, us: u32| {
+                        delay.pause_ns(us.saturating_mul(1000));
+                    };
+                    scan.pen(self.read_pen_irq(pause_us)?);
+                }
+                ScanStep::Convert {
+                    control_word,
+                    count,
+                    burst,
+                } => {
+                    let burst = burst && !interleaved;
+                    let result = self.convert_all(control_word, count, burst, |value| {
+                        scan.feed(value);
+                        if !burst {
+                            gap();
+                        }
+                    });
+                    if let Err(error) = result {
+                        if !scan.recover(&self.core.config) {
+                            return Err(error);
+                        }
+                    }
+                }
+                ScanStep::Done(result) => return result,
+            }
+        }
+    }
+
+    /// Reads the PENIRQ pin through the glitch filter, if the driver owns the pin and the
+    /// interrupt is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `pause_us` - The function pausing between two samples of the filter, in microseconds,
+    ///   given the delay provider owned by the driver.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `Some(true)` if the pen is down, `Some(false)` if it is up, or `None`
+    /// if the pen state is not available.
+    pub(crate) fn read_pen_irq(
+        &mut self,
+        mut pause_us: impl FnMut(&mut DELAY, u32),
+    ) -> Result<Option<bool>, Error<<SPI as ErrorType>::Error>> {
+        if !self.core.config.irq_on {
+            return Ok(None);
+        }
+        filter_pen_irq(
+            || self.irq_pin.read_level(),
+            &self.core.config,
+            |us| pause_us(&mut self.delay, us),
+        )
+        .map_err(Error::Pin)
+    }
+
+    /// Samples the touch points of the current contact, until it is released.
+    ///
+    /// The samples are taken [`stream::STROKE_SAMPLE_INTERVAL_US`] apart. If the screen is not
+    /// touched, the iterator ends right away. It also ends after yielding an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - The delay provider used to wait between samples, or [`OwnedDelay`].
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the touch points of the contact.
+    #[cfg(feature = "stream")]
+    pub fn points<'a, D: RoutineDelay<DELAY>>(
+        &'a mut self,
+        delay: &'a mut D,
+    ) -> stream::Points<'a, SPI, IRQ, DELAY, D> {
+        stream::Points::new(self, delay)
+    }
+
+    /// Waits for a touch, up to a timeout.
+    ///
+    /// The touch screen is polled every millisecond. The time spent on the conversions is not
+    /// accounted for, so the actual timeout is slightly longer than requested. While sampling is
+    /// inhibited, the wait continues without sampling.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - The delay provider used to wait between polls, or [`OwnedDelay`].
+    /// * `timeout_ms` - The maximum time to wait in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the first `TouchPoint` detected, or [`Error::Timeout`] if no touch
+    /// was detected in time.
+    pub fn get_touch_blocking<D: RoutineDelay<DELAY>>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<TouchPoint, Error<<SPI as ErrorType>::Error>> {
+        let mut elapsed_us = 0_u64;
+        loop {
+            if let Some(point) = self.poll_touch(elapsed_us, timeout_ms)? {
+                return Ok(point);
+            }
+            delay.pause_us(&mut self.delay, TOUCH_POLL_INTERVAL_US);
+            elapsed_us = elapsed_us.saturating_add(TOUCH_POLL_INTERVAL_US as u64);
+        }
+    }
+
+    /// Polls for a touch once, as part of a wait with a timeout.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed_us` - The time spent waiting so far in microseconds.
+    /// * `timeout_ms` - The maximum time to wait in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TouchPoint` if a touch was detected, `None` if the wait goes on,
+    /// or [`Error::Timeout`] if no touch was detected in time.
+    fn poll_touch(
+        &mut self,
+        elapsed_us: u64,
+        timeout_ms: u32,
+    ) -> Result<Option<TouchPoint>, Error<<SPI as ErrorType>::Error>> {
+        match self.get_touch() {
+            Ok(Some(point)) => return Ok(Some(point)),
+            Ok(None) | Err(Error::Inhibited) => {}
+            Err(error) => return Err(error),
+        }
+        if elapsed_us >= (timeout_ms as u64).saturating_mul(1000) {
+            return Err(Error::Timeout);
+        }
+        Ok(None)
+    }
+
+    /// Polls for a touch, up to a number of attempts.
+    ///
+    /// Variant of [`Tsc2046::get_touch_blocking`] for targets without a delay provider, bounded by
+    /// the number of polls instead of wall-clock time. While sampling is inhibited, attempts are
+    /// still counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_attempts` - The maximum number of polls (a value of 0 is treated as 1).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the first `TouchPoint` detected, or [`Error::Timeout`] if no touch
+    /// was detected within the attempts.
+    pub fn get_touch_polling(
+        &mut self,
+        max_attempts: u32,
+    ) -> Result<TouchPoint, Error<<SPI as ErrorType>::Error>> {
+        for _ in 0..max_attempts.max(1) {
+            match self.get_touch() {
+                Ok(Some(point)) => return Ok(point),
+                Ok(None) | Err(Error::Inhibited) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Returns the health information collected while operating.
+    ///
+    /// # Returns
+    ///
+    /// A copy of the `Diagnostics`, suitable for device health telemetry.
+    pub fn diagnostics(&self) -> Diagnostics {
+        self.core.diagnostics
+    }
+}
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: InputPin,
+{
+    /// Configures the glitch filter applied to the PENIRQ pin.
+    ///
+    /// The PENIRQ line is known to produce short spikes during ADC activity, so a pen-down is only
+    /// registered if the line stays low for `samples` consecutive reads, taken `interval_us`
+    /// microseconds apart.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The number of consecutive low samples required (a value of 0 is treated as 1).
+    /// * `interval_us` - The delay between two consecutive samples in microseconds.
+    pub fn set_irq_glitch_filter(&mut self, samples: u8, interval_us: u32) {
+        self.core.config.irq_filter_samples = samples.max(1);
+        self.core.config.irq_filter_interval_us = interval_us;
+    }
+
+    /// Checks whether the pen is down, based on the filtered state of the PENIRQ pin.
+    ///
+    /// The interrupt pin has to be enabled with [`Tsc2046::set_irq`] for PENIRQ to be driven by
+    /// the chip.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - The delay provider used to space out the samples of the glitch filter.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `true` if the PENIRQ line stayed low for the configured number of
+    /// samples, or an error if reading the pin fails.
+    pub fn is_pen_down<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<bool, Error<<SPI as ErrorType>::Error>> {
+        let down = filter_pen_irq(
+            || self.irq_pin.read_level(),
+            &self.core.config,
+            |us| delay.delay_us(us),
+        );
+        Ok(down.map_err(Error::Pin)? == Some(true))
+    }
+}
+impl<SPI, IRQ> Tsc2046<SPI, IRQ, NoDelay>
+where
+    SPI: SpiDevice,
+{
+    /// Hands a delay provider over to the driver.
+    ///
+    /// The timing-dependent operations are then available without passing a delay provider to
+    /// every call, see [`Tsc2046::wait_for_touch`] and [`Tsc2046::pen_down`].
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - The delay provider.
+    ///
+    /// # Returns
+    ///
+    /// The driver instance owning the delay provider.
+    pub fn with_delay<DELAY: DelayNs>(self, delay: DELAY) -> Tsc2046<SPI, IRQ, DELAY> {
+        Tsc2046 {
+            spi: self.spi,
+            irq_pin: self.irq_pin,
+            delay,
+            core: self.core,
+        }
+    }
+}
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: DelayNs,
+{
+    /// Waits for a touch, up to a timeout, with the delay provider owned by the driver.
+    ///
+    /// Same as [`Tsc2046::get_touch_blocking`] with [`OwnedDelay`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout_ms` - The maximum time to wait in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the first `TouchPoint` detected, or [`Error::Timeout`] if no touch
+    /// was detected in time.
+    pub fn wait_for_touch(
+        &mut self,
+        timeout_ms: u32,
+    ) -> Result<TouchPoint, Error<<SPI as ErrorType>::Error>> {
+        self.get_touch_blocking(&mut OwnedDelay, timeout_ms)
+    }
+}
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: InputPin,
+    DELAY: DelayNs,
+{
+    /// Checks whether the pen is down, with the delay provider owned by the driver.
+    ///
+    /// Same as [`Tsc2046::is_pen_down`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `true` if the PENIRQ line stayed low for the configured number of
+    /// samples, or an error if reading the pin fails.
+    pub fn pen_down(&mut self) -> Result<bool, Error<<SPI as ErrorType>::Error>> {
+        let down = filter_pen_irq(
+            || self.irq_pin.read_level(),
+            &self.core.config,
+            |us| self.delay.delay_us(us),
+        );
+        Ok(down.map_err(Error::Pin)? == Some(true))
+    }
+}
+
+/// Samples the PENIRQ pin through the configured glitch filter.
+///
+/// A pen-down is only registered if the line stays low for every sample, so a single-sample
+/// glitch reads as pen-up.
+///
+/// # Arguments
+///
+/// * `read_level` - The function reading the level of the pin, see
+///   [`OptionalInputPin::read_level`].
+/// * `config` - The configuration holding the glitch filter parameters.
+/// * `pause_us` - The function pausing between two samples, in microseconds.
+///
+/// # Returns
+///
+/// A `Result` containing `Some(true)` if the line stayed low for every sample, `Some(false)` if
+/// it did not, `None` if no pin is connected, or the kind of the error if reading the pin fails.
+pub(crate) fn filter_pen_irq(
+    mut read_level: impl FnMut() -> Result<Option<bool>, ErrorKind>,
+    config: &Config,
+    mut pause_us: impl FnMut(u32),
+) -> Result<Option<bool>, ErrorKind> {
+    for sample in 0..config.irq_filter_samples {
+        if sample > 0 {
+            pause_us(config.irq_filter_interval_us);
+        }
+        match read_level()? {
+            None => return Ok(None),
+            Some(true) => return Ok(Some(false)),
+            Some(false) => {}
         }
     }
+    Ok(Some(true))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mock_peripherals::{MockOperation, MockSimpleHalSpiDevice};
+    use crate::mock_peripherals::{
+        FakeTsc2046, MockDelay, MockInputPin, MockOperation, MockSimpleHalSpiDevice,
+    };
+    #[cfg(feature = "aux")]
+    use std::sync::Mutex;
 
     // Predefined control words for testing
     const CTRL_WORD_X_NO_IRQ: u8 = 0b11010011;
@@ -173,6 +2041,7 @@ mod tests {
     const CTRL_WORD_Z2_IRQ: u8 = 0b11000000;
 
     //Helper function to assert SPI operations
+    #[allow(clippy::assertions_on_constants, clippy::manual_memcpy)] // the helper predates the lints
     fn assert_spi_operations<Word: std::cmp::PartialEq + std::fmt::Debug + std::marker::Copy>(
         ops: &mut [Operation<'_, Word>],
         expected_ops: &[MockOperation<'_, Word>],
@@ -366,4 +2235,1192 @@ mod tests {
         test_driver.set_irq(true).expect("Could not set IRQ");
         assert_eq!(test_driver.get_touch(), Ok(Some(expected_touch_point)));
     }
+
+    #[test]
+    fn test_prepare_for_sleep() {
+        let expected_ops_init = [
+            MockOperation::Write(&[CTRL_WORD_X_NO_IRQ]),
+            MockOperation::Read(&INIT_RETURN_BUF),
+        ];
+        let expected_ops_sleep = [
+            MockOperation::Write(&[CTRL_WORD_X_IRQ]),
+            MockOperation::Read(&INIT_RETURN_BUF),
+        ];
+        let mut mock_spi_dev = MockSimpleHalSpiDevice::new();
+
+        mock_spi_dev
+            .expect_transaction()
+            .times(1)
+            .returning(move |operations| {
+                assert_spi_operations(operations, &expected_ops_init);
+                Ok(())
+            });
+        mock_spi_dev
+            .expect_transaction()
+            .times(1)
+            .returning(move |operations| {
+                assert_spi_operations(operations, &expected_ops_sleep);
+                Ok(())
+            });
+        let mut test_driver =
+            Tsc2046::new(mock_spi_dev, false, 100.0).expect("Could not create driver");
+        assert_eq!(test_driver.prepare_for_sleep(), Ok(()));
+    }
+
+    #[test]
+    fn test_irq_glitch_filter() {
+        let expected_ops_init = [
+            MockOperation::Write(&[CTRL_WORD_X_IRQ]),
+            MockOperation::Read(&INIT_RETURN_BUF),
+        ];
+        let mut mock_spi_dev = MockSimpleHalSpiDevice::new();
+        mock_spi_dev
+            .expect_transaction()
+            .times(1)
+            .returning(move |operations| {
+                assert_spi_operations(operations, &expected_ops_init);
+                Ok(())
+            });
+        // A spike of two low samples followed by a stable low level.
+        let mut mock_irq_pin = MockInputPin::new();
+        let mut pin_levels = [false, false, true, false, false, false].into_iter();
+        mock_irq_pin
+            .expect_is_high()
+            .times(6)
+            .returning(move || Ok(pin_levels.next().unwrap()));
+        let mut delay = MockDelay::default();
+        let mut test_driver = Tsc2046::new(mock_spi_dev, true, 100.0)
+            .expect("Could not create driver")
+            .with_irq_pin(mock_irq_pin);
+        test_driver.set_irq_glitch_filter(3, 10);
+        assert_eq!(test_driver.is_pen_down(&mut delay), Ok(false));
+        assert_eq!(test_driver.is_pen_down(&mut delay), Ok(true));
+        assert_eq!(delay.elapsed_ns, 40_000);
+    }
+
+    #[test]
+    fn test_zero_irq_filter_samples() {
+        let mut mock_irq_pin = MockInputPin::new();
+        mock_irq_pin
+            .expect_is_high()
+            .times(1)
+            .returning(|| Ok(true));
+        let config = Config {
+            irq_on: true,
+            irq_filter_samples: 0,
+            ..Config::new()
+        };
+        let mut test_driver = Tsc2046::from_config(FakeTsc2046::default(), config)
+            .expect("Could not create driver")
+            .with_irq_pin(mock_irq_pin);
+        // The PENIRQ pin is still read once, so a high level is not taken for a touch.
+        assert_eq!(test_driver.config().irq_filter_samples, 1);
+        assert_eq!(
+            test_driver.is_pen_down(&mut MockDelay::default()),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_get_sample_confidence() {
+        let mut fake_chip = FakeTsc2046::default();
+        // The first X conversion is consumed by the initial register update.
+        fake_chip.queue(
+            FakeTsc2046::X,
+            &[0, 100, 104, 100, 104, 1000, 1064, 1000, 1064],
+        );
+        fake_chip.queue(FakeTsc2046::Y, &[100]);
+        fake_chip.queue(FakeTsc2046::Z1, &[5]);
+        fake_chip.queue(FakeTsc2046::Z2, &[2053]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 1000.0).expect("Could not create driver");
+        test_driver.set_oversampling(4);
+        let steady_sample = test_driver
+            .get_sample()
+            .expect("Could not read sample")
+            .expect("No touch detected");
+        assert_eq!(steady_sample.point.x, 102);
+        assert_eq!(steady_sample.confidence, 1.0 - 4.0 / 64.0);
+        let noisy_sample = test_driver
+            .get_sample()
+            .expect("Could not read sample")
+            .expect("No touch detected");
+        assert_eq!(noisy_sample.point.x, 1032);
+        assert_eq!(noisy_sample.confidence, 0.0);
+    }
+
+    #[test]
+    fn test_inhibit_and_sampling_gate() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[100]);
+        fake_chip.queue(FakeTsc2046::Y, &[100]);
+        fake_chip.queue(FakeTsc2046::Z1, &[5]);
+        fake_chip.queue(FakeTsc2046::Z2, &[2053]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.inhibit();
+        assert_eq!(test_driver.get_touch(), Err(Error::Inhibited));
+        test_driver.resume();
+        assert!(test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .is_some());
+        test_driver.set_sampling_gate(Some(|| false));
+        assert_eq!(test_driver.get_touch(), Err(Error::Inhibited));
+        test_driver.set_sampling_gate(None);
+        assert!(test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .is_some());
+    }
+
+    #[test]
+    fn test_reference_warmup() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::VBAT, &[1500, 1500]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        // The reference is always on without the interrupt pin.
+        assert_eq!(test_driver.read_vbat(), Ok(3662));
+        assert_eq!(test_driver.spi.commands[1..], [0b10100111]);
+        assert_eq!(test_driver.spi.delay_ns, 0);
+
+        test_driver.set_irq(true).expect("Could not set IRQ");
+        test_driver.set_reference_warmup(300);
+        test_driver.spi.commands.clear();
+        assert_eq!(test_driver.read_vbat(), Ok(3662));
+        assert_eq!(
+            test_driver.spi.commands,
+            [0b10100111, 0b10100111, CTRL_WORD_X_IRQ]
+        );
+        assert_eq!(test_driver.spi.delay_ns, 300_000);
+    }
+
+    #[test]
+    #[cfg(feature = "aux")]
+    fn test_aux_scaling() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::AUX, &[1000]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let reading = test_driver.read_aux().expect("Could not read AUX");
+        assert_eq!((reading.raw, reading.value), (1000, 1000.0));
+        test_driver.set_aux_scaling(AuxScaling::Linear {
+            scale: 0.5,
+            offset: -10.0,
+        });
+        let reading = test_driver.read_aux().expect("Could not read AUX");
+        assert_eq!((reading.raw, reading.value), (1000, 490.0));
+        test_driver.set_aux_scaling(AuxScaling::Custom(|raw| (raw / 100) as f32));
+        assert_eq!(
+            test_driver.read_aux().map(|reading| reading.value),
+            Ok(10.0)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "aux")]
+    fn test_aux_oversampling() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::AUX, &[1000, 1010, 1020, 1030]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_aux_oversampling(4);
+        assert_eq!(test_driver.read_aux().map(|reading| reading.raw), Ok(1015));
+        assert_eq!(test_driver.spi.commands.len(), 5);
+    }
+
+    #[test]
+    #[cfg(feature = "aux")]
+    fn test_aux_median() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::AUX, &[1000, 4095, 1010, 1000, 1200, 3000]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_aux_acquisition_mode(AcquisitionMode::Median);
+        assert_eq!(test_driver.read_aux().map(|reading| reading.raw), Ok(1010));
+        test_driver.set_aux_acquisition_mode(AcquisitionMode::MajorityVote { tolerance: 20 });
+        assert_eq!(
+            test_driver.read_aux(),
+            Err(Error::InvalidReading(Channel::Aux))
+        );
+    }
+
+    #[test]
+    fn test_threshold_compensation() {
+        let mut fake_chip = FakeTsc2046::default();
+        // Temperature difference of 200 counts, about 40.6 °C.
+        fake_chip.queue(FakeTsc2046::TEMP0, &[600]);
+        fake_chip.queue(FakeTsc2046::TEMP1, &[800]);
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        // z = 0.5 * (1.2 - 1) = 0.1
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 0.05).expect("Could not create driver");
+        assert_eq!(test_driver.get_touch(), Ok(None));
+        test_driver.set_threshold_compensation(Some(ThresholdCompensation {
+            reference_celsius: 25.0,
+            per_degree: 0.01,
+        }));
+        let temperature = test_driver
+            .update_threshold_compensation()
+            .expect("Could not read temperature");
+        assert!((temperature - 40.5).abs() < 0.5);
+        assert!(test_driver.get_touch().unwrap().is_some());
+        test_driver.set_threshold_compensation(None);
+        assert_eq!(test_driver.get_touch(), Ok(None));
+    }
+
+    #[test]
+    fn test_read_all() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 1000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[300]);
+        fake_chip.queue(FakeTsc2046::Z2, &[3000]);
+        fake_chip.queue(FakeTsc2046::TEMP0, &[0, 100]);
+        fake_chip.queue(FakeTsc2046::TEMP1, &[200]);
+        fake_chip.queue(FakeTsc2046::VBAT, &[1638]);
+        fake_chip.queue(FakeTsc2046::AUX, &[42]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, true, 100.0).expect("Could not create driver");
+        let snapshot = test_driver.read_all().expect("Could not read the inputs");
+        assert_eq!(
+            snapshot,
+            ChannelSnapshot {
+                x: 1000,
+                y: 2000,
+                z1: 300,
+                z2: 3000,
+                temp0: 100,
+                temp1: 200,
+                vbat: 1638,
+                aux: 42,
+            }
+        );
+        assert_eq!(snapshot.vbat_mv(), 3999);
+        // The initial register update, then the whole snapshot in a single transaction.
+        assert_eq!(test_driver.spi.commands.len(), 11);
+        assert_eq!(
+            test_driver.spi.delay_ns,
+            DEFAULT_REFERENCE_WARMUP_US as u64 * 1000
+        );
+        assert_eq!(
+            test_driver.spi.commands.last(),
+            test_driver.spi.commands.first()
+        );
+        assert_eq!(test_driver.diagnostics().stats.conversions, 11);
+    }
+
+    #[test]
+    fn test_burst_oversampling() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 1000, 1010, 1020, 1030]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_oversampling(4);
+        test_driver.set_burst_oversampling(true);
+        let point = test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert_eq!((point.x, point.y), (1015, 2000));
+        // The initial register update, then one transaction per channel.
+        assert_eq!(test_driver.spi.transactions, 5);
+        assert_eq!(test_driver.spi.commands.len(), 17);
+        assert_eq!(test_driver.diagnostics().stats.conversions, 17);
+    }
+
+    #[test]
+    fn test_interleaved_sample() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 1000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_oversampling(4);
+        test_driver.set_burst_oversampling(true);
+        let commands_before = test_driver.spi.commands.len();
+        let mut gaps = 0;
+        let sample = test_driver
+            .get_sample_interleaved(|| gaps += 1)
+            .expect("Could not read sample");
+        assert_eq!(
+            sample.map(|sample| (sample.point.x, sample.point.y)),
+            Some((1000, 2000))
+        );
+        // Every conversion went through its own transaction, followed by a gap.
+        assert_eq!(gaps, test_driver.spi.commands.len() - commands_before);
+        assert_eq!(
+            test_driver.diagnostics().stats.conversions as usize,
+            test_driver.spi.commands.len()
+        );
+    }
+
+    #[test]
+    fn test_read_all_failure() {
+        let mut test_driver =
+            Tsc2046::new(FakeTsc2046::default(), true, 100.0).expect("Could not create driver");
+        test_driver.spi.failing_channel = Some(FakeTsc2046::VBAT);
+        // The failing conversion is not known within the single transaction.
+        assert_eq!(
+            test_driver.read_all(),
+            Err(Error::SpiBatch(mock_peripherals::Error::Fault))
+        );
+        assert_eq!(
+            test_driver.diagnostics().last_error,
+            Some(Error::SpiBatch(embedded_hal::spi::ErrorKind::Other))
+        );
+        // With a transaction per input, the failing channel is reported.
+        test_driver.set_interleaved(true);
+        assert_eq!(
+            test_driver.read_all(),
+            Err(Error::Spi {
+                channel: Channel::Vbat,
+                error: mock_peripherals::Error::Fault,
+            })
+        );
+    }
+
+    #[test]
+    fn test_interleaved_read_all() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[1000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_interleaved(true);
+        let conversions_before = test_driver.diagnostics().stats.conversions;
+        let snapshot = test_driver.read_all().expect("Could not read inputs");
+        assert_eq!((snapshot.x, snapshot.y), (1000, 2000));
+        // One transaction per input, none holding the bus for the whole snapshot.
+        assert_eq!(
+            test_driver.diagnostics().stats.conversions - conversions_before,
+            8
+        );
+    }
+
+    #[test]
+    fn test_axis_ranges() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 100, 100, 2000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let range = AxisRange {
+            min: 150,
+            max: 3900,
+        };
+        test_driver
+            .set_axis_ranges(Some(range), Some(range), OutOfRangePolicy::Reject)
+            .expect("Could not set the ranges");
+        assert_eq!(test_driver.get_touch(), Ok(None));
+        assert_eq!(test_driver.diagnostics().stats.no_touches, 1);
+        test_driver
+            .set_axis_ranges(Some(range), Some(range), OutOfRangePolicy::Clamp)
+            .expect("Could not set the ranges");
+        let sample = test_driver
+            .get_sample()
+            .expect("Could not read sample")
+            .expect("No touch detected");
+        assert_eq!((sample.point.x, sample.point.y), (150, 2000));
+        // The pressure is computed from the raw X conversion, not the clamped one.
+        assert!((sample.point.z - 100.0 / 4096.0 * 0.2).abs() < 1e-6);
+        let point = test_driver.get_touch().expect("Could not read touch");
+        assert_eq!(point.map(|point| (point.x, point.y)), Some((2000, 2000)));
+        let inverted = AxisRange {
+            min: 3900,
+            max: 150,
+        };
+        assert_eq!(
+            test_driver.set_axis_ranges(Some(inverted), None, OutOfRangePolicy::Clamp),
+            Err(ConfigError::InvertedRange)
+        );
+        assert_eq!(test_driver.config().x_range, Some(range));
+    }
+
+    #[test]
+    fn test_ignore_regions() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 100, 2000]);
+        fake_chip.queue(FakeTsc2046::Y, &[100, 2000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let cutout = Rect {
+            x_min: 0,
+            y_min: 0,
+            x_max: 500,
+            y_max: 500,
+        };
+        for _ in 0..MAX_IGNORE_REGIONS {
+            assert!(test_driver.add_ignore_region(cutout));
+        }
+        assert!(!test_driver.add_ignore_region(cutout));
+        assert_eq!(test_driver.get_touch(), Ok(None));
+        assert!(test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .is_some());
+        // Only the position is read for ignored touches.
+        assert_eq!(test_driver.spi.commands.len(), 7);
+    }
+
+    #[test]
+    fn test_active_area() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 3000, 1000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000, 1500]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_active_area(Some(Rect {
+            x_min: 1000,
+            y_min: 1000,
+            x_max: 2000,
+            y_max: 2000,
+        }));
+        assert_eq!(test_driver.get_touch(), Ok(None));
+        let point = test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert_eq!((point.x, point.y), (0, 2047));
+    }
+
+    #[test]
+    fn test_pressure_first_scan() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 1000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[10, 500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_scan_order(ScanOrder::PressureFirst {
+            min_z1: 20,
+            with_z2: false,
+        });
+        assert_eq!(test_driver.get_touch(), Ok(None));
+        // The register update, then only Z1 for the idle poll.
+        assert_eq!(test_driver.spi.commands.len(), 2);
+        let point = test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert_eq!((point.x, point.y), (1000, 2000));
+        assert_eq!(test_driver.spi.commands.len(), 6);
+    }
+
+    #[test]
+    fn test_raw_command() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::AUX, &[1234]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        assert_eq!(test_driver.raw_command(0b11100100), Ok(1234));
+        assert_eq!(test_driver.spi.commands[1..], [0b11100100]);
+    }
+
+    #[test]
+    #[cfg(feature = "aux")]
+    fn test_observer() {
+        static TRAFFIC: Mutex<Vec<(u8, u16)>> = Mutex::new(Vec::new());
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 100]);
+        fake_chip.queue(FakeTsc2046::AUX, &[200]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, true, 100.0).expect("Could not create driver");
+        test_driver.set_observer(Some(|command, result| {
+            TRAFFIC.lock().unwrap().push((command, result))
+        }));
+        test_driver.read_aux().expect("Could not read AUX");
+        assert_eq!(
+            *TRAFFIC.lock().unwrap(),
+            [(0b11100111, 200), (0b11100111, 200), (CTRL_WORD_X_IRQ, 100)]
+        );
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn test_trace() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[4095]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.raw_command(CTRL_WORD_X_NO_IRQ).unwrap();
+        let entries: Vec<trace::TraceEntry> = test_driver.trace().iter().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[1],
+            trace::TraceEntry {
+                command: CTRL_WORD_X_NO_IRQ,
+                response: 4095
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_touch_blocking() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[0, 0, 0, 1000, 0]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let mut delay = MockDelay::default();
+        let point = test_driver.get_touch_blocking(&mut delay, 10);
+        assert_eq!(point.map(|point| point.x), Ok(2048));
+        assert_eq!(delay.elapsed_ns, 3_000_000);
+        let mut delay = MockDelay::default();
+        assert_eq!(
+            test_driver.get_touch_blocking(&mut delay, 10),
+            Err(Error::Timeout)
+        );
+        assert_eq!(delay.elapsed_ns, 10_000_000);
+    }
+
+    #[test]
+    fn test_get_touch_polling() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[0, 0, 1000, 0]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        assert_eq!(test_driver.get_touch_polling(2), Err(Error::Timeout));
+        assert!(test_driver.get_touch_polling(1).is_ok());
+        assert_eq!(test_driver.get_touch_polling(0), Err(Error::Timeout));
+    }
+
+    #[test]
+    fn test_single_ended_mode() {
+        let fake_chip = FakeTsc2046::default();
+        let mut test_driver =
+            Tsc2046::new(fake_chip, true, 100.0).expect("Could not create driver");
+        test_driver.set_measurement_mode(MeasurementMode::SingleEnded {
+            internal_reference: false,
+        });
+        test_driver.get_touch().unwrap();
+        test_driver.set_measurement_mode(MeasurementMode::SingleEnded {
+            internal_reference: true,
+        });
+        test_driver.get_touch().unwrap();
+        assert_eq!(
+            test_driver.spi.commands[1..],
+            [
+                0b11010100, 0b10010100, 0b10110100, 0b11000100, 0b11010110, 0b10010110, 0b10110110,
+                0b11000110
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reinit() {
+        let fake_chip = FakeTsc2046::default();
+        let mut test_driver =
+            Tsc2046::new(fake_chip, true, 100.0).expect("Could not create driver");
+        test_driver.reinit().expect("Could not re-initialize");
+        assert_eq!(test_driver.spi.commands, [CTRL_WORD_X_IRQ, CTRL_WORD_X_IRQ]);
+    }
+
+    #[test]
+    fn test_auto_refresh() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 4095, 0, 2000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_auto_refresh(true);
+        let point = test_driver.get_touch().unwrap().expect("No touch detected");
+        assert_eq!(point.x, 2000);
+        assert_eq!(
+            test_driver.spi.commands[1..],
+            [
+                CTRL_WORD_X_NO_IRQ,
+                CTRL_WORD_Y_NO_IRQ,
+                CTRL_WORD_Z1_NO_IRQ,
+                CTRL_WORD_Z2_NO_IRQ,
+                CTRL_WORD_X_NO_IRQ,
+                CTRL_WORD_X_NO_IRQ,
+                CTRL_WORD_Y_NO_IRQ,
+                CTRL_WORD_Z1_NO_IRQ,
+                CTRL_WORD_Z2_NO_IRQ,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_auto_refresh_records_kept_sample() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 4095, 0, 2000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_auto_refresh(true);
+        test_driver.get_touch().unwrap().expect("No touch detected");
+        // Only the sample kept after the refresh is counted.
+        let diagnostics = test_driver.diagnostics();
+        assert_eq!(diagnostics.stats.touches, 1);
+        assert!(!diagnostics.stuck);
+        // Once the readings become stuck, they trigger a single refresh.
+        for _ in 0..60 {
+            test_driver.get_touch().unwrap().expect("No touch detected");
+        }
+        let diagnostics = test_driver.diagnostics();
+        assert!(diagnostics.stuck);
+        assert_eq!(diagnostics.stats.touches, 61);
+        // The initial register write, the first scan with its refresh and retry, 60 scans, and
+        // the refresh and retry of the scan in which the readings became stuck.
+        assert_eq!(test_driver.spi.commands.len(), 1 + 9 + 60 * 4 + 5);
+    }
+
+    #[test]
+    fn test_refresh_interval() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 1000, 1000, 0, 1000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_refresh_interval(3);
+        for _ in 0..3 {
+            let point = test_driver.get_touch().unwrap().expect("No touch detected");
+            assert_eq!(point.x, 1000);
+        }
+        // The register is sent again before the third scan.
+        let scan = [
+            CTRL_WORD_X_NO_IRQ,
+            CTRL_WORD_Y_NO_IRQ,
+            CTRL_WORD_Z1_NO_IRQ,
+            CTRL_WORD_Z2_NO_IRQ,
+        ];
+        assert_eq!(test_driver.spi.commands[1..9], [scan, scan].concat());
+        assert_eq!(
+            test_driver.spi.commands[9..],
+            [&[CTRL_WORD_X_NO_IRQ][..], &scan].concat()
+        );
+    }
+
+    #[test]
+    fn test_config_snapshot_and_restore() {
+        let fake_chip = FakeTsc2046::default();
+        let mut test_driver =
+            Tsc2046::new(fake_chip, true, 100.0).expect("Could not create driver");
+        test_driver.set_oversampling(4);
+        let snapshot = test_driver.config();
+        test_driver.set_irq(false).unwrap();
+        test_driver.set_touch_threshold(5.0);
+        test_driver.set_oversampling(1);
+        test_driver.apply_config(snapshot).unwrap();
+        let restored = test_driver.config();
+        assert!(restored.irq_on);
+        assert_eq!(restored.touch_threshold, 100.0);
+        assert_eq!(restored.oversampling, 4);
+        assert_eq!(test_driver.spi.commands.last(), Some(&CTRL_WORD_X_IRQ));
+    }
+
+    #[test]
+    fn test_sample_quality() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048, 2048, 2000, 2100, 4095]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        // z = 0.5 * (z2 / z1 - 1), marginal for z2 = 1094 with a threshold of 0.05.
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1050, 1094, 1050]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 0.05).expect("Could not create driver");
+        let quality =
+            |driver: &mut Tsc2046<FakeTsc2046>| driver.get_sample().unwrap().unwrap().quality;
+        assert_eq!(quality(&mut test_driver), SampleQuality::Good);
+        assert_eq!(quality(&mut test_driver), SampleQuality::MarginalPressure);
+        test_driver.set_oversampling(2);
+        assert_eq!(quality(&mut test_driver), SampleQuality::HighVariance);
+        test_driver.set_oversampling(1);
+        assert_eq!(quality(&mut test_driver), SampleQuality::Railed);
+    }
+
+    #[test]
+    fn test_padding_bytes() {
+        let mut spi = MockSimpleHalSpiDevice::new();
+        let mut sequence = mockall::Sequence::new();
+        spi.expect_transaction()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|operations| {
+                assert_eq!(operations.len(), 2);
+                match &operations[1] {
+                    Operation::Read(frame) => assert_eq!(frame.len(), 2),
+                    _ => panic!("Expected a read operation"),
+                }
+                Ok(())
+            });
+        spi.expect_transaction()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|operations| {
+                // The result is followed by the padding in the same read.
+                assert_eq!(operations.len(), 2);
+                match &operations[1] {
+                    Operation::Read(frame) => assert_eq!(frame.len(), 4),
+                    _ => panic!("Expected a read operation"),
+                }
+                Ok(())
+            });
+        let mut test_driver = Tsc2046::new(spi, false, 100.0).expect("Could not create driver");
+        test_driver.set_padding_bytes(2);
+        test_driver.reinit().expect("Could not re-initialize");
+    }
+
+    #[test]
+    fn test_error_channel() {
+        let cases = [
+            (CTRL_WORD_X_IRQ, Channel::X),
+            (CTRL_WORD_Y_IRQ, Channel::Y),
+            (CTRL_WORD_Z1_NO_IRQ, Channel::Z1),
+            (CTRL_WORD_Z2_NO_IRQ, Channel::Z2),
+        ];
+        for (control_word, channel) in cases {
+            assert_eq!(
+                Channel::from_control_word(&ControlBit::from_bits_retain(control_word)),
+                channel
+            );
+        }
+        for channel in AuxChannel::ALL {
+            assert_eq!(
+                Channel::from_control_word(&protocol::single_ended_control_word(channel)),
+                Channel::from(channel)
+            );
+        }
+    }
+
+    #[test]
+    fn test_spi_error_channel() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[2000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        fake_chip.failing_channel = Some(FakeTsc2046::Z1);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        assert_eq!(
+            test_driver.get_touch(),
+            Err(Error::Spi {
+                channel: Channel::Z1,
+                error: mock_peripherals::Error::Fault,
+            })
+        );
+        test_driver.spi.failing_channel = Some(FakeTsc2046::AUX);
+        assert_eq!(
+            test_driver.read_single_ended(AuxChannel::Aux),
+            Err(Error::Spi {
+                channel: Channel::Aux,
+                error: mock_peripherals::Error::Fault,
+            })
+        );
+    }
+
+    #[test]
+    fn test_partial_sample() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        // The position is read, then the Z1 conversion fails.
+        fake_chip.failing_channel = Some(FakeTsc2046::Z1);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        test_driver.set_partial_sample_policy(PartialSamplePolicy::Degraded);
+        let sample = test_driver
+            .get_sample()
+            .expect("Partial sample not returned")
+            .expect("No sample");
+        assert_eq!(sample.quality, SampleQuality::Degraded);
+        assert!(sample.point.z.is_nan());
+        assert_eq!(
+            test_driver.diagnostics().last_error,
+            Some(Error::Spi {
+                channel: Channel::Z1,
+                error: embedded_hal::spi::ErrorKind::Other,
+            })
+        );
+    }
+
+    #[test]
+    fn test_pressure_fallback() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[100]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z2, &[0xFFF]);
+        let mut mock_irq_pin = MockInputPin::new();
+        mock_irq_pin.expect_is_high().returning(|| Ok(false));
+        let mut test_driver = Tsc2046::new(fake_chip, true, 1000.0)
+            .expect("Could not create driver")
+            .with_irq_pin(mock_irq_pin);
+        for _ in 0..20 {
+            assert_eq!(test_driver.get_sample(), Ok(None));
+        }
+        assert!(test_driver.diagnostics().pressure_fallback);
+        let sample = test_driver
+            .get_sample()
+            .expect("Could not read sample")
+            .expect("No touch detected");
+        assert_eq!((sample.point.x, sample.point.y), (100, 200));
+        assert_eq!(sample.quality, SampleQuality::Degraded);
+        assert_eq!(test_driver.reinit(), Ok(()));
+        assert!(!test_driver.diagnostics().pressure_fallback);
+    }
+
+    #[test]
+    fn test_pen_irq_glitch_skips_scan() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[100]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z2, &[0xFFF]);
+        // Once glitching, every low sample of PENIRQ is followed by a high one.
+        let glitching = Arc::new(AtomicBool::new(false));
+        let pin_glitching = Arc::clone(&glitching);
+        let mut high = false;
+        let mut mock_irq_pin = MockInputPin::new();
+        mock_irq_pin.expect_is_high().returning(move || {
+            let level = high;
+            high = pin_glitching.load(Ordering::Relaxed) && !high;
+            Ok(level)
+        });
+        let mut test_driver = Tsc2046::new(fake_chip, true, 1000.0)
+            .expect("Could not create driver")
+            .with_irq_pin(mock_irq_pin);
+        test_driver.set_irq_glitch_filter(2, 10);
+        for _ in 0..20 {
+            assert_eq!(test_driver.get_sample(), Ok(None));
+        }
+        assert!(test_driver.diagnostics().pressure_fallback);
+        glitching.store(true, Ordering::Relaxed);
+        test_driver.spi.commands.clear();
+        assert_eq!(test_driver.get_sample(), Ok(None));
+        assert!(test_driver.spi.commands.is_empty());
+        glitching.store(false, Ordering::Relaxed);
+        assert!(test_driver
+            .get_sample()
+            .expect("Could not read sample")
+            .is_some());
+    }
+
+    #[test]
+    fn test_pen_up_skips_conversions() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut mock_irq_pin = MockInputPin::new();
+        mock_irq_pin
+            .expect_is_high()
+            .times(1)
+            .returning(|| Ok(true));
+        let mut test_driver = Tsc2046::new(fake_chip, true, 100.0)
+            .expect("Could not create driver")
+            .with_irq_pin(mock_irq_pin);
+        test_driver.set_irq_glitch_filter(3, 10);
+        test_driver.spi.commands.clear();
+        // The first high sample ends the filter, and the scan, without any conversion.
+        assert_eq!(test_driver.get_sample(), Ok(None));
+        assert!(test_driver.spi.commands.is_empty());
+        assert!(!test_driver.diagnostics().pressure_fallback);
+    }
+
+    #[test]
+    fn test_pressure_fallback_ignores_glitches() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[100]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z2, &[0xFFF]);
+        // PENIRQ only ever dips low for a single sample.
+        let mut high = true;
+        let mut mock_irq_pin = MockInputPin::new();
+        mock_irq_pin.expect_is_high().returning(move || {
+            high = !high;
+            Ok(high)
+        });
+        let mut test_driver = Tsc2046::new(fake_chip, true, 1000.0)
+            .expect("Could not create driver")
+            .with_irq_pin(mock_irq_pin);
+        test_driver.set_irq_glitch_filter(2, 10);
+        for _ in 0..40 {
+            assert_eq!(test_driver.get_sample(), Ok(None));
+        }
+        assert!(!test_driver.diagnostics().pressure_fallback);
+    }
+
+    #[test]
+    fn test_zero_pressure_policy() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[100]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z1, &[0]);
+        fake_chip.queue(FakeTsc2046::Z2, &[100]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 1000.0).expect("Could not create driver");
+        assert_eq!(test_driver.get_touch(), Ok(None));
+        test_driver.set_zero_pressure_policy(ZeroPressurePolicy::Clamp);
+        let point = test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert!(point.z.is_finite());
+        test_driver.set_zero_pressure_policy(ZeroPressurePolicy::InvalidReading);
+        assert_eq!(
+            test_driver.get_touch(),
+            Err(Error::InvalidReading(Channel::Z1))
+        );
+        assert_eq!(
+            test_driver.diagnostics().last_error,
+            Some(Error::InvalidReading(Channel::Z1))
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_mode() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[100]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[400]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 1000.0).expect("Could not create driver");
+        let point = test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert_eq!(point.z, 0.0);
+        test_driver.set_arithmetic_mode(ArithmeticMode::Checked);
+        assert_eq!(
+            test_driver.get_touch(),
+            Err(Error::InvalidReading(Channel::Z2))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "aux")]
+    fn test_maximum_oversampling() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::AUX, &[0xFFF]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 1000.0).expect("Could not create driver");
+        test_driver.set_aux_oversampling(1000);
+        assert_eq!(test_driver.config().aux_oversampling, MAX_OVERSAMPLING);
+        let reading = test_driver.read_aux().expect("Could not read AUX");
+        assert_eq!(reading.raw, 0xFFF);
+        assert_eq!(
+            test_driver.spi.commands.len(),
+            1 + MAX_OVERSAMPLING as usize
+        );
+    }
+
+    #[test]
+    fn test_majority_vote() {
+        let mut fake_chip = FakeTsc2046::default();
+        // The first X conversion is consumed by the initial register update.
+        fake_chip.queue(FakeTsc2046::X, &[0, 100, 3000, 104, 100, 3000, 2000]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 1000.0).expect("Could not create driver");
+        test_driver.set_acquisition_mode(AcquisitionMode::MajorityVote { tolerance: 8 });
+        let sample = test_driver
+            .get_sample()
+            .expect("Could not read sample")
+            .expect("No touch detected");
+        assert_eq!((sample.point.x, sample.point.y), (102, 200));
+        assert_eq!(
+            test_driver.get_sample(),
+            Err(Error::InvalidReading(Channel::X))
+        );
+        assert_eq!(test_driver.diagnostics().stats.rejected_votes, 1);
+    }
+
+    #[test]
+    fn test_with_defaults() {
+        let mut fake_chip = FakeTsc2046::default();
+        // The first X conversion is consumed by the initial register update.
+        fake_chip.queue(FakeTsc2046::X, &[0, 100, 3000, 104]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver = Tsc2046::with_defaults(fake_chip).expect("Could not create driver");
+        assert!(test_driver.config().irq_on);
+        assert_eq!(test_driver.config().acquisition, AcquisitionMode::Median);
+        let point = test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert_eq!((point.x, point.y), (104, 200));
+    }
+
+    #[test]
+    fn test_config_validation() {
+        const CONFIG: Config = Config {
+            oversampling: 4,
+            x_range: Some(AxisRange {
+                min: 200,
+                max: 3900,
+            }),
+            ..Config::new()
+        };
+        const _: () = assert!(CONFIG.validate().is_ok());
+        let invalid = [
+            (
+                Config {
+                    touch_threshold: f32::NAN,
+                    ..CONFIG
+                },
+                ConfigError::InvalidThreshold,
+            ),
+            (
+                Config {
+                    oversampling: MAX_OVERSAMPLING + 1,
+                    ..CONFIG
+                },
+                ConfigError::InvalidSampleCount,
+            ),
+            (
+                Config {
+                    padding_bytes: MAX_PADDING_BYTES as u8 + 1,
+                    ..CONFIG
+                },
+                ConfigError::TooManyPaddingBytes,
+            ),
+            (
+                Config {
+                    active_area: Some(Rect {
+                        x_min: 1000,
+                        y_min: 0,
+                        x_max: 900,
+                        y_max: 4095,
+                    }),
+                    ..CONFIG
+                },
+                ConfigError::InvertedRange,
+            ),
+        ];
+        for (config, error) in invalid {
+            assert_eq!(config.validate(), Err(error));
+        }
+    }
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(
+            Config::from(Preset::Balanced).acquisition,
+            Config::default().acquisition
+        );
+        let mut fake_chip = FakeTsc2046::default();
+        // Z1 is read first, and the scan is aborted while it stays low.
+        fake_chip.queue(FakeTsc2046::Z1, &[0, 500]);
+        fake_chip.queue(FakeTsc2046::X, &[0, 1000]);
+        fake_chip.queue(FakeTsc2046::Y, &[2000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut test_driver =
+            Tsc2046::with_preset(fake_chip, Preset::LowLatency).expect("Could not create driver");
+        test_driver.set_irq(false).expect("Could not disable IRQ");
+        let commands_before = test_driver.spi.commands.len();
+        assert_eq!(test_driver.get_touch().expect("Could not read touch"), None);
+        assert_eq!(test_driver.spi.commands.len(), commands_before + 1);
+        let point = test_driver
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert_eq!((point.x, point.y), (1000, 2000));
+
+        let config = Config::from(Preset::LowNoise);
+        assert_eq!(config.oversampling, 16);
+        assert!(config.burst_oversampling && config.auto_refresh);
+    }
+
+    #[test]
+    fn test_oversample_without_samples() {
+        let reading = protocol::Reading::new(AcquisitionMode::Averaging, 0);
+        assert_eq!(reading.remaining(), 0);
+        assert_eq!(reading.result(), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_stored_delay() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[100]);
+        fake_chip.queue(FakeTsc2046::Y, &[200]);
+        fake_chip.queue(FakeTsc2046::Z1, &[0, 0, 0, 500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut mock_irq_pin = MockInputPin::new();
+        mock_irq_pin.expect_is_high().returning(|| Ok(false));
+        let mut test_driver = Tsc2046::new(fake_chip, true, 100.0)
+            .expect("Could not create driver")
+            .with_irq_pin(mock_irq_pin)
+            .with_delay(MockDelay::default());
+        test_driver.set_irq_glitch_filter(3, 50);
+        assert_eq!(test_driver.pen_down(), Ok(true));
+        assert_eq!(test_driver.delay.elapsed_ns, 100_000);
+        let point = test_driver.wait_for_touch(10).expect("No touch detected");
+        assert_eq!((point.x, point.y), (100, 200));
+        // Every scan also spaces out the samples of the glitch filter.
+        assert_eq!(test_driver.delay.elapsed_ns, 5 * 100_000 + 3_000_000);
+        // The routines taking a delay provider use the owned one with `OwnedDelay`.
+        test_driver
+            .estimate_noise_floor(3, 250, &mut OwnedDelay)
+            .expect("Could not measure the noise floor");
+        assert_eq!(
+            test_driver.delay.elapsed_ns,
+            5 * 100_000 + 3_000_000 + 500_000
+        );
+        #[cfg(feature = "stream")]
+        {
+            let points = test_driver.points(&mut OwnedDelay).take(2).count();
+            assert_eq!(points, 2);
+            assert_eq!(
+                test_driver.delay.elapsed_ns,
+                7 * 100_000 + 3_500_000 + stream::STROKE_SAMPLE_INTERVAL_US as u64 * 1000
+            );
+        }
+    }
+
+    #[cfg(feature = "uom")]
+    #[test]
+    fn test_uom_quantities() {
+        use uom::si::electric_potential::millivolt;
+        use uom::si::thermodynamic_temperature::kelvin;
+
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::VBAT, &[2048]);
+        fake_chip.queue(FakeTsc2046::TEMP0, &[1000]);
+        fake_chip.queue(FakeTsc2046::TEMP1, &[1100]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let vbat = test_driver
+            .read_vbat_quantity()
+            .expect("Could not read VBAT");
+        assert!((vbat.get::<millivolt>() - 5000.0).abs() < 0.01);
+        let temperature = test_driver
+            .read_temperature_quantity()
+            .expect("Could not read temperature");
+        let expected = 2.573 * 100.0 * 2500.0 / 4096.0;
+        assert!((temperature.get::<kelvin>() - expected).abs() < 0.01);
+    }
 }
@@ -1,10 +1,18 @@
 use mockall::*;
 
 #[derive(Debug, PartialEq)]
-pub enum Error {}
+pub enum Error {
+    /// Failure injected by a fake peripheral.
+    Fault,
+}
 impl embedded_hal::spi::Error for Error {
     fn kind(&self) -> embedded_hal::spi::ErrorKind {
-        todo!()
+        embedded_hal::spi::ErrorKind::Other
+    }
+}
+impl embedded_hal::digital::Error for Error {
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
     }
 }
 
@@ -63,3 +71,219 @@ pub enum MockOperation<'a, Word: 'static> {
     Read(&'a [Word]),
     Write(&'a [Word]),
 }
+
+mock! {
+    pub InputPin {}
+    impl embedded_hal::digital::InputPin for InputPin {
+        fn is_high(&mut self) -> Result<bool, Error>;
+        fn is_low(&mut self) -> Result<bool, Error>;
+    }
+    impl embedded_hal::digital::ErrorType for InputPin {
+        type Error = Error;
+    }
+}
+
+/// Delay provider that only accumulates the requested delays.
+#[derive(Debug, Default)]
+pub struct MockDelay {
+    pub elapsed_ns: u64,
+}
+impl embedded_hal::delay::DelayNs for MockDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += ns as u64;
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for MockDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += ns as u64;
+    }
+}
+
+/// Runs a future to completion, polling it in a loop.
+#[cfg(any(feature = "async", feature = "storage-async"))]
+pub fn block_on<T>(future: impl core::future::Future<Output = T>) -> T {
+    let mut future = core::pin::pin!(future);
+    let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+    loop {
+        if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Fake chip select pin, which records every level it is driven to.
+#[derive(Debug, Default)]
+pub struct FakeCs {
+    /// The levels the pin was driven to, `true` for high.
+    pub toggles: Vec<bool>,
+}
+impl embedded_hal::digital::ErrorType for FakeCs {
+    type Error = core::convert::Infallible;
+}
+impl embedded_hal::digital::OutputPin for FakeCs {
+    fn set_low(&mut self) -> Result<(), core::convert::Infallible> {
+        self.toggles.push(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), core::convert::Infallible> {
+        self.toggles.push(true);
+        Ok(())
+    }
+}
+#[cfg(feature = "eh0")]
+impl embedded_hal_02::digital::v2::OutputPin for FakeCs {
+    type Error = core::convert::Infallible;
+
+    fn set_low(&mut self) -> Result<(), core::convert::Infallible> {
+        self.toggles.push(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), core::convert::Infallible> {
+        self.toggles.push(true);
+        Ok(())
+    }
+}
+
+/// Fake TSC2046 chip, which answers every conversion with the next value queued for the
+/// addressed channel and records every control byte it receives.
+#[derive(Debug, Default)]
+pub struct FakeTsc2046 {
+    /// Values returned for each channel, indexed by the A2-A0 address bits of the control byte.
+    pub channels: [std::collections::VecDeque<u16>; 8],
+    /// Every control byte received by the chip.
+    pub commands: Vec<u8>,
+    /// The total delay requested within transactions, in nanoseconds.
+    pub delay_ns: u64,
+    /// The channel whose conversions fail with [`Error::Fault`], if any.
+    pub failing_channel: Option<usize>,
+    /// The number of transactions performed.
+    pub transactions: u32,
+}
+impl FakeTsc2046 {
+    /// Address of the X position channel.
+    pub const X: usize = 0b101;
+    /// Address of the Y position channel.
+    pub const Y: usize = 0b001;
+    /// Address of the Z1 pressure channel.
+    pub const Z1: usize = 0b011;
+    /// Address of the Z2 pressure channel.
+    pub const Z2: usize = 0b100;
+    /// Address of the first temperature channel.
+    pub const TEMP0: usize = 0b000;
+    /// Address of the second temperature channel.
+    pub const TEMP1: usize = 0b111;
+    /// Address of the battery voltage channel.
+    pub const VBAT: usize = 0b010;
+    /// Address of the auxiliary input channel.
+    pub const AUX: usize = 0b110;
+
+    /// Queues values to be returned for a channel. The last value is repeated once the queue is
+    /// exhausted.
+    pub fn queue(&mut self, channel: usize, values: &[u16]) {
+        self.channels[channel].extend(values);
+    }
+
+    fn convert(&mut self, command: u8) -> Result<u16, Error> {
+        self.commands.push(command);
+        let address = ((command >> 4) & 0b111) as usize;
+        if self.failing_channel == Some(address) {
+            return Err(Error::Fault);
+        }
+        let channel = &mut self.channels[address];
+        Ok(if channel.len() > 1 {
+            channel.pop_front().unwrap()
+        } else {
+            channel.front().copied().unwrap_or(0)
+        })
+    }
+}
+impl embedded_hal::spi::ErrorType for FakeTsc2046 {
+    type Error = Error;
+}
+impl embedded_hal::spi::SpiDevice<u8> for FakeTsc2046 {
+    fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+    ) -> Result<(), Error> {
+        self.transactions += 1;
+        let mut result = None;
+        for op in operations {
+            match op {
+                embedded_hal::spi::Operation::Write(write) => {
+                    if let Some(&command) = write.first() {
+                        result = Some(self.convert(command)?);
+                    }
+                }
+                embedded_hal::spi::Operation::Read(read) => {
+                    // Only the first read after a command carries the result.
+                    read.fill(0);
+                    if let Some(result) = result.take() {
+                        read[0] = (result >> 5) as u8;
+                        read[1] = (result << 3) as u8;
+                    }
+                }
+                embedded_hal::spi::Operation::DelayNs(ns) => self.delay_ns += *ns as u64,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+#[cfg(feature = "async")]
+impl embedded_hal_async::spi::SpiDevice<u8> for FakeTsc2046 {
+    async fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+    ) -> Result<(), Error> {
+        embedded_hal::spi::SpiDevice::transaction(self, operations)
+    }
+}
+
+/// Fake PENIRQ pin, which stays low and counts the waits for its low level.
+#[cfg(all(feature = "async", feature = "stream"))]
+#[derive(Debug, Default)]
+pub struct FakePenIrq {
+    /// The number of waits for the low level.
+    pub waits: u32,
+}
+#[cfg(all(feature = "async", feature = "stream"))]
+impl embedded_hal::digital::ErrorType for FakePenIrq {
+    type Error = core::convert::Infallible;
+}
+#[cfg(all(feature = "async", feature = "stream"))]
+impl embedded_hal::digital::InputPin for FakePenIrq {
+    fn is_high(&mut self) -> Result<bool, core::convert::Infallible> {
+        Ok(false)
+    }
+
+    fn is_low(&mut self) -> Result<bool, core::convert::Infallible> {
+        Ok(true)
+    }
+}
+#[cfg(all(feature = "async", feature = "stream"))]
+impl embedded_hal_async::digital::Wait for FakePenIrq {
+    async fn wait_for_high(&mut self) -> Result<(), core::convert::Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), core::convert::Infallible> {
+        self.waits += 1;
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), core::convert::Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), core::convert::Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), core::convert::Infallible> {
+        core::future::pending().await
+    }
+}
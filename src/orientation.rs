@@ -0,0 +1,329 @@
+use core::num::NonZeroU32;
+
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::guided::GuidedRoutine;
+use crate::{
+    Error, OptionalDelay, OptionalInputPin, Rect, RoutineDelay, TouchPoint, TouchSample, Tsc2046,
+};
+
+/// The interval between two samples of the detection routine, in milliseconds.
+const DETECTION_SAMPLE_INTERVAL_MS: u32 = 10;
+/// The largest raw coordinate.
+const RAW_MAX: u16 = 4095;
+/// The smallest raw distance between the two corners for the detection to be trusted.
+const MIN_CORNER_DISTANCE: u16 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Orientation of the touch panel relative to the screen.
+///
+/// The four rotations of the panel, and their mirror images, are the combinations of the three
+/// settings.
+pub struct Orientation {
+    /// Whether the raw X and Y axes are swapped, the raw Y axis running along the screen width.
+    pub swap_xy: bool,
+    /// Whether the screen x-coordinate decreases along the raw axis, after the swap.
+    pub invert_x: bool,
+    /// Whether the screen y-coordinate decreases along the raw axis, after the swap.
+    pub invert_y: bool,
+}
+
+impl Orientation {
+    /// Applies the orientation to a raw touch point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The raw touch point.
+    ///
+    /// # Returns
+    ///
+    /// The touch point with its x-coordinate along the screen width and its y-coordinate along
+    /// the screen height, both in the raw range, increasing from the top left corner.
+    pub fn apply(&self, point: &TouchPoint) -> TouchPoint {
+        self.apply_within(
+            point,
+            &Rect {
+                x_min: 0,
+                y_min: 0,
+                x_max: RAW_MAX,
+                y_max: RAW_MAX,
+            },
+        )
+    }
+
+    /// Applies the orientation to a raw touch point, flipping the inverted axes within bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The raw touch point.
+    /// * `bounds` - The raw bounds of the screen, after the swap of the axes.
+    ///
+    /// # Returns
+    ///
+    /// The touch point with its x-coordinate along the screen width and its y-coordinate along
+    /// the screen height, increasing from the top left corner of the bounds. An inverted axis is
+    /// mirrored around the center of the bounds, so the bounds map onto themselves.
+    pub fn apply_within(&self, point: &TouchPoint, bounds: &Rect) -> TouchPoint {
+        let (x, y) = if self.swap_xy {
+            (point.y, point.x)
+        } else {
+            (point.x, point.y)
+        };
+        let flip = |value: u16, invert: bool, min: u16, max: u16| {
+            if invert {
+                min.saturating_add(max).saturating_sub(value)
+            } else {
+                value
+            }
+        };
+        TouchPoint {
+            x: flip(x, self.invert_x, bounds.x_min, bounds.x_max),
+            y: flip(y, self.invert_y, bounds.y_min, bounds.y_max),
+            z: point.z,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Step of the orientation detection routine.
+pub enum OrientationStep {
+    /// The user should touch the top left corner of the screen.
+    TouchTopLeft,
+    /// The user should touch the top right corner of the screen.
+    TouchTopRight,
+    /// The measurements are complete.
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Sum of the raw positions measured during a step.
+struct CornerSum {
+    /// The number of positions measured.
+    samples: u16,
+    /// The sum of the x-coordinates.
+    x: u32,
+    /// The sum of the y-coordinates.
+    y: u32,
+}
+
+impl CornerSum {
+    /// Adds a position to the sum.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The raw touch point.
+    fn add(&mut self, point: &TouchPoint) {
+        self.samples = self.samples.saturating_add(1);
+        self.x = self.x.saturating_add(point.x as u32);
+        self.y = self.y.saturating_add(point.y as u32);
+    }
+
+    /// Returns the average position.
+    fn average(&self) -> (u16, u16) {
+        let samples = NonZeroU32::new(self.samples as u32).unwrap_or(NonZeroU32::MIN);
+        ((self.x / samples) as u16, (self.y / samples) as u16)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Guided routine inferring the orientation of the panel from touches on two screen corners.
+///
+/// The top left corner, then the top right corner, are touched. The raw axis changing most
+/// between the two corners runs along the screen width, and the direction of the change tells
+/// whether it is inverted. The raw position of the top corners along the other axis tells whether
+/// the screen height is inverted.
+pub struct OrientationDetector {
+    /// The number of positions measured per step.
+    samples_per_step: u16,
+    /// The positions measured on the top left corner.
+    top_left: CornerSum,
+    /// The positions measured on the top right corner.
+    top_right: CornerSum,
+}
+
+impl OrientationDetector {
+    /// Creates a new orientation detector.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples_per_step` - The number of positions measured per corner, at least 1.
+    pub fn new(samples_per_step: u16) -> Self {
+        Self {
+            samples_per_step: samples_per_step.max(1),
+            top_left: CornerSum::default(),
+            top_right: CornerSum::default(),
+        }
+    }
+
+    /// Returns the current step of the routine.
+    pub fn step(&self) -> OrientationStep {
+        if self.top_left.samples < self.samples_per_step {
+            OrientationStep::TouchTopLeft
+        } else if self.top_right.samples < self.samples_per_step {
+            OrientationStep::TouchTopRight
+        } else {
+            OrientationStep::Done
+        }
+    }
+
+    /// Records the raw position of a touch for the current step.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The raw touch point.
+    pub fn record(&mut self, point: &TouchPoint) {
+        match self.step() {
+            OrientationStep::TouchTopLeft => self.top_left.add(point),
+            OrientationStep::TouchTopRight => self.top_right.add(point),
+            OrientationStep::Done => {}
+        }
+    }
+
+    /// Infers the orientation from the measurements.
+    ///
+    /// # Returns
+    ///
+    /// The `Orientation`, or `None` if the routine is not done, or the two corners were touched
+    /// too close to each other.
+    pub fn orientation(&self) -> Option<Orientation> {
+        if self.step() != OrientationStep::Done {
+            return None;
+        }
+        let (left_x, left_y) = self.top_left.average();
+        let (right_x, right_y) = self.top_right.average();
+        let swap_xy = right_y.abs_diff(left_y) > right_x.abs_diff(left_x);
+        let (width_from, width_to, height_at) = if swap_xy {
+            (left_y, right_y, left_x)
+        } else {
+            (left_x, right_x, left_y)
+        };
+        if width_from.abs_diff(width_to) < MIN_CORNER_DISTANCE {
+            return None;
+        }
+        Some(Orientation {
+            swap_xy,
+            invert_x: width_to < width_from,
+            invert_y: height_at > RAW_MAX / 2,
+        })
+    }
+}
+
+impl GuidedRoutine for OrientationDetector {
+    type Step = OrientationStep;
+
+    fn pending_step(&self) -> Option<OrientationStep> {
+        Some(self.step()).filter(|step| *step != OrientationStep::Done)
+    }
+
+    fn record_sample(&mut self, sample: &TouchSample) {
+        self.record(&sample.point);
+    }
+}
+
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
+    /// Runs the orientation detection routine.
+    ///
+    /// The `prompt` is called whenever the user should touch the next corner, which the
+    /// application indicates on the screen. Every step only starts measuring once the panel has
+    /// been released, so a touch is never counted for the wrong corner.
+    ///
+    /// # Arguments
+    ///
+    /// * `detector` - The detector collecting the measurements.
+    /// * `delay` - The delay provider used to wait between samples, or [`crate::OwnedDelay`].
+    /// * `timeout_ms` - The maximum duration of the routine in milliseconds.
+    /// * `prompt` - The function asking the user to touch a corner.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the detected orientation, `None` if the corners were touched too
+    /// close to each other, or [`Error::Timeout`] if the routine did not complete in time. The
+    /// orientation is applied with [`crate::calibration::Calibration::with_orientation`].
+    pub fn detect_orientation<D: RoutineDelay<DELAY>>(
+        &mut self,
+        detector: &mut OrientationDetector,
+        delay: &mut D,
+        timeout_ms: u32,
+        prompt: impl FnMut(OrientationStep),
+    ) -> Result<Option<Orientation>, Error<<SPI as ErrorType>::Error>> {
+        self.run_guided(
+            detector,
+            delay,
+            DETECTION_SAMPLE_INTERVAL_MS,
+            timeout_ms,
+            prompt,
+        )?;
+        Ok(detector.orientation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::{FakeTsc2046, MockDelay};
+
+    #[test]
+    fn test_detect_rotated_panel() {
+        let mut fake_chip = FakeTsc2046::default();
+        // The first X conversion is consumed by the initial register update. A Z1 of 0 reads as
+        // released. The raw Y axis runs along the screen width, and the raw X axis decreases
+        // from the top to the bottom of the screen.
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048, 3900, 2048, 3900]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048, 200, 2048, 3800]);
+        fake_chip.queue(FakeTsc2046::Z1, &[0, 500, 0, 500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[4095, 600, 4095, 600]);
+        let mut test_driver =
+            Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let mut detector = OrientationDetector::new(1);
+        let mut prompts = Vec::new();
+        let orientation = test_driver
+            .detect_orientation(&mut detector, &mut MockDelay::default(), 1000, |step| {
+                prompts.push(step)
+            })
+            .expect("Detection failed")
+            .expect("No orientation");
+        assert_eq!(
+            prompts,
+            [
+                OrientationStep::TouchTopLeft,
+                OrientationStep::TouchTopRight
+            ]
+        );
+        assert_eq!(
+            orientation,
+            Orientation {
+                swap_xy: true,
+                invert_x: false,
+                invert_y: true,
+            }
+        );
+        let top_left = orientation.apply(&TouchPoint {
+            x: 3900,
+            y: 200,
+            z: 1.0,
+        });
+        assert_eq!((top_left.x, top_left.y), (200, 195));
+    }
+
+    #[test]
+    fn test_corners_too_close() {
+        let mut detector = OrientationDetector::new(1);
+        detector.record(&TouchPoint {
+            x: 1000,
+            y: 300,
+            z: 1.0,
+        });
+        assert_eq!(detector.step(), OrientationStep::TouchTopRight);
+        detector.record(&TouchPoint {
+            x: 1200,
+            y: 320,
+            z: 1.0,
+        });
+        assert_eq!(detector.orientation(), None);
+    }
+}
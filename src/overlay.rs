@@ -0,0 +1,198 @@
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{
+    Circle, Line, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle,
+};
+
+use crate::calibration::ScreenPoint;
+use crate::zones::ZoneSet;
+
+/// The length of the arms of the calibration target crosshairs in pixels.
+const TARGET_ARM: i32 = 6;
+/// The diameter of the touch point markers in pixels.
+const POINT_DIAMETER: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Colors used to draw the debug overlay.
+pub struct OverlayStyle<C> {
+    /// The color of the touch point markers.
+    pub point: C,
+    /// The color of the zone outlines.
+    pub zone: C,
+    /// The color filling the active zone, the one hit by the last touch point.
+    pub active_zone: C,
+    /// The color of the calibration target crosshairs.
+    pub target: C,
+}
+
+/// Debug overlay drawing the last `K` touch points, zones and calibration targets onto any
+/// `DrawTarget`.
+#[derive(Debug, Clone)]
+pub struct DebugOverlay<const K: usize> {
+    /// The last touch points, stored circularly.
+    points: [Option<Point>; K],
+    /// The index the next touch point is stored at.
+    next: usize,
+}
+
+impl<const K: usize> Default for DebugOverlay<K> {
+    fn default() -> Self {
+        Self {
+            points: [None; K],
+            next: 0,
+        }
+    }
+}
+
+impl<const K: usize> DebugOverlay<K> {
+    /// Creates a new debug overlay without touch points.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a touch point, dropping the oldest one if `K` points are stored already.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point mapped to screen space.
+    pub fn push(&mut self, point: &ScreenPoint) {
+        if K == 0 {
+            return;
+        }
+        if let Some(slot) = self.points.get_mut(self.next) {
+            *slot = Some(Point::new(point.x as i32, point.y as i32));
+        }
+        self.next = self.next.wrapping_add(1).checked_rem(K).unwrap_or_default();
+    }
+
+    /// Returns the last touch point added, if any.
+    fn last(&self) -> Option<Point> {
+        let index = self.next.checked_sub(1).unwrap_or(K.saturating_sub(1));
+        self.points.get(index).copied().flatten()
+    }
+
+    /// Removes all touch points.
+    pub fn clear(&mut self) {
+        self.points = [None; K];
+        self.next = 0;
+    }
+
+    /// Draws the overlay.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The target to draw onto.
+    /// * `style` - The colors of the overlay.
+    /// * `zones` - The zones to outline, in screen space. The zone hit by the last touch point is
+    ///   filled as well.
+    /// * `targets` - The calibration targets to mark with crosshairs, in screen space.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether drawing succeeded or not.
+    pub fn draw<D, C, const N: usize>(
+        &self,
+        target: &mut D,
+        style: &OverlayStyle<C>,
+        zones: &ZoneSet<N>,
+        targets: &[Point],
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+        C: PixelColor,
+    {
+        let active = self.last().and_then(|point| {
+            let (x, y) = (u16::try_from(point.x).ok()?, u16::try_from(point.y).ok()?);
+            zones.hit(x, y)
+        });
+        let zone_style = PrimitiveStyle::with_stroke(style.zone, 1);
+        let active_style = PrimitiveStyleBuilder::new()
+            .stroke_color(style.zone)
+            .stroke_width(1)
+            .fill_color(style.active_zone)
+            .build();
+        for zone in zones.iter() {
+            let bounds = Rectangle::with_corners(
+                Point::new(i32::from(zone.area.x_min), i32::from(zone.area.y_min)),
+                Point::new(i32::from(zone.area.x_max), i32::from(zone.area.y_max)),
+            );
+            let style = if Some(zone) == active {
+                active_style
+            } else {
+                zone_style
+            };
+            bounds.into_styled(style).draw(target)?;
+        }
+        let target_style = PrimitiveStyle::with_stroke(style.target, 1);
+        for center in targets {
+            let offset = |dx: i32, dy: i32| {
+                Point::new(center.x.saturating_add(dx), center.y.saturating_add(dy))
+            };
+            Line::new(offset(-TARGET_ARM, 0), offset(TARGET_ARM, 0))
+                .into_styled(target_style)
+                .draw(target)?;
+            Line::new(offset(0, -TARGET_ARM), offset(0, TARGET_ARM))
+                .into_styled(target_style)
+                .draw(target)?;
+        }
+        let point_style = PrimitiveStyle::with_fill(style.point);
+        for point in self.points.iter().flatten() {
+            Circle::with_center(*point, POINT_DIAMETER)
+                .into_styled(point_style)
+                .draw(target)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zones::Zone;
+    use crate::Rect;
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    #[test]
+    fn test_draw_overlay() {
+        let mut overlay = DebugOverlay::<2>::new();
+        for x in [10, 20, 30] {
+            overlay.push(&ScreenPoint {
+                x,
+                y: 10,
+                z: 10.0,
+                physical: None,
+            });
+        }
+        let mut display = MockDisplay::<BinaryColor>::new();
+        display.set_allow_overdraw(true);
+        let style = OverlayStyle {
+            point: BinaryColor::On,
+            zone: BinaryColor::On,
+            active_zone: BinaryColor::On,
+            target: BinaryColor::On,
+        };
+        let zone = |id, x_min, y_min| Zone {
+            id,
+            area: Rect {
+                x_min,
+                y_min,
+                x_max: x_min + 9,
+                y_max: y_min + 9,
+            },
+        };
+        let mut zones = ZoneSet::<2>::new();
+        assert!(zones.add(zone(1, 25, 5)));
+        assert!(zones.add(zone(2, 40, 40)));
+        overlay
+            .draw(&mut display, &style, &zones, &[Point::new(50, 20)])
+            .expect("Could not draw overlay");
+        // The oldest point has been dropped.
+        assert_eq!(display.get_pixel(Point::new(10, 10)), None);
+        assert_eq!(display.get_pixel(Point::new(30, 10)), Some(BinaryColor::On));
+        // The zone hit by the last point is filled, the other one only outlined.
+        assert_eq!(display.get_pixel(Point::new(26, 13)), Some(BinaryColor::On));
+        assert_eq!(display.get_pixel(Point::new(40, 45)), Some(BinaryColor::On));
+        assert_eq!(display.get_pixel(Point::new(45, 45)), None);
+        assert_eq!(display.get_pixel(Point::new(56, 20)), Some(BinaryColor::On));
+    }
+}
@@ -0,0 +1,316 @@
+//! Persistence of the calibration in NOR flash.
+//!
+//! The calibration is stored as the versioned record of [`Calibration::to_bytes`], so a record
+//! saved by an older firmware is migrated on load. The blocking functions take an
+//! `embedded-storage` flash, and their `_async` counterparts an `embedded-storage-async` flash, so
+//! async firmware can save without blocking the executor.
+
+#[cfg(feature = "storage")]
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+#[cfg(feature = "storage-async")]
+use embedded_storage_async::nor_flash::{
+    NorFlash as AsyncNorFlash, ReadNorFlash as AsyncReadNorFlash,
+};
+
+use crate::calibration::{Calibration, CalibrationFormatError, CALIBRATION_RECORD_LEN};
+
+/// The size of the storage slot of a calibration record, leaving room for the fields appended by
+/// later format versions.
+pub const CALIBRATION_SLOT_LEN: usize = 64;
+/// The value of erased NOR flash bytes.
+const ERASED: u8 = 0xFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Error saving or loading a calibration.
+pub enum PersistError<E> {
+    /// The flash reported an error.
+    Flash(E),
+    /// The stored record cannot be loaded, for example because nothing was saved yet.
+    Format(CalibrationFormatError),
+    /// The offset is not aligned to the erase size, or the read or write size of the flash does
+    /// not fit the slot.
+    Alignment,
+}
+
+/// Computes the number of bytes to access for a record, given the access granularity.
+///
+/// # Arguments
+///
+/// * `len` - The number of bytes needed.
+/// * `granularity` - The read or write size of the flash.
+///
+/// # Returns
+///
+/// `len` rounded up to the granularity, or `None` if it does not fit the slot.
+fn access_len(len: usize, granularity: usize) -> Option<usize> {
+    let granularity = granularity.max(1);
+    len.div_ceil(granularity)
+        .checked_mul(granularity)
+        .filter(|&len| len <= CALIBRATION_SLOT_LEN)
+}
+
+/// Prepares the slot contents of a calibration.
+///
+/// # Arguments
+///
+/// * `calibration` - The calibration.
+/// * `offset` - The offset of the slot.
+/// * `write_size` - The write size of the flash.
+/// * `erase_size` - The erase size of the flash.
+///
+/// # Returns
+///
+/// The slot contents and the number of bytes to write, or [`PersistError::Alignment`].
+fn prepare<E>(
+    calibration: &Calibration,
+    offset: u32,
+    write_size: usize,
+    erase_size: usize,
+) -> Result<([u8; CALIBRATION_SLOT_LEN], usize), PersistError<E>> {
+    if !(offset as usize).is_multiple_of(erase_size.max(1)) {
+        return Err(PersistError::Alignment);
+    }
+    let len = access_len(CALIBRATION_RECORD_LEN, write_size).ok_or(PersistError::Alignment)?;
+    let mut slot = [ERASED; CALIBRATION_SLOT_LEN];
+    slot.iter_mut()
+        .zip(calibration.to_bytes())
+        .for_each(|(byte, value)| *byte = value);
+    Ok((slot, len))
+}
+
+/// Computes the number of bytes to read for a slot.
+///
+/// # Arguments
+///
+/// * `read_size` - The read size of the flash.
+///
+/// # Returns
+///
+/// The largest multiple of the read size within the slot, or [`PersistError::Alignment`].
+fn read_len<E>(read_size: usize) -> Result<usize, PersistError<E>> {
+    let len = CALIBRATION_SLOT_LEN.saturating_sub(
+        CALIBRATION_SLOT_LEN
+            .checked_rem(read_size)
+            .unwrap_or_default(),
+    );
+    if len < CALIBRATION_RECORD_LEN {
+        return Err(PersistError::Alignment);
+    }
+    Ok(len)
+}
+
+/// Saves a calibration, erasing the sector at the given offset.
+///
+/// # Arguments
+///
+/// * `flash` - The flash storing the calibration.
+/// * `offset` - The offset of the slot, aligned to the erase size of the flash.
+/// * `calibration` - The calibration to save.
+///
+/// # Returns
+///
+/// A `Result` indicating whether the calibration was saved.
+#[cfg(feature = "storage")]
+pub fn save_calibration<F: NorFlash>(
+    flash: &mut F,
+    offset: u32,
+    calibration: &Calibration,
+) -> Result<(), PersistError<F::Error>> {
+    let (slot, len) = prepare(calibration, offset, F::WRITE_SIZE, F::ERASE_SIZE)?;
+    let sector_end = offset.saturating_add(F::ERASE_SIZE as u32);
+    flash
+        .erase(offset, sector_end)
+        .map_err(PersistError::Flash)?;
+    flash
+        .write(offset, slot.get(..len).ok_or(PersistError::Alignment)?)
+        .map_err(PersistError::Flash)
+}
+
+/// Loads a calibration saved by [`save_calibration`].
+///
+/// # Arguments
+///
+/// * `flash` - The flash storing the calibration.
+/// * `offset` - The offset of the slot.
+///
+/// # Returns
+///
+/// A `Result` containing the calibration, migrated if saved by an older firmware.
+#[cfg(feature = "storage")]
+pub fn load_calibration<F: ReadNorFlash>(
+    flash: &mut F,
+    offset: u32,
+) -> Result<Calibration, PersistError<F::Error>> {
+    let mut slot = [ERASED; CALIBRATION_SLOT_LEN];
+    let len = read_len(F::READ_SIZE)?;
+    flash
+        .read(offset, slot.get_mut(..len).ok_or(PersistError::Alignment)?)
+        .map_err(PersistError::Flash)?;
+    Calibration::from_bytes(&slot).map_err(PersistError::Format)
+}
+
+/// Saves a calibration, erasing the sector at the given offset, without blocking.
+///
+/// Same as [`save_calibration`], for async flash drivers.
+///
+/// # Arguments
+///
+/// * `flash` - The flash storing the calibration.
+/// * `offset` - The offset of the slot, aligned to the erase size of the flash.
+/// * `calibration` - The calibration to save.
+///
+/// # Returns
+///
+/// A `Result` indicating whether the calibration was saved.
+#[cfg(feature = "storage-async")]
+pub async fn save_calibration_async<F: AsyncNorFlash>(
+    flash: &mut F,
+    offset: u32,
+    calibration: &Calibration,
+) -> Result<(), PersistError<F::Error>> {
+    let (slot, len) = prepare(calibration, offset, F::WRITE_SIZE, F::ERASE_SIZE)?;
+    let sector_end = offset.saturating_add(F::ERASE_SIZE as u32);
+    flash
+        .erase(offset, sector_end)
+        .await
+        .map_err(PersistError::Flash)?;
+    flash
+        .write(offset, slot.get(..len).ok_or(PersistError::Alignment)?)
+        .await
+        .map_err(PersistError::Flash)
+}
+
+/// Loads a calibration saved by [`save_calibration_async`], without blocking.
+///
+/// Same as [`load_calibration`], for async flash drivers.
+///
+/// # Arguments
+///
+/// * `flash` - The flash storing the calibration.
+/// * `offset` - The offset of the slot.
+///
+/// # Returns
+///
+/// A `Result` containing the calibration, migrated if saved by an older firmware.
+#[cfg(feature = "storage-async")]
+pub async fn load_calibration_async<F: AsyncReadNorFlash>(
+    flash: &mut F,
+    offset: u32,
+) -> Result<Calibration, PersistError<F::Error>> {
+    let mut slot = [ERASED; CALIBRATION_SLOT_LEN];
+    let len = read_len(F::READ_SIZE)?;
+    flash
+        .read(offset, slot.get_mut(..len).ok_or(PersistError::Alignment)?)
+        .await
+        .map_err(PersistError::Flash)?;
+    Calibration::from_bytes(&slot).map_err(PersistError::Format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_storage::nor_flash::ErrorType;
+
+    /// Flash held in RAM, with a write size of 4 bytes and sectors of 256 bytes.
+    struct RamFlash([u8; 1024]);
+
+    impl ErrorType for RamFlash {
+        type Error = Infallible;
+    }
+
+    #[cfg(feature = "storage")]
+    impl ReadNorFlash for RamFlash {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Infallible> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[cfg(feature = "storage")]
+    impl NorFlash for RamFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 256;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Infallible> {
+            self.0[from as usize..to as usize].fill(ERASED);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Infallible> {
+            let offset = offset as usize;
+            self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "storage-async")]
+    impl AsyncReadNorFlash for RamFlash {
+        const READ_SIZE: usize = 1;
+
+        async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Infallible> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[cfg(feature = "storage-async")]
+    impl AsyncNorFlash for RamFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 256;
+
+        async fn erase(&mut self, from: u32, to: u32) -> Result<(), Infallible> {
+            self.0[from as usize..to as usize].fill(ERASED);
+            Ok(())
+        }
+
+        async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Infallible> {
+            let offset = offset as usize;
+            self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "storage")]
+    #[test]
+    fn test_save_and_load() {
+        let mut flash = RamFlash([0; 1024]);
+        let calibration = Calibration::new(200, 3900, 300, 3800, 320, 240);
+        assert_eq!(
+            load_calibration(&mut flash, 256),
+            Err(PersistError::Format(CalibrationFormatError::BadMarker))
+        );
+        assert_eq!(
+            save_calibration(&mut flash, 100, &calibration),
+            Err(PersistError::Alignment)
+        );
+        save_calibration(&mut flash, 256, &calibration).expect("Could not save");
+        assert_eq!(load_calibration(&mut flash, 256), Ok(calibration));
+    }
+
+    #[cfg(feature = "storage-async")]
+    #[test]
+    fn test_save_and_load_async() {
+        use crate::mock_peripherals::block_on;
+
+        let mut flash = RamFlash([0; 1024]);
+        let calibration = Calibration::new(200, 3900, 300, 3800, 320, 240);
+        block_on(save_calibration_async(&mut flash, 512, &calibration)).expect("Could not save");
+        assert_eq!(
+            block_on(load_calibration_async(&mut flash, 512)),
+            Ok(calibration)
+        );
+    }
+}
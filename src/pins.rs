@@ -0,0 +1,110 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{Error as _, ErrorKind, InputPin};
+
+/// Placeholder type for an optional pin that is not connected to the driver.
+pub struct NoPin;
+
+/// Placeholder type for a delay provider that is not owned by the driver.
+pub struct NoDelay;
+
+/// An input pin which may or may not be connected to the driver.
+pub trait OptionalInputPin {
+    /// Whether a pin is connected, known at compile time.
+    const CONNECTED: bool = true;
+
+    /// Reads the level of the pin.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `Some(true)` if the pin is high, `Some(false)` if it is low, `None` if
+    /// no pin is connected, or the kind of the error if reading the pin fails.
+    fn read_level(&mut self) -> Result<Option<bool>, ErrorKind>;
+}
+
+impl OptionalInputPin for NoPin {
+    const CONNECTED: bool = false;
+
+    fn read_level(&mut self) -> Result<Option<bool>, ErrorKind> {
+        Ok(None)
+    }
+}
+
+impl<P: InputPin> OptionalInputPin for P {
+    fn read_level(&mut self) -> Result<Option<bool>, ErrorKind> {
+        self.is_high().map(Some).map_err(|e| e.kind())
+    }
+}
+
+/// A PENIRQ pin which may or may not be awaited by the async driver.
+#[cfg(feature = "async")]
+pub trait OptionalWait {
+    /// Waits for the pin to be low.
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to `true` once the pin is low, `false` right away if no pin is
+    /// connected, or the kind of the error if waiting on the pin fails.
+    fn wait_low(&mut self) -> impl core::future::Future<Output = Result<bool, ErrorKind>>;
+}
+
+#[cfg(feature = "async")]
+impl OptionalWait for NoPin {
+    async fn wait_low(&mut self) -> Result<bool, ErrorKind> {
+        Ok(false)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P: embedded_hal_async::digital::Wait> OptionalWait for P {
+    async fn wait_low(&mut self) -> Result<bool, ErrorKind> {
+        self.wait_for_low().await.map_err(|e| e.kind())?;
+        Ok(true)
+    }
+}
+
+/// A delay provider which may or may not be available.
+pub trait OptionalDelay {
+    /// Pauses execution, if a delay provider is available.
+    ///
+    /// # Arguments
+    ///
+    /// * `ns` - The duration of the pause in nanoseconds.
+    fn pause_ns(&mut self, ns: u32);
+}
+
+impl OptionalDelay for NoDelay {
+    fn pause_ns(&mut self, _ns: u32) {}
+}
+
+impl<D: DelayNs> OptionalDelay for D {
+    fn pause_ns(&mut self, ns: u32) {
+        self.delay_ns(ns);
+    }
+}
+
+/// Selects the delay provider owned by the driver, set with [`crate::Tsc2046::with_delay`], for
+/// the routines taking a delay provider.
+pub struct OwnedDelay;
+
+/// Delay provider of a driver routine: one passed by the caller, or [`OwnedDelay`].
+pub trait RoutineDelay<DELAY> {
+    /// Pauses execution.
+    ///
+    /// # Arguments
+    ///
+    /// * `owned` - The delay provider owned by the driver.
+    /// * `us` - The duration of the pause in microseconds.
+    fn pause_us(&mut self, owned: &mut DELAY, us: u32);
+}
+
+impl<DELAY: DelayNs> RoutineDelay<DELAY> for OwnedDelay {
+    fn pause_us(&mut self, owned: &mut DELAY, us: u32) {
+        owned.delay_us(us);
+    }
+}
+
+impl<D: DelayNs, DELAY> RoutineDelay<DELAY> for D {
+    fn pause_us(&mut self, _owned: &mut DELAY, us: u32) {
+        self.delay_us(us);
+    }
+}
@@ -0,0 +1,399 @@
+use crate::diagnostics::log2;
+use crate::PressureSemantics;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Pressure band of a touch point.
+pub enum PressureBand {
+    /// A light touch, like a brush of the finger.
+    Light,
+    /// A touch with normal pressure.
+    Normal,
+    /// A firm press, like the press of a stylus.
+    Firm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Classifier mapping the pressure value of a touch point into pressure bands.
+///
+/// The bands are defined by the pressure values firmer than which a touch belongs to them.
+/// Firmer means lower in the raw ratio, the default, and higher in the other
+/// [`PressureSemantics`], which must match the semantics configured in the driver.
+pub struct PressureClassifier {
+    /// The pressure value firmer than which a touch is at least `Normal`.
+    normal: f32,
+    /// The pressure value firmer than which a touch is `Firm`.
+    firm: f32,
+    /// The domain of the pressure values.
+    semantics: PressureSemantics,
+}
+
+impl PressureClassifier {
+    /// Creates a new pressure classifier for raw ratio pressure values.
+    ///
+    /// # Arguments
+    ///
+    /// * `normal_below` - The pressure value below which a touch is classified as `Normal`.
+    /// * `firm_below` - The pressure value below which a touch is classified as `Firm`.
+    pub fn new(normal_below: f32, firm_below: f32) -> Self {
+        Self {
+            normal: normal_below,
+            firm: firm_below,
+            semantics: PressureSemantics::RawRatio,
+        }
+    }
+
+    /// Creates a new pressure classifier for pressure values in the given domain.
+    ///
+    /// # Arguments
+    ///
+    /// * `normal` - The pressure value firmer than which a touch is classified as `Normal`.
+    /// * `firm` - The pressure value firmer than which a touch is classified as `Firm`.
+    /// * `semantics` - The domain of the pressure values.
+    pub fn with_semantics(normal: f32, firm: f32, semantics: PressureSemantics) -> Self {
+        Self {
+            normal,
+            firm,
+            semantics,
+        }
+    }
+
+    /// Classifies a pressure value.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The pressure value of the touch point.
+    ///
+    /// # Returns
+    ///
+    /// The `PressureBand` the pressure value belongs to.
+    pub fn classify(&self, z: f32) -> PressureBand {
+        if self.semantics.is_firmer(z, self.firm) {
+            PressureBand::Firm
+        } else if self.semantics.is_firmer(z, self.normal) {
+            PressureBand::Normal
+        } else {
+            PressureBand::Light
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A pressure value recorded at a known applied force.
+pub struct ForceSample {
+    /// The pressure value read from the chip.
+    pub z: f32,
+    /// The force applied while reading the pressure value, in any unit.
+    pub force: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Calibration mapping pressure values into estimated forces.
+///
+/// The contact resistance of a resistive panel is roughly inversely proportional to the applied
+/// force, so the curve `force = offset + gain / z` is fitted to the recorded samples.
+pub struct ForceCalibration {
+    /// The constant term of the fitted curve.
+    offset: f32,
+    /// The coefficient of the inverse pressure term of the fitted curve.
+    gain: f32,
+}
+
+impl ForceCalibration {
+    /// Fits the calibration curve to the recorded samples with the method of least squares.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The pressure values recorded at known applied forces.
+    ///
+    /// # Returns
+    ///
+    /// The fitted `ForceCalibration`, or `None` if there are fewer than two samples with a positive
+    /// pressure value, or all of them have the same pressure value.
+    pub fn fit(samples: &[ForceSample]) -> Option<Self> {
+        let mut count = 0.0_f32;
+        let mut sum_u = 0.0_f32;
+        let mut sum_f = 0.0_f32;
+        let mut sum_uu = 0.0_f32;
+        let mut sum_uf = 0.0_f32;
+        for sample in samples.iter().filter(|sample| sample.z > 0.0) {
+            let u = 1.0 / sample.z;
+            count += 1.0;
+            sum_u += u;
+            sum_f += sample.force;
+            sum_uu += u * u;
+            sum_uf += u * sample.force;
+        }
+        let denominator = count * sum_uu - sum_u * sum_u;
+        if count < 2.0 || denominator <= f32::EPSILON * sum_uu * count {
+            return None;
+        }
+        let gain = (count * sum_uf - sum_u * sum_f) / denominator;
+        Some(Self {
+            offset: (sum_f - gain * sum_u) / count,
+            gain,
+        })
+    }
+
+    /// Estimates the force applied for a pressure value.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The pressure value of the touch point.
+    ///
+    /// # Returns
+    ///
+    /// The estimated force, in the unit of the calibration samples, never below zero.
+    pub fn estimate(&self, z: f32) -> f32 {
+        (self.offset + self.gain / z.max(f32::EPSILON)).max(0.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Kind of object touching the panel, estimated from the contact area.
+pub enum ContactKind {
+    /// A small contact, like the tip of a stylus.
+    Stylus,
+    /// A large contact, like a finger.
+    Finger,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Estimator of the size of the contact between the panel layers.
+///
+/// The pressure value is the touch resistance relative to the X-plate resistance, so multiplying
+/// it by the X-plate resistance from the panel datasheet gives the touch resistance in ohms. The
+/// touch resistance is roughly inversely proportional to the contact area, with a constant that
+/// depends on the panel and is found by touching it with objects of known size. The estimate is
+/// crude, but sufficient to tell a stylus from a finger.
+pub struct ContactEstimator {
+    /// The resistance of the X plate in ohms.
+    x_plate_ohms: f32,
+    /// The product of the touch resistance and the contact area, in ohm square millimeters.
+    area_constant: f32,
+    /// The smallest contact area of a finger, in square millimeters.
+    finger_min_area: f32,
+}
+
+impl ContactEstimator {
+    /// Creates a new contact estimator.
+    ///
+    /// # Arguments
+    ///
+    /// * `x_plate_ohms` - The resistance of the X plate in ohms.
+    /// * `area_constant` - The product of the touch resistance and the contact area, in ohm
+    ///   square millimeters.
+    /// * `finger_min_area` - The smallest contact area of a finger, in square millimeters.
+    pub fn new(x_plate_ohms: f32, area_constant: f32, finger_min_area: f32) -> Self {
+        Self {
+            x_plate_ohms,
+            area_constant,
+            finger_min_area,
+        }
+    }
+
+    /// Computes the touch resistance for a pressure value.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The pressure value of the touch point.
+    ///
+    /// # Returns
+    ///
+    /// The touch resistance in ohms.
+    pub fn touch_resistance(&self, z: f32) -> f32 {
+        self.x_plate_ohms * z
+    }
+
+    /// Estimates the contact area for a pressure value.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The pressure value of the touch point.
+    ///
+    /// # Returns
+    ///
+    /// The estimated contact area in square millimeters. Touch resistances below one ohm are
+    /// treated as one ohm.
+    pub fn contact_area(&self, z: f32) -> f32 {
+        self.area_constant / self.touch_resistance(z).max(1.0)
+    }
+
+    /// Classifies the object touching the panel.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The pressure value of the touch point.
+    ///
+    /// # Returns
+    ///
+    /// The estimated `ContactKind`.
+    pub fn classify(&self, z: f32) -> ContactKind {
+        if self.contact_area(z) < self.finger_min_area {
+            ContactKind::Stylus
+        } else {
+            ContactKind::Finger
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Response curve mapping pressure values into a normalized pressure, for example to shape the
+/// brush response of a drawing application.
+///
+/// The pressure value is first normalized linearly between a light and a firm touch, to 0.0 and
+/// 1.0 respectively, then raised to the power `gamma`.
+pub struct PressureCurve {
+    /// The pressure value of a light touch, mapped to 0.0.
+    light_z: f32,
+    /// The pressure value of a firm touch, mapped to 1.0.
+    firm_z: f32,
+    /// The exponent applied to the normalized pressure.
+    gamma: f32,
+}
+
+impl PressureCurve {
+    /// Creates a new linear pressure curve.
+    ///
+    /// # Arguments
+    ///
+    /// * `light_z` - The pressure value of a light touch, mapped to 0.0.
+    /// * `firm_z` - The pressure value of a firm touch, mapped to 1.0. As the pressure value
+    ///   decreases with the applied pressure, it is smaller than `light_z`.
+    pub fn new(light_z: f32, firm_z: f32) -> Self {
+        Self {
+            light_z,
+            firm_z,
+            gamma: 1.0,
+        }
+    }
+
+    /// Sets the exponent applied to the normalized pressure.
+    ///
+    /// # Arguments
+    ///
+    /// * `gamma` - The exponent, positive. Values below 1.0 make light touches more
+    ///   pronounced, values above 1.0 require firmer touches.
+    pub fn with_gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Maps a pressure value through the curve.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The pressure value of the touch point.
+    ///
+    /// # Returns
+    ///
+    /// The normalized pressure, between 0.0 and 1.0.
+    pub fn apply(&self, z: f32) -> f32 {
+        let span = self.light_z - self.firm_z;
+        if span == 0.0 {
+            return if z <= self.firm_z { 1.0 } else { 0.0 };
+        }
+        let normalized = ((self.light_z - z) / span).clamp(0.0, 1.0);
+        if normalized == 0.0 || self.gamma == 1.0 {
+            return normalized;
+        }
+        exp2(self.gamma * log2(normalized)).clamp(0.0, 1.0)
+    }
+}
+
+/// Approximates the power of 2 of a number.
+///
+/// The integer part is written into the exponent of the float representation, and the power of
+/// the fractional part is approximated by the series of `exp(f * ln(2))`, accurate to about 1e-6.
+fn exp2(value: f32) -> f32 {
+    if value < -126.0 {
+        return 0.0;
+    }
+    if value >= 128.0 {
+        return f32::INFINITY;
+    }
+    let mut integer = value as i32;
+    if integer as f32 > value {
+        integer = integer.saturating_sub(1);
+    }
+    let x = (value - integer as f32) * core::f32::consts::LN_2;
+    let fraction = 1.0
+        + x * (1.0
+            + x / 2.0 * (1.0 + x / 3.0 * (1.0 + x / 4.0 * (1.0 + x / 5.0 * (1.0 + x / 6.0)))));
+    f32::from_bits((integer.saturating_add(127) as u32) << 23) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let classifier = PressureClassifier::new(40.0, 10.0);
+        assert_eq!(classifier.classify(80.0), PressureBand::Light);
+        assert_eq!(classifier.classify(40.0), PressureBand::Light);
+        assert_eq!(classifier.classify(25.0), PressureBand::Normal);
+        assert_eq!(classifier.classify(5.0), PressureBand::Firm);
+    }
+
+    #[test]
+    fn test_force_calibration_fit() {
+        // Samples following force = 2 + 600 / z exactly.
+        let samples = [
+            ForceSample {
+                z: 60.0,
+                force: 12.0,
+            },
+            ForceSample {
+                z: 20.0,
+                force: 32.0,
+            },
+            ForceSample {
+                z: 10.0,
+                force: 62.0,
+            },
+        ];
+        let calibration = ForceCalibration::fit(&samples).expect("Could not fit curve");
+        assert!((calibration.estimate(30.0) - 22.0).abs() < 1e-3);
+        assert!((calibration.estimate(1e9) - 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_force_calibration_degenerate() {
+        let samples = [
+            ForceSample {
+                z: 20.0,
+                force: 10.0,
+            },
+            ForceSample {
+                z: 20.0,
+                force: 30.0,
+            },
+        ];
+        assert_eq!(ForceCalibration::fit(&samples), None);
+        assert_eq!(ForceCalibration::fit(&samples[..1]), None);
+    }
+
+    #[test]
+    fn test_contact_estimator() {
+        let estimator = ContactEstimator::new(400.0, 40_000.0, 20.0);
+        assert_eq!(estimator.touch_resistance(0.5), 200.0);
+        assert_eq!(estimator.contact_area(0.5), 200.0);
+        assert_eq!(estimator.classify(0.5), ContactKind::Finger);
+        assert_eq!(estimator.contact_area(10.0), 10.0);
+        assert_eq!(estimator.classify(10.0), ContactKind::Stylus);
+        assert_eq!(estimator.contact_area(0.0), 40_000.0);
+    }
+
+    #[test]
+    fn test_pressure_curve() {
+        let linear = PressureCurve::new(40.0, 10.0);
+        assert_eq!(linear.apply(50.0), 0.0);
+        assert_eq!(linear.apply(25.0), 0.5);
+        assert_eq!(linear.apply(5.0), 1.0);
+        let curve = linear.with_gamma(2.0);
+        assert!((curve.apply(25.0) - 0.25).abs() < 1e-4);
+        let curve = linear.with_gamma(0.5);
+        assert!((curve.apply(25.0) - 0.5_f32.sqrt()).abs() < 1e-4);
+        assert_eq!(curve.apply(10.0), 1.0);
+    }
+}
@@ -0,0 +1,1393 @@
+//! Transport-agnostic core of the driver.
+//!
+//! Everything that does not depend on how the bytes reach the chip lives here: building the
+//! control words, decoding the results, combining repeated conversions and turning raw readings
+//! into touch samples. The sequencing of a touch scan is the [`Scan`] state machine, which yields
+//! the next conversions to perform and consumes their results. The state both flavors share, with
+//! the frames of the conversions, is the [`Core`]. The blocking driver and the async flavor only
+//! move bytes and read pins, following the steps of the machine.
+
+use core::num::NonZeroU32;
+
+use embedded_hal::spi::Operation;
+
+use crate::diagnostics::Diagnostics;
+use crate::types::{self, AuxChannel, Axes, ControlBit, PowerMode, Resolution};
+use crate::{
+    AcquisitionMode, ArithmeticMode, AxisRange, Channel, Config, Error, MeasurementMode,
+    OutOfRangePolicy, PartialSamplePolicy, PressureSemantics, Rect, SampleQuality, ScanOrder,
+    TouchPoint, TouchSample, ZeroPressurePolicy, MAX_BURST_LEN, MAX_OVERSAMPLING,
+    MAX_PADDING_BYTES,
+};
+
+/// The spread of oversampled conversions (in ADC counts) above which a sample has high variance.
+const HIGH_VARIANCE_SPREAD: u16 = CONFIDENCE_SPREAD_LIMIT / 2;
+/// The fraction of the touch threshold above which the pressure of a sample is marginal.
+const MARGINAL_PRESSURE_RATIO: f32 = 0.9;
+/// The spread of oversampled conversions (in ADC counts) at which the confidence drops to zero.
+const CONFIDENCE_SPREAD_LIMIT: u16 = 64;
+
+/// Outcome of the evaluation of the raw readings of a touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Evaluation {
+    /// The screen is not touched.
+    NoTouch,
+    /// The screen is touched.
+    Touch(TouchSample),
+    /// The reading of the channel is not usable.
+    Invalid(Channel),
+}
+
+/// Brings the settings of a configuration within the limits of the driver.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to apply.
+///
+/// # Returns
+///
+/// The configuration with the oversampling, padding, PENIRQ filter samples and trace capacity
+/// clamped.
+pub(crate) fn normalize(config: Config) -> Config {
+    Config {
+        oversampling: config.oversampling.clamp(1, MAX_OVERSAMPLING),
+        irq_filter_samples: config.irq_filter_samples.max(1),
+        #[cfg(feature = "aux")]
+        aux_oversampling: config.aux_oversampling.clamp(1, MAX_OVERSAMPLING),
+        padding_bytes: config.padding_bytes.min(MAX_PADDING_BYTES as u8),
+        #[cfg(feature = "trace")]
+        trace_capacity: config
+            .trace_capacity
+            .clamp(1, crate::trace::MAX_TRACE_CAPACITY),
+        ..config
+    }
+}
+
+/// Builds the control word programming the configured power-down mode.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the driver.
+///
+/// # Returns
+///
+/// The control word of an X conversion with the configured power-down bits.
+pub(crate) fn register_control_word(config: &Config) -> ControlBit {
+    types::control_word(Axes::X.ctrl_bits(), Resolution::Bits12, power_mode(config))
+}
+
+/// Selects the power-down mode of the touch screen conversions.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the driver.
+///
+/// # Returns
+///
+/// The power-down mode keeping PENIRQ enabled if the interrupt pin is used, or everything powered
+/// otherwise.
+fn power_mode(config: &Config) -> PowerMode {
+    if config.irq_on {
+        PowerMode::PowerDown
+    } else {
+        PowerMode::AlwaysOn
+    }
+}
+
+/// Builds the control word of a touch screen conversion.
+///
+/// # Arguments
+///
+/// * `axis` - The axis to convert.
+/// * `config` - The configuration of the driver.
+///
+/// # Returns
+///
+/// The control word with the configured power-down and measurement mode bits.
+pub(crate) fn axis_control_word(axis: Axes, config: &Config) -> ControlBit {
+    let power = match (config.measurement_mode, power_mode(config)) {
+        // Internal reference on, PENIRQ unaffected.
+        (
+            MeasurementMode::SingleEnded {
+                internal_reference: true,
+            },
+            PowerMode::PowerDown,
+        ) => PowerMode::ReferenceOn,
+        (_, power) => power,
+    };
+    let control_word = types::control_word(axis.ctrl_bits(), Resolution::Bits12, power);
+    match config.measurement_mode {
+        MeasurementMode::SingleEnded { .. } => control_word | ControlBit::SER,
+        MeasurementMode::Differential => control_word,
+    }
+}
+
+/// Builds the control word of a single-ended conversion against the internal reference.
+///
+/// # Arguments
+///
+/// * `channel` - The channel to convert.
+///
+/// # Returns
+///
+/// The control word with the internal reference and the ADC powered.
+pub(crate) fn single_ended_control_word(channel: AuxChannel) -> ControlBit {
+    // The internal reference and the ADC stay on.
+    types::control_word(channel.ctrl_bits(), Resolution::Bits12, PowerMode::AlwaysOn)
+}
+
+/// Decodes the 12 bit result of a conversion from the bytes read after the control byte.
+///
+/// # Arguments
+///
+/// * `buf` - The two bytes read.
+///
+/// # Returns
+///
+/// The result of the conversion.
+pub(crate) fn decode(buf: &[u8; 2]) -> u16 {
+    (((buf[0] as u16) << 8 | buf[1] as u16) >> 3) & 0xFFF
+}
+
+/// Computes the confidence of a position from the spread of its conversions.
+///
+/// # Arguments
+///
+/// * `spread` - The worst spread of the X and Y conversions.
+///
+/// # Returns
+///
+/// The confidence, ranging from 0.0 (unreliable) to 1.0 (reliable).
+pub(crate) fn confidence(spread: u16) -> f32 {
+    1.0 - spread.min(CONFIDENCE_SPREAD_LIMIT) as f32 / CONFIDENCE_SPREAD_LIMIT as f32
+}
+
+/// Selects the median of three conversions of the same channel.
+///
+/// # Arguments
+///
+/// * `values` - The three conversions.
+///
+/// # Returns
+///
+/// The median and the spread (maximum minus minimum) of the conversions.
+pub(crate) fn median(mut values: [u16; 3]) -> (u16, u16) {
+    values.sort_unstable();
+    let [low, middle, high] = values;
+    (middle, high.saturating_sub(low))
+}
+
+/// Selects the result of three conversions of the same channel by majority.
+///
+/// # Arguments
+///
+/// * `votes` - The three conversions.
+/// * `tolerance` - The largest difference between two agreeing conversions.
+///
+/// # Returns
+///
+/// The average of the closest pair of conversions and their difference, or `None` if no pair
+/// agrees within the tolerance.
+pub(crate) fn vote(votes: [u16; 3], tolerance: u16) -> Option<(u16, u16)> {
+    [
+        (votes[0], votes[1]),
+        (votes[0], votes[2]),
+        (votes[1], votes[2]),
+    ]
+    .into_iter()
+    .map(|(a, b)| (a.midpoint(b), a.abs_diff(b)))
+    .min_by_key(|&(_, difference)| difference)
+    .filter(|&(_, difference)| difference <= tolerance)
+}
+
+/// How the conversions of a reading are combined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combine {
+    /// The conversions are averaged.
+    Average,
+    /// The median of three conversions is selected.
+    Median,
+    /// The result of three conversions is selected by majority, within a tolerance.
+    Vote(u16),
+}
+
+/// Accumulator of the repeated conversions of a single channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Reading {
+    /// How the conversions are combined.
+    combine: Combine,
+    /// The number of conversions of the reading.
+    count: u16,
+    /// The number of conversions received so far.
+    received: u16,
+    /// The sum of the conversions, on 32 bits, which cannot overflow below 2^20 conversions.
+    sum: u32,
+    /// The smallest conversion.
+    min: u16,
+    /// The largest conversion.
+    max: u16,
+    /// The first three conversions, for the median and the majority vote.
+    values: [u16; 3],
+}
+
+impl Reading {
+    /// Creates a reading according to an acquisition mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The acquisition mode.
+    /// * `samples` - The number of conversions averaged by [`AcquisitionMode::Averaging`]. The
+    ///   other modes always take three conversions.
+    ///
+    /// # Returns
+    ///
+    /// The empty reading.
+    pub(crate) fn new(mode: AcquisitionMode, samples: u16) -> Self {
+        let (combine, count) = match mode {
+            AcquisitionMode::Averaging => (Combine::Average, samples),
+            AcquisitionMode::Median => (Combine::Median, 3),
+            AcquisitionMode::MajorityVote { tolerance } => (Combine::Vote(tolerance), 3),
+        };
+        Self {
+            combine,
+            count,
+            received: 0,
+            sum: 0,
+            min: u16::MAX,
+            max: u16::MIN,
+            values: [0; 3],
+        }
+    }
+
+    /// Returns the number of conversions still missing.
+    pub(crate) fn remaining(&self) -> u16 {
+        self.count.saturating_sub(self.received)
+    }
+
+    /// Checks whether the conversions may be issued in bursts, several per transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// `true` if burst oversampling is enabled, the conversions are not interleaved and they are
+    /// averaged.
+    pub(crate) fn bursts(&self, config: &Config) -> bool {
+        config.burst_oversampling && !config.interleaved && self.combine == Combine::Average
+    }
+
+    /// Adds the result of a conversion to the reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The result of the conversion.
+    pub(crate) fn feed(&mut self, value: u16) {
+        if let Some(slot) = self.values.get_mut(self.received as usize) {
+            *slot = value;
+        }
+        self.received = self.received.saturating_add(1);
+        self.sum = self.sum.saturating_add(value as u32);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Combines the conversions received.
+    ///
+    /// # Returns
+    ///
+    /// The value and the spread (maximum minus minimum) of the conversions, or `None` if they were
+    /// rejected by the majority vote.
+    pub(crate) fn result(&self) -> Option<(u16, u16)> {
+        match self.combine {
+            Combine::Average => {
+                let average = self
+                    .sum
+                    .checked_div(self.received as u32)
+                    .unwrap_or_default();
+                Some((average as u16, self.max.saturating_sub(self.min)))
+            }
+            Combine::Median => Some(median(self.values)),
+            Combine::Vote(tolerance) => vote(self.values, tolerance),
+        }
+    }
+}
+
+/// Reads a channel according to an acquisition mode.
+///
+/// # Arguments
+///
+/// * `mode` - The acquisition mode.
+/// * `samples` - The number of conversions averaged by [`AcquisitionMode::Averaging`]. The other
+///   modes always take three conversions.
+/// * `read` - The function performing a single conversion.
+///
+/// # Returns
+///
+/// A `Result` containing the value and the spread of the conversions, `None` if they were
+/// rejected by the majority vote, or an error if any of the conversions fails.
+#[cfg(feature = "aux")]
+pub(crate) fn acquire<E>(
+    mode: AcquisitionMode,
+    samples: u16,
+    mut read: impl FnMut() -> Result<u16, E>,
+) -> Result<Option<(u16, u16)>, E> {
+    let mut reading = Reading::new(mode, samples);
+    while reading.remaining() > 0 {
+        reading.feed(read()?);
+    }
+    Ok(reading.result())
+}
+
+/// Checks whether a conversion sits at one of the rails of the ADC.
+///
+/// # Arguments
+///
+/// * `value` - The result of the conversion.
+///
+/// # Returns
+///
+/// `true` if the result is 0 or full scale.
+pub(crate) fn at_rail(value: u16) -> bool {
+    value == 0 || value == 0xFFF
+}
+
+/// Applies the plausible range of an axis to a position.
+///
+/// # Arguments
+///
+/// * `value` - The raw position.
+/// * `range` - The plausible range of the axis, if restricted.
+/// * `config` - The configuration of the driver.
+///
+/// # Returns
+///
+/// The position, clamped according to the configured policy, or `None` if it is rejected.
+pub(crate) fn constrain(value: u16, range: Option<AxisRange>, config: &Config) -> Option<u16> {
+    let Some(range) = range else {
+        return Some(value);
+    };
+    match config.out_of_range {
+        _ if (range.min..=range.max).contains(&value) => Some(value),
+        OutOfRangePolicy::Reject => None,
+        OutOfRangePolicy::Clamp => Some(value.clamp(range.min, range.max.max(range.min))),
+    }
+}
+
+/// Stretches a point of the active area to the full raw range.
+///
+/// # Arguments
+///
+/// * `point` - The touch point, inside the active area.
+/// * `area` - The active area.
+///
+/// # Returns
+///
+/// The touch point with coordinates between 0 and 4095.
+pub(crate) fn map_active_area(point: &TouchPoint, area: &Rect) -> TouchPoint {
+    let stretch = |value: u16, min: u16, max: u16| {
+        let offset = value.saturating_sub(min) as u32;
+        let extent = NonZeroU32::new(max.saturating_sub(min) as u32).unwrap_or(NonZeroU32::MIN);
+        (offset.saturating_mul(0xFFF) / extent).min(0xFFF) as u16
+    };
+    TouchPoint {
+        x: stretch(point.x, area.x_min, area.x_max),
+        y: stretch(point.y, area.y_min, area.y_max),
+        z: point.z,
+    }
+}
+
+/// Builds a sample without pressure, from the position alone.
+///
+/// # Arguments
+///
+/// * `x_raw` - The raw X position.
+/// * `y_raw` - The raw Y position.
+/// * `spread` - The worst spread of the X and Y conversions.
+///
+/// # Returns
+///
+/// The degraded touch sample.
+pub(crate) fn degraded(x_raw: u16, y_raw: u16, spread: u16) -> TouchSample {
+    TouchSample {
+        point: TouchPoint {
+            x: x_raw,
+            y: y_raw,
+            z: f32::NAN,
+        },
+        confidence: confidence(spread),
+        quality: SampleQuality::Degraded,
+    }
+}
+
+/// Computes the pressure below which a touch is marginal.
+///
+/// # Arguments
+///
+/// * `semantics` - The domain of the pressure.
+/// * `threshold` - The effective touch threshold, in the domain.
+///
+/// # Returns
+///
+/// The threshold moved towards the firm side by [`MARGINAL_PRESSURE_RATIO`] of its raw ratio, which
+/// keeps it within the domain.
+fn marginal_threshold(semantics: PressureSemantics, threshold: f32) -> f32 {
+    let threshold = threshold.max(f32::MIN_POSITIVE);
+    match semantics {
+        PressureSemantics::RawRatio => threshold * MARGINAL_PRESSURE_RATIO,
+        PressureSemantics::Inverted => semantics.from_raw(MARGINAL_PRESSURE_RATIO / threshold),
+        PressureSemantics::Normalized => {
+            semantics.from_raw((1.0 / threshold - 1.0) * MARGINAL_PRESSURE_RATIO)
+        }
+    }
+}
+
+/// Finds the reported position of a touch.
+///
+/// # Arguments
+///
+/// * `position` - The raw X and Y positions.
+/// * `config` - The configuration of the driver.
+///
+/// # Returns
+///
+/// The position constrained to the axis ranges, or `None` if it is rejected by the axis ranges,
+/// lies in an ignore region or outside of the active area.
+pub(crate) fn locate((x_raw, y_raw): (u16, u16), config: &Config) -> Option<(u16, u16)> {
+    let x = constrain(x_raw, config.x_range, config)?;
+    let y = constrain(y_raw, config.y_range, config)?;
+    let ignored = config
+        .ignore_regions
+        .iter()
+        .flatten()
+        .any(|region| region.contains(x, y));
+    let outside = config.active_area.is_some_and(|area| !area.contains(x, y));
+    (!ignored && !outside).then_some((x, y))
+}
+
+/// Turns the raw readings of a touch into a touch sample.
+///
+/// # Arguments
+///
+/// * `position` - The raw X and Y positions.
+/// * `spread` - The worst spread of the X and Y conversions.
+/// * `pressure` - The raw Z1 and Z2 values.
+/// * `threshold` - The effective touch threshold.
+/// * `config` - The configuration of the driver.
+///
+/// # Returns
+///
+/// The outcome of the evaluation, following the configured zero pressure policy and arithmetic
+/// mode.
+pub(crate) fn evaluate(
+    (x_raw, y_raw): (u16, u16),
+    spread: u16,
+    (z1_raw, z2_raw): (u16, u16),
+    threshold: f32,
+    config: &Config,
+) -> Evaluation {
+    // The clamped value only serves the pressure calculation, the rail check sees the conversion.
+    let z1_divisor = match (z1_raw, config.zero_pressure) {
+        (0, ZeroPressurePolicy::NoTouch) => return Evaluation::NoTouch,
+        (0, ZeroPressurePolicy::Clamp) => 1,
+        (0, ZeroPressurePolicy::InvalidReading) => return Evaluation::Invalid(Channel::Z1),
+        (z1_raw, _) => z1_raw,
+    };
+    let z_value = x_raw as f32 / 4096_f32 * (z2_raw as f32 / z1_divisor as f32 - 1.0f32);
+    let z_value = match config.arithmetic {
+        _ if z_value >= 0.0 => z_value,
+        ArithmeticMode::Saturating => 0.0,
+        ArithmeticMode::Checked => return Evaluation::Invalid(Channel::Z2),
+    };
+    let semantics = config.pressure_semantics;
+    let z_value = semantics.from_raw(z_value);
+    if !semantics.is_firmer(z_value, threshold) {
+        return Evaluation::NoTouch;
+    }
+    let quality = if [x_raw, y_raw, z1_raw, z2_raw].into_iter().any(at_rail) {
+        SampleQuality::Railed
+    } else if spread > HIGH_VARIANCE_SPREAD {
+        SampleQuality::HighVariance
+    } else if !semantics.is_firmer(z_value, marginal_threshold(semantics, threshold)) {
+        SampleQuality::MarginalPressure
+    } else {
+        SampleQuality::Good
+    };
+    Evaluation::Touch(TouchSample {
+        point: TouchPoint {
+            x: x_raw,
+            y: y_raw,
+            z: z_value,
+        },
+        confidence: confidence(spread),
+        quality,
+    })
+}
+
+/// State of the driver shared by the blocking and the async flavor.
+///
+/// The flavors hold the bus, the pins and the delay provider, and pass the bytes and pin levels
+/// through the methods below, so both apply the configuration, gate the sampling and report the
+/// exchanges in the same way.
+pub(crate) struct Core {
+    /// The configuration of the driver.
+    pub(crate) config: Config,
+    /// Whether acquisitions are suppressed or not.
+    pub(crate) inhibited: bool,
+    /// The offset applied to the touch threshold by the temperature compensation.
+    pub(crate) threshold_offset: f32,
+    /// The number of scans since the control register was last sent.
+    pub(crate) scans_since_refresh: u16,
+    /// The health information collected while operating.
+    pub(crate) diagnostics: Diagnostics,
+    /// The most recent SPI exchanges.
+    #[cfg(feature = "trace")]
+    pub(crate) trace: crate::trace::TraceBuffer,
+}
+
+impl Core {
+    /// Creates the state of a new driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the driver, applied with [`Core::apply_config`] before
+    ///   the first command is sent.
+    pub(crate) fn new(config: Config) -> Self {
+        Self {
+            config,
+            inhibited: false,
+            threshold_offset: 0.0,
+            scans_since_refresh: 0,
+            diagnostics: Diagnostics::default(),
+            #[cfg(feature = "trace")]
+            trace: crate::trace::TraceBuffer::default(),
+        }
+    }
+
+    /// Applies a configuration, before the control register is sent.
+    ///
+    /// The settings are brought within the limits of the driver, the trace buffer is resized
+    /// and the temperature compensation offset is cleared if the compensation is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to apply.
+    pub(crate) fn apply_config(&mut self, config: Config) {
+        self.config = normalize(config);
+        #[cfg(feature = "trace")]
+        if self.trace.capacity() != self.config.trace_capacity {
+            self.trace = crate::trace::TraceBuffer::with_capacity(self.config.trace_capacity);
+        }
+        if self.config.threshold_compensation.is_none() {
+            self.threshold_offset = 0.0;
+        }
+    }
+
+    /// Checks whether an acquisition may take place, recording the refusal in the diagnostics.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the driver is not inhibited and the sampling gate, if any,
+    /// allows sampling, or [`Error::Inhibited`].
+    pub(crate) fn check_sampling<E: embedded_hal::spi::Error>(&mut self) -> Result<(), Error<E>> {
+        if !self.inhibited && self.config.sampling_gate.is_none_or(|gate| gate()) {
+            return Ok(());
+        }
+        self.diagnostics.record(Err(Error::Inhibited))
+    }
+
+    /// Starts a touch scan, counting it towards the refresh interval of the control register.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the scan, or [`Error::Inhibited`] if sampling is not allowed.
+    pub(crate) fn start_scan<E: embedded_hal::spi::Error>(&mut self) -> Result<Scan, Error<E>> {
+        self.check_sampling()?;
+        let refresh = self.config.refresh_interval != 0 && {
+            self.scans_since_refresh = self.scans_since_refresh.saturating_add(1);
+            self.scans_since_refresh >= self.config.refresh_interval
+        };
+        let threshold = self.config.touch_threshold + self.threshold_offset;
+        Ok(Scan::new(threshold, refresh))
+    }
+
+    /// Records that the control register was sent.
+    pub(crate) fn registered(&mut self) {
+        self.scans_since_refresh = 0;
+    }
+
+    /// Passes a control byte and the decoded result to the observer, if any, and to the trace
+    /// buffer if enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The control byte sent.
+    /// * `result` - The decoded result of the conversion.
+    pub(crate) fn observe(&mut self, command: u8, result: u16) {
+        #[cfg(feature = "trace")]
+        self.trace.push(command, result);
+        if let Some(observer) = self.config.observer {
+            observer(command, result);
+        }
+    }
+
+    /// Builds the frame of a single conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word of the conversion.
+    ///
+    /// # Returns
+    ///
+    /// The frame, with room for the configured padding.
+    pub(crate) fn frame(&self, control_word: ControlBit) -> Frame {
+        Frame {
+            command: [control_word.bits()],
+            response: [0; 2 + MAX_PADDING_BYTES],
+            len: self.frame_len(),
+        }
+    }
+
+    /// Returns the length of the frame read after a control byte: the result and the padding.
+    pub(crate) fn frame_len(&self) -> usize {
+        (self.config.padding_bytes as usize)
+            .min(MAX_PADDING_BYTES)
+            .saturating_add(2)
+    }
+
+    /// Decodes the result of a transferred frame and reports the exchange.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The transferred frame.
+    ///
+    /// # Returns
+    ///
+    /// The 12 bit result of the conversion.
+    pub(crate) fn complete(&mut self, frame: &Frame) -> u16 {
+        let [command] = frame.command;
+        let [high, low, ..] = frame.response;
+        let result = decode(&[high, low]);
+        self.observe(command, result);
+        result
+    }
+
+    /// Builds the frames of a burst of identical conversions.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_word` - The control word of the conversions.
+    /// * `count` - The number of conversions, at most [`MAX_BURST_LEN`].
+    ///
+    /// # Returns
+    ///
+    /// The frames, with room for the configured padding.
+    pub(crate) fn burst(&self, control_word: ControlBit, count: u16) -> Burst {
+        Burst {
+            command: [control_word.bits()],
+            responses: [[0; 2 + MAX_PADDING_BYTES]; MAX_BURST_LEN as usize],
+            len: self.frame_len(),
+            count: usize::from(count.min(MAX_BURST_LEN)),
+        }
+    }
+
+    /// Decodes the results of a transferred burst and reports the exchanges.
+    ///
+    /// # Arguments
+    ///
+    /// * `burst` - The transferred burst.
+    /// * `feed` - The function receiving the result of every conversion.
+    pub(crate) fn complete_burst(&mut self, burst: &Burst, mut feed: impl FnMut(u16)) {
+        let [command] = burst.command;
+        for [high, low, ..] in burst.responses.iter().take(burst.count) {
+            let result = decode(&[*high, *low]);
+            self.observe(command, result);
+            feed(result);
+        }
+    }
+}
+
+/// SPI frame of a single conversion: the control byte, then the result and the padding.
+#[derive(Debug)]
+pub(crate) struct Frame {
+    /// The control byte.
+    command: [u8; 1],
+    /// The result of the conversion, followed by the padding.
+    response: [u8; 2 + MAX_PADDING_BYTES],
+    /// The number of bytes read after the control byte.
+    len: usize,
+}
+
+impl Frame {
+    /// Returns the channel converted by the frame.
+    pub(crate) fn channel(&self) -> Channel {
+        let [command] = self.command;
+        Channel::from_control_word(&ControlBit::from_bits_retain(command))
+    }
+
+    /// Builds the SPI operations of the frame.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the write of the control byte and the read of the response, or
+    /// [`Error::FrameOverflow`] if the response does not fit the frame.
+    pub(crate) fn operations<E>(&mut self) -> Result<[Operation<'_, u8>; 2], Error<E>> {
+        let channel = self.channel();
+        let response = self
+            .response
+            .get_mut(..self.len)
+            .ok_or(Error::FrameOverflow(channel))?;
+        Ok([Operation::Write(&self.command), Operation::Read(response)])
+    }
+}
+
+/// SPI frames of a burst of identical conversions, issued in a single transaction.
+#[derive(Debug)]
+pub(crate) struct Burst {
+    /// The control byte, sent before every conversion.
+    command: [u8; 1],
+    /// The result of every conversion, followed by the padding.
+    responses: [[u8; 2 + MAX_PADDING_BYTES]; MAX_BURST_LEN as usize],
+    /// The number of bytes read after every control byte.
+    len: usize,
+    /// The number of conversions.
+    count: usize,
+}
+
+impl Burst {
+    /// Returns the channel converted by the burst.
+    pub(crate) fn channel(&self) -> Channel {
+        let [command] = self.command;
+        Channel::from_control_word(&ControlBit::from_bits_retain(command))
+    }
+
+    /// Returns the number of SPI operations of the burst: a write and a read per conversion.
+    pub(crate) fn operation_count(&self) -> usize {
+        self.count.saturating_mul(2)
+    }
+
+    /// Builds the SPI operations of the burst, of which the first
+    /// [`operation_count`](Self::operation_count) are used.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the write of the control byte and the read of the response of every
+    /// frame, or [`Error::FrameOverflow`] if the response does not fit the frames.
+    pub(crate) fn operations<E>(
+        &mut self,
+    ) -> Result<[Operation<'_, u8>; 2 * MAX_BURST_LEN as usize], Error<E>> {
+        let channel = self.channel();
+        let mut operations: [Operation<'_, u8>; 2 * MAX_BURST_LEN as usize] =
+            core::array::from_fn(|_| Operation::Write(&self.command));
+        for (pair, response) in operations
+            .chunks_exact_mut(2)
+            .zip(self.responses.iter_mut())
+        {
+            if let [_, read] = pair {
+                let response = response
+                    .get_mut(..self.len)
+                    .ok_or(Error::FrameOverflow(channel))?;
+                *read = Operation::Read(response);
+            }
+        }
+        Ok(operations)
+    }
+}
+
+/// Step of a touch scan, performed by a front-end of the driver.
+#[derive(Debug)]
+pub(crate) enum ScanStep<E> {
+    /// Send the control register, then carry on with the scan.
+    Refresh,
+    /// Read the PENIRQ pin and pass the pen state to [`Scan::pen`].
+    ReadPen,
+    /// Convert a channel `count` times and pass every result to [`Scan::feed`]. If a conversion
+    /// fails, [`Scan::recover`] tells whether the scan still produces a sample.
+    Convert {
+        /// The control word of the conversions.
+        control_word: ControlBit,
+        /// The number of conversions.
+        count: u16,
+        /// Whether several conversions may share a transaction.
+        burst: bool,
+    },
+    /// The scan is complete, with its result.
+    Done(Result<Option<TouchSample>, Error<E>>),
+}
+
+/// Reading of a touch scan in progress.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    /// Z1, read before the position by [`ScanOrder::PressureFirst`].
+    EarlyZ1,
+    /// Z2, read before the position by [`ScanOrder::PressureFirst`].
+    EarlyZ2,
+    /// The X position.
+    X,
+    /// The Y position.
+    Y,
+    /// Z1, read after the position.
+    Z1,
+    /// Z2, read after the position.
+    Z2,
+}
+
+impl Target {
+    /// Returns the axis converted by the reading.
+    fn axis(self) -> Axes {
+        match self {
+            Target::EarlyZ1 | Target::Z1 => Axes::Z1,
+            Target::EarlyZ2 | Target::Z2 => Axes::Z2,
+            Target::X => Axes::X,
+            Target::Y => Axes::Y,
+        }
+    }
+}
+
+/// Phase of a touch scan.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    /// The control register has to be sent.
+    Refresh,
+    /// The pen state has to be read.
+    Pen,
+    /// The pen state is known, the conversions have not started.
+    Start,
+    /// A reading is in progress.
+    Read(Target),
+    /// A pressure conversion failed, and the scan ends with a degraded sample.
+    Degraded,
+    /// The scan is complete.
+    Done,
+}
+
+/// State machine sequencing the conversions of a touch scan.
+///
+/// The machine covers the whole scan: the periodic and automatic refresh of the control register,
+/// the early exit on a filtered PENIRQ pen-up, the scan order with its early exit, the pressure
+/// fallback, the acquisition of every channel, the partial sample policy, the axis ranges, the
+/// ignore regions and the active area. A front-end repeatedly calls [`Scan::step`] and performs
+/// the steps until the scan is done.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Scan {
+    /// The effective touch threshold.
+    threshold: f32,
+    /// The phase of the scan.
+    phase: Phase,
+    /// The reading in progress.
+    reading: Reading,
+    /// The pen state reported by PENIRQ, if available.
+    pen_down: Option<bool>,
+    /// Whether the control register was already refreshed after an anomalous sample.
+    refreshed: bool,
+    /// The pressure read before the position, with Z2 if it was read as well.
+    early_pressure: Option<(u16, Option<u16>)>,
+    /// The raw X position and the spread of its conversions.
+    x: (u16, u16),
+    /// The raw X and Y positions.
+    position: (u16, u16),
+    /// The reported position, constrained to the axis ranges.
+    located: (u16, u16),
+    /// The worst spread of the X and Y conversions.
+    spread: u16,
+    /// The raw Z1 value, read after the position.
+    z1: u16,
+    /// The raw Z2 value of the evaluated readings.
+    z2: u16,
+    /// Whether the readings have been evaluated.
+    evaluated: bool,
+}
+
+impl Scan {
+    /// Creates a new scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The effective touch threshold.
+    /// * `refresh` - Whether the control register is sent before the scan, see
+    ///   [`Core::start_scan`].
+    ///
+    /// # Returns
+    ///
+    /// The scan, about to perform its first step.
+    pub(crate) fn new(threshold: f32, refresh: bool) -> Self {
+        Self {
+            threshold,
+            phase: if refresh { Phase::Refresh } else { Phase::Pen },
+            reading: Reading::new(AcquisitionMode::Averaging, 0),
+            pen_down: None,
+            refreshed: false,
+            early_pressure: None,
+            x: (0, 0),
+            position: (0, 0),
+            located: (0, 0),
+            spread: 0,
+            z1: 0,
+            z2: 0,
+            evaluated: false,
+        }
+    }
+
+    /// Passes the pen state requested by [`ScanStep::ReadPen`].
+    ///
+    /// # Arguments
+    ///
+    /// * `pen_down` - `Some(true)` if the pen is down, `Some(false)` if it is up, or `None` if the
+    ///   pen state is not available.
+    pub(crate) fn pen(&mut self, pen_down: Option<bool>) {
+        self.pen_down = pen_down;
+        self.phase = Phase::Start;
+    }
+
+    /// Returns the raw readings of the scan, the position and the pressure it evaluated.
+    ///
+    /// # Returns
+    ///
+    /// The raw conversions, 0 for the channels the scan did not read.
+    #[cfg(feature = "replay")]
+    pub(crate) fn raw(&self) -> crate::replay::RawConversions {
+        let (x, y) = self.position;
+        crate::replay::RawConversions {
+            x,
+            y,
+            z1: self.z1,
+            z2: self.z2,
+            spread: self.spread,
+        }
+    }
+
+    /// Checks whether the raw readings of the scan have been evaluated, all channels being read.
+    #[cfg(feature = "replay")]
+    pub(crate) fn evaluated(&self) -> bool {
+        self.evaluated
+    }
+
+    /// Passes the result of a conversion requested by [`ScanStep::Convert`].
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The result of the conversion.
+    pub(crate) fn feed(&mut self, value: u16) {
+        self.reading.feed(value);
+    }
+
+    /// Determines the next step of the scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the driver.
+    /// * `diagnostics` - The diagnostics, recording the outcome of the scan.
+    ///
+    /// # Returns
+    ///
+    /// The step to perform.
+    pub(crate) fn step<E: embedded_hal::spi::Error>(
+        &mut self,
+        config: &Config,
+        diagnostics: &mut Diagnostics,
+    ) -> ScanStep<E> {
+        loop {
+            let target = match self.phase {
+                Phase::Refresh => {
+                    self.phase = Phase::Pen;
+                    return ScanStep::Refresh;
+                }
+                Phase::Pen => return ScanStep::ReadPen,
+                Phase::Start => {
+                    // The filtered PENIRQ is trusted to skip the conversions of an untouched panel.
+                    if self.pen_down == Some(false) {
+                        return self.finish(Ok(None), diagnostics);
+                    }
+                    self.early_pressure = None;
+                    match config.scan_order {
+                        ScanOrder::PressureFirst { .. } => self.begin(Target::EarlyZ1, config),
+                        ScanOrder::PositionFirst => self.begin(Target::X, config),
+                    }
+                    continue;
+                }
+                Phase::Read(target) => target,
+                Phase::Degraded => {
+                    let (x, y) = self.located;
+                    return self.report(degraded(x, y, self.spread), config, diagnostics);
+                }
+                Phase::Done => return ScanStep::Done(Ok(None)),
+            };
+            let remaining = self.reading.remaining();
+            if remaining > 0 {
+                return ScanStep::Convert {
+                    control_word: axis_control_word(target.axis(), config),
+                    count: remaining,
+                    burst: self.reading.bursts(config),
+                };
+            }
+            let Some(result) = self.reading.result() else {
+                self.phase = Phase::Done;
+                return ScanStep::Done(diagnostics.record_rejected_vote(target.axis().into()));
+            };
+            if let Some(step) = self.complete(target, result, config, diagnostics) {
+                return step;
+            }
+        }
+    }
+
+    /// Decides whether a scan whose conversion failed still produces a sample.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the driver.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the position is known and the partial sample policy allows a degraded sample,
+    /// which the next step reports, or `false` if the error ends the scan.
+    pub(crate) fn recover(&mut self, config: &Config) -> bool {
+        let pressure = matches!(self.phase, Phase::Read(Target::Z1 | Target::Z2));
+        if !pressure || config.partial_samples != PartialSamplePolicy::Degraded {
+            return false;
+        }
+        self.phase = Phase::Degraded;
+        true
+    }
+
+    /// Starts a reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The reading to start.
+    /// * `config` - The configuration of the driver.
+    fn begin(&mut self, target: Target, config: &Config) {
+        let mode = match target {
+            Target::X | Target::Y => config.acquisition,
+            _ => AcquisitionMode::Averaging,
+        };
+        self.reading = Reading::new(mode, config.oversampling);
+        self.phase = Phase::Read(target);
+    }
+
+    /// Ends the scan, recording its result.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The result of the scan.
+    /// * `diagnostics` - The diagnostics.
+    ///
+    /// # Returns
+    ///
+    /// The final step of the scan.
+    fn finish<E: embedded_hal::spi::Error>(
+        &mut self,
+        result: Result<Option<TouchSample>, Channel>,
+        diagnostics: &mut Diagnostics,
+    ) -> ScanStep<E> {
+        self.phase = Phase::Done;
+        ScanStep::Done(match result {
+            Ok(Some(sample)) => {
+                diagnostics.record_touch(sample.point.x, sample.point.y);
+                Ok(Some(sample))
+            }
+            Ok(None) => {
+                diagnostics.record_no_touch();
+                Ok(None)
+            }
+            Err(channel) => diagnostics.record(Err(Error::InvalidReading(channel))),
+        })
+    }
+
+    /// Processes a completed reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The completed reading.
+    /// * `(value, spread)` - The value and the spread of its conversions.
+    /// * `config` - The configuration of the driver.
+    /// * `diagnostics` - The diagnostics.
+    ///
+    /// # Returns
+    ///
+    /// The step to perform if the scan ends or restarts, or `None` if it carries on with the
+    /// next reading.
+    fn complete<E: embedded_hal::spi::Error>(
+        &mut self,
+        target: Target,
+        (value, spread): (u16, u16),
+        config: &Config,
+        diagnostics: &mut Diagnostics,
+    ) -> Option<ScanStep<E>> {
+        let next = match (target, config.scan_order) {
+            (Target::EarlyZ1, ScanOrder::PressureFirst { with_z2: true, .. }) => {
+                self.early_pressure = Some((value, None));
+                Target::EarlyZ2
+            }
+            (Target::EarlyZ1 | Target::EarlyZ2, ScanOrder::PressureFirst { min_z1, .. }) => {
+                let (z1, z2) = match self.early_pressure {
+                    Some((z1, None)) => (z1, Some(value)),
+                    _ => (value, None),
+                };
+                if z1 <= min_z1 || z2.is_some_and(|z2| z2 <= z1) {
+                    (self.z1, self.z2) = (z1, z2.unwrap_or_default());
+                    return Some(self.finish(Ok(None), diagnostics));
+                }
+                self.early_pressure = Some((z1, z2));
+                Target::X
+            }
+            (Target::EarlyZ1 | Target::EarlyZ2 | Target::X, _) => {
+                self.x = (value, spread);
+                Target::Y
+            }
+            (Target::Y, _) => {
+                let (x_raw, x_spread) = self.x;
+                self.position = (x_raw, value);
+                self.spread = x_spread.max(spread);
+                // Only the reported position is constrained, the pressure is computed from the
+                // raw X.
+                let Some(located) = locate(self.position, config) else {
+                    return Some(self.finish(Ok(None), diagnostics));
+                };
+                self.located = located;
+                if diagnostics.pressure_fallback && self.pen_down.is_some() {
+                    let sample = degraded(located.0, located.1, self.spread);
+                    return Some(self.report(sample, config, diagnostics));
+                }
+                match self.early_pressure {
+                    Some((z1, Some(z2))) => {
+                        return Some(self.evaluate((z1, z2), config, diagnostics))
+                    }
+                    Some((z1, None)) => {
+                        self.z1 = z1;
+                        Target::Z2
+                    }
+                    None => Target::Z1,
+                }
+            }
+            (Target::Z1, _) => {
+                self.z1 = value;
+                Target::Z2
+            }
+            (Target::Z2, _) => return Some(self.evaluate((self.z1, value), config, diagnostics)),
+        };
+        self.begin(next, config);
+        None
+    }
+
+    /// Evaluates the raw readings of the scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `pressure` - The raw Z1 and Z2 values.
+    /// * `config` - The configuration of the driver.
+    /// * `diagnostics` - The diagnostics.
+    ///
+    /// # Returns
+    ///
+    /// The step to perform: the end of the scan, or a refresh of the control register after an
+    /// anomalous sample.
+    fn evaluate<E: embedded_hal::spi::Error>(
+        &mut self,
+        (z1_raw, z2_raw): (u16, u16),
+        config: &Config,
+        diagnostics: &mut Diagnostics,
+    ) -> ScanStep<E> {
+        (self.z1, self.z2) = (z1_raw, z2_raw);
+        self.evaluated = true;
+        if self.pen_down == Some(true) {
+            diagnostics.record_pressure(at_rail(z1_raw) || at_rail(z2_raw));
+        }
+        let (x, y) = self.located;
+        match evaluate(
+            self.position,
+            self.spread,
+            (z1_raw, z2_raw),
+            self.threshold,
+            config,
+        ) {
+            Evaluation::Touch(sample) => {
+                let sample = TouchSample {
+                    point: TouchPoint {
+                        x,
+                        y,
+                        ..sample.point
+                    },
+                    ..sample
+                };
+                self.report(sample, config, diagnostics)
+            }
+            Evaluation::NoTouch => self.finish(Ok(None), diagnostics),
+            Evaluation::Invalid(channel) => self.finish(Err(channel), diagnostics),
+        }
+    }
+
+    /// Reports a touch, unless it suggests that the chip lost its state and the control register
+    /// is refreshed automatically, in which case the scan is repeated once after the refresh. The
+    /// discarded touch is not recorded in the diagnostics, and the readings becoming stuck trigger
+    /// the refresh only once, not on every stuck scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - The touch sample, in the raw range.
+    /// * `config` - The configuration of the driver.
+    /// * `diagnostics` - The diagnostics.
+    ///
+    /// # Returns
+    ///
+    /// The step to perform.
+    fn report<E: embedded_hal::spi::Error>(
+        &mut self,
+        sample: TouchSample,
+        config: &Config,
+        diagnostics: &mut Diagnostics,
+    ) -> ScanStep<E> {
+        let anomalous = sample.quality == SampleQuality::Railed
+            || diagnostics.becomes_stuck(sample.point.x, sample.point.y);
+        if config.auto_refresh && anomalous && !self.refreshed {
+            self.refreshed = true;
+            self.phase = Phase::Pen;
+            return ScanStep::Refresh;
+        }
+        match self.finish(Ok(Some(sample)), diagnostics) {
+            ScanStep::Done(Ok(Some(sample))) => ScanStep::Done(Ok(Some(report(sample, config)))),
+            step => step,
+        }
+    }
+}
+
+/// Maps a reported sample to the active area, if configured.
+///
+/// # Arguments
+///
+/// * `sample` - The touch sample, in the raw range.
+/// * `config` - The configuration of the driver.
+///
+/// # Returns
+///
+/// The touch sample as reported by the driver.
+fn report(sample: TouchSample, config: &Config) -> TouchSample {
+    match config.active_area {
+        Some(area) => TouchSample {
+            point: map_active_area(&sample.point, &area),
+            ..sample
+        },
+        None => sample,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let config = Config::default();
+        assert_eq!(
+            evaluate((2048, 1000), 0, (500, 1000), 2.0, &config),
+            Evaluation::Touch(TouchSample {
+                point: TouchPoint {
+                    x: 2048,
+                    y: 1000,
+                    z: 0.5,
+                },
+                confidence: 1.0,
+                quality: SampleQuality::Good,
+            })
+        );
+        assert_eq!(
+            evaluate((2048, 1000), 0, (100, 4000), 2.0, &config),
+            Evaluation::NoTouch
+        );
+        assert_eq!(
+            evaluate((2048, 1000), 0, (0, 600), 2.0, &config),
+            Evaluation::NoTouch
+        );
+        let config = Config {
+            pressure_semantics: PressureSemantics::Normalized,
+            ..Config::default()
+        };
+        // A raw ratio of 0.5 normalizes to 2/3, firmer than 0.5.
+        assert!(matches!(
+            evaluate((2048, 1000), 0, (500, 1000), 0.5, &config),
+            Evaluation::Touch(sample) if (sample.point.z - 2.0 / 3.0).abs() < 1e-6
+        ));
+        assert_eq!(
+            evaluate((2048, 1000), 0, (500, 1000), 0.7, &config),
+            Evaluation::NoTouch
+        );
+        assert_eq!(median([300, 100, 200]), (200, 200));
+    }
+
+    #[test]
+    fn test_marginal_threshold() {
+        assert!((marginal_threshold(PressureSemantics::RawRatio, 2.0) - 1.8).abs() < 1e-6);
+        assert!((marginal_threshold(PressureSemantics::Inverted, 0.9) - 1.0).abs() < 1e-6);
+        assert_eq!(
+            marginal_threshold(PressureSemantics::Inverted, 4000.0),
+            4096.0
+        );
+        // A firm normalized threshold stays below full scale.
+        let marginal = marginal_threshold(PressureSemantics::Normalized, 0.95);
+        assert!(marginal > 0.95 && marginal < 1.0);
+        let config = Config {
+            pressure_semantics: PressureSemantics::Normalized,
+            ..Config::default()
+        };
+        assert!(matches!(
+            evaluate((2048, 1000), 0, (1000, 1010), 0.95, &config),
+            Evaluation::Touch(sample) if sample.quality == SampleQuality::Good
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_clamped_z1_is_railed() {
+        let config = Config {
+            zero_pressure: ZeroPressurePolicy::Clamp,
+            ..Config::default()
+        };
+        // Z2 = Z1 keeps the pressure of the clamped Z1 at zero, well below the threshold.
+        assert!(matches!(
+            evaluate((2048, 1000), 0, (0, 1), 2.0, &config),
+            Evaluation::Touch(sample) if sample.quality == SampleQuality::Railed
+        ));
+    }
+
+    #[test]
+    fn test_scan_steps() {
+        let config = Config {
+            acquisition: AcquisitionMode::Averaging,
+            oversampling: 2,
+            partial_samples: PartialSamplePolicy::Degraded,
+            ..Config::default()
+        };
+        let mut diagnostics = Diagnostics::default();
+        let mut scan = Scan::new(2.0, true);
+        let mut converted = Vec::new();
+        let result = loop {
+            match scan.step::<crate::mock_peripherals::Error>(&config, &mut diagnostics) {
+                ScanStep::Refresh => converted.push(None),
+                ScanStep::ReadPen => scan.pen(Some(true)),
+                ScanStep::Convert {
+                    control_word,
+                    count,
+                    burst,
+                } => {
+                    assert_eq!(count, 2);
+                    assert!(!burst);
+                    let axis = Channel::from_control_word(&control_word);
+                    converted.push(Some(axis));
+                    match axis {
+                        // The pressure conversions fail, after the position was read.
+                        Channel::Z1 => assert!(scan.recover(&config)),
+                        _ => (0..count).for_each(|value| scan.feed(1000 + value)),
+                    }
+                }
+                ScanStep::Done(result) => break result,
+            }
+        };
+        assert_eq!(
+            converted,
+            [None, Some(Channel::X), Some(Channel::Y), Some(Channel::Z1)]
+        );
+        let sample = result.expect("Scan failed").expect("No touch detected");
+        assert_eq!((sample.point.x, sample.point.y), (1000, 1000));
+        assert_eq!(sample.quality, SampleQuality::Degraded);
+        assert_eq!(diagnostics.stats.touches, 1);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{PressureSemantics, TouchPoint};
+
+/// The smallest gain of an acceleration profile, so a misconfigured profile cannot freeze or
+/// invert the motion.
+pub const MIN_GAIN: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Struct representing the relative motion reported in trackpad mode.
+pub struct RelativeReport {
+    /// The motion along the x axis since the last report.
+    pub dx: i16,
+    /// The motion along the y axis since the last report.
+    pub dy: i16,
+    /// Whether the button is pressed or not.
+    pub button: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// Pointer acceleration profile applied to the motion in trackpad mode.
+///
+/// The gain applied to the motion depends on the speed, which is the raw distance travelled along
+/// the main axis of the motion since the last reading. The gain never drops below [`MIN_GAIN`].
+pub enum Acceleration {
+    /// Constant gain of 1.
+    #[default]
+    Flat,
+    /// Gain of `1 + factor * speed`, limited to `max_gain`.
+    Linear {
+        /// The increase of the gain per unit of speed.
+        factor: f32,
+        /// The maximum gain.
+        max_gain: f32,
+    },
+    /// Gain of `1 + linear * speed + quadratic * speed²`, limited to `max_gain`.
+    Polynomial {
+        /// The coefficient of the linear term.
+        linear: f32,
+        /// The coefficient of the quadratic term.
+        quadratic: f32,
+        /// The maximum gain.
+        max_gain: f32,
+    },
+}
+
+impl Acceleration {
+    /// Computes the gain of the profile for a speed.
+    ///
+    /// # Arguments
+    ///
+    /// * `speed` - The raw distance travelled since the last reading.
+    ///
+    /// # Returns
+    ///
+    /// The gain applied to the motion, between [`MIN_GAIN`] and the maximum gain of the profile.
+    pub fn gain(&self, speed: f32) -> f32 {
+        match *self {
+            Acceleration::Flat => 1.0,
+            Acceleration::Linear { factor, max_gain } => {
+                (1.0 + factor * speed).min(max_gain).max(MIN_GAIN)
+            }
+            Acceleration::Polynomial {
+                linear,
+                quadratic,
+                max_gain,
+            } => (1.0 + linear * speed + quadratic * speed * speed)
+                .min(max_gain)
+                .max(MIN_GAIN),
+        }
+    }
+}
+
+/// Converter turning absolute touch readings into relative motion, like a trackpad.
+#[derive(Debug)]
+pub struct Trackpad {
+    /// The raw distance corresponding to one count of motion.
+    divisor: u16,
+    /// The pressure value firmer than which the button is pressed, if enabled.
+    button_threshold: Option<f32>,
+    /// The domain of the pressure values.
+    semantics: PressureSemantics,
+    /// The acceleration profile applied to the motion.
+    acceleration: Acceleration,
+    /// The last touch point of the current contact, if any.
+    last: Option<TouchPoint>,
+    /// The accelerated raw motion not yet reported along the x axis.
+    remainder_x: f32,
+    /// The accelerated raw motion not yet reported along the y axis.
+    remainder_y: f32,
+}
+
+impl Trackpad {
+    /// Creates a new trackpad converter.
+    ///
+    /// # Arguments
+    ///
+    /// * `divisor` - The raw distance corresponding to one count of motion (a value of 0 is
+    ///   treated as 1).
+    pub fn new(divisor: u16) -> Self {
+        Self {
+            divisor: divisor.max(1),
+            button_threshold: None,
+            semantics: PressureSemantics::RawRatio,
+            acceleration: Acceleration::Flat,
+            last: None,
+            remainder_x: 0.0,
+            remainder_y: 0.0,
+        }
+    }
+
+    /// Enables pressing the button by pressing the touch screen firmly.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The pressure value firmer than which the button is pressed.
+    pub fn with_press_button(mut self, threshold: f32) -> Self {
+        self.button_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets the domain of the pressure values, which must match the semantics configured in the
+    /// driver. Firmer means lower in the raw ratio, the default, and higher in the other domains.
+    ///
+    /// # Arguments
+    ///
+    /// * `semantics` - The domain of the pressure values.
+    pub fn with_pressure_semantics(mut self, semantics: PressureSemantics) -> Self {
+        self.semantics = semantics;
+        self
+    }
+
+    /// Sets the acceleration profile applied to the motion.
+    ///
+    /// # Arguments
+    ///
+    /// * `acceleration` - The acceleration profile.
+    pub fn with_acceleration(mut self, acceleration: Acceleration) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    /// Updates the converter with the latest touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The `RelativeReport` with the motion since the last reading. Motion smaller than one count
+    /// is carried over to the next report.
+    pub fn update(&mut self, touch: Option<TouchPoint>) -> RelativeReport {
+        let Some(point) = touch else {
+            self.last = None;
+            self.remainder_x = 0.0;
+            self.remainder_y = 0.0;
+            return RelativeReport::default();
+        };
+        if let Some(last) = self.last {
+            let raw_dx = point.x as f32 - last.x as f32;
+            let raw_dy = point.y as f32 - last.y as f32;
+            let gain = self.acceleration.gain(raw_dx.abs().max(raw_dy.abs()));
+            self.remainder_x += raw_dx * gain;
+            self.remainder_y += raw_dy * gain;
+        }
+        self.last = Some(point);
+        let divisor = self.divisor as f32;
+        let dx = (self.remainder_x / divisor) as i32;
+        let dy = (self.remainder_y / divisor) as i32;
+        self.remainder_x -= dx as f32 * divisor;
+        self.remainder_y -= dy as f32 * divisor;
+        RelativeReport {
+            dx: dx.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            dy: dy.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            button: self
+                .button_threshold
+                .is_some_and(|threshold| self.semantics.is_firmer(point.z, threshold)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: u16, y: u16, z: f32) -> Option<TouchPoint> {
+        Some(TouchPoint { x, y, z })
+    }
+
+    #[test]
+    fn test_relative_motion() {
+        let mut trackpad = Trackpad::new(4);
+        assert_eq!(
+            trackpad.update(point(1000, 1000, 20.0)),
+            RelativeReport::default()
+        );
+        let report = trackpad.update(point(1010, 994, 20.0));
+        assert_eq!((report.dx, report.dy), (2, -1));
+        // The remaining motion is carried over.
+        let report = trackpad.update(point(1012, 992, 20.0));
+        assert_eq!((report.dx, report.dy), (1, -1));
+        assert_eq!(trackpad.update(None), RelativeReport::default());
+        let report = trackpad.update(point(2000, 2000, 20.0));
+        assert_eq!((report.dx, report.dy), (0, 0));
+    }
+
+    #[test]
+    fn test_press_button() {
+        let mut trackpad = Trackpad::new(1).with_press_button(10.0);
+        assert!(!trackpad.update(point(1000, 1000, 20.0)).button);
+        assert!(trackpad.update(point(1000, 1000, 5.0)).button);
+        assert!(!trackpad.update(None).button);
+        let mut trackpad = Trackpad::new(1)
+            .with_press_button(0.6)
+            .with_pressure_semantics(PressureSemantics::Normalized);
+        assert!(!trackpad.update(point(1000, 1000, 0.3)).button);
+        assert!(trackpad.update(point(1000, 1000, 0.8)).button);
+    }
+
+    #[test]
+    fn test_acceleration_profiles() {
+        assert_eq!(Acceleration::Flat.gain(100.0), 1.0);
+        let linear = Acceleration::Linear {
+            factor: 0.05,
+            max_gain: 3.0,
+        };
+        assert_eq!(linear.gain(20.0), 2.0);
+        assert_eq!(linear.gain(100.0), 3.0);
+        let polynomial = Acceleration::Polynomial {
+            linear: 0.0,
+            quadratic: 0.01,
+            max_gain: 4.0,
+        };
+        assert_eq!(polynomial.gain(10.0), 2.0);
+        let decelerating = Acceleration::Linear {
+            factor: -0.1,
+            max_gain: 3.0,
+        };
+        assert_eq!(decelerating.gain(100.0), MIN_GAIN);
+        let misconfigured = Acceleration::Linear {
+            factor: 0.05,
+            max_gain: 0.0,
+        };
+        assert_eq!(misconfigured.gain(20.0), MIN_GAIN);
+
+        let mut trackpad = Trackpad::new(2).with_acceleration(linear);
+        trackpad.update(point(1000, 1000, 20.0));
+        let slow = trackpad.update(point(1002, 1000, 20.0));
+        assert_eq!(slow.dx, 1);
+        let fast = trackpad.update(point(1022, 1000, 20.0));
+        assert_eq!(fast.dx, 20);
+    }
+}
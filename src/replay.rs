@@ -0,0 +1,332 @@
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::protocol::{self, Evaluation};
+use crate::{
+    Channel, Config, Error, OptionalDelay, OptionalInputPin, TouchPoint, TouchSample, Tsc2046,
+};
+
+/// The length of a single recorded sample in bytes.
+pub const RECORD_LEN: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Raw conversions of a single scan, before any evaluation by the driver.
+///
+/// The channels a scan did not read, as it ended early, are 0: the position and pressure after
+/// an early exit of [`ScanOrder::PressureFirst`](crate::ScanOrder::PressureFirst), the pressure
+/// of a position outside of the axis ranges, or everything if the filtered PENIRQ reported no
+/// touch.
+pub struct RawConversions {
+    /// The raw X position.
+    pub x: u16,
+    /// The raw Y position.
+    pub y: u16,
+    /// The raw Z1 pressure conversion.
+    pub z1: u16,
+    /// The raw Z2 pressure conversion.
+    pub z2: u16,
+    /// The worst spread of the X and Y conversions, 0 without oversampling.
+    pub spread: u16,
+}
+
+impl RawConversions {
+    /// Evaluates the conversions like the driver does after a scan.
+    ///
+    /// The axis ranges, ignore regions, active area, pressure semantics and touch threshold of
+    /// the configuration are applied, so a recorded trace can be replayed against other settings.
+    /// The temperature compensation of the threshold is not applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to evaluate the conversions with.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TouchSample` if the conversions register as a touch, or the
+    /// channel of an invalid reading.
+    pub fn evaluate(&self, config: &Config) -> Result<Option<TouchSample>, Channel> {
+        let Some((x, y)) = protocol::locate((self.x, self.y), config) else {
+            return Ok(None);
+        };
+        let sample = match protocol::evaluate(
+            (self.x, self.y),
+            self.spread,
+            (self.z1, self.z2),
+            config.touch_threshold,
+            config,
+        ) {
+            Evaluation::Touch(sample) => sample,
+            Evaluation::NoTouch => return Ok(None),
+            Evaluation::Invalid(channel) => return Err(channel),
+        };
+        let point = TouchPoint {
+            x,
+            y,
+            ..sample.point
+        };
+        let point = match &config.active_area {
+            Some(area) => protocol::map_active_area(&point, area),
+            None => point,
+        };
+        Ok(Some(TouchSample { point, ..sample }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Timestamped raw conversions of a recorded trace.
+pub struct TraceSample {
+    /// The raw conversions of the scan.
+    pub raw: RawConversions,
+    /// The timestamp of the scan in milliseconds.
+    pub timestamp_ms: u32,
+}
+
+/// Recorder serializing timestamped raw conversions into a user provided buffer.
+///
+/// Every sample takes [`RECORD_LEN`] bytes: the timestamp, the X, Y, Z1 and Z2 conversions and
+/// the spread of the position, in little-endian order. Recording the conversions rather than the
+/// touch points lets the replay apply different thresholds, ranges and pressure settings.
+#[derive(Debug)]
+pub struct TraceRecorder<'a> {
+    /// The buffer the samples are written to.
+    buf: &'a mut [u8],
+    /// The number of bytes written.
+    len: usize,
+}
+
+impl<'a> TraceRecorder<'a> {
+    /// Creates a new recorder.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The buffer the samples are written to.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// Records the raw conversions of a scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - The raw conversions, read with [`Tsc2046::get_raw`].
+    /// * `timestamp_ms` - The timestamp of the scan in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the sample was recorded, `false` if the buffer is full.
+    pub fn record(&mut self, raw: &RawConversions, timestamp_ms: u32) -> bool {
+        let Some(record) = self
+            .buf
+            .get_mut(self.len..self.len.saturating_add(RECORD_LEN))
+            .and_then(|record| <&mut [u8; RECORD_LEN]>::try_from(record).ok())
+        else {
+            return false;
+        };
+        record
+            .iter_mut()
+            .zip(
+                timestamp_ms.to_le_bytes().into_iter().chain(
+                    [raw.x, raw.y, raw.z1, raw.z2, raw.spread]
+                        .into_iter()
+                        .flat_map(u16::to_le_bytes),
+                ),
+            )
+            .for_each(|(byte, value)| *byte = value);
+        self.len = self.len.saturating_add(RECORD_LEN);
+        true
+    }
+
+    /// Returns the recorded trace.
+    ///
+    /// # Returns
+    ///
+    /// The part of the buffer holding the recorded samples.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buf.get(..self.len).unwrap_or_default()
+    }
+}
+
+/// Replayer reading back the samples of a trace recorded by a `TraceRecorder`.
+///
+/// The raw conversions can be evaluated with [`RawConversions::evaluate`], and the touch samples
+/// fed to the processing pipeline, like an `EventTracker` or a `GestureRecognizer`, to tune it
+/// offline against real-world traces. An incomplete sample at the end of the trace is ignored.
+#[derive(Debug, Clone)]
+pub struct TraceReplayer<'a> {
+    /// The recorded trace.
+    trace: &'a [u8],
+    /// The position of the next sample in the trace.
+    position: usize,
+}
+
+impl<'a> TraceReplayer<'a> {
+    /// Creates a new replayer.
+    ///
+    /// # Arguments
+    ///
+    /// * `trace` - The recorded trace.
+    pub fn new(trace: &'a [u8]) -> Self {
+        Self { trace, position: 0 }
+    }
+}
+
+impl Iterator for TraceReplayer<'_> {
+    type Item = TraceSample;
+
+    fn next(&mut self) -> Option<TraceSample> {
+        let record = self
+            .trace
+            .get(self.position..self.position.saturating_add(RECORD_LEN))?;
+        let record = <&[u8; RECORD_LEN]>::try_from(record).ok()?;
+        self.position = self.position.saturating_add(RECORD_LEN);
+        let [t0, t1, t2, t3, x0, x1, y0, y1, z10, z11, z20, z21, s0, s1] = *record;
+        Some(TraceSample {
+            raw: RawConversions {
+                x: u16::from_le_bytes([x0, x1]),
+                y: u16::from_le_bytes([y0, y1]),
+                z1: u16::from_le_bytes([z10, z11]),
+                z2: u16::from_le_bytes([z20, z21]),
+                spread: u16::from_le_bytes([s0, s1]),
+            },
+            timestamp_ms: u32::from_le_bytes([t0, t1, t2, t3]),
+        })
+    }
+}
+
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
+    /// Reads the raw conversions of a scan, without evaluating them.
+    ///
+    /// The scan is the one of [`Tsc2046::get_sample`], so the conversions are the ones the driver
+    /// evaluates. They are meant to be recorded with a [`TraceRecorder`], and evaluated later with
+    /// [`RawConversions::evaluate`]. Conversions evaluated as an invalid reading are returned all
+    /// the same, so their replay reproduces the error.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `RawConversions`, or an error if any of the reads fails.
+    pub fn get_raw(&mut self) -> Result<RawConversions, Error<<SPI as ErrorType>::Error>> {
+        let mut scan = self.core.start_scan()?;
+        match self.scan(&mut scan, false, || {}) {
+            Err(Error::InvalidReading(_)) if scan.evaluated() => Ok(scan.raw()),
+            Err(error) => Err(error),
+            Ok(_) => Ok(scan.raw()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "events"))]
+mod tests {
+    use super::*;
+    use crate::event::{EventKind, EventTracker};
+    use crate::mock_peripherals::FakeTsc2046;
+    use crate::AxisRange;
+
+    #[test]
+    fn test_raw_conversions_follow_scan() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 3000]);
+        fake_chip.queue(FakeTsc2046::Y, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[5]);
+        fake_chip.queue(FakeTsc2046::Z2, &[3000]);
+        let mut driver = Tsc2046::new(fake_chip, false, 2.0).expect("Could not create driver");
+        let config = Config {
+            scan_order: crate::ScanOrder::PressureFirst {
+                min_z1: 10,
+                with_z2: false,
+            },
+            ..driver.config()
+        };
+        driver.apply_config(config).expect("Could not apply config");
+        driver.spi.commands.clear();
+        // The early exit skips the position, like the scan of `get_touch`.
+        let raw = driver.get_raw().expect("Could not read the conversions");
+        assert_eq!(
+            raw,
+            RawConversions {
+                z1: 5,
+                ..RawConversions::default()
+            }
+        );
+        assert!(driver
+            .spi
+            .commands
+            .iter()
+            .all(|&command| { (command >> 4) & 0b111 == FakeTsc2046::Z1 as u8 }));
+
+        // A position outside of the axis ranges is not followed by the pressure.
+        let config = Config {
+            scan_order: crate::ScanOrder::PositionFirst,
+            x_range: Some(AxisRange { min: 0, max: 2000 }),
+            ..config
+        };
+        driver.apply_config(config).expect("Could not apply config");
+        let raw = driver.get_raw().expect("Could not read the conversions");
+        assert_eq!((raw.x, raw.y, raw.z1, raw.z2), (3000, 1000, 0, 0));
+        assert_eq!(raw.evaluate(&config), Ok(None));
+        assert_eq!(driver.get_touch(), Ok(None));
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut fake_chip = FakeTsc2046::default();
+        // The first X conversion is consumed by the initial register update.
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048, 2048, 2048]);
+        fake_chip.queue(FakeTsc2046::Y, &[1000, 1000, 1000]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500, 500, 0]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1000, 1000, 4000]);
+        let mut driver = Tsc2046::new(fake_chip, false, 2.0).expect("Could not create driver");
+        driver.set_acquisition_mode(crate::AcquisitionMode::Averaging);
+
+        let mut buf = [0_u8; 3 * RECORD_LEN + 5];
+        let mut recorder = TraceRecorder::new(&mut buf);
+        for timestamp_ms in [10, 20, 30] {
+            let raw = driver.get_raw().expect("Could not read the conversions");
+            assert!(recorder.record(&raw, timestamp_ms));
+        }
+        assert!(!recorder.record(&RawConversions::default(), 40));
+        assert_eq!(recorder.as_bytes().len(), 3 * RECORD_LEN);
+
+        let raw = RawConversions {
+            x: 2048,
+            y: 1000,
+            z1: 500,
+            z2: 1000,
+            spread: 0,
+        };
+        let mut replayer = TraceReplayer::new(&buf);
+        assert_eq!(
+            replayer.next(),
+            Some(TraceSample {
+                raw,
+                timestamp_ms: 10
+            })
+        );
+
+        let config = driver.config();
+        let mut tracker = EventTracker::new();
+        for sample in TraceReplayer::new(&buf) {
+            let touch = sample.raw.evaluate(&config).expect("Invalid reading");
+            tracker.update(touch.map(|sample| sample.point), sample.timestamp_ms);
+        }
+        let kinds: Vec<EventKind> = core::iter::from_fn(|| tracker.next_event())
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(kinds, [EventKind::Down, EventKind::Move, EventKind::Up]);
+
+        // The same trace replayed against other settings.
+        let config = Config {
+            x_range: Some(AxisRange { min: 0, max: 2000 }),
+            ..config
+        };
+        assert_eq!(raw.evaluate(&config), Ok(None));
+        let config = Config {
+            touch_threshold: 0.4,
+            ..Config::default()
+        };
+        assert_eq!(raw.evaluate(&config), Ok(None));
+    }
+}
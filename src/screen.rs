@@ -0,0 +1,256 @@
+use core::marker::PhantomData;
+
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::calibration::ScreenPoint;
+use crate::{Error, NoDelay, NoPin, OptionalDelay, OptionalInputPin, TouchPoint, Tsc2046};
+
+/// Raw bounds and axis orientation of a panel, fixed at compile time.
+///
+/// The raw edges follow the convention of [`crate::calibration::Calibration`]: they can be given
+/// in any order, so an inverted axis has its minimum above its maximum, and they refer to the raw
+/// axes after the swap.
+pub trait PanelGeometry {
+    /// Whether the raw X and Y axes are swapped, the raw Y axis running along the screen width.
+    const SWAP_XY: bool = false;
+    /// The raw x value at the left edge of the screen.
+    const X_MIN: u16;
+    /// The raw x value at the right edge of the screen.
+    const X_MAX: u16;
+    /// The raw y value at the top edge of the screen.
+    const Y_MIN: u16;
+    /// The raw y value at the bottom edge of the screen.
+    const Y_MAX: u16;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Panel geometry mapping the full raw range onto the screen, without rotation.
+pub struct FullRange;
+
+impl PanelGeometry for FullRange {
+    const X_MIN: u16 = 0;
+    const X_MAX: u16 = 4095;
+    const Y_MIN: u16 = 0;
+    const Y_MAX: u16 = 4095;
+}
+
+/// Driver flavor mapping the touch points onto a screen of `W` by `H` pixels fixed at compile
+/// time.
+///
+/// The raw bounds and the orientation of the panel `P`, by default the full raw range, are
+/// mapped onto the screen. With an active area set with [`Tsc2046::set_active_area`], the
+/// active area already spans the full raw range. The scale factors are constants, so the mapping
+/// folds into a multiplication and a division by a constant in hot sampling loops. Use a
+/// [`crate::calibration::Calibration`] on the inner driver when the mapping has to be measured at
+/// runtime.
+pub struct ScreenTsc2046<
+    SPI,
+    const W: u16,
+    const H: u16,
+    IRQ = NoPin,
+    DELAY = NoDelay,
+    P = FullRange,
+> {
+    /// The driver sampled for touches.
+    driver: Tsc2046<SPI, IRQ, DELAY>,
+    /// The geometry of the panel, only known at compile time.
+    geometry: PhantomData<P>,
+}
+
+impl<SPI, const W: u16, const H: u16, IRQ, DELAY, P: PanelGeometry>
+    ScreenTsc2046<SPI, W, H, IRQ, DELAY, P>
+{
+    /// Wraps a driver, the screen dimensions and the raw bounds being checked at compile time.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - The driver sampled for touches.
+    pub fn new(driver: Tsc2046<SPI, IRQ, DELAY>) -> Self {
+        const { assert!(W > 0 && H > 0, "the screen dimensions must not be zero") };
+        const {
+            assert!(
+                P::X_MIN != P::X_MAX && P::Y_MIN != P::Y_MAX,
+                "the raw bounds must not be empty"
+            )
+        };
+        Self {
+            driver,
+            geometry: PhantomData,
+        }
+    }
+
+    /// Maps a touch point onto the screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point read from the chip.
+    ///
+    /// # Returns
+    ///
+    /// The `ScreenPoint` with coordinates clamped to the screen, without physical coordinates.
+    pub const fn map(point: &TouchPoint) -> ScreenPoint {
+        let (x, y) = if P::SWAP_XY {
+            (point.y, point.x)
+        } else {
+            (point.x, point.y)
+        };
+        ScreenPoint {
+            x: scale::<W>(x, P::X_MIN, P::X_MAX),
+            y: scale::<H>(y, P::Y_MIN, P::Y_MAX),
+            z: point.z,
+            physical: None,
+        }
+    }
+
+    /// Returns the wrapped driver, for the settings which are not screen related.
+    pub fn driver(&mut self) -> &mut Tsc2046<SPI, IRQ, DELAY> {
+        &mut self.driver
+    }
+
+    /// Releases the wrapped driver.
+    pub fn release(self) -> Tsc2046<SPI, IRQ, DELAY> {
+        self.driver
+    }
+}
+
+impl<SPI, const W: u16, const H: u16, IRQ, DELAY, P: PanelGeometry>
+    ScreenTsc2046<SPI, W, H, IRQ, DELAY, P>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
+    /// Reads the touch point from the TSC2046 chip, mapped onto the screen.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ScreenPoint` if a touch is detected, or `None` otherwise.
+    pub fn get_touch(&mut self) -> Result<Option<ScreenPoint>, Error<<SPI as ErrorType>::Error>> {
+        Ok(self.driver.get_touch()?.map(|point| Self::map(&point)))
+    }
+}
+
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY> {
+    /// Turns the driver into the flavor mapping touch points onto a screen of `W` by `H` pixels.
+    ///
+    /// # Returns
+    ///
+    /// The `ScreenTsc2046` wrapping the driver.
+    pub fn into_screen<const W: u16, const H: u16>(self) -> ScreenTsc2046<SPI, W, H, IRQ, DELAY> {
+        ScreenTsc2046::new(self)
+    }
+
+    /// Turns the driver into the flavor mapping the raw bounds of the panel `P` onto a screen of
+    /// `W` by `H` pixels.
+    ///
+    /// # Returns
+    ///
+    /// The `ScreenTsc2046` wrapping the driver.
+    pub fn into_panel_screen<const W: u16, const H: u16, P: PanelGeometry>(
+        self,
+    ) -> ScreenTsc2046<SPI, W, H, IRQ, DELAY, P> {
+        ScreenTsc2046::new(self)
+    }
+}
+
+/// Scales a raw coordinate onto the pixels of a screen dimension.
+///
+/// # Arguments
+///
+/// * `raw` - The raw coordinate.
+/// * `from` - The raw value at the first pixel.
+/// * `to` - The raw value at the last pixel.
+///
+/// # Returns
+///
+/// The pixel coordinate, between 0 and `LEN - 1`.
+const fn scale<const LEN: u16>(raw: u16, from: u16, to: u16) -> u16 {
+    let (offset, span) = if from <= to {
+        (raw.saturating_sub(from), to.saturating_sub(from))
+    } else {
+        (from.saturating_sub(raw), from.saturating_sub(to))
+    };
+    let offset = if offset > span { span } else { offset };
+    match (offset as u32)
+        .saturating_mul((LEN as u32).saturating_sub(1))
+        .checked_div(span as u32)
+    {
+        Some(pixel) => pixel as u16,
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::FakeTsc2046;
+
+    #[test]
+    fn test_screen_mapping() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 4095]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut screen = Tsc2046::new(fake_chip, false, 100.0)
+            .expect("Could not create driver")
+            .into_screen::<320, 240>();
+        let point = screen
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert_eq!((point.x, point.y), (319, 119));
+        const ORIGIN: ScreenPoint =
+            ScreenTsc2046::<FakeTsc2046, 320, 240>::map(&TouchPoint { x: 0, y: 0, z: 1.0 });
+        assert_eq!((ORIGIN.x, ORIGIN.y), (0, 0));
+    }
+
+    /// A panel rotated by a quarter turn, the raw X axis running from the bottom to the top.
+    struct RotatedPanel;
+
+    impl PanelGeometry for RotatedPanel {
+        const SWAP_XY: bool = true;
+        const X_MIN: u16 = 300;
+        const X_MAX: u16 = 3800;
+        const Y_MIN: u16 = 3900;
+        const Y_MAX: u16 = 200;
+    }
+
+    #[test]
+    fn test_panel_geometry() {
+        type Screen = ScreenTsc2046<FakeTsc2046, 321, 241, NoPin, NoDelay, RotatedPanel>;
+        let top_left = Screen::map(&TouchPoint {
+            x: 3900,
+            y: 300,
+            z: 1.0,
+        });
+        assert_eq!((top_left.x, top_left.y), (0, 0));
+        let center = Screen::map(&TouchPoint {
+            x: 2050,
+            y: 2050,
+            z: 1.0,
+        });
+        assert_eq!((center.x, center.y), (160, 120));
+        // Positions beyond the raw edges are clamped to the screen.
+        let beyond = Screen::map(&TouchPoint {
+            x: 0,
+            y: 4095,
+            z: 1.0,
+        });
+        assert_eq!((beyond.x, beyond.y), (320, 240));
+
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 3900]);
+        fake_chip.queue(FakeTsc2046::Y, &[3800]);
+        fake_chip.queue(FakeTsc2046::Z1, &[500]);
+        fake_chip.queue(FakeTsc2046::Z2, &[600]);
+        let mut screen = Tsc2046::new(fake_chip, false, 100.0)
+            .expect("Could not create driver")
+            .into_panel_screen::<321, 241, RotatedPanel>();
+        let point = screen
+            .get_touch()
+            .expect("Could not read touch")
+            .expect("No touch detected");
+        assert_eq!((point.x, point.y), (320, 0));
+    }
+}
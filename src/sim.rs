@@ -0,0 +1,332 @@
+//! Host-side simulation of a touch panel.
+//!
+//! A [`PanelModel`] plays a script of touch actions and answers the conversions of the driver
+//! like a TSC2046 wired to a panel would, so that the processing stages (events, filters,
+//! gestures) can be regression-tested on the host with the real driver in the loop. The script
+//! advances with a [`SimClock`], which is also the delay provider handed to the driver.
+
+use core::cell::Cell;
+use core::convert::Infallible;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+
+/// The largest result of a 12 bit conversion.
+const FULL_SCALE: u16 = 0xFFF;
+/// The Z2 conversion of a touch, the Z1 conversion being derived from the pressure.
+const TOUCH_Z2: f32 = 3000.0;
+
+/// Simulated time, shared by the panel model and the code under test.
+#[derive(Debug, Default)]
+pub struct SimClock {
+    /// The elapsed time in nanoseconds.
+    now_ns: Cell<u64>,
+}
+
+impl SimClock {
+    /// Returns the elapsed time.
+    ///
+    /// # Returns
+    ///
+    /// The elapsed time in milliseconds.
+    pub fn now_ms(&self) -> u32 {
+        (self.now_ns.get() / 1_000_000) as u32
+    }
+
+    /// Advances the time.
+    ///
+    /// # Arguments
+    ///
+    /// * `ns` - The time to advance by, in nanoseconds.
+    pub fn advance_ns(&self, ns: u64) {
+        self.now_ns.set(self.now_ns.get().saturating_add(ns));
+    }
+}
+
+impl DelayNs for &SimClock {
+    fn delay_ns(&mut self, ns: u32) {
+        self.advance_ns(ns as u64);
+    }
+}
+
+/// A step of a touch scenario. The positions are raw ADC coordinates, between 0 and 4095.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// The panel is not touched.
+    Release {
+        /// The duration of the step in milliseconds.
+        duration_ms: u32,
+    },
+    /// The panel is pressed at a fixed position.
+    Press {
+        /// The X position.
+        x: u16,
+        /// The Y position.
+        y: u16,
+        /// The duration of the step in milliseconds.
+        duration_ms: u32,
+    },
+    /// The contact moves at a constant speed from the position of the previous step, or stays at
+    /// the target if the panel was not touched.
+    Drag {
+        /// The X position reached at the end of the step.
+        x: u16,
+        /// The Y position reached at the end of the step.
+        y: u16,
+        /// The duration of the step in milliseconds.
+        duration_ms: u32,
+    },
+}
+
+impl Action {
+    /// Returns the duration of the step.
+    ///
+    /// # Returns
+    ///
+    /// The duration in milliseconds.
+    fn duration_ms(&self) -> u32 {
+        match *self {
+            Action::Release { duration_ms }
+            | Action::Press { duration_ms, .. }
+            | Action::Drag { duration_ms, .. } => duration_ms,
+        }
+    }
+}
+
+/// Simulated touch panel and TSC2046, scripted with touch actions.
+///
+/// The panel is released once the script is over. The time only advances through the clock, so
+/// the driver must be given the clock as delay provider, or the clock be advanced between samples.
+pub struct PanelModel<'a> {
+    /// The touch scenario.
+    script: &'a [Action],
+    /// The clock driving the scenario.
+    clock: &'a SimClock,
+    /// The time the scenario started at, in milliseconds.
+    start_ms: u32,
+    /// The standard deviation of the noise added to the positions, in ADC counts.
+    noise_sigma: f32,
+    /// The pressure of the contacts, as computed by the driver (lower is firmer).
+    pressure: f32,
+    /// The state of the noise generator.
+    rng: u32,
+}
+
+impl<'a> PanelModel<'a> {
+    /// Creates a new panel model, without noise and with a firm pressure of 1.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `script` - The touch scenario, starting at the current time of the clock.
+    /// * `clock` - The clock driving the scenario.
+    ///
+    /// # Returns
+    ///
+    /// The panel model.
+    pub fn new(script: &'a [Action], clock: &'a SimClock) -> Self {
+        Self {
+            script,
+            clock,
+            start_ms: clock.now_ms(),
+            noise_sigma: 0.0,
+            pressure: 1.0,
+            rng: 0x2545_F491,
+        }
+    }
+
+    /// Adds Gaussian noise to the position conversions.
+    ///
+    /// # Arguments
+    ///
+    /// * `sigma` - The standard deviation of the noise in ADC counts.
+    /// * `seed` - The seed of the noise generator, making the noise reproducible.
+    ///
+    /// # Returns
+    ///
+    /// The panel model with noise.
+    pub fn with_noise(mut self, sigma: f32, seed: u32) -> Self {
+        self.noise_sigma = sigma;
+        self.rng = seed.max(1);
+        self
+    }
+
+    /// Sets the pressure of the contacts.
+    ///
+    /// # Arguments
+    ///
+    /// * `pressure` - The pressure as computed by the driver, lower values meaning firmer touches.
+    ///
+    /// # Returns
+    ///
+    /// The panel model with the given pressure.
+    pub fn with_pressure(mut self, pressure: f32) -> Self {
+        self.pressure = pressure.max(0.0);
+        self
+    }
+
+    /// Returns the position of the contact at the current time.
+    ///
+    /// # Returns
+    ///
+    /// The X and Y positions, or `None` if the panel is not touched.
+    pub fn contact(&self) -> Option<(u16, u16)> {
+        let mut elapsed_ms = self.clock.now_ms().saturating_sub(self.start_ms);
+        let mut position = None;
+        for action in self.script {
+            let duration_ms = action.duration_ms();
+            let from = position;
+            position = match *action {
+                Action::Release { .. } => None,
+                Action::Press { x, y, .. } | Action::Drag { x, y, .. } => Some((x, y)),
+            };
+            if elapsed_ms < duration_ms {
+                return match (*action, from) {
+                    (Action::Drag { x, y, .. }, Some((from_x, from_y))) => {
+                        let progress = elapsed_ms as f32 / duration_ms as f32;
+                        let lerp = |from: u16, to: u16| {
+                            (from as f32 + (to as f32 - from as f32) * progress) as u16
+                        };
+                        Some((lerp(from_x, x), lerp(from_y, y)))
+                    }
+                    _ => position,
+                };
+            }
+            elapsed_ms = elapsed_ms.saturating_sub(duration_ms);
+        }
+        None
+    }
+
+    /// Draws a sample of the standard normal distribution.
+    ///
+    /// # Returns
+    ///
+    /// The sample, approximated by the sum of twelve uniform samples.
+    fn gaussian(&mut self) -> f32 {
+        let mut sum = 0.0;
+        for _ in 0..12 {
+            // xorshift32
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 17;
+            self.rng ^= self.rng << 5;
+            sum += self.rng as f32 / u32::MAX as f32;
+        }
+        sum - 6.0
+    }
+
+    /// Adds noise to a position conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The noiseless conversion.
+    ///
+    /// # Returns
+    ///
+    /// The noisy conversion, clamped to the range of the ADC.
+    fn noisy(&mut self, value: u16) -> u16 {
+        if self.noise_sigma == 0.0 {
+            return value;
+        }
+        let noisy = value as f32 + self.gaussian() * self.noise_sigma;
+        noisy.clamp(0.0, FULL_SCALE as f32) as u16
+    }
+
+    /// Performs the conversion requested by a control byte.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The control byte.
+    ///
+    /// # Returns
+    ///
+    /// The 12 bit result of the conversion.
+    fn convert(&mut self, command: u8) -> u16 {
+        let contact = self.contact();
+        match ((command >> 4) & 0b111, contact) {
+            (0b101, Some((x, _))) => self.noisy(x),
+            (0b001, Some((_, y))) => self.noisy(y),
+            (0b011, Some((x, _))) => {
+                // z = x / 4096 * (z2 / z1 - 1), solved for z1.
+                let ratio = 1.0 + self.pressure * 4096.0 / (x.max(1) as f32);
+                ((TOUCH_Z2 / ratio) as u16).max(1)
+            }
+            (0b100, Some(_)) => TOUCH_Z2 as u16,
+            (0b100, None) => FULL_SCALE,
+            _ => 0,
+        }
+    }
+}
+
+impl ErrorType for PanelModel<'_> {
+    type Error = Infallible;
+}
+
+impl SpiDevice for PanelModel<'_> {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+        let mut result = None;
+        for operation in operations {
+            match operation {
+                Operation::Write(write) => {
+                    if let Some(&command) = write.first() {
+                        result = Some(self.convert(command));
+                    }
+                }
+                Operation::Read(read) => {
+                    read.fill(0);
+                    // Only the first read after a command carries the result.
+                    if let Some(result) = result.take() {
+                        let bytes = (result << 3).to_be_bytes();
+                        read.iter_mut()
+                            .zip(bytes)
+                            .for_each(|(byte, value)| *byte = value);
+                    }
+                }
+                Operation::Transfer(read, _) => read.fill(0),
+                Operation::TransferInPlace(buf) => buf.fill(0),
+                Operation::DelayNs(ns) => self.clock.advance_ns(*ns as u64),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "stream"))]
+mod tests {
+    use super::*;
+    use crate::Tsc2046;
+
+    const SCRIPT: [Action; 3] = [
+        Action::Release { duration_ms: 50 },
+        Action::Press {
+            x: 1000,
+            y: 2000,
+            duration_ms: 200,
+        },
+        Action::Drag {
+            x: 3000,
+            y: 2000,
+            duration_ms: 100,
+        },
+    ];
+
+    #[test]
+    fn test_scripted_stroke() {
+        let clock = SimClock::default();
+        let panel = PanelModel::new(&SCRIPT, &clock).with_noise(4.0, 7);
+        let mut driver = Tsc2046::new(panel, false, 2.0).expect("Could not create driver");
+        let mut delay = &clock;
+        let first = driver
+            .get_touch_blocking(&mut delay, 100)
+            .expect("No touch detected");
+        assert_eq!(clock.now_ms(), 50);
+        assert!(first.x.abs_diff(1000) < 30 && first.y.abs_diff(2000) < 30);
+        assert!((first.z - 1.0).abs() < 0.1);
+        let points: std::vec::Vec<_> = driver.points(&mut delay).collect();
+        let last = points
+            .last()
+            .expect("No points sampled")
+            .expect("Could not read touch");
+        // Sampled 10 ms before the end of the drag.
+        assert!(last.x.abs_diff(2800) < 30);
+        assert_eq!(clock.now_ms(), 350);
+    }
+}
@@ -0,0 +1,56 @@
+//! Stages of the processing of the touch readings, selected by type.
+//!
+//! A stage set to [`Disabled`] is compiled out entirely, so unused stages cost no flash.
+
+use crate::TouchPoint;
+
+/// Placeholder for a disabled stage.
+///
+/// Its stage implementations pass the data through unchanged and are inlined, so a disabled stage
+/// compiles to nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Disabled;
+
+/// Stage filtering the touch readings, such as in an
+/// [`EventTracker`](crate::event::EventTracker).
+pub trait FilterStage {
+    /// Filters a touch reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `touch` - The result of the latest touch reading, `None` if the screen is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The filtered reading.
+    fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint>;
+}
+
+/// Stage mapping the filtered touch points to screen space, such as in an
+/// [`EventTracker`](crate::event::EventTracker).
+pub trait CalibrationStage {
+    /// Maps a touch point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The touch point.
+    ///
+    /// # Returns
+    ///
+    /// The touch point with mapped coordinates.
+    fn calibrate(&self, point: TouchPoint) -> TouchPoint;
+}
+
+impl FilterStage for Disabled {
+    #[inline(always)]
+    fn filter(&mut self, touch: Option<TouchPoint>) -> Option<TouchPoint> {
+        touch
+    }
+}
+
+impl CalibrationStage for Disabled {
+    #[inline(always)]
+    fn calibrate(&self, point: TouchPoint) -> TouchPoint {
+        point
+    }
+}
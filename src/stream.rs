@@ -0,0 +1,351 @@
+#[cfg(feature = "async")]
+use core::future::Future;
+#[cfg(feature = "async")]
+use core::pin::Pin;
+#[cfg(feature = "async")]
+use core::task::{Context, Poll};
+
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+#[cfg(feature = "async")]
+use crate::asynch::AsyncTsc2046;
+#[cfg(feature = "async")]
+use crate::event::{
+    CalibrationStage, Disabled, EventTracker, FilterStage, GestureStage, TouchEvent,
+};
+#[cfg(feature = "async")]
+use crate::OptionalWait;
+use crate::{Error, OptionalDelay, OptionalInputPin, RoutineDelay, TouchPoint, Tsc2046};
+
+/// The interval between two samples of a stroke in microseconds.
+pub const STROKE_SAMPLE_INTERVAL_US: u32 = 10_000;
+
+/// Asynchronous source of touch events, without allocation or channels.
+///
+/// The source samples the chip with the async driver whenever no event is queued, and sleeps in
+/// between: [`STROKE_SAMPLE_INTERVAL_US`] with the delay provider of the driver during a contact,
+/// and until PENIRQ goes low between contacts, if the driver owns a PENIRQ pin implementing
+/// [`embedded_hal_async::digital::Wait`]. Without a PENIRQ pin, the source polls the chip every
+/// sample interval.
+///
+/// Events are pulled with [`EventSource::next`], or polled with `poll_next` through the
+/// [`futures_core::Stream`] returned by [`EventSource::into_stream`]. Dropping the source, or
+/// calling [`EventSource::into_tracker`] to keep the pending events and the state of the filter,
+/// calibration and gesture stages, hands the driver back.
+#[cfg(feature = "async")]
+pub struct EventSource<'a, SPI, DELAY, IRQ, F = Disabled, C = Disabled, G: GestureStage = Disabled>
+{
+    /// The driver sampled for touches.
+    driver: &'a mut AsyncTsc2046<SPI, DELAY, IRQ>,
+    /// The tracker turning samples into events.
+    tracker: EventTracker<F, C, G>,
+    /// The clock providing the timestamps of the samples, in milliseconds.
+    now_ms: fn() -> u32,
+    /// Whether the last sample detected a touch.
+    touching: bool,
+}
+
+#[cfg(feature = "async")]
+impl<'a, SPI, DELAY, IRQ, F, C, G> EventSource<'a, SPI, DELAY, IRQ, F, C, G>
+where
+    SPI: embedded_hal_async::spi::SpiDevice,
+    DELAY: embedded_hal_async::delay::DelayNs,
+    IRQ: OptionalInputPin + OptionalWait,
+    F: FilterStage,
+    C: CalibrationStage,
+    G: GestureStage,
+{
+    /// Creates a new event source.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - The driver sampled for touches.
+    /// * `tracker` - The tracker turning samples into events, with its configuration and stages.
+    /// * `now_ms` - The clock providing the timestamps of the samples, in milliseconds.
+    pub fn new(
+        driver: &'a mut AsyncTsc2046<SPI, DELAY, IRQ>,
+        tracker: EventTracker<F, C, G>,
+        now_ms: fn() -> u32,
+    ) -> Self {
+        Self {
+            driver,
+            tracker,
+            now_ms,
+            touching: false,
+        }
+    }
+
+    /// Waits for the next touch event.
+    ///
+    /// While sampling is inhibited, no sample is taken and the wait goes on.
+    ///
+    /// # Returns
+    ///
+    /// The next event, or an error if the sampling failed.
+    pub async fn next(&mut self) -> Result<TouchEvent, Error<<SPI as ErrorType>::Error>> {
+        loop {
+            if let Some(event) = self.tracker.next_event() {
+                return Ok(event);
+            }
+            self.driver.wait_for_sample(self.touching).await?;
+            match self.driver.get_touch().await {
+                Ok(touch) => {
+                    self.touching = touch.is_some();
+                    self.tracker.update(touch, (self.now_ms)());
+                }
+                Err(Error::Inhibited) => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Turns the source into a [`futures_core::Stream`] of touch events, for executors and
+    /// combinators polling with `poll_next`. The stream never ends.
+    ///
+    /// # Returns
+    ///
+    /// The stream, which has to be pinned before polling, e.g. with [`core::pin::pin!`].
+    pub fn into_stream(self) -> EventStream<Self, impl Future<Output = Advanced<SPI, Self>>> {
+        EventStream {
+            source: Some(self),
+            advance: Self::advance,
+            future: None,
+        }
+    }
+
+    /// Waits for the next touch event, passing the source along.
+    ///
+    /// # Returns
+    ///
+    /// The next event, or an error if the sampling failed, and the source.
+    async fn advance(mut self) -> Advanced<SPI, Self> {
+        let event = self.next().await;
+        (event, self)
+    }
+
+    /// Releases the tracker, with the events not yet consumed.
+    pub fn into_tracker(self) -> EventTracker<F, C, G> {
+        self.tracker
+    }
+}
+
+/// The next event of an [`EventSource`], or the error of its sampling, and the source itself.
+#[cfg(feature = "async")]
+type Advanced<SPI, S> = (Result<TouchEvent, Error<<SPI as ErrorType>::Error>>, S);
+
+/// Stream of touch events, created by [`EventSource::into_stream`].
+///
+/// Between two events, the wait for the next one is kept in the stream itself, so no allocation
+/// is needed.
+#[cfg(feature = "async")]
+pub struct EventStream<S, FUT> {
+    /// The source, while no wait is in progress.
+    source: Option<S>,
+    /// The function starting the wait for the next event.
+    advance: fn(S) -> FUT,
+    /// The wait for the next event in progress, if any.
+    future: Option<FUT>,
+}
+
+#[cfg(feature = "async")]
+impl<S, FUT, T> futures_core::Stream for EventStream<S, FUT>
+where
+    FUT: Future<Output = (T, S)>,
+{
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        // SAFETY: the wait in progress is never moved out of the pinned stream, it is only
+        // dropped in place once it completes.
+        let this = unsafe { self.get_unchecked_mut() };
+        if let Some(source) = this.source.take() {
+            this.future = Some((this.advance)(source));
+        }
+        let Some(future) = this.future.as_mut() else {
+            return Poll::Pending;
+        };
+        // SAFETY: see above, the wait stays in place until it is dropped.
+        let future = unsafe { Pin::new_unchecked(future) };
+        let Poll::Ready((item, source)) = future.poll(cx) else {
+            return Poll::Pending;
+        };
+        this.future = None;
+        this.source = Some(source);
+        Poll::Ready(Some(item))
+    }
+}
+
+/// Iterator over the touch points of a single contact, created by [`Tsc2046::points`].
+pub struct Points<'a, SPI, IRQ, DELAY, D> {
+    /// The driver sampled for touches.
+    driver: &'a mut Tsc2046<SPI, IRQ, DELAY>,
+    /// The delay provider used to wait between samples.
+    delay: &'a mut D,
+    /// Whether the contact ended, or sampling failed.
+    done: bool,
+    /// Whether the next sample is the first one of the stroke.
+    first: bool,
+}
+
+impl<'a, SPI, IRQ, DELAY, D> Points<'a, SPI, IRQ, DELAY, D> {
+    /// Creates a new iterator over the points of a contact.
+    pub(crate) fn new(driver: &'a mut Tsc2046<SPI, IRQ, DELAY>, delay: &'a mut D) -> Self {
+        Self {
+            driver,
+            delay,
+            done: false,
+            first: true,
+        }
+    }
+}
+
+impl<SPI, IRQ, DELAY, D> Iterator for Points<'_, SPI, IRQ, DELAY, D>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+    D: RoutineDelay<DELAY>,
+{
+    type Item = Result<TouchPoint, Error<<SPI as ErrorType>::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if !self.first {
+            self.delay
+                .pause_us(&mut self.driver.delay, STROKE_SAMPLE_INTERVAL_US);
+        }
+        self.first = false;
+        match self.driver.get_touch() {
+            Ok(Some(point)) => Some(Ok(point)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "async")]
+    use crate::event::EventKind;
+    #[cfg(feature = "async")]
+    use crate::mock_peripherals::{block_on, FakePenIrq};
+    use crate::mock_peripherals::{FakeTsc2046, MockDelay};
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_next_sleeps_between_samples() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        // No touch on the first sample.
+        fake_chip.queue(FakeTsc2046::Z1, &[0, 1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut driver = block_on(AsyncTsc2046::new(
+            fake_chip,
+            MockDelay::default(),
+            false,
+            100.0,
+        ))
+        .expect("Could not create driver");
+        let mut source = EventSource::new(&mut driver, EventTracker::new(), || 0);
+        let event = block_on(source.next()).expect("Could not read event");
+        assert_eq!(event.kind, EventKind::Down);
+        // Without PENIRQ, the source slept one interval before each of the two samples.
+        assert_eq!(
+            driver.into_blocking().delay.elapsed_ns,
+            2 * STROKE_SAMPLE_INTERVAL_US as u64 * 1000
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_next_awaits_pen_irq() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048, 2100]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut driver = block_on(AsyncTsc2046::new(
+            fake_chip,
+            MockDelay::default(),
+            true,
+            100.0,
+        ))
+        .expect("Could not create driver")
+        .with_irq_pin(FakePenIrq::default());
+        let mut source = EventSource::new(&mut driver, EventTracker::new(), || 0);
+        let down = block_on(source.next()).expect("Could not read event");
+        assert_eq!(down.kind, EventKind::Down);
+        let moved = block_on(source.next()).expect("Could not read event");
+        assert_eq!((moved.kind, moved.point.x), (EventKind::Move, 2100));
+        // The idle wait is on PENIRQ, the wait within the contact on the delay provider.
+        let driver = driver.into_blocking();
+        assert_eq!(driver.irq_pin.waits, 1);
+        assert_eq!(
+            driver.delay.elapsed_ns,
+            STROKE_SAMPLE_INTERVAL_US as u64 * 1000
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_stream_polls_events() {
+        use futures_core::Stream;
+
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048, 2100]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[1000]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut driver = block_on(AsyncTsc2046::new(
+            fake_chip,
+            MockDelay::default(),
+            false,
+            100.0,
+        ))
+        .expect("Could not create driver");
+        let source = EventSource::new(&mut driver, EventTracker::new(), || 0);
+        let mut stream = core::pin::pin!(source.into_stream());
+        let mut cx = Context::from_waker(core::task::Waker::noop());
+        let mut poll = || loop {
+            if let Poll::Ready(event) = stream.as_mut().poll_next(&mut cx) {
+                break event
+                    .expect("The stream ended")
+                    .expect("Could not read event");
+            }
+        };
+        assert_eq!(poll().kind, EventKind::Down);
+        let moved = poll();
+        assert_eq!((moved.kind, moved.point.x), (EventKind::Move, 2100));
+    }
+
+    #[test]
+    fn test_points_of_stroke() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 1000, 1100, 1200]);
+        fake_chip.queue(FakeTsc2046::Y, &[2048]);
+        // Released on the third sample.
+        fake_chip.queue(FakeTsc2046::Z1, &[1000, 1000, 0]);
+        fake_chip.queue(FakeTsc2046::Z2, &[1200]);
+        let mut driver = Tsc2046::new(fake_chip, false, 100.0).expect("Could not create driver");
+        let mut delay = MockDelay::default();
+        let xs: Vec<u16> = driver
+            .points(&mut delay)
+            .map(|point| point.expect("Could not read point").x)
+            .collect();
+        assert_eq!(xs, [1000, 1100]);
+        assert_eq!(
+            delay.elapsed_ns,
+            2 * STROKE_SAMPLE_INTERVAL_US as u64 * 1000
+        );
+    }
+}
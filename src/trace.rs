@@ -0,0 +1,140 @@
+/// The default number of SPI exchanges kept in the trace buffer.
+pub const TRACE_CAPACITY: usize = 16;
+/// The largest number of SPI exchanges the trace buffer can keep.
+pub const MAX_TRACE_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// A single SPI exchange with the chip.
+pub struct TraceEntry {
+    /// The control byte sent.
+    pub command: u8,
+    /// The decoded result of the conversion.
+    pub response: u16,
+}
+
+/// Ring buffer of the most recent SPI exchanges with the chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceBuffer {
+    /// The exchanges, stored circularly in the first `capacity` entries.
+    entries: [TraceEntry; MAX_TRACE_CAPACITY],
+    /// The number of exchanges kept, between 1 and [`MAX_TRACE_CAPACITY`].
+    capacity: usize,
+    /// The index of the oldest exchange.
+    head: usize,
+    /// The number of exchanges stored.
+    len: usize,
+}
+
+impl Default for TraceBuffer {
+    fn default() -> Self {
+        Self::with_capacity(TRACE_CAPACITY)
+    }
+}
+
+impl TraceBuffer {
+    /// Creates an empty trace buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of exchanges kept, clamped between 1 and
+    ///   [`MAX_TRACE_CAPACITY`].
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: [TraceEntry::default(); MAX_TRACE_CAPACITY],
+            capacity: capacity.clamp(1, MAX_TRACE_CAPACITY),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of exchanges kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Records an exchange, dropping the oldest one if the buffer is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The control byte sent.
+    /// * `response` - The decoded result of the conversion.
+    pub(crate) fn push(&mut self, command: u8, response: u16) {
+        let entry = TraceEntry { command, response };
+        if self.len < self.capacity {
+            let index = self.index(self.len);
+            if let Some(slot) = self.entries.get_mut(index) {
+                *slot = entry;
+                self.len = self.len.saturating_add(1);
+            }
+        } else if let Some(slot) = self.entries.get_mut(self.head) {
+            *slot = entry;
+            self.head = self.index(1);
+        }
+    }
+
+    /// Returns the number of exchanges stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether no exchange was recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the stored exchanges.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the exchanges, from the oldest to the most recent.
+    pub fn iter(&self) -> impl Iterator<Item = TraceEntry> + '_ {
+        (0..self.len)
+            .filter_map(move |index| self.entries.get(self.index(index)))
+            .copied()
+    }
+
+    /// Returns the position of an exchange in the ring buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset of the exchange from the oldest one.
+    fn index(&self, offset: usize) -> usize {
+        self.head
+            .wrapping_add(offset)
+            .checked_rem(self.capacity)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_buffer_wraps() {
+        let mut trace = TraceBuffer::default();
+        assert!(trace.is_empty());
+        for response in 0..TRACE_CAPACITY as u16 + 2 {
+            trace.push(0x80, response);
+        }
+        assert_eq!(trace.len(), TRACE_CAPACITY);
+        let responses: Vec<u16> = trace.iter().map(|entry| entry.response).collect();
+        assert_eq!(responses[0], 2);
+        assert_eq!(responses[TRACE_CAPACITY - 1], TRACE_CAPACITY as u16 + 1);
+    }
+
+    #[test]
+    fn test_trace_buffer_capacity() {
+        let mut trace = TraceBuffer::with_capacity(3);
+        for response in 0..5 {
+            trace.push(0x80, response);
+        }
+        let responses: Vec<u16> = trace.iter().map(|entry| entry.response).collect();
+        assert_eq!(responses, [2, 3, 4]);
+        assert_eq!(TraceBuffer::with_capacity(0).capacity(), 1);
+        assert_eq!(
+            TraceBuffer::with_capacity(usize::MAX).capacity(),
+            MAX_TRACE_CAPACITY
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::event::PressLevel;
+use crate::guided::GuidedRoutine;
+use crate::{
+    Error, OptionalDelay, OptionalInputPin, PressureSemantics, RoutineDelay, TouchSample, Tsc2046,
+};
+
+/// The interval between two samples of the tuning routine, in milliseconds.
+const TUNING_SAMPLE_INTERVAL_MS: u32 = 10;
+/// The touch threshold while measuring, as a raw ratio. It is far above the pressure values of
+/// deliberate touches, but below the values read on a released panel, where Z1 is only noise.
+const TUNING_RELEASE_THRESHOLD: f32 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Step of the pressure threshold tuning routine.
+pub enum TuningStep {
+    /// The user should touch the panel lightly.
+    TouchLightly,
+    /// The user should press the panel firmly.
+    TouchFirmly,
+    /// The measurements are complete.
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Thresholds proposed by the tuning routine.
+pub struct ThresholdProposal {
+    /// The touch threshold of the driver, registering the light touches with margin.
+    pub touch_threshold: f32,
+    /// The press level separating the firm touches from the light ones, with hysteresis.
+    pub press_level: PressLevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Range of the pressure values measured during a step.
+struct PressureRange {
+    /// The number of pressure values measured.
+    samples: u16,
+    /// The smallest pressure value, the firmest touch.
+    min: f32,
+    /// The largest pressure value, the lightest touch.
+    max: f32,
+}
+
+impl PressureRange {
+    /// An empty range.
+    const EMPTY: Self = Self {
+        samples: 0,
+        min: f32::INFINITY,
+        max: f32::NEG_INFINITY,
+    };
+
+    /// Adds a pressure value to the range.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The pressure value.
+    fn add(&mut self, z: f32) {
+        self.samples = self.samples.saturating_add(1);
+        self.min = self.min.min(z);
+        self.max = self.max.max(z);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Guided routine proposing pressure thresholds from light and firm touches of the user.
+///
+/// The pressure values of light touches, then of firm touches, are measured. The touch
+/// threshold is placed above the lightest touch, and the press level in the gap between the two
+/// distributions. The `margin` is the fraction of the lightest pressure value added to the touch
+/// threshold, and the fraction of the gap kept free on each side of the press level, at most 0.5.
+pub struct ThresholdTuner {
+    /// The number of pressure values measured per step.
+    samples_per_step: u16,
+    /// The safety margin of the proposed thresholds.
+    margin: f32,
+    /// The pressure values of the light touches.
+    light: PressureRange,
+    /// The pressure values of the firm touches.
+    firm: PressureRange,
+}
+
+impl ThresholdTuner {
+    /// Creates a new threshold tuner.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples_per_step` - The number of pressure values measured per step, at least 1.
+    /// * `margin` - The safety margin of the proposed thresholds, clamped between 0.0 and 0.5.
+    pub fn new(samples_per_step: u16, margin: f32) -> Self {
+        Self {
+            samples_per_step: samples_per_step.max(1),
+            margin: margin.clamp(0.0, 0.5),
+            light: PressureRange::EMPTY,
+            firm: PressureRange::EMPTY,
+        }
+    }
+
+    /// Returns the current step of the routine.
+    pub fn step(&self) -> TuningStep {
+        if self.light.samples < self.samples_per_step {
+            TuningStep::TouchLightly
+        } else if self.firm.samples < self.samples_per_step {
+            TuningStep::TouchFirmly
+        } else {
+            TuningStep::Done
+        }
+    }
+
+    /// Records the pressure value of a touch for the current step.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The pressure value, read with the touch threshold disabled.
+    pub fn record(&mut self, z: f32) {
+        match self.step() {
+            TuningStep::TouchLightly => self.light.add(z),
+            TuningStep::TouchFirmly => self.firm.add(z),
+            TuningStep::Done => {}
+        }
+    }
+
+    /// Proposes thresholds from the measurements.
+    ///
+    /// # Returns
+    ///
+    /// The `ThresholdProposal`, or `None` if the routine is not done, or the firm touches do not
+    /// read clearly below the light ones.
+    pub fn proposal(&self) -> Option<ThresholdProposal> {
+        if self.step() != TuningStep::Done || self.firm.max >= self.light.min {
+            return None;
+        }
+        let gap = self.light.min - self.firm.max;
+        Some(ThresholdProposal {
+            touch_threshold: self.light.max * (1.0 + self.margin),
+            press_level: PressLevel {
+                press: self.firm.max + gap * self.margin,
+                release: self.light.min - gap * self.margin,
+                semantics: PressureSemantics::RawRatio,
+            },
+        })
+    }
+}
+
+impl GuidedRoutine for ThresholdTuner {
+    type Step = TuningStep;
+
+    fn pending_step(&self) -> Option<TuningStep> {
+        Some(self.step()).filter(|step| *step != TuningStep::Done)
+    }
+
+    fn record_sample(&mut self, sample: &TouchSample) {
+        self.record(sample.point.z);
+    }
+}
+
+impl<SPI, IRQ, DELAY> Tsc2046<SPI, IRQ, DELAY>
+where
+    SPI: SpiDevice,
+    IRQ: OptionalInputPin,
+    DELAY: OptionalDelay,
+{
+    /// Runs the pressure threshold tuning routine.
+    ///
+    /// The `prompt` is called whenever the user should perform the next step. The touch
+    /// threshold is relaxed to a raw ratio of 50 while measuring, and every step only
+    /// starts measuring once the panel has been released, as told by PENIRQ if available, or by
+    /// the relaxed threshold, so a touch is never counted for the wrong step. The configured
+    /// threshold is restored afterwards, the proposal is not applied. The pressure is measured as
+    /// the raw ratio, and the proposed touch threshold and press level converted to the
+    /// configured [`PressureSemantics`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tuner` - The tuner collecting the measurements.
+    /// * `delay` - The delay provider used to wait between samples, or [`crate::OwnedDelay`].
+    /// * `timeout_ms` - The maximum duration of the routine in milliseconds.
+    /// * `prompt` - The function asking the user to perform a step.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the proposed thresholds, `None` if the light and firm touches could
+    /// not be told apart, or [`Error::Timeout`] if the routine did not complete in time.
+    pub fn tune_threshold<D: RoutineDelay<DELAY>>(
+        &mut self,
+        tuner: &mut ThresholdTuner,
+        delay: &mut D,
+        timeout_ms: u32,
+        prompt: impl FnMut(TuningStep),
+    ) -> Result<Option<ThresholdProposal>, Error<<SPI as ErrorType>::Error>> {
+        let touch_threshold = self.core.config.touch_threshold;
+        let semantics = self.core.config.pressure_semantics;
+        self.core.config.touch_threshold = TUNING_RELEASE_THRESHOLD;
+        self.core.config.pressure_semantics = PressureSemantics::RawRatio;
+        let result = self
+            .run_guided(tuner, delay, TUNING_SAMPLE_INTERVAL_MS, timeout_ms, prompt)
+            .map(|()| tuner.proposal());
+        self.core.config.touch_threshold = touch_threshold;
+        self.core.config.pressure_semantics = semantics;
+        result.map(|proposal| {
+            proposal.map(|proposal| ThresholdProposal {
+                touch_threshold: semantics.from_raw(proposal.touch_threshold),
+                press_level: PressLevel {
+                    press: semantics.from_raw(proposal.press_level.press),
+                    release: semantics.from_raw(proposal.press_level.release),
+                    semantics,
+                },
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_peripherals::{FakeTsc2046, MockDelay};
+
+    #[test]
+    fn test_tune_threshold() {
+        let mut fake_chip = FakeTsc2046::default();
+        // The first X conversion is consumed by the initial register update. With X at half
+        // scale, the pressure value is (Z2 / Z1 - 1) / 2, and a Z1 of 0 reads as released.
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[0, 100, 100, 0, 100, 100]);
+        fake_chip.queue(FakeTsc2046::Z2, &[0, 900, 1100, 0, 300, 340]);
+        let mut test_driver = Tsc2046::new(fake_chip, false, 2.0).expect("Could not create driver");
+        let mut tuner = ThresholdTuner::new(2, 0.25);
+        let proposal = test_driver
+            .tune_threshold(&mut tuner, &mut MockDelay::default(), 1000, |_| {})
+            .expect("Tuning failed")
+            .expect("No proposal");
+        assert!((proposal.touch_threshold - 6.25).abs() < 1e-4);
+        assert!((proposal.press_level.press - 1.9).abs() < 1e-4);
+        assert!((proposal.press_level.release - 3.3).abs() < 1e-4);
+        assert_eq!(test_driver.config().touch_threshold, 2.0);
+    }
+
+    #[test]
+    fn test_tune_threshold_noisy_release() {
+        let mut fake_chip = FakeTsc2046::default();
+        // A released panel reads a Z1 of a few counts, a pressure value of about 400.
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[5, 100, 100, 5, 100, 100]);
+        fake_chip.queue(FakeTsc2046::Z2, &[4000, 900, 1100, 4000, 300, 340]);
+        let mut test_driver = Tsc2046::new(fake_chip, false, 2.0).expect("Could not create driver");
+        let mut tuner = ThresholdTuner::new(2, 0.25);
+        let mut prompts = 0;
+        let proposal = test_driver
+            .tune_threshold(&mut tuner, &mut MockDelay::default(), 1000, |_| {
+                prompts += 1
+            })
+            .expect("Tuning failed")
+            .expect("No proposal");
+        assert!((proposal.touch_threshold - 6.25).abs() < 1e-4);
+        assert_eq!(prompts, 2);
+    }
+
+    #[test]
+    fn test_tune_threshold_normalized() {
+        let mut fake_chip = FakeTsc2046::default();
+        fake_chip.queue(FakeTsc2046::X, &[0, 2048]);
+        fake_chip.queue(FakeTsc2046::Z1, &[0, 100, 100, 0, 100, 100]);
+        fake_chip.queue(FakeTsc2046::Z2, &[0, 900, 1100, 0, 300, 340]);
+        let mut test_driver = Tsc2046::new(fake_chip, false, 0.2).expect("Could not create driver");
+        test_driver.set_pressure_semantics(PressureSemantics::Normalized);
+        let mut tuner = ThresholdTuner::new(2, 0.25);
+        let proposal = test_driver
+            .tune_threshold(&mut tuner, &mut MockDelay::default(), 1000, |_| {})
+            .expect("Tuning failed")
+            .expect("No proposal");
+        let level = proposal.press_level;
+        assert_eq!(level.semantics, PressureSemantics::Normalized);
+        assert!((level.press - 1.0 / 2.9).abs() < 1e-4);
+        assert!((level.release - 1.0 / 4.3).abs() < 1e-4);
+        assert_eq!(level.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_tuner_overlapping_touches() {
+        let mut tuner = ThresholdTuner::new(1, 0.1);
+        assert_eq!(tuner.step(), TuningStep::TouchLightly);
+        tuner.record(3.0);
+        assert_eq!(tuner.step(), TuningStep::TouchFirmly);
+        tuner.record(4.0);
+        assert_eq!(tuner.step(), TuningStep::Done);
+        assert_eq!(tuner.proposal(), None);
+    }
+}
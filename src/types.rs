@@ -1,6 +1,7 @@
 use bitflags::bitflags;
 
 bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct ControlBit: u8 {
         const PD0 = 0b00000001;
         const PD1 = 0b00000010;
@@ -18,8 +19,10 @@ bitflags! {
         const Z2 = Self::A2.bits();
         const XPOS = Self::A2.bits() | Self::A0.bits();
         const AUX = Self::A2.bits() | Self::A1.bits();
+        const ADDRESS = Self::A2.bits() | Self::A1.bits() | Self::A0.bits();
     }
 }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Axes {
     X,
     Y,
@@ -27,6 +30,8 @@ pub enum Axes {
     Z2,
 }
 impl Axes {
+    pub const ALL: [Axes; 4] = [Axes::X, Axes::Y, Axes::Z1, Axes::Z2];
+
     pub fn ctrl_bits(&self) -> ControlBit {
         match self {
             Axes::X => ControlBit::XPOS,
@@ -36,3 +41,141 @@ impl Axes {
         }
     }
 }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuxChannel {
+    Temp0,
+    Temp1,
+    Vbat,
+    Aux,
+}
+impl AuxChannel {
+    pub const ALL: [AuxChannel; 4] = [
+        AuxChannel::Temp0,
+        AuxChannel::Temp1,
+        AuxChannel::Vbat,
+        AuxChannel::Aux,
+    ];
+
+    pub fn ctrl_bits(&self) -> ControlBit {
+        match self {
+            AuxChannel::Temp0 => ControlBit::TEMP0,
+            AuxChannel::Temp1 => ControlBit::TEMP1,
+            AuxChannel::Vbat => ControlBit::VBAT,
+            AuxChannel::Aux => ControlBit::AUX,
+        }
+    }
+}
+
+/// Power-down mode selected by the PD1 and PD0 bits of a control word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)] // the table covers every encoding, the driver only uses some
+pub enum PowerMode {
+    /// Powered down between conversions, with PENIRQ enabled.
+    PowerDown = 0b00,
+    /// Internal reference off, ADC on, PENIRQ disabled.
+    AdcOn = 0b01,
+    /// Internal reference on, ADC off, PENIRQ enabled.
+    ReferenceOn = 0b10,
+    /// Internal reference and ADC always on, PENIRQ disabled.
+    AlwaysOn = 0b11,
+}
+
+/// Resolution selected by the MODE bit of a control word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)] // the table covers every encoding, the driver only uses some
+pub enum Resolution {
+    /// 12 bit conversions.
+    Bits12 = 0,
+    /// 8 bit conversions.
+    Bits8 = 1,
+}
+
+/// Checks whether the channel at a mux address is a touch screen channel, converted in
+/// differential mode by default.
+const fn is_touch_channel(address: usize) -> bool {
+    matches!(address, 0b001 | 0b011 | 0b100 | 0b101)
+}
+
+/// Builds the table of the control words.
+#[allow(clippy::indexing_slicing)] // the indices are bounded by the loops, checked at compile time
+const fn build_control_words() -> [[[u8; 4]; 2]; 8] {
+    let mut table = [[[0; 4]; 2]; 8];
+    let mut address = 0;
+    while address < 8 {
+        let mut resolution = 0;
+        while resolution < 2 {
+            let mut power = 0;
+            while power < 4 {
+                // The touch screen channels are ratiometric, the others single-ended.
+                let ser = if is_touch_channel(address) {
+                    0
+                } else {
+                    ControlBit::SER.bits()
+                };
+                table[address][resolution][power] = ControlBit::S.bits()
+                    | (address as u8) << 4
+                    | (resolution as u8) << 3
+                    | ser
+                    | power as u8;
+                power = power.saturating_add(1);
+            }
+            resolution = resolution.saturating_add(1);
+        }
+        address = address.saturating_add(1);
+    }
+    table
+}
+
+/// The control words, indexed by mux address, resolution and power-down mode. The touch screen
+/// channels are converted in differential mode, the other channels single-ended.
+pub const CONTROL_WORDS: [[[u8; 4]; 2]; 8] = build_control_words();
+
+/// Looks up the control word of a conversion.
+///
+/// # Arguments
+///
+/// * `channel` - The address bits of the channel, as returned by `ctrl_bits`.
+/// * `resolution` - The resolution of the conversion.
+/// * `power` - The power-down mode following the conversion.
+///
+/// # Returns
+///
+/// The control word.
+pub fn control_word(channel: ControlBit, resolution: Resolution, power: PowerMode) -> ControlBit {
+    CONTROL_WORDS
+        .get((channel.bits() >> 4) as usize & 0b111)
+        .and_then(|words| words.get(resolution as usize))
+        .and_then(|words| words.get(power as usize))
+        .map_or(ControlBit::S, |&word| ControlBit::from_bits_retain(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_words() {
+        let word = |channel, power| control_word(channel, Resolution::Bits12, power).bits();
+        assert_eq!(word(Axes::X.ctrl_bits(), PowerMode::PowerDown), 0xD0);
+        assert_eq!(word(Axes::X.ctrl_bits(), PowerMode::AlwaysOn), 0xD3);
+        assert_eq!(word(Axes::Y.ctrl_bits(), PowerMode::PowerDown), 0x90);
+        assert_eq!(word(Axes::Z1.ctrl_bits(), PowerMode::PowerDown), 0xB0);
+        assert_eq!(word(Axes::Z2.ctrl_bits(), PowerMode::PowerDown), 0xC0);
+        assert_eq!(
+            word(AuxChannel::Vbat.ctrl_bits(), PowerMode::AlwaysOn),
+            0xA7
+        );
+        assert_eq!(
+            word(AuxChannel::Temp0.ctrl_bits(), PowerMode::AlwaysOn),
+            0x87
+        );
+        assert_eq!(
+            word(AuxChannel::Temp1.ctrl_bits(), PowerMode::AlwaysOn),
+            0xF7
+        );
+        assert_eq!(
+            control_word(ControlBit::AUX, Resolution::Bits8, PowerMode::AdcOn).bits(),
+            0xED
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use crate::Rect;
+
+/// The number of zones the [`IndexedZoneSet`] buckets, one bit per zone in every cell.
+pub const MAX_INDEXED_ZONES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Rectangular area of the screen reacting to touches.
+pub struct Zone {
+    /// The identifier reported when the zone is hit.
+    pub id: u16,
+    /// The area of the zone, in the coordinates of the touch points.
+    pub area: Rect,
+}
+
+/// Set of up to `N` zones, hit-tested in the order they were added.
+///
+/// Overlapping zones are resolved in favor of the zone added first. Hit-testing scans the zones
+/// linearly, which is fine for a handful of zones. With dozens of them, an [`IndexedZoneSet`] avoids
+/// testing the zones far from the touch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZoneSet<const N: usize> {
+    /// The zones, in the order they were added.
+    zones: [Option<Zone>; N],
+}
+
+impl<const N: usize> Default for ZoneSet<N> {
+    fn default() -> Self {
+        Self { zones: [None; N] }
+    }
+}
+
+impl<const N: usize> ZoneSet<N> {
+    /// Creates a new empty zone set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `zone` - The zone to add.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the zone was added, `false` if the set is full.
+    pub fn add(&mut self, zone: Zone) -> bool {
+        let Some(slot) = self.zones.iter_mut().find(|slot| slot.is_none()) else {
+            return false;
+        };
+        *slot = Some(zone);
+        true
+    }
+
+    /// Returns an iterator over the zones, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Zone> {
+        self.zones.iter().flatten()
+    }
+
+    /// Returns the zone at a position, scanning all the zones.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the position.
+    /// * `y` - The y-coordinate of the position.
+    ///
+    /// # Returns
+    ///
+    /// The first zone added containing the position, if any.
+    pub fn hit(&self, x: u16, y: u16) -> Option<&Zone> {
+        self.zones
+            .iter()
+            .flatten()
+            .find(|zone| zone.area.contains(x, y))
+    }
+
+    /// Returns the zone at an index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the zone, in the order the zones were added.
+    fn get(&self, index: usize) -> Option<&Zone> {
+        self.zones.get(index)?.as_ref()
+    }
+}
+
+/// Set of up to `N` zones, indexed by a grid of `COLS` by `ROWS` buckets over an area.
+///
+/// Every bucket records which zones overlap it, and hit-testing only checks the zones
+/// overlapping the bucket of the touch, in the same order as [`ZoneSet::hit`]. The buckets are
+/// updated whenever a zone is added, so the index never goes stale. The memory budget is fixed
+/// at one 64 bit mask per bucket. The first [`MAX_INDEXED_ZONES`] zones are bucketed, later
+/// ones are always tested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedZoneSet<const N: usize, const COLS: usize, const ROWS: usize> {
+    /// The zones, in the order they were added.
+    zones: ZoneSet<N>,
+    /// The area covered by the buckets.
+    area: Rect,
+    /// The mask of the zones overlapping every bucket, indexed by row and then by column.
+    buckets: [[u64; COLS]; ROWS],
+}
+
+impl<const N: usize, const COLS: usize, const ROWS: usize> IndexedZoneSet<N, COLS, ROWS> {
+    /// Creates a new empty indexed zone set.
+    ///
+    /// # Arguments
+    ///
+    /// * `area` - The area covered by the buckets, usually the whole screen. Touches outside of
+    ///   it fall back to a linear scan.
+    pub fn new(area: Rect) -> Self {
+        Self {
+            zones: ZoneSet::new(),
+            area,
+            buckets: [[0; COLS]; ROWS],
+        }
+    }
+
+    /// Returns the indexed zones.
+    pub fn zones(&self) -> &ZoneSet<N> {
+        &self.zones
+    }
+
+    /// Adds a zone and records it in the buckets it overlaps.
+    ///
+    /// # Arguments
+    ///
+    /// * `zone` - The zone to add.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the zone was added, `false` if the set is full.
+    pub fn add(&mut self, zone: Zone) -> bool {
+        let Some((bit, slot)) = self
+            .zones
+            .zones
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| slot.is_none())
+        else {
+            return false;
+        };
+        *slot = Some(zone);
+        if bit >= MAX_INDEXED_ZONES {
+            return true;
+        }
+        let area = self.area;
+        let (Some((col_min, row_min)), Some((col_max, row_max))) = (
+            self.bucket(
+                zone.area.x_min.max(area.x_min),
+                zone.area.y_min.max(area.y_min),
+            ),
+            self.bucket(
+                zone.area.x_max.min(area.x_max),
+                zone.area.y_max.min(area.y_max),
+            ),
+        ) else {
+            return true;
+        };
+        for row in self
+            .buckets
+            .iter_mut()
+            .take(row_max.saturating_add(1))
+            .skip(row_min)
+        {
+            for bucket in row.iter_mut().take(col_max.saturating_add(1)).skip(col_min) {
+                *bucket |= 1 << bit;
+            }
+        }
+        true
+    }
+
+    /// Returns the bucket of a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the position.
+    /// * `y` - The y-coordinate of the position.
+    ///
+    /// # Returns
+    ///
+    /// The column and row of the bucket, or `None` if the position is outside of the area.
+    fn bucket(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        if COLS == 0 || ROWS == 0 || !self.area.contains(x, y) {
+            return None;
+        }
+        let cell = |value: u16, min: u16, max: u16, count: usize| {
+            let extent = (max.saturating_sub(min) as usize).saturating_add(1);
+            (value.saturating_sub(min) as usize)
+                .saturating_mul(count)
+                .checked_div(extent)
+                .unwrap_or_default()
+        };
+        Some((
+            cell(x, self.area.x_min, self.area.x_max, COLS),
+            cell(y, self.area.y_min, self.area.y_max, ROWS),
+        ))
+    }
+
+    /// Returns the zone at a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the position.
+    /// * `y` - The y-coordinate of the position.
+    ///
+    /// # Returns
+    ///
+    /// The first zone added containing the position, if any, like [`ZoneSet::hit`].
+    pub fn hit(&self, x: u16, y: u16) -> Option<&Zone> {
+        let Some((col, row)) = self.bucket(x, y) else {
+            return self.zones.hit(x, y);
+        };
+        let mut candidates = self
+            .buckets
+            .get(row)
+            .and_then(|row| row.get(col))
+            .copied()
+            .unwrap_or_default();
+        while candidates != 0 {
+            let bit = candidates.trailing_zeros() as usize;
+            candidates &= candidates.wrapping_sub(1);
+            if let Some(zone) = self.zones.get(bit).filter(|zone| zone.area.contains(x, y)) {
+                return Some(zone);
+            }
+        }
+        self.zones
+            .zones
+            .iter()
+            .skip(MAX_INDEXED_ZONES)
+            .flatten()
+            .find(|zone| zone.area.contains(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indexed_hit_matches_linear_scan() {
+        let screen = Rect {
+            x_min: 0,
+            y_min: 0,
+            x_max: 319,
+            y_max: 239,
+        };
+        let mut zones = ZoneSet::<80>::new();
+        let mut indexed = IndexedZoneSet::<80, 8, 6>::new(screen);
+        // A 10 by 8 grid of 32 by 30 pixel buttons, the last 16 of which are not indexed.
+        for id in 0..80 {
+            let (col, row) = (id % 10, id / 10);
+            let zone = Zone {
+                id,
+                area: Rect {
+                    x_min: col * 32,
+                    y_min: row * 30,
+                    x_max: col * 32 + 29,
+                    y_max: row * 30 + 27,
+                },
+            };
+            assert!(zones.add(zone));
+            assert!(indexed.add(zone));
+            // The index stays in sync with the zones added so far.
+            let (x, y) = (col * 32 + 10, row * 30 + 10);
+            assert_eq!(indexed.hit(x, y).map(|zone| zone.id), Some(id));
+        }
+        assert_eq!(indexed.zones(), &zones);
+        assert!(!indexed.add(Zone {
+            id: 80,
+            area: screen
+        }));
+        for y in (0..260).step_by(3) {
+            for x in (0..340).step_by(3) {
+                assert_eq!(indexed.hit(x, y), zones.hit(x, y));
+            }
+        }
+        assert_eq!(indexed.hit(100, 70).map(|zone| zone.id), Some(23));
+        assert_eq!(indexed.hit(100, 235).map(|zone| zone.id), Some(73));
+        assert_eq!(indexed.hit(31, 0), None);
+    }
+}